@@ -28,7 +28,7 @@ fn main() {
                 .unwrap(),
         )
         .unwrap();
-    timestamp_list.set_attribute(qattribute::SORTED);
+    timestamp_list.set_attribute(qattribute::SORTED).unwrap();
     println!("modified timestamp list: {}", timestamp_list);
 
     // Pop timestamp
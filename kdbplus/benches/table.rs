@@ -0,0 +1,72 @@
+//! Benchmarks of building, cloning and serializing a large table.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use kdbplus::ipc::*;
+use kdbplus::qattribute;
+
+/// Number of rows of a benchmark table.
+const ROWS: usize = 100_000;
+
+/// Build a trade table with `ROWS` rows:
+/// ```q
+/// ([] time: `timestamp$til ROWS; sym: ROWS#`AAPL`MSFT`GOOG; price: 0.5 * til ROWS; size: til ROWS)
+/// ```
+fn build_table() -> K {
+    let time = K::new_temporal_list(
+        (0..ROWS as i64).map(QTimestamp::from_raw).collect(),
+        qattribute::SORTED,
+    );
+    let sym = K::new_symbol_list(
+        ["AAPL", "MSFT", "GOOG"]
+            .iter()
+            .cycle()
+            .take(ROWS)
+            .map(|symbol| symbol.to_string())
+            .collect(),
+        qattribute::NONE,
+    );
+    let price = K::new_float_list(
+        (0..ROWS).map(|i| 0.5 * i as f64).collect(),
+        qattribute::NONE,
+    );
+    let size = K::new_long_list((0..ROWS as i64).collect(), qattribute::NONE);
+    let keys = K::new_symbol_list(
+        vec![
+            String::from("time"),
+            String::from("sym"),
+            String::from("price"),
+            String::from("size"),
+        ],
+        qattribute::NONE,
+    );
+    let values = K::new_compound_list(vec![time, sym, price, size]);
+    K::new_dictionary(keys, values).unwrap().flip().unwrap()
+}
+
+fn table_benchmark(criterion: &mut Criterion) {
+    criterion.bench_function("build table", |bencher| bencher.iter(build_table));
+
+    let table = build_table();
+    criterion.bench_function("clone table", |bencher| {
+        bencher.iter(|| black_box(&table).clone())
+    });
+    criterion.bench_function("clone and modify table", |bencher| {
+        bencher.iter(|| {
+            let mut cloned = black_box(&table).clone();
+            cloned
+                .get_mut_column("price")
+                .unwrap()
+                .as_mut_vec::<F>()
+                .unwrap()[0] = 0.0;
+            cloned
+        })
+    });
+    criterion.bench_function("serialize table", |bencher| {
+        bencher.iter(|| {
+            async_std::task::block_on(black_box(&table).serialize(qmsg_type::synchronous, true))
+        })
+    });
+}
+
+criterion_group!(benches, table_benchmark);
+criterion_main!(benches);
@@ -103,7 +103,7 @@
 // >> Load Libraries
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
-use super::qtype;
+use super::{attribute, qattribute, qtype};
 use std::convert::TryInto;
 use std::ffi::CStr;
 use std::os::raw::{
@@ -870,10 +870,13 @@ pub trait KUtility {
     /// `s#1 2 3
     /// q)setter 777
     /// 'not a simple list
+    /// q)setter 3 1 2
+    /// 's-fail
     /// ```
     /// # Note
-    /// q does NOT validate the attribute. Wrong attribute can lead to suboptimal behavior or application crash if
-    ///  you are unfortunate.
+    /// Precondition of the attribute is validated, i.e., sorted (`s#`), unique (`u#`) and parted (`p#`) attributes
+    ///  return `"s-fail"`, `"u-fail"` and `"p-fail"` respectively if the list does not satisfy it. An unknown
+    ///  attribute returns `"domain"`.
     fn set_attribute(&mut self, attribute: i8) -> Result<(), &'static str>;
 
     /// Serialize q object and return serialized q byte list object on success: otherwise null.
@@ -1294,11 +1297,53 @@ impl KUtility for K {
 
     #[inline]
     fn set_attribute(&mut self, attribute: i8) -> Result<(), &'static str> {
-        match unsafe { (**self).qtype } {
-            _t @ qtype::BOOL_LIST..=qtype::TIME_LIST => {
-                Ok(unsafe { (**self).attribute = attribute })
+        let list = *self;
+        let satisfied = match unsafe { (**self).qtype } {
+            qtype::BOOL_LIST | qtype::BYTE_LIST | qtype::STRING => {
+                attribute::satisfies(list.as_mut_slice::<G>(), attribute, Ord::cmp)
+            }
+            qtype::GUID_LIST => {
+                attribute::satisfies(list.as_mut_slice::<[G; 16]>(), attribute, Ord::cmp)
+            }
+            qtype::SHORT_LIST => {
+                attribute::satisfies(list.as_mut_slice::<H>(), attribute, Ord::cmp)
+            }
+            qtype::INT_LIST
+            | qtype::MONTH_LIST
+            | qtype::DATE_LIST
+            | qtype::MINUTE_LIST
+            | qtype::SECOND_LIST
+            | qtype::TIME_LIST => {
+                attribute::satisfies(list.as_mut_slice::<I>(), attribute, Ord::cmp)
+            }
+            qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+                attribute::satisfies(list.as_mut_slice::<J>(), attribute, Ord::cmp)
+            }
+            qtype::REAL_LIST => {
+                attribute::satisfies(list.as_mut_slice::<E>(), attribute, attribute::compare_real)
+            }
+            qtype::FLOAT_LIST | qtype::DATETIME_LIST => attribute::satisfies(
+                list.as_mut_slice::<F>(),
+                attribute,
+                attribute::compare_float,
+            ),
+            qtype::SYMBOL_LIST => {
+                attribute::satisfies(list.as_mut_slice::<S>(), attribute, |left, right| unsafe {
+                    CStr::from_ptr(*left).cmp(CStr::from_ptr(*right))
+                })
+            }
+            _ => return Err("not a simple list\0"),
+        };
+        if satisfied {
+            unsafe { (**self).attribute = attribute };
+            Ok(())
+        } else {
+            match attribute {
+                qattribute::SORTED => Err("s-fail\0"),
+                qattribute::UNIQUE => Err("u-fail\0"),
+                qattribute::PARTED => Err("p-fail\0"),
+                _ => Err("domain\0"),
             }
-            _ => Err("not a simple list\0"),
         }
     }
 
@@ -0,0 +1,149 @@
+//! This module provides validation and enforcement of q attributes shared by `api` and `ipc` module.
+//!  Functions take a comparison function so that the same logic applies to any element type
+//!  (e.g. float with null smaller than any other value as q does).
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::qattribute;
+use std::cmp::Ordering;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Comparison %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Compare two real values in q order, i.e., null is smaller than any other value and equal to null.
+pub(crate) fn compare_real(left: &f32, right: &f32) -> Ordering {
+    match (left.is_nan(), right.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => left.partial_cmp(right).unwrap(),
+    }
+}
+
+/// Compare two float values in q order, i.e., null is smaller than any other value and equal to null.
+pub(crate) fn compare_float(left: &f64, right: &f64) -> Ordering {
+    match (left.is_nan(), right.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => left.partial_cmp(right).unwrap(),
+    }
+}
+
+//%% Validation %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Check if a list satisfies a precondition of an attribute.
+/// - sorted: elements are in ascending order.
+/// - unique: no element appears twice.
+/// - parted: equal elements are contiguous.
+/// - grouped and none: always satisfied.
+/// - others: never satisfied.
+pub(crate) fn satisfies<T, C>(list: &[T], attribute: i8, compare: C) -> bool
+where
+    C: Fn(&T, &T) -> Ordering,
+{
+    match attribute {
+        qattribute::SORTED => list
+            .windows(2)
+            .all(|pair| compare(&pair[0], &pair[1]) != Ordering::Greater),
+        qattribute::UNIQUE => {
+            let mut sorted = list.iter().collect::<Vec<&T>>();
+            sorted.sort_by(|left, right| compare(left, right));
+            sorted
+                .windows(2)
+                .all(|pair| compare(pair[0], pair[1]) != Ordering::Equal)
+        }
+        qattribute::PARTED => {
+            // Heads of runs must be unique.
+            let mut heads = list
+                .iter()
+                .enumerate()
+                .filter(|(i, element)| *i == 0 || compare(&list[i - 1], element) != Ordering::Equal)
+                .map(|(_, element)| element)
+                .collect::<Vec<&T>>();
+            heads.sort_by(|left, right| compare(left, right));
+            heads
+                .windows(2)
+                .all(|pair| compare(pair[0], pair[1]) != Ordering::Equal)
+        }
+        qattribute::GROUPED | qattribute::NONE => true,
+        _ => false,
+    }
+}
+
+/// Check if a list which satisfied an attribute still satisfies it after an element was added at `index`.
+#[cfg(feature = "ipc-core")]
+pub(crate) fn satisfies_at<T, C>(list: &[T], attribute: i8, index: usize, compare: C) -> bool
+where
+    C: Fn(&T, &T) -> Ordering,
+{
+    match attribute {
+        qattribute::SORTED => {
+            (index == 0 || compare(&list[index - 1], &list[index]) != Ordering::Greater)
+                && (index + 1 >= list.len()
+                    || compare(&list[index], &list[index + 1]) != Ordering::Greater)
+        }
+        qattribute::UNIQUE => list
+            .iter()
+            .enumerate()
+            .all(|(i, element)| i == index || compare(element, &list[index]) != Ordering::Equal),
+        _ => satisfies(list, attribute, compare),
+    }
+}
+
+//%% Enforcement %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Rearrange a list so that it satisfies an attribute.
+/// - sorted: sort in ascending order.
+/// - unique: remove duplicates keeping the first occurrence (`distinct` in q).
+/// - parted: gather equal elements in order of their first occurrence.
+/// - grouped and none: do nothing.
+#[cfg(feature = "ipc-core")]
+pub(crate) fn apply<T, C>(list: &mut Vec<T>, attribute: i8, compare: C)
+where
+    T: Clone,
+    C: Fn(&T, &T) -> Ordering,
+{
+    match attribute {
+        qattribute::SORTED => list.sort_by(compare),
+        qattribute::UNIQUE => {
+            let first = first_occurrences(list, compare);
+            let mut i = 0;
+            list.retain(|_| {
+                i += 1;
+                first[i - 1] == i - 1
+            });
+        }
+        qattribute::PARTED => {
+            let first = first_occurrences(list, compare);
+            let mut order = (0..list.len()).collect::<Vec<usize>>();
+            order.sort_by_key(|i| first[*i]);
+            *list = order.into_iter().map(|i| list[i].clone()).collect();
+        }
+        _ => (),
+    }
+}
+
+/// Return an index of the first occurrence of each element's value.
+#[cfg(feature = "ipc-core")]
+fn first_occurrences<T, C>(list: &[T], compare: C) -> Vec<usize>
+where
+    C: Fn(&T, &T) -> Ordering,
+{
+    let mut indices = (0..list.len()).collect::<Vec<usize>>();
+    indices.sort_by(|i, j| compare(&list[*i], &list[*j]).then(i.cmp(j)));
+    let mut first = vec![0; list.len()];
+    let mut head = 0;
+    for (k, i) in indices.iter().enumerate() {
+        if k == 0 || compare(&list[indices[k - 1]], &list[*i]) != Ordering::Equal {
+            head = *i;
+        }
+        first[*i] = head;
+    }
+    first
+}
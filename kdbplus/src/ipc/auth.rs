@@ -0,0 +1,385 @@
+//! This module provides authentication of q clients connecting to an acceptor.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::Result;
+use sha1_smol::Sha1;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+use std::{env, fs, io};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Authenticator %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Check of a credential sent by a q client in the handshake. Set it to an acceptor with
+///  [`QStreamBuilder::authenticator`](struct.QStreamBuilder.html#method.authenticator).
+///
+/// `authenticate` runs on a blocking thread and so it may read a file or verify an expensive password hash.
+/// # Example
+/// ```
+/// use kdbplus::ipc::*;
+/// use std::net::SocketAddr;
+///
+/// /// Accept users whose password is the reverse of the name.
+/// struct Palindrome;
+///
+/// impl Authenticator for Palindrome {
+///     fn authenticate(&self, user: &str, password: &str, _peer_addr: Option<SocketAddr>) -> bool {
+///         user.chars().rev().eq(password.chars())
+///     }
+/// }
+///
+/// let builder = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 7000).authenticator(Palindrome);
+/// ```
+pub trait Authenticator: Send + Sync {
+    /// Check if a client is allowed to connect.
+    /// # Parameters
+    /// - `user`: User name sent by the client.
+    /// - `password`: Password sent by the client.
+    /// - `peer_addr`: Address of the client. `None` for Unix domain socket.
+    fn authenticate(&self, user: &str, password: &str, peer_addr: Option<SocketAddr>) -> bool;
+}
+
+//%% AccountFile %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Accounts listed in a file in the form of `user:hash` per line. The file is reloaded when its modification
+///  time changes. If the reload fails, the accounts loaded last are kept.
+///
+/// The hash is one of:
+/// - hexadecimal SHA-1 of the password, which `.Q.sha1` of q generates.
+/// - bcrypt hash starting with `$2`. Requires `auth-bcrypt` feature.
+/// - Argon2 hash in the PHC string format starting with `$argon2`. Requires `auth-argon2` feature.
+///
+/// A file with a hash whose feature is not enabled is not loaded. Empty lines and lines starting with `#` are ignored.
+/// # Example
+/// ```no_run
+/// use kdbplus::ipc::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let accounts = AccountFile::new("/etc/kdbplus/accounts")?;
+///     let mut socket = QStreamBuilder::new(ConnectionMethod::TCP, "0.0.0.0", 7000)
+///         .authenticator(accounts)
+///         .accept()
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+pub struct AccountFile {
+    /// Path of the account file.
+    path: PathBuf,
+    /// Accounts loaded last.
+    accounts: RwLock<Accounts>,
+}
+
+/// Snapshot of an account file.
+struct Accounts {
+    /// Modification time of the file when it was loaded.
+    modified: Option<SystemTime>,
+    /// Map from user name to password hash.
+    hashes: HashMap<String, String>,
+}
+
+//%% AccountMap %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Accounts held in memory as user names and plain passwords.
+/// # Example
+/// ```
+/// use kdbplus::ipc::*;
+///
+/// let accounts = AccountMap::new()
+///     .insert("reluctant", "slowday")
+///     .insert("mattew", "oracle");
+/// assert!(accounts.authenticate("mattew", "oracle", None));
+/// assert!(!accounts.authenticate("mattew", "slowday", None));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AccountMap {
+    /// Map from user name to password.
+    passwords: HashMap<String, String>,
+}
+
+//%% AllowAll %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Accept any client. Intended for local tests only.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AllowAll;
+
+//%% IpAllowlist %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Accept a client only if its address is in one of allowed networks and another authenticator accepts its
+///  credential. A client via Unix domain socket is regarded as `127.0.0.1`.
+/// # Example
+/// ```
+/// use kdbplus::ipc::*;
+///
+/// # fn main() -> Result<()> {
+/// let authenticator = IpAllowlist::new(AllowAll)
+///     .allow("127.0.0.1")?
+///     .allow("10.1.0.0/16")?;
+/// assert!(authenticator.authenticate("user", "", Some("10.1.2.3:50000".parse().unwrap())));
+/// assert!(!authenticator.authenticate("user", "", Some("10.2.0.1:50000".parse().unwrap())));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct IpAllowlist<A> {
+    /// Allowed networks as pairs of an address and a prefix length.
+    networks: Vec<(IpAddr, u8)>,
+    /// Authenticator of a credential.
+    inner: A,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% AccountFile %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl AccountFile {
+    /// Load accounts from a file.
+    /// # Errors
+    /// - IO error if the file cannot be read.
+    /// - IO error of `Unsupported` if a hash needs a feature which is not enabled.
+    pub fn new<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified_time(&path);
+        let hashes = load_accounts(&path)?;
+        Ok(AccountFile {
+            path,
+            accounts: RwLock::new(Accounts { modified, hashes }),
+        })
+    }
+
+    /// Load accounts from a file specified by `KDBPLUS_ACCOUNT_FILE`.
+    /// # Errors
+    /// - IO error if the variable is not set or the file cannot be read.
+    /// - IO error of `Unsupported` if a hash needs a feature which is not enabled.
+    pub fn from_env() -> Result<Self> {
+        let path = env::var("KDBPLUS_ACCOUNT_FILE").map_err(|_| {
+            io::Error::new(io::ErrorKind::NotFound, "KDBPLUS_ACCOUNT_FILE is not set")
+        })?;
+        Self::new(path)
+    }
+
+    /// Reload the file if it was modified since the last load.
+    fn refresh(&self) {
+        let modified = modified_time(&self.path);
+        if modified == self.accounts.read().unwrap().modified {
+            return;
+        }
+        if let Ok(hashes) = load_accounts(&self.path) {
+            *self.accounts.write().unwrap() = Accounts { modified, hashes };
+        }
+    }
+}
+
+impl Authenticator for AccountFile {
+    fn authenticate(&self, user: &str, password: &str, _peer_addr: Option<SocketAddr>) -> bool {
+        self.refresh();
+        match self.accounts.read().unwrap().hashes.get(user) {
+            Some(hash) => verify_password(password, hash),
+            None => false,
+        }
+    }
+}
+
+//%% AccountMap %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl AccountMap {
+    /// Create an empty map, which rejects any client.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace an account.
+    pub fn insert(mut self, user: &str, password: &str) -> Self {
+        self.passwords
+            .insert(user.to_string(), password.to_string());
+        self
+    }
+}
+
+impl Authenticator for AccountMap {
+    fn authenticate(&self, user: &str, password: &str, _peer_addr: Option<SocketAddr>) -> bool {
+        self.passwords
+            .get(user)
+            .is_some_and(|expected| expected == password)
+    }
+}
+
+//%% AllowAll %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Authenticator for AllowAll {
+    fn authenticate(&self, _user: &str, _password: &str, _peer_addr: Option<SocketAddr>) -> bool {
+        true
+    }
+}
+
+//%% IpAllowlist %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl<A> IpAllowlist<A>
+where
+    A: Authenticator,
+{
+    /// Create an allowlist without any network, which rejects any client until a network is allowed.
+    /// # Parameters
+    /// - `inner`: Authenticator of a credential of a client from an allowed network.
+    pub fn new(inner: A) -> Self {
+        IpAllowlist {
+            networks: Vec::new(),
+            inner,
+        }
+    }
+
+    /// Allow a network in the CIDR notation, e.g., `"10.0.0.0/8"`, or a single address, e.g., `"::1"`.
+    /// # Errors
+    /// IO error of `InvalidInput` if `network` cannot be parsed.
+    pub fn allow(mut self, network: &str) -> Result<Self> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid network: {}", network),
+            )
+        };
+        let (address, prefix) = match network.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (network, None),
+        };
+        let address: IpAddr = address.parse().map_err(|_| invalid())?;
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().map_err(|_| invalid())?,
+            None => max_prefix,
+        };
+        if prefix > max_prefix {
+            return Err(invalid().into());
+        }
+        self.networks.push((address, prefix));
+        Ok(self)
+    }
+
+    /// Check if an address belongs to one of allowed networks.
+    fn is_allowed(&self, address: IpAddr) -> bool {
+        let address = address.to_canonical();
+        self.networks
+            .iter()
+            .any(|(network, prefix)| match (network, address) {
+                (IpAddr::V4(network), IpAddr::V4(address)) => {
+                    let mask = u32::MAX.checked_shl(32 - *prefix as u32).unwrap_or(0);
+                    u32::from(*network) & mask == u32::from(address) & mask
+                }
+                (IpAddr::V6(network), IpAddr::V6(address)) => {
+                    let mask = u128::MAX.checked_shl(128 - *prefix as u32).unwrap_or(0);
+                    u128::from(*network) & mask == u128::from(address) & mask
+                }
+                _ => false,
+            })
+    }
+}
+
+impl<A> Authenticator for IpAllowlist<A>
+where
+    A: Authenticator,
+{
+    fn authenticate(&self, user: &str, password: &str, peer_addr: Option<SocketAddr>) -> bool {
+        let address = peer_addr
+            .map(|address| address.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        self.is_allowed(address) && self.inner.authenticate(user, password, peer_addr)
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Get the modification time of a file if available.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Read a map from user name to password hash from an account file.
+fn load_accounts(path: &Path) -> io::Result<HashMap<String, String>> {
+    let contents = fs::read_to_string(path)?;
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(user, hash)| {
+            check_hash(user, hash)?;
+            Ok((user.to_string(), hash.to_string()))
+        })
+        .collect()
+}
+
+/// Check if a hash can be verified with the enabled features so that an account is not locked out silently.
+fn check_hash(user: &str, hash: &str) -> io::Result<()> {
+    let feature = if hash.starts_with("$2") && cfg!(not(feature = "auth-bcrypt")) {
+        "auth-bcrypt"
+    } else if hash.starts_with("$argon2") && cfg!(not(feature = "auth-argon2")) {
+        "auth-argon2"
+    } else {
+        return Ok(());
+    };
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("hash of {} needs `{}` feature", user, feature),
+    ))
+}
+
+/// Verify a password against a hash of an account file.
+fn verify_password(password: &str, hash: &str) -> bool {
+    if hash.starts_with("$2") {
+        verify_bcrypt(password, hash)
+    } else if hash.starts_with("$argon2") {
+        verify_argon2(password, hash)
+    } else {
+        let mut hasher = Sha1::new();
+        hasher.update(password.as_bytes());
+        hasher.digest().to_string().eq_ignore_ascii_case(hash)
+    }
+}
+
+/// Verify a password against a bcrypt hash.
+#[cfg(feature = "auth-bcrypt")]
+fn verify_bcrypt(password: &str, hash: &str) -> bool {
+    bcrypt::verify(password, hash).unwrap_or(false)
+}
+
+/// bcrypt hash is rejected without `auth-bcrypt` feature.
+#[cfg(not(feature = "auth-bcrypt"))]
+fn verify_bcrypt(_password: &str, _hash: &str) -> bool {
+    false
+}
+
+/// Verify a password against an Argon2 hash.
+#[cfg(feature = "auth-argon2")]
+fn verify_argon2(password: &str, hash: &str) -> bool {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        argon2::Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Argon2 hash is rejected without `auth-argon2` feature.
+#[cfg(not(feature = "auth-argon2"))]
+fn verify_argon2(_password: &str, _hash: &str) -> bool {
+    false
+}
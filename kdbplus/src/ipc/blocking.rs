@@ -0,0 +1,757 @@
+//! This module provides a blocking client of q/kdb+ over the sockets of the standard library for applications
+//!  which do not run an async runtime, e.g., command line tools and batch jobs. [`QStream`] has the same
+//!  methods as the asynchronous [`QStream`](../struct.QStream.html) without `.await` and shares the codec and
+//!  the compression with it.
+//!
+//! This module is enabled by `blocking` feature, which does not depend on tokio. TLS connection additionally
+//!  requires `blocking-tls` feature whose backend is native-tls. [`QStream::connect_with_options`] takes the same
+//!  [`TlsConfig`](../struct.TlsConfig.html) as the asynchronous client.
+//! # Time Limits
+//! `QTimeouts::read` and `QTimeouts::write` bound a whole message rather than each system call. A stream whose
+//!  read or write timed out is poisoned as the asynchronous stream. `QTimeouts::connect` does not bound name
+//!  resolution, which the standard library cannot cancel.
+//! # Example
+//! ```no_run
+//! use kdbplus::ipc::blocking::QStream;
+//! use kdbplus::ipc::*;
+//! use kdbplus::qattribute;
+//!
+//! fn main() -> Result<()> {
+//!     let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "ideal:person")?;
+//!
+//!     // Set remote function with asynchronous message
+//!     socket.send_async_message(&"collatz:{[n] seq:enlist n; while[not n = 1; seq,: n:$[n mod 2; 1 + 3 * n; `long$n % 2]]; seq}")?;
+//!
+//!     // Send a functional query synchronously
+//!     let message = K::new_compound_list(vec![
+//!         K::new_symbol(String::from("collatz")),
+//!         K::new_long(100),
+//!     ]);
+//!     println!("collatz[100]: {}", socket.send_sync_message(&message)?);
+//!
+//!     // Send a text query.
+//!     let result = socket.send_sync_message(&"collatz[12]")?;
+//!     assert_eq!(result.len(), 10);
+//!
+//!     socket.send_async_message(&K::new_compound_list(vec![
+//!         K::new_string(String::from("show"), qattribute::NONE),
+//!         K::new_symbol(String::from("goodbye")),
+//!     ]))?;
+//!     socket.shutdown()
+//! }
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::codec::{
+    check_poisoned, decode_message, encode_object, encode_text, handshake_request, MessageHeader,
+};
+use super::error::Error;
+use super::{qmsg_type, ConnectionMethod, QTimeouts, Result, K};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use super::codec::create_sockfile_path;
+#[cfg(target_os = "linux")]
+use std::os::linux::net::SocketAddrExt;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+#[cfg(feature = "blocking-tls")]
+use super::TlsConfig;
+#[cfg(feature = "blocking-tls")]
+use native_tls::{HandshakeError, TlsStream};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Query %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Feature of query object sent by the blocking [`QStream`]. Implemented for `&str` and `K` as the asynchronous
+///  [`Query`](../trait.Query.html).
+pub trait Query {
+    /// Serialize into q IPC bytes including a header (encoding, message type, compresssion flag and total message length).
+    ///  If the connection is within the same host, the message is not compressed under any conditions.
+    /// # Parameters
+    /// - `message_type`: Message type. One of followings:
+    ///   - `qmsg_type::asynchronous`
+    ///   - `qmsg_type::synchronous`
+    ///   - `qmsg_type::response`
+    /// - `is_local`: Flag of whether the connection is within the same host.
+    fn serialize(&self, message_type: u8, is_local: bool) -> Vec<u8>;
+}
+
+//%% Stream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Socket of the standard library communicating with q.
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(feature = "blocking-tls")]
+    Tls(TlsStream<TcpStream>),
+    #[cfg(unix)]
+    Uds(UnixStream),
+}
+
+//%% QStream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Blocking stream to communicate with q/kdb+.
+pub struct QStream {
+    /// Actual stream to communicate.
+    stream: Stream,
+    /// Connection method.
+    method: ConnectionMethod,
+    /// Indicator of whether the connection is within the same host.
+    local: bool,
+    /// Time limits of read and write.
+    timeouts: QTimeouts,
+    /// Indicator of whether a read or write timed out.
+    poisoned: bool,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Query %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Text query.
+impl Query for &str {
+    fn serialize(&self, message_type: u8, _: bool) -> Vec<u8> {
+        encode_text(self, message_type)
+    }
+}
+
+/// Functional query.
+impl Query for K {
+    fn serialize(&self, message_type: u8, is_local: bool) -> Vec<u8> {
+        encode_object(self, message_type, is_local)
+    }
+}
+
+//%% Stream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Stream {
+    /// Set a time limit of each read. `None` blocks indefinitely.
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Self::Tcp(socket) => socket.set_read_timeout(timeout),
+            #[cfg(feature = "blocking-tls")]
+            Self::Tls(socket) => socket.get_ref().set_read_timeout(timeout),
+            #[cfg(unix)]
+            Self::Uds(socket) => socket.set_read_timeout(timeout),
+        }
+    }
+
+    /// Set a time limit of each write. `None` blocks indefinitely.
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Self::Tcp(socket) => socket.set_write_timeout(timeout),
+            #[cfg(feature = "blocking-tls")]
+            Self::Tls(socket) => socket.get_ref().set_write_timeout(timeout),
+            #[cfg(unix)]
+            Self::Uds(socket) => socket.set_write_timeout(timeout),
+        }
+    }
+
+    /// Shutdown the connection. TLS stream sends `close_notify` first.
+    fn shutdown(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tcp(socket) => socket.shutdown(Shutdown::Both),
+            #[cfg(feature = "blocking-tls")]
+            Self::Tls(socket) => {
+                socket.shutdown()?;
+                socket.get_ref().shutdown(Shutdown::Both)
+            }
+            #[cfg(unix)]
+            Self::Uds(socket) => socket.shutdown(Shutdown::Both),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(socket) => socket.read(buffer),
+            #[cfg(feature = "blocking-tls")]
+            Self::Tls(socket) => socket.read(buffer),
+            #[cfg(unix)]
+            Self::Uds(socket) => socket.read(buffer),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(socket) => socket.write(buffer),
+            #[cfg(feature = "blocking-tls")]
+            Self::Tls(socket) => socket.write(buffer),
+            #[cfg(unix)]
+            Self::Uds(socket) => socket.write(buffer),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tcp(socket) => socket.flush(),
+            #[cfg(feature = "blocking-tls")]
+            Self::Tls(socket) => socket.flush(),
+            #[cfg(unix)]
+            Self::Uds(socket) => socket.flush(),
+        }
+    }
+}
+
+//%% QStream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QStream {
+    /// Connect to q/kdb+ specifying a connection method, destination host, destination port and access credential.
+    /// # Parameters
+    /// - `method`: Connection method. One of followings:
+    ///   - TCP
+    ///   - TLS: Requires `blocking-tls` feature.
+    ///   - UDS: Available on Unix.
+    /// - `host`: Hostname or IP address of the target q process. Empty `str` for Unix domain socket.
+    /// - `port`: Port of the target q process.
+    /// - `credential`: Credential in the form of `username:password` to connect to the target q process.
+    /// # Errors
+    /// IO error of `Unsupported` if the connection method is not available in this build.
+    /// # Example
+    /// See the example of [`blocking`](index.html).
+    pub fn connect(
+        method: ConnectionMethod,
+        host: &str,
+        port: u16,
+        credential: &str,
+    ) -> Result<Self> {
+        Self::connect_with_timeouts(method, host, port, credential, QTimeouts::default())
+    }
+
+    /// Connect to q/kdb+ with time limits. `timeouts.connect` and `timeouts.handshake` apply to this call and
+    ///  `timeouts.read` and `timeouts.write` are kept for subsequent operations.
+    /// # Parameters
+    /// - `method`: Connection method.
+    /// - `host`: Hostname or IP address of the target q process. Empty `str` for Unix domain socket.
+    /// - `port`: Port of the target q process.
+    /// - `credential`: Credential in the form of `username:password` to connect to the target q process.
+    /// - `timeouts`: Time limits of operations.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::ipc::blocking::QStream;
+    /// use kdbplus::ipc::error::Error;
+    /// use kdbplus::ipc::*;
+    /// use std::time::Duration;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let timeouts = QTimeouts {
+    ///         connect: Some(Duration::from_secs(3)),
+    ///         handshake: Some(Duration::from_secs(3)),
+    ///         read: Some(Duration::from_secs(60)),
+    ///         write: Some(Duration::from_secs(10)),
+    ///     };
+    ///     let mut socket =
+    ///         QStream::connect_with_timeouts(ConnectionMethod::TCP, "localhost", 5000, "ideal:person", timeouts)?;
+    ///
+    ///     // Override the read time limit for a heavy query.
+    ///     match socket.send_sync_message_timeout(&"count select from trade", Duration::from_secs(600)) {
+    ///         Ok(result) => println!("count: {}", result),
+    ///         Err(Error::Timeout(_)) => assert!(socket.is_poisoned()),
+    ///         Err(error) => return Err(error),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn connect_with_timeouts(
+        method: ConnectionMethod,
+        host: &str,
+        port: u16,
+        credential: &str,
+        timeouts: QTimeouts,
+    ) -> Result<Self> {
+        Self::establish(
+            method,
+            host,
+            port,
+            credential,
+            timeouts,
+            connect_tls_default,
+        )
+    }
+
+    /// Connect to q/kdb+ with time limits and TLS settings. `tls` is used only with `ConnectionMethod::TLS`.
+    /// # Parameters
+    /// - `method`: Connection method.
+    /// - `host`: Hostname or IP address of the target q process. Empty `str` for Unix domain socket.
+    /// - `port`: Port of the target q process.
+    /// - `credential`: Credential in the form of `username:password` to connect to the target q process.
+    /// - `timeouts`: Time limits of operations.
+    /// - `tls`: TLS settings. See [`TlsConfig`](../struct.TlsConfig.html).
+    /// # Errors
+    /// IO error of `InvalidInput` if a certificate or a key in `tls` is invalid.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::ipc::blocking::QStream;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let config = TlsConfig::new()
+    ///         .add_root_certificate_pem(&std::fs::read("/etc/pki/internal-ca.pem")?)
+    ///         .identity_pkcs12(&std::fs::read("/etc/pki/client.p12")?, "secret")
+    ///         .hostname("kdb.internal");
+    ///     let mut socket = QStream::connect_with_options(
+    ///         ConnectionMethod::TLS,
+    ///         "10.0.0.8",
+    ///         5000,
+    ///         "ideal:person",
+    ///         QTimeouts::default(),
+    ///         config,
+    ///     )?;
+    ///     println!("{}", socket.send_sync_message(&"1+1")?);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "blocking-tls")]
+    pub fn connect_with_options(
+        method: ConnectionMethod,
+        host: &str,
+        port: u16,
+        credential: &str,
+        timeouts: QTimeouts,
+        tls: TlsConfig,
+    ) -> Result<Self> {
+        Self::establish(
+            method,
+            host,
+            port,
+            credential,
+            timeouts,
+            |host, port, timeouts| connect_tls(host, port, timeouts, &tls),
+        )
+    }
+
+    /// Connect to q/kdb+ and run the handshake. `connect_tls` establishes a TLS connection.
+    fn establish<F>(
+        method: ConnectionMethod,
+        host: &str,
+        port: u16,
+        credential: &str,
+        timeouts: QTimeouts,
+        connect_tls: F,
+    ) -> Result<Self>
+    where
+        F: FnOnce(&str, u16, &QTimeouts) -> Result<Stream>,
+    {
+        let (mut stream, local, capability) = match method {
+            ConnectionMethod::TCP => {
+                let socket = connect_tcp(host, port, timeouts.connect)?;
+                let is_local = matches!(host, "localhost" | "127.0.0.1");
+                (Stream::Tcp(socket), is_local, 3)
+            }
+            ConnectionMethod::TLS => (connect_tls(host, port, &timeouts)?, false, 3),
+            ConnectionMethod::UDS => (connect_uds(port)?, true, 6),
+        };
+        let deadline = deadline(timeouts.handshake);
+        handshake(&mut stream, credential, capability, deadline)?;
+        Ok(QStream {
+            stream,
+            method,
+            local,
+            timeouts,
+            poisoned: false,
+        })
+    }
+
+    /// Shutdown the socket for a q process.
+    /// # Example
+    /// See the example of [`blocking`](index.html).
+    pub fn shutdown(mut self) -> Result<()> {
+        Ok(self.stream.shutdown()?)
+    }
+
+    /// Send a message with a specified message type without waiting for a response even for a synchronous message.
+    ///  If you need to receive a response you need to use [`receive_message`](#method.receive_message).
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    /// - `message_type`: Asynchronous or synchronous.
+    /// # Errors
+    /// `Error::Timeout("write")` if the message is not sent within the write time limit. The stream is poisoned then.
+    pub fn send_message(&mut self, message: &dyn Query, message_type: u8) -> Result<()> {
+        check_poisoned(self.poisoned)?;
+        let deadline = deadline(self.timeouts.write);
+        let result = self.send_within(message, message_type, deadline, "write");
+        self.poison_on_timeout(result)
+    }
+
+    /// Send a message asynchronously.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    /// # Example
+    /// See the example of [`blocking`](index.html).
+    pub fn send_async_message(&mut self, message: &dyn Query) -> Result<()> {
+        self.send_message(message, qmsg_type::asynchronous)
+    }
+
+    /// Send a message synchronously.
+    /// # Note
+    /// Remote function must NOT send back a message of asynchronous or synchronous type durning execution of the function.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    /// # Example
+    /// See the example of [`blocking`](index.html).
+    pub fn send_sync_message(&mut self, message: &dyn Query) -> Result<K> {
+        self.send_message(message, qmsg_type::synchronous)?;
+        let response = self.receive_message()?;
+        expect_response(response)
+    }
+
+    /// Send a message synchronously and wait for the response at most `timeout` instead of the read and write
+    ///  time limits of the stream.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    /// - `timeout`: Time limit of the whole round trip.
+    /// # Errors
+    /// `Error::Timeout("query")` if the response does not arrive in time. The stream is poisoned then.
+    /// # Example
+    /// See the example of [`connect_with_timeouts`](#method.connect_with_timeouts).
+    pub fn send_sync_message_timeout(
+        &mut self,
+        message: &dyn Query,
+        timeout: Duration,
+    ) -> Result<K> {
+        check_poisoned(self.poisoned)?;
+        let deadline = deadline(Some(timeout));
+        let result = self
+            .send_within(message, qmsg_type::synchronous, deadline, "query")
+            .and_then(|_| self.receive_within(deadline, "query"))
+            .and_then(expect_response);
+        self.poison_on_timeout(result)
+    }
+
+    /// Receive a message from a remote q process. The received message is parsed as `K` and message type is
+    ///  stored in the first returned value.
+    /// # Errors
+    /// `Error::Timeout("read")` if no message arrives within the read time limit. The stream is poisoned then.
+    pub fn receive_message(&mut self) -> Result<(u8, K)> {
+        check_poisoned(self.poisoned)?;
+        let deadline = deadline(self.timeouts.read);
+        let result = self.receive_within(deadline, "read");
+        self.poison_on_timeout(result)
+    }
+
+    /// Receive a message waiting at most `timeout` instead of the read time limit of the stream.
+    /// # Errors
+    /// `Error::Timeout("read")` if no message arrives in time. The stream is poisoned then.
+    pub fn receive_message_timeout(&mut self, timeout: Duration) -> Result<(u8, K)> {
+        check_poisoned(self.poisoned)?;
+        let result = self.receive_within(deadline(Some(timeout)), "read");
+        self.poison_on_timeout(result)
+    }
+
+    /// Get time limits of read and write.
+    pub fn get_timeouts(&self) -> QTimeouts {
+        self.timeouts
+    }
+
+    /// Set time limits of read and write. `connect` and `handshake` have no effect on a connected stream.
+    pub fn set_timeouts(&mut self, timeouts: QTimeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// Check if a read or write timed out. A poisoned stream returns an error for any message exchange and
+    ///  must be reconnected.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Return underlying connection type. One of `TCP`, `TLS` or `UDS`.
+    pub fn get_connection_type(&self) -> &str {
+        match self.method {
+            ConnectionMethod::TCP => "TCP",
+            ConnectionMethod::TLS => "TLS",
+            ConnectionMethod::UDS => "UDS",
+        }
+    }
+
+    /// Enforce compression if the size of a message exceeds 2000 regardless of locality of the connection.
+    ///  This flag is not revertible intentionally.
+    pub fn enforce_compression(&mut self) {
+        self.local = false;
+    }
+
+    /// Serialize and send a message by a deadline.
+    fn send_within(
+        &mut self,
+        message: &dyn Query,
+        message_type: u8,
+        deadline: Option<Instant>,
+        operation: &'static str,
+    ) -> Result<()> {
+        let bytes = message.serialize(message_type, self.local);
+        write_all(&mut self.stream, &bytes, deadline, operation)
+    }
+
+    /// Receive a message by a deadline with decompression if necessary.
+    fn receive_within(
+        &mut self,
+        deadline: Option<Instant>,
+        operation: &'static str,
+    ) -> Result<(u8, K)> {
+        // Read header
+        let mut header_buffer = [0u8; 8];
+        read_exact(&mut self.stream, &mut header_buffer, deadline, operation).map_err(|error| {
+            // The expected message is header or EOF (close due to q process failure resulting from a bad query)
+            match error {
+                Error::Timeout(_) => error,
+                error => io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    format!("Connection dropped: {}", error),
+                )
+                .into(),
+            }
+        })?;
+
+        // Parse message header
+        let header = MessageHeader::from_bytes(header_buffer);
+
+        // Read body
+        let mut body = vec![0_u8; header.length as usize - MessageHeader::size()];
+        read_exact(&mut self.stream, &mut body, deadline, operation).map_err(|error| {
+            // Fails if q process fails before reading the body
+            match error {
+                Error::Timeout(_) => error,
+                error => io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("Failed to read body of message: {}", error),
+                )
+                .into(),
+            }
+        })?;
+
+        Ok(decode_message(&header, body))
+    }
+
+    /// Poison the stream if the result is a timeout.
+    fn poison_on_timeout<T>(&mut self, result: Result<T>) -> Result<T> {
+        if let Err(Error::Timeout(_)) = result {
+            self.poisoned = true;
+        }
+        result
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% QStream Connector %%//vvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Establish a TCP connection trying resolved addresses in order until the first successful one.
+/// # Parameters
+/// - `host`: Hostname or IP address of the target q process.
+/// - `port`: Port of the target q process.
+/// - `timeout`: Time limit to connect to all addresses.
+fn connect_tcp(host: &str, port: u16, timeout: Option<Duration>) -> Result<TcpStream> {
+    let deadline = deadline(timeout);
+    let addresses = (host, port)
+        .to_socket_addrs()
+        .map_err(|error| io::Error::new(io::ErrorKind::NotFound, error.to_string()))?;
+    for address in addresses {
+        let socket = match remaining(deadline, "connect")? {
+            Some(timeout) => TcpStream::connect_timeout(&address, timeout),
+            None => TcpStream::connect(address),
+        };
+        match socket {
+            Ok(socket) => return Ok(socket),
+            Err(error) if is_timeout(&error) => return Err(Error::timeout("connect")),
+            // Try next address.
+            Err(_) => continue,
+        }
+    }
+    // All addresses failed.
+    Err(io::Error::new(io::ErrorKind::ConnectionRefused, "failed to connect").into())
+}
+
+/// Connect to q process via TCP and run TLS handshake within the handshake time limit.
+/// # Parameters
+/// - `host`: Hostname or IP address of the target q process.
+/// - `port`: Port of the target q process.
+/// - `timeouts`: Time limits of connection and handshake.
+/// - `config`: TLS settings.
+#[cfg(feature = "blocking-tls")]
+fn connect_tls(host: &str, port: u16, timeouts: &QTimeouts, config: &TlsConfig) -> Result<Stream> {
+    // Fail fast on an invalid certificate or key before connecting.
+    let connector = config.build_native_connector()?;
+    let socket = connect_tcp(host, port, timeouts.connect)?;
+    socket.set_read_timeout(timeouts.handshake)?;
+    socket.set_write_timeout(timeouts.handshake)?;
+    match connector.connect(config.domain(host), socket) {
+        Ok(socket) => Ok(Stream::Tls(socket)),
+        // A time limit of the underlying socket expired in the middle of the handshake.
+        Err(HandshakeError::WouldBlock(_)) => Err(Error::timeout("handshake")),
+        Err(HandshakeError::Failure(error)) => {
+            Err(io::Error::new(io::ErrorKind::ConnectionAborted, error.to_string()).into())
+        }
+    }
+}
+
+/// Connect over TLS with default settings.
+#[cfg(feature = "blocking-tls")]
+fn connect_tls_default(host: &str, port: u16, timeouts: &QTimeouts) -> Result<Stream> {
+    connect_tls(host, port, timeouts, &TlsConfig::default())
+}
+
+/// TLS is not available without `blocking-tls` feature.
+#[cfg(not(feature = "blocking-tls"))]
+fn connect_tls_default(_: &str, _: u16, _: &QTimeouts) -> Result<Stream> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "TLS requires `blocking-tls` feature",
+    )
+    .into())
+}
+
+/// Connect to q process via Unix domain socket. The socket file is used in the abstract namespace on Linux as q
+///  does.
+#[cfg(unix)]
+fn connect_uds(port: u16) -> Result<Stream> {
+    let uds_path = create_sockfile_path(port)?;
+    #[cfg(target_os = "linux")]
+    let socket = UnixStream::connect_addr(&std::os::unix::net::SocketAddr::from_abstract_name(
+        uds_path.as_bytes(),
+    )?)?;
+    #[cfg(not(target_os = "linux"))]
+    let socket = UnixStream::connect(&uds_path)?;
+    Ok(Stream::Uds(socket))
+}
+
+/// Unix domain socket is not available on this platform.
+#[cfg(not(unix))]
+fn connect_uds(_: u16) -> Result<Stream> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix domain socket is not supported",
+    )
+    .into())
+}
+
+/// Send a credential and receive a common capacity.
+fn handshake(
+    stream: &mut Stream,
+    credential: &str,
+    capability: u8,
+    deadline: Option<Instant>,
+) -> Result<()> {
+    write_all(
+        stream,
+        &handshake_request(credential, capability),
+        deadline,
+        "handshake",
+    )?;
+    // Placeholder of common capablility
+    let mut cap = [0u8; 1];
+    match read_exact(stream, &mut cap, deadline, "handshake") {
+        Ok(_) => Ok(()),
+        Err(error @ Error::Timeout(_)) => Err(error),
+        // Connection is closed in case of authentication failure
+        Err(_) => {
+            Err(io::Error::new(io::ErrorKind::ConnectionAborted, "authentication failure").into())
+        }
+    }
+}
+
+//%% QStream Query %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Return the body of a response. Other message types result in an error.
+fn expect_response((message_type, message): (u8, K)) -> Result<K> {
+    match message_type {
+        qmsg_type::response => Ok(message),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("expected a response: {}", message),
+        )
+        .into()),
+    }
+}
+
+/// Read bytes exactly the same length of bytes as the buffer length by a deadline.
+fn read_exact(
+    stream: &mut Stream,
+    buffer: &mut [u8],
+    deadline: Option<Instant>,
+    operation: &'static str,
+) -> Result<()> {
+    let mut read_total = 0;
+    while read_total < buffer.len() {
+        stream.set_read_timeout(remaining(deadline, operation)?)?;
+        match stream.read(&mut buffer[read_total..]) {
+            // The peer closed the connection.
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            Ok(read) => read_total += read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) if is_timeout(&error) => return Err(Error::timeout(operation)),
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Write all bytes in the buffer by a deadline.
+fn write_all(
+    stream: &mut Stream,
+    buffer: &[u8],
+    deadline: Option<Instant>,
+    operation: &'static str,
+) -> Result<()> {
+    let mut write_total = 0;
+    while write_total < buffer.len() {
+        stream.set_write_timeout(remaining(deadline, operation)?)?;
+        match stream.write(&buffer[write_total..]) {
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero).into()),
+            Ok(written) => write_total += written,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) if is_timeout(&error) => return Err(Error::timeout(operation)),
+            Err(error) => return Err(error.into()),
+        }
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// Deadline of an operation starting now.
+fn deadline(limit: Option<Duration>) -> Option<Instant> {
+    limit.map(|limit| Instant::now() + limit)
+}
+
+/// Time left until a deadline. `Error::Timeout(operation)` is returned if it has passed.
+fn remaining(deadline: Option<Instant>, operation: &'static str) -> Result<Option<Duration>> {
+    match deadline {
+        None => Ok(None),
+        Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+            // Zero duration is rejected by the socket as an invalid time limit.
+            Some(left) if !left.is_zero() => Ok(Some(left)),
+            _ => Err(Error::timeout(operation)),
+        },
+    }
+}
+
+/// Check if an IO error is an expiration of a socket time limit. Unix reports `WouldBlock` and Windows reports
+///  `TimedOut`.
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
@@ -0,0 +1,571 @@
+//! This module provides casting between q types equivalent to q's `$`.
+//!
+//! The shape of the result follows the source object, i.e., an atom is cast to an atom and a list to a list,
+//!  while the target type can be given as either an atom type or a list type. The only exception is a string,
+//!  which is parsed into an atom when an atom type is given (`"J"$"42"`) and cast element-wise when a list type
+//!  is given (`` `long$"42" ``).
+//!
+//! | Source                  | Target                  | Rule                                                  |
+//! |-------------------------|-------------------------|-------------------------------------------------------|
+//! | numeric / temporal      | numeric / temporal      | Underlying value is converted. Null and infinity are kept. Narrowing wraps as q does and float is rounded. |
+//! | timestamp / datetime    | date / month            | Truncated to the date or month.                       |
+//! | timestamp / datetime    | minute / second / time / timespan | Time of the day.                            |
+//! | timespan / minute / second / time | timespan / minute / second / time | Rescaled and truncated.             |
+//! | string                  | atom type               | Parsed. Unparsable text becomes null as q does.       |
+//! | char / string           | symbol                  | `` `$ ``                                              |
+//! | symbol                  | char                    | `string` in q.                                        |
+//! | compound list           | any                     | Each element is cast. The result is a simple list if all elements become atoms of the same type. |
+//!
+//! # Example
+//! ```
+//! use kdbplus::{qattribute, qinf_base, qnull_base, qtype};
+//! use kdbplus::ipc::*;
+//!
+//! fn main() {
+//!     let q_int_list = K::new_int_list(vec![1, qnull_base::I, qinf_base::I], qattribute::NONE);
+//!     assert_eq!(
+//!         format!("{}", q_int_list.cast(qtype::LONG_LIST).unwrap()),
+//!         String::from("1 0N 0W")
+//!     );
+//!
+//!     let q_string = K::new_string(String::from("42"), qattribute::NONE);
+//!     assert_eq!(q_string.cast(qtype::LONG_ATOM).unwrap().get_long(), Ok(42));
+//!
+//!     let q_timestamp = K::from("2020.01.02D03:04:05.000000000".parse::<QTimestamp>().unwrap());
+//!     assert_eq!(
+//!         format!("{}", q_timestamp.cast(qtype::DATE_ATOM).unwrap()),
+//!         String::from("2020.01.02")
+//!     );
+//! }
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::*;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Element of a numeric or temporal object during a cast.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Number {
+    /// Null.
+    Null,
+    /// Positive infinity.
+    Inf,
+    /// Negative infinity.
+    NInf,
+    /// Underlying value of a bool, byte, char, short, int, long or integral temporal type.
+    Integral(J),
+    /// Underlying value of a real, float or datetime.
+    Fractional(F),
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl K {
+    /// Cast to another q type as q's `$` does. `qtype` is either an atom type or a list type.
+    /// # Example
+    /// ```
+    /// use kdbplus::{qattribute, qnull_base, qtype};
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     // Narrowing
+    ///     let q_float_list = K::new_float_list(vec![1.5, -2.5, qnull_base::F], qattribute::NONE);
+    ///     assert_eq!(
+    ///         format!("{}", q_float_list.cast(qtype::INT_LIST).unwrap()),
+    ///         String::from("2 -3 0Ni")
+    ///     );
+    ///
+    ///     // Symbol and string
+    ///     let q_string = K::new_string(String::from("AAPL"), qattribute::NONE);
+    ///     let q_symbol = q_string.cast(qtype::SYMBOL_ATOM).unwrap();
+    ///     assert_eq!(format!("{}", q_symbol), String::from("`AAPL"));
+    ///     assert_eq!(q_symbol.cast(qtype::STRING).unwrap().as_string(), Ok("AAPL"));
+    ///
+    ///     // Parse
+    ///     let q_strings = K::new_compound_list(vec![
+    ///         K::new_string(String::from("2020.01.31"), qattribute::NONE),
+    ///         K::new_string(String::from("bad"), qattribute::NONE),
+    ///     ]);
+    ///     assert_eq!(
+    ///         format!("{}", q_strings.cast(qtype::DATE_ATOM).unwrap()),
+    ///         String::from("2020.01.31 0Nd")
+    ///     );
+    ///
+    ///     // Invalid cast
+    ///     assert!(q_symbol.cast(qtype::LONG_ATOM).is_err());
+    /// }
+    /// ```
+    pub fn cast(&self, qtype: i8) -> Result<K> {
+        let source = self.0.qtype;
+        let target = qtype.wrapping_abs();
+        let invalid = || Error::invalid_cast(source, qtype);
+        if !is_castable_type(target) {
+            return Err(invalid());
+        }
+        match source {
+            qtype::COMPOUND_LIST => {
+                let elements = self
+                    .as_vec::<K>()
+                    .unwrap()
+                    .iter()
+                    .map(|element| element.cast(qtype))
+                    .collect::<Result<Vec<K>>>()?;
+                if elements.iter().all(|element| element.0.qtype == -target) {
+                    Ok(join_atoms(target, &elements))
+                } else {
+                    Ok(K::new_compound_list(elements))
+                }
+            }
+            _ if source.wrapping_abs() == target => Ok(self.clone()),
+            qtype::STRING if qtype < 0 => Ok(parse(self.as_string().unwrap(), qtype)),
+            qtype::SYMBOL_ATOM if target == qtype::STRING => Ok(K::new_string(
+                String::from(self.get_symbol().unwrap()),
+                qattribute::NONE,
+            )),
+            qtype::SYMBOL_LIST if target == qtype::STRING => Ok(K::new_compound_list(
+                self.as_vec::<S>()
+                    .unwrap()
+                    .iter()
+                    .map(|symbol| K::new_string(String::from(symbol.as_str()), qattribute::NONE))
+                    .collect(),
+            )),
+            qtype::CHAR if target == qtype::SYMBOL_LIST => {
+                Ok(K::new_symbol(String::from(self.get_char().unwrap())))
+            }
+            // A string becomes one symbol as `` `$"abc" `` does.
+            qtype::STRING if target == qtype::SYMBOL_LIST => {
+                Ok(K::new_symbol(String::from(self.as_string().unwrap())))
+            }
+            _ if is_numeric_type(source.wrapping_abs())
+                && is_numeric_type(target)
+                && is_convertible(source.wrapping_abs(), target) =>
+            {
+                let numbers = numbers(self)
+                    .into_iter()
+                    .map(|number| convert(source.wrapping_abs(), target, number))
+                    .collect::<Vec<Number>>();
+                let list = encode(target, numbers);
+                if source < 0 {
+                    Ok(into_atom(list))
+                } else {
+                    Ok(list)
+                }
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Type %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Check if a list type can be a target of a cast.
+fn is_castable_type(qtype: i8) -> bool {
+    matches!(qtype, qtype::BOOL_LIST | qtype::GUID_LIST)
+        || (qtype::BYTE_LIST..=qtype::TIME_LIST).contains(&qtype)
+}
+
+/// Check if a list type is a numeric or temporal type including char.
+fn is_numeric_type(qtype: i8) -> bool {
+    !matches!(
+        qtype,
+        qtype::GUID_LIST | qtype::SYMBOL_LIST | qtype::COMPOUND_LIST
+    ) && is_castable_type(qtype)
+}
+
+/// Check if a list type is a temporal type denoting a point in time.
+fn is_point_type(qtype: i8) -> bool {
+    matches!(
+        qtype,
+        qtype::TIMESTAMP_LIST | qtype::MONTH_LIST | qtype::DATE_LIST | qtype::DATETIME_LIST
+    )
+}
+
+/// Check if a list type is a temporal type denoting a duration.
+fn is_span_type(qtype: i8) -> bool {
+    matches!(
+        qtype,
+        qtype::TIMESPAN_LIST | qtype::MINUTE_LIST | qtype::SECOND_LIST | qtype::TIME_LIST
+    )
+}
+
+/// Check if elements of a numeric list type can be converted into another numeric list type.
+fn is_convertible(source: i8, target: i8) -> bool {
+    let is_temporal = |qtype| is_point_type(qtype) || is_span_type(qtype);
+    match (is_temporal(source), is_temporal(target)) {
+        (true, true) => {
+            (is_point_type(source) && is_point_type(target))
+                || (is_span_type(source) && is_span_type(target))
+                || matches!(source, qtype::TIMESTAMP_LIST | qtype::DATETIME_LIST)
+        }
+        (true, false) => target != qtype::STRING,
+        (false, true) => source != qtype::STRING,
+        (false, false) => true,
+    }
+}
+
+//%% Decode %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Decode an integral value with its null and infinities.
+fn integral<T>(value: T, null: T, inf: T, ninf: T) -> Number
+where
+    T: Copy + PartialEq + Into<J>,
+{
+    if value == null {
+        Number::Null
+    } else if value == inf {
+        Number::Inf
+    } else if value == ninf {
+        Number::NInf
+    } else {
+        Number::Integral(value.into())
+    }
+}
+
+/// Decode a floating point value.
+fn fractional(value: F) -> Number {
+    if value.is_nan() {
+        Number::Null
+    } else if value == F::INFINITY {
+        Number::Inf
+    } else if value == F::NEG_INFINITY {
+        Number::NInf
+    } else {
+        Number::Fractional(value)
+    }
+}
+
+/// Decode elements of a numeric atom or list.
+fn numbers(object: &K) -> Vec<Number> {
+    let short = |value: &H| integral(*value, qnull_base::H, qinf_base::H, qninf_base::H);
+    let int = |value: &I| integral(*value, qnull_base::I, qinf_base::I, qninf_base::I);
+    let long = |value: &J| integral(*value, qnull_base::J, qinf_base::J, qninf_base::J);
+    match &object.0.value {
+        k0_inner::byte(byte) => vec![Number::Integral(*byte as J)],
+        k0_inner::short(value) => vec![short(value)],
+        k0_inner::int(value) => vec![int(value)],
+        k0_inner::long(value) => vec![long(value)],
+        k0_inner::real(value) => vec![fractional(*value as F)],
+        k0_inner::float(value) => vec![fractional(*value)],
+        k0_inner::string(string) => string
+            .chars()
+            .map(|character| Number::Integral(character as G as J))
+            .collect(),
+        k0_inner::list(k0_list::byte(list)) => list
+            .iter()
+            .map(|byte| Number::Integral(*byte as J))
+            .collect(),
+        k0_inner::list(k0_list::short(list)) => list.iter().map(short).collect(),
+        k0_inner::list(k0_list::int(list)) => list.iter().map(int).collect(),
+        k0_inner::list(k0_list::long(list)) => list.iter().map(long).collect(),
+        k0_inner::list(k0_list::real(list)) => {
+            list.iter().map(|value| fractional(*value as F)).collect()
+        }
+        k0_inner::list(k0_list::float(list)) => {
+            list.iter().map(|value| fractional(*value)).collect()
+        }
+        _ => unreachable!(),
+    }
+}
+
+//%% Convert %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Get nanoseconds of a unit of an integral temporal type.
+fn unit_nanos(qtype: i8) -> i128 {
+    match qtype {
+        qtype::DATE_LIST => ONE_DAY_NANOS as i128,
+        qtype::MINUTE_LIST => 60_000_000_000,
+        qtype::SECOND_LIST => 1_000_000_000,
+        qtype::TIME_LIST => 1_000_000,
+        _ => 1,
+    }
+}
+
+/// Build an integral value which becomes infinity if it is out of the finite range `(-inf, inf)`.
+fn bounded(value: i128, inf: J) -> Number {
+    if value >= inf as i128 {
+        Number::Inf
+    } else if value <= -(inf as i128) {
+        Number::NInf
+    } else {
+        Number::Integral(value as J)
+    }
+}
+
+/// Convert an element from a temporal type to another. Other conversions keep the underlying value.
+fn convert(source: i8, target: i8, number: Number) -> Number {
+    let is_temporal = |qtype| is_point_type(qtype) || is_span_type(qtype);
+    if source == target || !is_temporal(source) || !is_temporal(target) {
+        return number;
+    }
+    let nanos = match (source, number) {
+        (qtype::MONTH_LIST, Number::Integral(month)) => {
+            match QMonth::from_raw(month as I).to_naive_date() {
+                Some(date) => date_to_q_date(date) as i128 * ONE_DAY_NANOS as i128,
+                None => return Number::Null,
+            }
+        }
+        (_, Number::Integral(value)) => value as i128 * unit_nanos(source),
+        (_, Number::Fractional(days)) => (days * ONE_DAY_NANOS as F).round() as i128,
+        (_, special) => return special,
+    };
+    // Only timestamp and datetime can be cast to a duration, which is the time of the day.
+    let nanos = if is_point_type(source) && is_span_type(target) {
+        nanos.rem_euclid(ONE_DAY_NANOS as i128)
+    } else {
+        nanos
+    };
+    match target {
+        qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => bounded(nanos, qinf_base::J),
+        qtype::DATETIME_LIST => Number::Fractional(nanos as F / ONE_DAY_NANOS as F),
+        qtype::MONTH_LIST => {
+            match bounded(nanos.div_euclid(ONE_DAY_NANOS as i128), qinf_base::I as J) {
+                Number::Integral(days) => {
+                    Number::Integral(QDate::from_raw(days as I).month().raw() as J)
+                }
+                special => special,
+            }
+        }
+        qtype::DATE_LIST => bounded(nanos.div_euclid(ONE_DAY_NANOS as i128), qinf_base::I as J),
+        _ => bounded(nanos / unit_nanos(target), qinf_base::I as J),
+    }
+}
+
+//%% Encode %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Encode an element into a bool.
+fn to_bool(number: &Number) -> G {
+    match number {
+        Number::Integral(value) => (*value != 0) as G,
+        Number::Fractional(value) => (*value != 0.0) as G,
+        _ => 1,
+    }
+}
+
+/// Encode an element into an integral type. Out of range values wrap and floating point values are rounded.
+fn to_integral<T>(number: &Number, null: T, inf: T, ninf: T, wrap: fn(J) -> T) -> T {
+    match number {
+        Number::Null => null,
+        Number::Inf => inf,
+        Number::NInf => ninf,
+        Number::Integral(value) => wrap(*value),
+        Number::Fractional(value) => wrap(value.round() as J),
+    }
+}
+
+/// Encode an element into a byte. Null and infinities wrap as q does.
+fn to_byte(number: &Number) -> G {
+    to_integral(
+        number,
+        qnull_base::J as G,
+        qinf_base::J as G,
+        qninf_base::J as G,
+        |value| value as G,
+    )
+}
+
+/// Encode an element into a floating point value.
+fn to_float(number: &Number) -> F {
+    match number {
+        Number::Null => qnull_base::F,
+        Number::Inf => qinf_base::F,
+        Number::NInf => qninf_base::F,
+        Number::Integral(value) => *value as F,
+        Number::Fractional(value) => *value,
+    }
+}
+
+/// Encode elements into a list of a numeric list type.
+fn encode(qtype: i8, numbers: Vec<Number>) -> K {
+    let list = match qtype {
+        qtype::BOOL_LIST => k0_list::new(numbers.iter().map(to_bool).collect::<Vec<G>>()),
+        qtype::BYTE_LIST => k0_list::new(numbers.iter().map(to_byte).collect::<Vec<G>>()),
+        qtype::STRING => {
+            return K::new_string(
+                numbers
+                    .iter()
+                    .map(|number| to_byte(number) as char)
+                    .collect(),
+                qattribute::NONE,
+            )
+        }
+        qtype::SHORT_LIST => k0_list::new(
+            numbers
+                .iter()
+                .map(|number| {
+                    to_integral(
+                        number,
+                        qnull_base::H,
+                        qinf_base::H,
+                        qninf_base::H,
+                        |value| value as H,
+                    )
+                })
+                .collect::<Vec<H>>(),
+        ),
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => k0_list::new(
+            numbers
+                .iter()
+                .map(|number| {
+                    to_integral(
+                        number,
+                        qnull_base::I,
+                        qinf_base::I,
+                        qninf_base::I,
+                        |value| value as I,
+                    )
+                })
+                .collect::<Vec<I>>(),
+        ),
+        qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => k0_list::new(
+            numbers
+                .iter()
+                .map(|number| {
+                    to_integral(
+                        number,
+                        qnull_base::J,
+                        qinf_base::J,
+                        qninf_base::J,
+                        |value| value,
+                    )
+                })
+                .collect::<Vec<J>>(),
+        ),
+        qtype::REAL_LIST => k0_list::new(
+            numbers
+                .iter()
+                .map(|number| to_float(number) as E)
+                .collect::<Vec<E>>(),
+        ),
+        _ => k0_list::new(numbers.iter().map(to_float).collect::<Vec<F>>()),
+    };
+    K::new(qtype, qattribute::NONE, k0_inner::list(list))
+}
+
+/// Convert a list with one element into an atom.
+pub(crate) fn into_atom(list: K) -> K {
+    let value = match list.0.value {
+        k0_inner::string(string) => k0_inner::byte(string.chars().next().unwrap() as G),
+        k0_inner::list(k0_list::byte(list)) => k0_inner::byte(list[0]),
+        k0_inner::list(k0_list::short(list)) => k0_inner::short(list[0]),
+        k0_inner::list(k0_list::int(list)) => k0_inner::int(list[0]),
+        k0_inner::list(k0_list::long(list)) => k0_inner::long(list[0]),
+        k0_inner::list(k0_list::real(list)) => k0_inner::real(list[0]),
+        k0_inner::list(k0_list::float(list)) => k0_inner::float(list[0]),
+        _ => unreachable!(),
+    };
+    K::new(-list.0.qtype, qattribute::NONE, value)
+}
+
+/// Join atoms of the same type into a simple list.
+fn join_atoms(qtype: i8, atoms: &[K]) -> K {
+    match qtype {
+        qtype::GUID_LIST => K::new_guid_list(
+            atoms.iter().map(|atom| atom.get_guid().unwrap()).collect(),
+            qattribute::NONE,
+        ),
+        qtype::SYMBOL_LIST => K::new(
+            qtype::SYMBOL_LIST,
+            qattribute::NONE,
+            k0_inner::list(k0_list::new(
+                atoms
+                    .iter()
+                    .map(|atom| match &atom.0.value {
+                        k0_inner::symbol(symbol) => symbol.clone(),
+                        _ => unreachable!(),
+                    })
+                    .collect::<Vec<S>>(),
+            )),
+        ),
+        _ => encode(qtype, atoms.iter().flat_map(numbers).collect()),
+    }
+}
+
+//%% Parse %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Parse a text into an atom of a given type (`"J"$"42"` in q). Unparsable text becomes null.
+fn parse(text: &str, qtype: i8) -> K {
+    let trimmed = text.trim();
+    let special = |suffix: char| match trimmed.strip_suffix(suffix).unwrap_or(trimmed) {
+        "0N" | "0n" => Some(Number::Null),
+        "0W" | "0w" => Some(Number::Inf),
+        "-0W" | "-0w" => Some(Number::NInf),
+        _ => None,
+    };
+    let number = match qtype {
+        qtype::BOOL_ATOM => {
+            return K::new_bool(matches!(
+                trimmed.chars().next(),
+                Some('1' | 't' | 'T' | 'y' | 'Y')
+            ))
+        }
+        qtype::GUID_ATOM => return K::new_guid(parse_guid(trimmed).unwrap_or(qnull_base::U)),
+        qtype::BYTE_ATOM => {
+            let digits = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+            return K::new_byte(G::from_str_radix(digits, 16).unwrap_or(0));
+        }
+        qtype::SYMBOL_ATOM => return K::new_symbol(text),
+        qtype::TIMESTAMP_ATOM => return K::from(trimmed.parse().unwrap_or(QTimestamp::NULL)),
+        qtype::MONTH_ATOM => return K::from(trimmed.parse().unwrap_or(QMonth::NULL)),
+        qtype::DATE_ATOM => return K::from(trimmed.parse().unwrap_or(QDate::NULL)),
+        qtype::DATETIME_ATOM => return K::from(trimmed.parse().unwrap_or(QDateTime::NULL)),
+        qtype::TIMESPAN_ATOM => return K::from(trimmed.parse().unwrap_or(QTimespan::NULL)),
+        qtype::MINUTE_ATOM => return K::from(trimmed.parse().unwrap_or(QMinute::NULL)),
+        qtype::SECOND_ATOM => return K::from(trimmed.parse().unwrap_or(QSecond::NULL)),
+        qtype::TIME_ATOM => return K::from(trimmed.parse().unwrap_or(QTime::NULL)),
+        qtype::SHORT_ATOM => special('h').unwrap_or_else(|| {
+            let digits = trimmed.strip_suffix('h').unwrap_or(trimmed);
+            digits
+                .parse::<H>()
+                .map_or(Number::Null, |value| Number::Integral(value as J))
+        }),
+        qtype::INT_ATOM => special('i').unwrap_or_else(|| {
+            let digits = trimmed.strip_suffix('i').unwrap_or(trimmed);
+            digits
+                .parse::<I>()
+                .map_or(Number::Null, |value| Number::Integral(value as J))
+        }),
+        qtype::LONG_ATOM => special('j').unwrap_or_else(|| {
+            let digits = trimmed.strip_suffix('j').unwrap_or(trimmed);
+            digits.parse::<J>().map_or(Number::Null, Number::Integral)
+        }),
+        qtype::REAL_ATOM => special('e').unwrap_or_else(|| {
+            let digits = trimmed.strip_suffix('e').unwrap_or(trimmed);
+            digits.parse::<F>().map_or(Number::Null, fractional)
+        }),
+        _ => special('f').unwrap_or_else(|| {
+            let digits = trimmed.strip_suffix('f').unwrap_or(trimmed);
+            digits.parse::<F>().map_or(Number::Null, fractional)
+        }),
+    };
+    into_atom(encode(-qtype, vec![number]))
+}
+
+/// Parse a GUID such as `8c680a01-5a49-5aab-5a65-d4bfddb6a661`.
+fn parse_guid(text: &str) -> Option<U> {
+    let digits = text.replace('-', "");
+    if digits.len() != 32 || !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut guid = [0; 16];
+    for (i, byte) in guid.iter_mut().enumerate() {
+        *byte = G::from_str_radix(&digits[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(guid)
+}
@@ -0,0 +1,519 @@
+//! This module provides the parts of q IPC protocol shared by the asynchronous and the blocking clients, i.e.,
+//!  message types, framing of messages, compression and the handshake.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::serialize::ENCODING;
+use super::Result;
+use super::{qtype, K};
+use std::convert::TryInto;
+use std::time::Duration;
+use std::{env, io};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variable
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Message Type %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+pub mod qmsg_type {
+    //! This module provides a list of q message type used for IPC.
+    //!  The motivation to contain them in a module is to tie them up as related items rather
+    //!  than scattered values. Hence user should use these indicators with `qmsg_type::` prefix, e.g., `qmsg_type::asynchronous`.
+    //!
+    //! The example below handles messages in the order they arrive on one stream. To receive messages in one task
+    //!  while sending them in another, split the stream with [`QStream::into_split`](../struct.QStream.html#method.into_split).
+    //!
+    //! # Example
+    //! ```no_run
+    //! use kdbplus::ipc::*;
+    //!
+    //! // Print `K` object.
+    //! fn print(obj: &K) {
+    //!     println!("{}", obj);
+    //! }
+    //!
+    //! // Calculate something from two long arguments.
+    //! fn nonsense(arg1: i64, arg2: i64) -> i64 {
+    //!     arg1 * arg2
+    //! }
+    //!
+    //! #[tokio::main]
+    //! async fn main() -> Result<()> {
+    //!     // Connect to qprocess running on localhost:5000 via TCP
+    //!     let mut socket =
+    //!         QStream::connect(ConnectionMethod::TCP, "localhost", 5000_u16, "ideal:person").await?;
+    //!
+    //!     // Set a function which sends back a non-response message during its execution.
+    //!     socket
+    //!         .send_async_message(
+    //!             &"complex:{neg[.z.w](`print; \"counter\"); what: .z.w (`nonsense; 1; 2); what*100}",
+    //!         )
+    //!         .await?;
+    //!
+    //!     // Send a query `(`complex; ::)` without waiting for a response.
+    //!     socket
+    //!         .send_message(
+    //!             &K::new_compound_list(vec![K::new_symbol(String::from("complex")), K::new_null()]),
+    //!             qmsg_type::synchronous,
+    //!         )
+    //!         .await?;
+    //!
+    //!     // Receive an asynchronous call from the function.
+    //!     match socket.receive_message().await {
+    //!         Ok((qmsg_type::asynchronous, message)) => {
+    //!             println!("asynchronous call: {}", message);
+    //!             let list = message.as_vec::<K>().unwrap();
+    //!             if list[0].get_symbol().unwrap() == "print" {
+    //!                 print(&list[1])
+    //!             }
+    //!         }
+    //!         _ => unreachable!(),
+    //!     }
+    //!
+    //!     // Receive a synchronous call from the function.
+    //!     match socket.receive_message().await {
+    //!         Ok((qmsg_type::synchronous, message)) => {
+    //!             println!("synchronous call: {}", message);
+    //!             let list = message.as_vec::<K>().unwrap();
+    //!             if list[0].get_symbol().unwrap() == "nonsense" {
+    //!                 let res = nonsense(list[1].get_long().unwrap(), list[2].get_long().unwrap());
+    //!                 // Send bach a response.
+    //!                 socket
+    //!                     .send_message(&K::new_long(res), qmsg_type::response)
+    //!                     .await?;
+    //!             }
+    //!         }
+    //!         _ => unreachable!(),
+    //!     }
+    //!
+    //!     // Receive a final result.
+    //!     match socket.receive_message().await {
+    //!         Ok((qmsg_type::response, message)) => {
+    //!             println!("final: {}", message);
+    //!         }
+    //!         _ => unreachable!(),
+    //!     }
+    //!
+    //!     Ok(())
+    //! }
+    //!```
+    /// Used to send a message to q/kdb+ asynchronously.
+    pub const asynchronous: u8 = 0;
+    /// Used to send a message to q/kdb+ synchronously.
+    pub const synchronous: u8 = 1;
+    /// Used by q/kdb+ to identify a response for a synchronous query.
+    pub const response: u8 = 2;
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% ConnectionMethod %%//vvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Connection method to q/kdb+.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionMethod {
+    TCP = 0,
+    TLS = 1,
+    /// Unix domanin socket.
+    UDS = 2,
+}
+
+//%% QTimeouts %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Time limits of operations on `QStream`. `None` waits indefinitely, which is the default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QTimeouts {
+    /// Time limit to establish a TCP connection including name resolution or a Unix domain socket connection.
+    pub connect: Option<Duration>,
+    /// Time limit of the TLS handshake and the q authentication handshake.
+    pub handshake: Option<Duration>,
+    /// Time limit to receive a message.
+    pub read: Option<Duration>,
+    /// Time limit to send a message.
+    pub write: Option<Duration>,
+}
+
+//%% MessageHeader %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Header of q IPC data frame.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MessageHeader {
+    /// Ennoding.
+    /// - 0: Big Endian
+    /// - 1: Little Endian
+    pub(crate) encoding: u8,
+    /// Message type. One of followings:
+    /// - 0: Asynchronous
+    /// - 1: Synchronous
+    /// - 2: Response
+    pub(crate) message_type: u8,
+    /// Indicator of whether the message is compressed or not.
+    /// - 0: Uncompressed
+    /// - 1: Compressed
+    pub(crate) compressed: u8,
+    /// Reserved byte.
+    _unused: u8,
+    /// Total length of the uncompressed message.
+    pub(crate) length: u32,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% MessageHeader %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl MessageHeader {
+    /// Constructor.
+    pub(crate) fn new(encoding: u8, message_type: u8, compressed: u8, length: u32) -> Self {
+        MessageHeader {
+            encoding: encoding,
+            message_type: message_type,
+            compressed: compressed,
+            _unused: 0,
+            length: length,
+        }
+    }
+
+    /// Constructor from bytes.
+    pub(crate) fn from_bytes(bytes: [u8; 8]) -> Self {
+        let encoding = bytes[0];
+
+        let length = match encoding {
+            0 => u32::from_be_bytes(bytes[4..8].try_into().unwrap()),
+            _ => u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+        };
+
+        // Build header
+        MessageHeader::new(encoding, bytes[1], bytes[2], length)
+    }
+
+    /// Length of bytes for a header.
+    pub(crate) fn size() -> usize {
+        8
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Message %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Serialize a text query into q IPC bytes including a header.
+/// # Parameters
+/// - `text`: q command in a string form.
+/// - `message_type`: Message type. One of `qmsg_type`.
+pub(crate) fn encode_text(text: &str, message_type: u8) -> Vec<u8> {
+    //  Build header //--------------------------------/
+    // Message header + (type indicator of string + header of string type) + string length
+    let byte_message = text.as_bytes();
+    let message_length = byte_message.len() as u32;
+    let total_length = MessageHeader::size() as u32 + 6 + message_length;
+
+    let total_length_bytes = match ENCODING {
+        0 => total_length.to_be_bytes(),
+        _ => total_length.to_le_bytes(),
+    };
+
+    // encode, message type, 0x00 for compression and 0x00 for reserved.
+    // Do not compress string data because it is highly unlikely that the length of the string query
+    //  is greater than 2000.
+    let mut message = Vec::with_capacity(message_length as usize + MessageHeader::size());
+    message.extend_from_slice(&[ENCODING, message_type, 0, 0]);
+    // total body length
+    message.extend_from_slice(&total_length_bytes);
+    // vector type and 0x00 for attribute
+    message.extend_from_slice(&[qtype::STRING as u8, 0]);
+
+    //  Build body //---------------------------------/
+    let length_info = match ENCODING {
+        0 => message_length.to_be_bytes(),
+        _ => message_length.to_le_bytes(),
+    };
+
+    // length of vector(message)
+    message.extend_from_slice(&length_info);
+    // message
+    message.extend_from_slice(byte_message);
+
+    message
+}
+
+/// Serialize a functional query into q IPC bytes including a header. A message larger than 2000 bytes is
+///  compressed unless the connection is within the same host.
+/// # Parameters
+/// - `object`: Query in a functional form.
+/// - `message_type`: Message type. One of `qmsg_type`.
+/// - `is_local`: Flag of whether the connection is within the same host.
+pub(crate) fn encode_object(object: &K, message_type: u8, is_local: bool) -> Vec<u8> {
+    //  Build header //--------------------------------/
+    // Message header + encoded data size
+    let mut byte_message = object.q_ipc_encode();
+    let message_length = byte_message.len();
+    let total_length = (MessageHeader::size() + message_length) as u32;
+
+    let total_length_bytes = match ENCODING {
+        0 => total_length.to_be_bytes(),
+        _ => total_length.to_le_bytes(),
+    };
+
+    // Compression is trigerred when entire message size is more than 2000 bytes
+    //  and the connection is with outseide.
+    if message_length > 1992 && !is_local {
+        // encode, message type, 0x00 for compression, 0x00 for reserved and 0x00000000 for total size
+        let mut message = Vec::with_capacity(message_length + 8);
+        message.extend_from_slice(&[ENCODING, message_type as u8, 0, 0, 0, 0, 0, 0]);
+        message.append(&mut byte_message);
+        // Try to encode entire message.
+        match compress(message) {
+            (true, compressed) => {
+                // Message was compressed
+                compressed
+            }
+            (false, mut uncompressed) => {
+                // Message was not compressed.
+                // Write original total data size.
+                uncompressed[4..8].copy_from_slice(&total_length_bytes);
+                uncompressed
+            }
+        }
+    } else {
+        // encode, message type, 0x00 for compression and 0x00 for reserved
+        let mut message = Vec::with_capacity(message_length + MessageHeader::size());
+        message.extend_from_slice(&[ENCODING, message_type as u8, 0, 0]);
+        // Total length of body
+        message.extend_from_slice(&total_length_bytes);
+        message.append(&mut byte_message);
+        message
+    }
+}
+
+/// Decompress a received body if necessary and parse it as `K`. The message type is stored in the first
+///  returned value.
+/// # Parameters
+/// - `header`: Header of the message.
+/// - `body`: Bytes following the header.
+pub(crate) fn decode_message(header: &MessageHeader, mut body: Vec<u8>) -> (u8, K) {
+    // Decompress if necessary
+    if header.compressed == 0x01 {
+        body = decompress(body, header.encoding);
+    }
+    (header.message_type, K::q_ipc_decode(&body, header.encoding))
+}
+
+/// Bytes sent by a client in the handshake, i.e., a credential followed by a capability and a null byte.
+pub(crate) fn handshake_request(credential: &str, capability: u8) -> Vec<u8> {
+    let mut bytes = credential.as_bytes().to_vec();
+    bytes.extend_from_slice(&[capability, 0]);
+    bytes
+}
+
+/// Return an error if a stream or its half is poisoned.
+pub(crate) fn check_poisoned(poisoned: bool) -> Result<()> {
+    match poisoned {
+        true => Err(io::Error::new(
+            io::ErrorKind::NotConnected,
+            "stream is poisoned by a previous timeout",
+        )
+        .into()),
+        false => Ok(()),
+    }
+}
+
+/// Build a path of a socket file.
+pub(crate) fn create_sockfile_path(port: u16) -> Result<String> {
+    // Create file path
+    let udspath = match env::var("QUDSPATH") {
+        Ok(dir) => format!("{}/kx.{}", dir, port),
+        Err(_) => format!("/tmp/kx.{}", port),
+    };
+
+    Ok(udspath)
+}
+
+//%% Compression %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Compress body. The combination of serializing the data and compressing will result in
+/// the same output as shown in the q language by using the -18! function e.g.
+/// serializing 2000 bools set to true, then compressing, will have the same output as `-18!2000#1b`.
+/// # Parameter
+/// - `raw`: Serialized message.
+/// - `encode`: `0` if Big Endian; `1` if Little Endian.
+pub(crate) fn compress(raw: Vec<u8>) -> (bool, Vec<u8>) {
+    let mut i = 0_u8;
+    let mut f = 0_u8;
+    let mut h0 = 0_usize;
+    let mut h = 0_usize;
+    let mut g: bool;
+    let mut compressed: Vec<u8> = Vec::with_capacity((raw.len()) / 2);
+    // Assure that vector is filled with 0
+    compressed.resize((raw.len()) / 2, 0_u8);
+
+    // Start index of compressed body
+    // 12 bytes are reserved for the header + size of raw bytes
+    let mut c = 12;
+    let mut d = c;
+    let e = compressed.len();
+    let mut p = 0_usize;
+    let mut q: usize;
+    let mut r: usize;
+    let mut s0 = 0_usize;
+
+    // Body starts from index 8
+    let mut s = 8_usize;
+    let t = raw.len();
+    let mut a = [0_i32; 256];
+
+    // Copy encode, message type, compressed and reserved
+    compressed[0..4].copy_from_slice(&raw[0..4]);
+    // Set compressed flag
+    compressed[2] = 1;
+
+    // Write size of raw bytes including a header
+    let raw_size = match ENCODING {
+        0 => (t as u32).to_be_bytes(),
+        _ => (t as u32).to_le_bytes(),
+    };
+    compressed[8..12].copy_from_slice(&raw_size);
+
+    while s < t {
+        if i == 0 {
+            if d > e - 17 {
+                // Early return when compressing to less than half failed
+                return (false, raw);
+            }
+            i = 1;
+            compressed[c] = f;
+            c = d;
+            d += 1;
+            f = 0;
+        }
+        g = s > t - 3;
+        if !g {
+            h = (raw[s] ^ raw[s + 1]) as usize;
+            p = a[h] as usize;
+            g = (0 == p) || (0 != (raw[s] ^ raw[p]));
+        }
+        if 0 < s0 {
+            a[h0] = s0 as i32;
+            s0 = 0;
+        }
+        if g {
+            h0 = h;
+            s0 = s;
+            compressed[d] = raw[s];
+            d += 1;
+            s += 1;
+        } else {
+            a[h] = s as i32;
+            f |= i;
+            p += 2;
+            s += 2;
+            r = s;
+            q = if s + 255 > t { t } else { s + 255 };
+            while (s < q) && (raw[p] == raw[s]) {
+                s += 1;
+                if s < q {
+                    p += 1;
+                }
+            }
+            compressed[d] = h as u8;
+            d += 1;
+            compressed[d] = (s - r) as u8;
+            d += 1;
+        }
+        i = i.wrapping_mul(2);
+    }
+    compressed[c] = f;
+    // Final compressed data size
+    let compressed_size = match ENCODING {
+        0 => (d as u32).to_be_bytes(),
+        _ => (d as u32).to_le_bytes(),
+    };
+    compressed[4..8].copy_from_slice(&compressed_size);
+    let _ = compressed.split_off(d);
+    (true, compressed)
+}
+
+/// Decompress body. The combination of decompressing and deserializing the data
+///  will result in the same output as shown in the q language by using the `-19!` function.
+/// # Parameter
+/// - `compressed`: Compressed serialized message.
+/// - `encoding`:
+///   - `0`: Big Endian
+///   - `1`: Little Endian.
+pub(crate) fn decompress(compressed: Vec<u8>, encoding: u8) -> Vec<u8> {
+    try_decompress(&compressed, encoding).expect("compressed message is corrupted")
+}
+
+/// Decompress body as [`decompress`] does checking bounds of both buffers. `None` if the compressed data
+///  is corrupted or truncated.
+pub(crate) fn try_decompress(compressed: &[u8], encoding: u8) -> Option<Vec<u8>> {
+    let mut n = 0;
+    let mut r: usize;
+    let mut f = 0_usize;
+
+    // Header has already been removed.
+    // Start index of decompressed bytes is 0
+    let mut s = 0_usize;
+    let mut p = s;
+    let mut i = 0_usize;
+
+    // Subtract 8 bytes from decoded bytes size as 8 bytes have already been taken as header
+    let size: [u8; 4] = compressed.get(0..4)?.try_into().unwrap();
+    let size = match encoding {
+        0 => i32::from_be_bytes(size),
+        _ => i32::from_le_bytes(size),
+    };
+    let size = usize::try_from(size.checked_sub(8)?).ok()?;
+    // Assure that vector is filled with 0
+    let mut decompressed = vec![0_u8; size];
+
+    // Start index of compressed body.
+    // 8 bytes have already been removed as header
+    let mut d = 4;
+    let mut aa = [0_usize; 256];
+    while s < decompressed.len() {
+        if i == 0 {
+            f = *compressed.get(d)? as usize;
+            d += 1;
+            i = 1;
+        }
+        if (f & i) != 0 {
+            r = aa[*compressed.get(d)? as usize];
+            n = *compressed.get(d + 1)? as usize;
+            d += 2;
+            // Copy 2 + n bytes from a preceding position. `r` is less than `s`.
+            if s + 2 + n > decompressed.len() {
+                return None;
+            }
+            for m in 0..2 + n {
+                decompressed[s + m] = decompressed[r + m];
+            }
+            s += 2;
+        } else {
+            decompressed[s] = *compressed.get(d)?;
+            s += 1;
+            d += 1;
+        }
+        while p < s - 1 {
+            aa[(decompressed[p] ^ decompressed[p + 1]) as usize] = p;
+            p += 1;
+        }
+        if (f & i) != 0 {
+            s += n;
+            p = s;
+        }
+        i *= 2;
+        if i == 256 {
+            i = 0;
+        }
+    }
+    Some(decompressed)
+}
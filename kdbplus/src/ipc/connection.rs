@@ -2,166 +2,116 @@
 // >> Load Libraries
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
-use super::serialize::ENCODING;
+use super::codec::{
+    check_poisoned, create_sockfile_path, decode_message, encode_object, encode_text,
+    handshake_request, MessageHeader,
+};
+use super::error::Error;
+use super::tls::{self, TlsAcceptor, TlsStream};
 use super::Result;
-use super::{qtype, K};
+use super::{qmsg_type, AccountFile, Authenticator, ConnectionMethod, QTimeouts, TlsConfig, K};
 use async_trait::async_trait;
-use io::BufRead;
-use once_cell::sync::Lazy;
-use sha1_smol::Sha1;
-use std::collections::HashMap;
-use std::convert::TryInto;
-use std::net::{IpAddr, Ipv4Addr};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::Path;
-use std::{env, fs, io, str};
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{TcpListener, TcpStream};
+use std::result::Result as StdResult;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fmt, io, str};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
 #[cfg(unix)]
 use tokio::net::{UnixListener, UnixStream};
-use tokio_native_tls::native_tls::{
-    Identity, TlsAcceptor as TlsAcceptorInner, TlsConnector as TlsConnectorInner,
-};
-use tokio_native_tls::{TlsAcceptor, TlsConnector, TlsStream};
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::time;
 use trust_dns_resolver::TokioAsyncResolver;
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Global Variable
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
-//%% QStream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
-
-pub mod qmsg_type {
-    //! This module provides a list of q message type used for IPC.
-    //!  The motivation to contain them in a module is to tie them up as related items rather
-    //!  than scattered values. Hence user should use these indicators with `qmsg_type::` prefix, e.g., `qmsg_type::asynchronous`.
-    //!
-    //! # Example
-    //! ```no_run
-    //! use kdbplus::ipc::*;
-    //!
-    //! // Print `K` object.
-    //! fn print(obj: &K) {
-    //!     println!("{}", obj);
-    //! }
-    //!
-    //! // Calculate something from two long arguments.
-    //! fn nonsense(arg1: i64, arg2: i64) -> i64 {
-    //!     arg1 * arg2
-    //! }
-    //!
-    //! #[tokio::main]
-    //! async fn main() -> Result<()> {
-    //!     // Connect to qprocess running on localhost:5000 via TCP
-    //!     let mut socket =
-    //!         QStream::connect(ConnectionMethod::TCP, "localhost", 5000_u16, "ideal:person").await?;
-    //!
-    //!     // Set a function which sends back a non-response message during its execution.
-    //!     socket
-    //!         .send_async_message(
-    //!             &"complex:{neg[.z.w](`print; \"counter\"); what: .z.w (`nonsense; 1; 2); what*100}",
-    //!         )
-    //!         .await?;
-    //!
-    //!     // Send a query `(`complex; ::)` without waiting for a response.
-    //!     socket
-    //!         .send_message(
-    //!             &K::new_compound_list(vec![K::new_symbol(String::from("complex")), K::new_null()]),
-    //!             qmsg_type::synchronous,
-    //!         )
-    //!         .await?;
-    //!
-    //!     // Receive an asynchronous call from the function.
-    //!     match socket.receive_message().await {
-    //!         Ok((qmsg_type::asynchronous, message)) => {
-    //!             println!("asynchronous call: {}", message);
-    //!             let list = message.as_vec::<K>().unwrap();
-    //!             if list[0].get_symbol().unwrap() == "print" {
-    //!                 print(&list[1])
-    //!             }
-    //!         }
-    //!         _ => unreachable!(),
-    //!     }
-    //!
-    //!     // Receive a synchronous call from the function.
-    //!     match socket.receive_message().await {
-    //!         Ok((qmsg_type::synchronous, message)) => {
-    //!             println!("synchronous call: {}", message);
-    //!             let list = message.as_vec::<K>().unwrap();
-    //!             if list[0].get_symbol().unwrap() == "nonsense" {
-    //!                 let res = nonsense(list[1].get_long().unwrap(), list[2].get_long().unwrap());
-    //!                 // Send bach a response.
-    //!                 socket
-    //!                     .send_message(&K::new_long(res), qmsg_type::response)
-    //!                     .await?;
-    //!             }
-    //!         }
-    //!         _ => unreachable!(),
-    //!     }
-    //!
-    //!     // Receive a final result.
-    //!     match socket.receive_message().await {
-    //!         Ok((qmsg_type::response, message)) => {
-    //!             println!("final: {}", message);
-    //!         }
-    //!         _ => unreachable!(),
-    //!     }
-    //!
-    //!     Ok(())
-    //! }
-    //!```
-    /// Used to send a message to q/kdb+ asynchronously.
-    pub const asynchronous: u8 = 0;
-    /// Used to send a message to q/kdb+ synchronously.
-    pub const synchronous: u8 = 1;
-    /// Used by q/kdb+ to identify a response for a synchronous query.
-    pub const response: u8 = 2;
-}
-
-//%% QStream Acceptor %%//vvvvvvvvvvvvvvvvvvvvvvvvvvv/
-
-/// Map from user name to password hashed with SHA1.
-const ACCOUNTS: Lazy<HashMap<String, String>> = Lazy::new(|| {
-    // Map from user to password
-    let mut map: HashMap<String, String> = HashMap::new();
-    // Open credential file
-    let file = fs::OpenOptions::new()
-        .read(true)
-        .open(env::var("KDBPLUS_ACCOUNT_FILE").expect("KDBPLUS_ACCOUNT_FILE is not set"))
-        .expect("failed to open account file");
-    let mut reader = io::BufReader::new(file);
-    let mut line = String::new();
-    loop {
-        match reader.read_line(&mut line) {
-            Ok(0) => break,
-            Ok(_) => {
-                let credential = line.as_str().split(':').collect::<Vec<&str>>();
-                let mut password = credential[1];
-                if password.ends_with('\n') {
-                    password = &password[0..password.len() - 1];
-                }
-                map.insert(credential[0].to_string(), password.to_string());
-                line.clear();
-            }
-            _ => unreachable!(),
-        }
-    }
-    map
-});
-
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Structs
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
-//%% ConnectionMethod %%//vvvvvvvvvvvvvvvvvvvvvvvvvvv/
+//%% Compression %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Policy to compress messages larger than 2000 bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Compress unless the connection is within the same host, which is the behaviour of q.
+    #[default]
+    Auto,
+    /// Compress regardless of locality of the connection.
+    Always,
+    /// Never compress.
+    Never,
+}
+
+//%% QStreamBuilder %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
-/// Connection method to q/kdb+.
-pub enum ConnectionMethod {
-    TCP = 0,
-    TLS = 1,
-    /// Unix domanin socket.
-    UDS = 2,
+/// Builder of `QStream` collecting connection options. [`QStream::connect`] and [`QStream::accept`] are
+///  shorthands of this builder with default options.
+/// # Example
+/// ```no_run
+/// use kdbplus::ipc::*;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let mut socket = QStreamBuilder::new(ConnectionMethod::TCP, "localhost", 5000)
+///         .credential("ideal:person")
+///         .nodelay(true)
+///         .keepalive(true)
+///         .compression(Compression::Always)
+///         .timeouts(QTimeouts {
+///             connect: Some(Duration::from_secs(3)),
+///             ..QTimeouts::default()
+///         })
+///         .connect()
+///         .await?;
+///     println!("{}", socket.send_sync_message(&"til 3").await?);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct QStreamBuilder {
+    /// Connection method.
+    method: ConnectionMethod,
+    /// Hostname or IP address of the target q process or this listener. Ignored for Unix domain socket.
+    host: String,
+    /// Port of the target q process or this listener.
+    port: u16,
+    /// Credential in the form of `username:password` sent by a client.
+    credential: String,
+    /// Time limits of operations.
+    timeouts: QTimeouts,
+    /// Whether to set `TCP_NODELAY`.
+    nodelay: bool,
+    /// Whether to set `SO_KEEPALIVE`.
+    keepalive: bool,
+    /// Size of the send buffer (`SO_SNDBUF`).
+    send_buffer_size: Option<u32>,
+    /// Size of the receive buffer (`SO_RCVBUF`).
+    recv_buffer_size: Option<u32>,
+    /// Capability byte sent by a client in the handshake.
+    capability: Option<u8>,
+    /// Compression policy.
+    compression: Compression,
+    /// Path of the Unix domain socket file.
+    uds_path: Option<String>,
+    /// TLS settings of a client.
+    tls: TlsConfig,
+    /// Check of a credential sent by a client to `accept`.
+    authenticator: Option<SharedAuthenticator>,
 }
 
+//%% SharedAuthenticator %%//vvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Authenticator shared by connections accepted with the same builder.
+#[derive(Clone)]
+struct SharedAuthenticator(Arc<dyn Authenticator>);
+
 //%% Query %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
 /// Feature of query object.
@@ -182,7 +132,7 @@ pub trait Query: Send + Sync {
 
 /// Features which streams communicating with q must have.
 #[async_trait]
-trait QStreamInner: Send + Sync {
+trait QStreamInner: AsyncRead + AsyncWrite + Unpin + Send + Sync {
     /// Shutdown underlying stream.
     async fn shutdown(&mut self, is_server: bool) -> Result<()>;
     /// Send a message with a specified message type without waiting for a response.
@@ -213,6 +163,10 @@ trait QStreamInner: Send + Sync {
     /// Receive a message from a remote q process. The received message is parsed as `K` and message type is
     ///  stored in the first returned value.
     async fn receive_message(&mut self) -> Result<(u8, K)>;
+    /// Wait for the first byte of the next message. Nothing is lost if this is cancelled.
+    async fn receive_first_byte(&mut self) -> Result<u8>;
+    /// Receive the rest of a message whose first byte was returned by `receive_first_byte`.
+    async fn receive_message_after(&mut self, first: u8) -> Result<(u8, K)>;
 }
 
 //%% QStream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
@@ -234,32 +188,58 @@ pub struct QStream {
     /// - `true`: Connection within the same host.
     /// - `false`: Connection with outseide.
     local: bool,
+    /// Time limits of read and write.
+    timeouts: QTimeouts,
+    /// Indicator of whether a read or write timed out. The stream is unusable afterwards because a partially
+    ///  sent or received message cannot be recovered.
+    poisoned: bool,
+    /// Slot of `QListener` held while the stream is alive.
+    slot: Option<OwnedSemaphorePermit>,
+}
+
+//%% QReader %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Receiving half of a [`QStream`] created by [`QStream::into_split`]. It can be moved to a task other than
+///  the one holding the [`QWriter`].
+pub struct QReader {
+    /// Reading half of the underlying stream.
+    stream: ReadHalf<Box<dyn QStreamInner>>,
+    /// Connection method.
+    method: ConnectionMethod,
+    /// Indicator of whether the stream is an acceptor or client.
+    listener: bool,
+    /// Indicator of whether the connection is within the same host.
+    local: bool,
+    /// Time limits inherited from the stream. Only `read` is used.
+    timeouts: QTimeouts,
+    /// Indicator of whether a read timed out.
+    poisoned: bool,
+    /// Slot of `QListener` shared with the writer.
+    slot: Option<Arc<OwnedSemaphorePermit>>,
 }
 
-//%% MessageHeader %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
-
-/// Header of q IPC data frame.
-#[derive(Clone, Copy, Debug)]
-struct MessageHeader {
-    /// Ennoding.
-    /// - 0: Big Endian
-    /// - 1: Little Endian
-    encoding: u8,
-    /// Message type. One of followings:
-    /// - 0: Asynchronous
-    /// - 1: Synchronous
-    /// - 2: Response
-    message_type: u8,
-    /// Indicator of whether the message is compressed or not.
-    /// - 0: Uncompressed
-    /// - 1: Compressed
-    compressed: u8,
-    /// Reserved byte.
-    _unused: u8,
-    /// Total length of the uncompressed message.
-    length: u32,
+//%% QWriter %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Sending half of a [`QStream`] created by [`QStream::into_split`].
+pub struct QWriter {
+    /// Writing half of the underlying stream.
+    stream: WriteHalf<Box<dyn QStreamInner>>,
+    /// Indicator of whether the connection is within the same host.
+    local: bool,
+    /// Time limits inherited from the stream. Only `write` is used.
+    timeouts: QTimeouts,
+    /// Indicator of whether a write timed out.
+    poisoned: bool,
+    /// Slot of `QListener` shared with the reader.
+    slot: Option<Arc<OwnedSemaphorePermit>>,
 }
 
+//%% ReuniteError %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Error returned by [`QReader::reunite`] for halves which are not split from the same stream. The halves
+///  are given back.
+pub struct ReuniteError(pub QReader, pub QWriter);
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Implementation
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -270,39 +250,7 @@ struct MessageHeader {
 #[async_trait]
 impl Query for &str {
     async fn serialize(&self, message_type: u8, _: bool) -> Vec<u8> {
-        //  Build header //--------------------------------/
-        // Message header + (type indicator of string + header of string type) + string length
-        let byte_message = self.as_bytes();
-        let message_length = byte_message.len() as u32;
-        let total_length = MessageHeader::size() as u32 + 6 + message_length;
-
-        let total_length_bytes = match ENCODING {
-            0 => total_length.to_be_bytes(),
-            _ => total_length.to_le_bytes(),
-        };
-
-        // encode, message type, 0x00 for compression and 0x00 for reserved.
-        // Do not compress string data because it is highly unlikely that the length of the string query
-        //  is greater than 2000.
-        let mut message = Vec::with_capacity(message_length as usize + MessageHeader::size());
-        message.extend_from_slice(&[ENCODING, message_type, 0, 0]);
-        // total body length
-        message.extend_from_slice(&total_length_bytes);
-        // vector type and 0x00 for attribute
-        message.extend_from_slice(&[qtype::STRING as u8, 0]);
-
-        //  Build body //---------------------------------/
-        let length_info = match ENCODING {
-            0 => message_length.to_be_bytes(),
-            _ => message_length.to_le_bytes(),
-        };
-
-        // length of vector(message)
-        message.extend_from_slice(&length_info);
-        // message
-        message.extend_from_slice(byte_message);
-
-        message
+        encode_text(self, message_type)
     }
 }
 
@@ -310,46 +258,7 @@ impl Query for &str {
 #[async_trait]
 impl Query for K {
     async fn serialize(&self, message_type: u8, is_local: bool) -> Vec<u8> {
-        //  Build header //--------------------------------/
-        // Message header + encoded data size
-        let mut byte_message = self.q_ipc_encode();
-        let message_length = byte_message.len();
-        let total_length = (MessageHeader::size() + message_length) as u32;
-
-        let total_length_bytes = match ENCODING {
-            0 => total_length.to_be_bytes(),
-            _ => total_length.to_le_bytes(),
-        };
-
-        // Compression is trigerred when entire message size is more than 2000 bytes
-        //  and the connection is with outseide.
-        if message_length > 1992 && !is_local {
-            // encode, message type, 0x00 for compression, 0x00 for reserved and 0x00000000 for total size
-            let mut message = Vec::with_capacity(message_length + 8);
-            message.extend_from_slice(&[ENCODING, message_type as u8, 0, 0, 0, 0, 0, 0]);
-            message.append(&mut byte_message);
-            // Try to encode entire message.
-            match compress(message).await {
-                (true, compressed) => {
-                    // Message was compressed
-                    return compressed;
-                }
-                (false, mut uncompressed) => {
-                    // Message was not compressed.
-                    // Write original total data size.
-                    uncompressed[4..8].copy_from_slice(&total_length_bytes);
-                    return uncompressed;
-                }
-            }
-        } else {
-            // encode, message type, 0x00 for compression and 0x00 for reserved
-            let mut message = Vec::with_capacity(message_length + MessageHeader::size());
-            message.extend_from_slice(&[ENCODING, message_type as u8, 0, 0]);
-            // Total length of body
-            message.extend_from_slice(&total_length_bytes);
-            message.append(&mut byte_message);
-            return message;
-        }
+        encode_object(self, message_type, is_local)
     }
 }
 
@@ -368,9 +277,17 @@ impl QStream {
             method: method,
             listener: is_listener,
             local: is_local,
+            timeouts: QTimeouts::default(),
+            poisoned: false,
+            slot: None,
         }
     }
 
+    /// Hold a slot of `QListener` until the stream is dropped.
+    pub(crate) fn hold_slot(&mut self, slot: OwnedSemaphorePermit) {
+        self.slot = Some(slot);
+    }
+
     /// Connect to q/kdb+ specifying a connection method, destination host, destination port and access credential.
     /// # Parameters
     /// - `method`: Connection method. One of followings:
@@ -424,39 +341,56 @@ impl QStream {
         port: u16,
         credential: &str,
     ) -> Result<Self> {
-        match method {
-            ConnectionMethod::TCP => {
-                let stream = connect_tcp(host, port, credential).await?;
-                let is_local = match host {
-                    "localhost" | "127.0.0.1" => true,
-                    _ => false,
-                };
-                Ok(QStream::new(
-                    Box::new(stream),
-                    ConnectionMethod::TCP,
-                    false,
-                    is_local,
-                ))
-            }
-            ConnectionMethod::TLS => {
-                let stream = connect_tls(host, port, credential).await?;
-                Ok(QStream::new(
-                    Box::new(stream),
-                    ConnectionMethod::TLS,
-                    false,
-                    false,
-                ))
-            }
-            ConnectionMethod::UDS => {
-                let stream = connect_uds(port, credential).await?;
-                Ok(QStream::new(
-                    Box::new(stream),
-                    ConnectionMethod::UDS,
-                    false,
-                    true,
-                ))
-            }
-        }
+        Self::connect_with_timeouts(method, host, port, credential, QTimeouts::default()).await
+    }
+
+    /// Connect to q/kdb+ with time limits. `timeouts.connect` and `timeouts.handshake` apply to this call and
+    ///  `timeouts.read` and `timeouts.write` are kept for subsequent operations.
+    /// # Parameters
+    /// - `method`: Connection method.
+    /// - `host`: Hostname or IP address of the target q process. Empty `str` for Unix domain socket.
+    /// - `port`: Port of the target q process.
+    /// - `credential`: Credential in the form of `username:password` to connect to the target q process.
+    /// - `timeouts`: Time limits of operations.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::ipc::error::Error;
+    /// use kdbplus::ipc::*;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let timeouts = QTimeouts {
+    ///         connect: Some(Duration::from_secs(3)),
+    ///         handshake: Some(Duration::from_secs(3)),
+    ///         read: Some(Duration::from_secs(60)),
+    ///         write: Some(Duration::from_secs(10)),
+    ///     };
+    ///     let mut socket =
+    ///         QStream::connect_with_timeouts(ConnectionMethod::TCP, "localhost", 5000, "ideal:person", timeouts)
+    ///             .await?;
+    ///
+    ///     // Override the read time limit for a heavy query.
+    ///     match socket.send_sync_message_timeout(&"count select from trade", Duration::from_secs(600)).await {
+    ///         Ok(result) => println!("count: {}", result),
+    ///         Err(Error::Timeout(_)) => assert!(socket.is_poisoned()),
+    ///         Err(error) => return Err(error),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn connect_with_timeouts(
+        method: ConnectionMethod,
+        host: &str,
+        port: u16,
+        credential: &str,
+        timeouts: QTimeouts,
+    ) -> Result<Self> {
+        QStreamBuilder::new(method, host, port)
+            .credential(credential)
+            .timeouts(timeouts)
+            .connect()
+            .await
     }
 
     /// Accept connection and does handshake.
@@ -509,88 +443,16 @@ impl QStream {
     /// - TLS acceptor sets `.kdbplus.close_tls_connection_` on q clien via an asynchronous message. This function is necessary to close
     ///  the socket from the server side without crashing server side application.
     /// - TLS acceptor and UDS acceptor use specific environmental variables to work. See the [Environmental Variable](../ipc/index.html#environmentl-variables) section for details.
+    /// - The listener is dropped after the first authenticated client. Use [`QListener`](struct.QListener.html) to serve multiple clients.
     pub async fn accept(method: ConnectionMethod, host: &str, port: u16) -> Result<Self> {
-        match method {
-            ConnectionMethod::TCP => {
-                // Bind to the endpoint.
-                let listener = TcpListener::bind(&format!("{}:{}", host, port)).await?;
-                // Listen to the endpoint.
-                let (mut socket, ip_address) = listener.accept().await?;
-                // Read untill null bytes and send back capacity.
-                while let Err(_) = read_client_input(&mut socket).await {
-                    // Continue to listen in case of error.
-                    socket = listener.accept().await?.0;
-                }
-                // Check if the connection is local
-                Ok(QStream::new(
-                    Box::new(socket),
-                    ConnectionMethod::TCP,
-                    true,
-                    ip_address.ip() == IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
-                ))
-            }
-            ConnectionMethod::TLS => {
-                // Bind to the endpoint.
-                let listener = TcpListener::bind(&format!("{}:{}", host, port)).await?;
-                // Check if key exists and decode an identity with a given password.
-                let identity = build_identity_from_cert().await?;
-                // Build TLS acceptor.
-                let tls_acceptor = TlsAcceptor::from(TlsAcceptorInner::new(identity).unwrap());
-                // Listen to the endpoint.
-                let (mut socket, _) = listener.accept().await?;
-                // TLS processing.
-                let mut tls_socket = tls_acceptor
-                    .accept(socket)
-                    .await
-                    .expect("failed to accept TLS connection");
-                // Read untill null bytes and send back a capacity.
-                while let Err(_) = read_client_input(&mut tls_socket).await {
-                    // Continue to listen in case of error.
-                    socket = listener.accept().await?.0;
-                    tls_socket = tls_acceptor
-                        .accept(socket)
-                        .await
-                        .expect("failed to accept TLS connection");
-                }
-                // TLS is always a remote connection
-                let mut qstream = QStream::new(
-                    Box::new(TlsStream::from(tls_socket)),
-                    ConnectionMethod::TCP,
-                    true,
-                    false,
-                );
-                // In order to close the connection from the server side, it needs to tell a client to close the connection.
-                // The `kdbplus_close_tls_connection_` will be called from the server at shutdown.
-                qstream
-                    .send_async_message(&".kdbplus.close_tls_connection_:{[] hclose .z.w;}")
-                    .await?;
-                Ok(qstream)
-            }
-            ConnectionMethod::UDS => {
-                // uild a sockt file path.
-                let uds_path = create_sockfile_path(port)?;
-                let abstract_sockfile_ = format!("\x00{}", uds_path);
-                let abstract_sockfile = Path::new(&abstract_sockfile_);
-                // Bind to the file
-                let listener = UnixListener::bind(&abstract_sockfile).unwrap();
-                // Listen to the endpoint
-                let (mut socket, _) = listener.accept().await?;
-                // Read untill null bytes and send back capacity.
-                while let Err(_) = read_client_input(&mut socket).await {
-                    // Continue to listen in case of error.
-                    socket = listener.accept().await?.0;
-                }
-                // UDS is always a local connection
-                Ok(QStream::new(Box::new(socket), method, true, true))
-            }
-        }
+        QStreamBuilder::new(method, host, port).accept().await
     }
 
     /// Shutdown the socket for a q process.
     /// # Example
     /// See the example of [`connect`](#method.connect).
     pub async fn shutdown(mut self) -> Result<()> {
-        self.stream.shutdown(self.listener).await
+        QStreamInner::shutdown(self.stream.as_mut(), self.listener).await
     }
 
     /// Send a message with a specified message type without waiting for a response even for a synchronous message.
@@ -606,9 +468,14 @@ impl QStream {
     /// # Example
     /// See the example of [`connect`](#method.connect).
     pub async fn send_message(&mut self, message: &dyn Query, message_type: u8) -> Result<()> {
-        self.stream
-            .send_message(message, message_type, self.local)
-            .await
+        self.check_poisoned()?;
+        let result = with_timeout(
+            "write",
+            self.timeouts.write,
+            self.stream.send_message(message, message_type, self.local),
+        )
+        .await;
+        self.poison_on_timeout(result)
     }
 
     /// Send a message asynchronously.
@@ -619,7 +486,14 @@ impl QStream {
     /// # Example
     /// See the example of [`connect`](#method.connect).
     pub async fn send_async_message(&mut self, message: &dyn Query) -> Result<()> {
-        self.stream.send_async_message(message, self.local).await
+        self.check_poisoned()?;
+        let result = with_timeout(
+            "write",
+            self.timeouts.write,
+            self.stream.send_async_message(message, self.local),
+        )
+        .await;
+        self.poison_on_timeout(result)
     }
 
     /// Send a message synchronously.
@@ -632,7 +506,42 @@ impl QStream {
     /// # Example
     /// See the example of [`connect`](#method.connect).
     pub async fn send_sync_message(&mut self, message: &dyn Query) -> Result<K> {
-        self.stream.send_sync_message(message, self.local).await
+        self.send_message(message, qmsg_type::synchronous).await?;
+        // Receive a response. If message type is not response it returns an error.
+        match self.receive_message().await? {
+            (qmsg_type::response, response) => Ok(response),
+            (_, message) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected a response: {}", message),
+            )
+            .into()),
+        }
+    }
+
+    /// Send a message synchronously and wait for the response at most `timeout` instead of the read and write
+    ///  time limits of the stream.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    /// - `timeout`: Time limit of the whole round trip.
+    /// # Errors
+    /// `Error::Timeout("query")` if the response does not arrive in time. The stream is poisoned then.
+    /// # Example
+    /// See the example of [`connect_with_timeouts`](#method.connect_with_timeouts).
+    pub async fn send_sync_message_timeout(
+        &mut self,
+        message: &dyn Query,
+        timeout: Duration,
+    ) -> Result<K> {
+        self.check_poisoned()?;
+        let result = with_timeout(
+            "query",
+            Some(timeout),
+            self.stream.send_sync_message(message, self.local),
+        )
+        .await;
+        self.poison_on_timeout(result)
     }
 
     /// Receive a message from a remote q process. The received message is parsed as `K` and message type is
@@ -640,7 +549,51 @@ impl QStream {
     /// # Example
     /// See the example of [`accept`](#method.accept).
     pub async fn receive_message(&mut self) -> Result<(u8, K)> {
-        self.stream.receive_message().await
+        let timeout = self.timeouts.read;
+        self.receive_message_within(timeout).await
+    }
+
+    /// Receive a message waiting at most `timeout` instead of the read time limit of the stream.
+    /// # Errors
+    /// `Error::Timeout("read")` if no message arrives in time. The stream is poisoned then.
+    pub async fn receive_message_timeout(&mut self, timeout: Duration) -> Result<(u8, K)> {
+        self.receive_message_within(Some(timeout)).await
+    }
+
+    /// Wait for the next message without the read time limit. Unlike `receive_message`, this can be
+    ///  cancelled, e.g., in `tokio::select!`, without losing any byte. Receive the message with
+    ///  `receive_message_after` afterwards.
+    pub(crate) async fn receive_first_byte(&mut self) -> Result<u8> {
+        self.check_poisoned()?;
+        self.stream.receive_first_byte().await
+    }
+
+    /// Receive the rest of a message whose first byte was returned by `receive_first_byte`.
+    pub(crate) async fn receive_message_after(&mut self, first: u8) -> Result<(u8, K)> {
+        self.check_poisoned()?;
+        let result = with_timeout(
+            "read",
+            self.timeouts.read,
+            self.stream.receive_message_after(first),
+        )
+        .await;
+        self.poison_on_timeout(result)
+    }
+
+    /// Get time limits of read and write.
+    pub fn get_timeouts(&self) -> QTimeouts {
+        self.timeouts
+    }
+
+    /// Set time limits of read and write. `connect` and `handshake` have no effect on a connected stream.
+    pub fn set_timeouts(&mut self, timeouts: QTimeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// Check if a read or write timed out. A poisoned stream returns an error for any message exchange and
+    ///  must be reconnected.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
     }
 
     /// Return underlying connection type. One of `TCP`, `TLS` or `UDS`.
@@ -659,6 +612,521 @@ impl QStream {
     pub fn enforce_compression(&mut self) {
         self.local = false;
     }
+
+    /// Split the stream into a reader and a writer which can be used by different tasks at the same time, e.g.,
+    ///  to keep receiving messages pushed by q while sending queries. The halves inherit the time limits of the
+    ///  stream and can be put back together with [`QReader::reunite`]. The connection is closed when both
+    ///  halves are dropped.
+    ///
+    /// Sending a synchronous message with the writer does not wait for the response, which the reader receives
+    ///  as a message of `qmsg_type::response`.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::ipc::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000_u16, "ideal:person").await?;
+    ///     let (mut reader, mut writer) = socket.into_split();
+    ///
+    ///     // Print messages pushed by q, e.g., updates of a tickerplant.
+    ///     let printer = tokio::spawn(async move {
+    ///         while let Ok((_, message)) = reader.receive_message().await {
+    ///             println!("{}", message);
+    ///         }
+    ///         reader
+    ///     });
+    ///
+    ///     // Ask q to push messages from another task.
+    ///     writer.send_async_message(&"neg[.z.w] \"hello\"").await?;
+    ///     writer.send_async_message(&"hclose .z.w").await?;
+    ///     let _ = printer.await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn into_split(self) -> (QReader, QWriter) {
+        let (reader, writer) = tokio::io::split(self.stream);
+        let slot = self.slot.map(Arc::new);
+        (
+            QReader {
+                stream: reader,
+                method: self.method,
+                listener: self.listener,
+                local: self.local,
+                timeouts: self.timeouts,
+                poisoned: self.poisoned,
+                slot: slot.clone(),
+            },
+            QWriter {
+                stream: writer,
+                local: self.local,
+                timeouts: self.timeouts,
+                poisoned: self.poisoned,
+                slot,
+            },
+        )
+    }
+
+    /// Inner function of `receive_message` and `receive_message_timeout`.
+    async fn receive_message_within(&mut self, timeout: Option<Duration>) -> Result<(u8, K)> {
+        self.check_poisoned()?;
+        let result = with_timeout("read", timeout, self.stream.receive_message()).await;
+        self.poison_on_timeout(result)
+    }
+
+    /// Return an error if the stream is poisoned.
+    fn check_poisoned(&self) -> Result<()> {
+        check_poisoned(self.poisoned)
+    }
+
+    /// Poison the stream if the result is a timeout.
+    fn poison_on_timeout<T>(&mut self, result: Result<T>) -> Result<T> {
+        if let Err(Error::Timeout(_)) = result {
+            self.poisoned = true;
+        }
+        result
+    }
+}
+
+//%% QReader %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QReader {
+    /// Receive a message from a remote q process. The received message is parsed as `K` and message type is
+    ///  stored in the first returned value.
+    /// # Errors
+    /// `Error::Timeout("read")` if no message arrives within the read time limit. The reader is poisoned then.
+    /// # Example
+    /// See the example of [`QStream::into_split`](struct.QStream.html#method.into_split).
+    pub async fn receive_message(&mut self) -> Result<(u8, K)> {
+        let timeout = self.timeouts.read;
+        self.receive_message_within(timeout).await
+    }
+
+    /// Receive a message waiting at most `timeout` instead of the read time limit.
+    /// # Errors
+    /// `Error::Timeout("read")` if no message arrives in time. The reader is poisoned then.
+    pub async fn receive_message_timeout(&mut self, timeout: Duration) -> Result<(u8, K)> {
+        self.receive_message_within(Some(timeout)).await
+    }
+
+    /// Check if a read timed out.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Put the reader and the writer split from the same stream back together.
+    /// # Errors
+    /// [`ReuniteError`] holding the halves if they are not split from the same stream.
+    #[allow(clippy::result_large_err)]
+    pub fn reunite(self, writer: QWriter) -> StdResult<QStream, ReuniteError> {
+        if !self.stream.is_pair_of(&writer.stream) {
+            return Err(ReuniteError(self, writer));
+        }
+        let QWriter {
+            stream,
+            timeouts,
+            poisoned,
+            slot,
+            ..
+        } = writer;
+        // Release the reference of the writer first so that the slot can be taken back.
+        drop(slot);
+        Ok(QStream {
+            stream: self.stream.unsplit(stream),
+            method: self.method,
+            listener: self.listener,
+            local: self.local,
+            timeouts: QTimeouts {
+                write: timeouts.write,
+                ..self.timeouts
+            },
+            poisoned: self.poisoned || poisoned,
+            slot: self.slot.and_then(|slot| Arc::try_unwrap(slot).ok()),
+        })
+    }
+
+    /// Inner function of `receive_message` and `receive_message_timeout`.
+    async fn receive_message_within(&mut self, timeout: Option<Duration>) -> Result<(u8, K)> {
+        check_poisoned(self.poisoned)?;
+        let result = with_timeout("read", timeout, receive_message(&mut self.stream)).await;
+        if let Err(Error::Timeout(_)) = result {
+            self.poisoned = true;
+        }
+        result
+    }
+}
+
+//%% QWriter %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QWriter {
+    /// Send a message with a specified message type. A response to a synchronous message is received by the
+    ///  reader.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    /// - `message_type`: Asynchronous, synchronous or response.
+    /// # Errors
+    /// `Error::Timeout("write")` if the message is not sent within the write time limit. The writer is poisoned then.
+    pub async fn send_message(&mut self, message: &dyn Query, message_type: u8) -> Result<()> {
+        check_poisoned(self.poisoned)?;
+        let bytes = message.serialize(message_type, self.local).await;
+        let result = with_timeout(
+            "write",
+            self.timeouts.write,
+            write_all_cancellation_safe(&mut self.stream, &bytes),
+        )
+        .await;
+        match result {
+            Ok(_) => Ok(()),
+            Err(error) => {
+                if let Error::Timeout(_) = error {
+                    self.poisoned = true;
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Send a message asynchronously.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    /// # Example
+    /// See the example of [`QStream::into_split`](struct.QStream.html#method.into_split).
+    pub async fn send_async_message(&mut self, message: &dyn Query) -> Result<()> {
+        self.send_message(message, qmsg_type::asynchronous).await
+    }
+
+    /// Check if a write timed out.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+}
+
+//%% ReuniteError %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl fmt::Debug for ReuniteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ReuniteError(..)")
+    }
+}
+
+impl fmt::Display for ReuniteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("tried to reunite halves that are not from the same stream")
+    }
+}
+
+impl std::error::Error for ReuniteError {}
+
+//%% QStreamBuilder %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QStreamBuilder {
+    /// Start building a stream with default options.
+    /// # Parameters
+    /// - `method`: Connection method.
+    /// - `host`: Hostname or IP address of the target q process or this listener. Empty `str` for UDS.
+    /// - `port`: Port of the target q process or this listener.
+    pub fn new(method: ConnectionMethod, host: &str, port: u16) -> Self {
+        QStreamBuilder {
+            method,
+            host: host.to_string(),
+            port,
+            credential: String::new(),
+            timeouts: QTimeouts::default(),
+            nodelay: false,
+            keepalive: false,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+            capability: None,
+            compression: Compression::Auto,
+            uds_path: None,
+            tls: TlsConfig::default(),
+            authenticator: None,
+        }
+    }
+
+    /// Set a credential in the form of `username:password` used by `connect`. Default: empty.
+    pub fn credential(mut self, credential: &str) -> Self {
+        self.credential = credential.to_string();
+        self
+    }
+
+    /// Set time limits. `handshake` also bounds the TLS handshake and authentication of a client in `accept`.
+    ///  Default: no limit.
+    pub fn timeouts(mut self, timeouts: QTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Disable Nagle's algorithm on a TCP or TLS connection. Default: `false`.
+    pub fn nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = nodelay;
+        self
+    }
+
+    /// Enable TCP keepalive on a TCP or TLS connection. Default: `false`.
+    pub fn keepalive(mut self, keepalive: bool) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+
+    /// Set the size of the send buffer of a TCP or TLS connection. Default: system default.
+    pub fn send_buffer_size(mut self, size: u32) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the size of the receive buffer of a TCP or TLS connection. Default: system default.
+    pub fn recv_buffer_size(mut self, size: u32) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the capability byte sent in the handshake by `connect`. Default: 3 for TCP and TLS and 6 for Unix
+    ///  domain socket. `accept` echoes the capability sent by a client.
+    pub fn capability(mut self, capability: u8) -> Self {
+        self.capability = Some(capability);
+        self
+    }
+
+    /// Set a compression policy. Default: `Compression::Auto`.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set a path of the Unix domain socket file. Default: `$QUDSPATH/kx.[port]` or `/tmp/kx.[port]` if
+    ///  `QUDSPATH` is not set. The path is used in the abstract namespace as q does.
+    pub fn uds_path(mut self, path: &str) -> Self {
+        self.uds_path = Some(path.to_string());
+        self
+    }
+
+    /// Set TLS settings used by `connect` with `ConnectionMethod::TLS`. Default: `TlsConfig::default()`.
+    /// # Example
+    /// See the example of [`TlsConfig`](struct.TlsConfig.html).
+    pub fn tls(mut self, config: TlsConfig) -> Self {
+        self.tls = config;
+        self
+    }
+
+    /// Set a check of a credential sent by a client to `accept`. Default: [`AccountFile`](struct.AccountFile.html)
+    ///  loaded from `KDBPLUS_ACCOUNT_FILE` when `accept` is called.
+    /// # Example
+    /// See the example of [`Authenticator`](trait.Authenticator.html).
+    pub fn authenticator<A>(mut self, authenticator: A) -> Self
+    where
+        A: Authenticator + 'static,
+    {
+        self.authenticator = Some(SharedAuthenticator(Arc::new(authenticator)));
+        self
+    }
+
+    /// Connect to q/kdb+.
+    /// # Example
+    /// See the example of [`QStreamBuilder`](struct.QStreamBuilder.html).
+    pub async fn connect(&self) -> Result<QStream> {
+        match self.method {
+            ConnectionMethod::TCP => {
+                let stream = connect_tcp(self).await?;
+                let is_local = matches!(self.host.as_str(), "localhost" | "127.0.0.1");
+                Ok(self.build(Box::new(stream), false, is_local))
+            }
+            ConnectionMethod::TLS => {
+                let stream = connect_tls(self).await?;
+                Ok(self.build(Box::new(stream), false, false))
+            }
+            ConnectionMethod::UDS => {
+                let stream = connect_uds(self).await?;
+                Ok(self.build(Box::new(stream), false, true))
+            }
+        }
+    }
+
+    /// Accept a connection and authenticate the client. See [`QStream::accept`] for details. The listener is
+    ///  dropped after the first authenticated client. Use [`QListener`](struct.QListener.html) to serve multiple
+    ///  clients.
+    pub async fn accept(&self) -> Result<QStream> {
+        let builder = self.clone().with_default_authenticator()?;
+        builder.accept_first().await
+    }
+
+    /// Apply `limit` to the handshake unless a handshake time limit is set.
+    pub(crate) fn with_default_handshake_timeout(mut self, limit: Duration) -> Self {
+        self.timeouts.handshake.get_or_insert(limit);
+        self
+    }
+
+    /// Load the default authenticator unless an authenticator is set.
+    /// # Errors
+    /// IO error if `KDBPLUS_ACCOUNT_FILE` is not set or the file cannot be read.
+    pub(crate) fn with_default_authenticator(mut self) -> Result<Self> {
+        if self.authenticator.is_none() {
+            self.authenticator = Some(SharedAuthenticator(Arc::new(AccountFile::from_env()?)));
+        }
+        Ok(self)
+    }
+
+    /// Bind a listener and return the first authenticated client.
+    async fn accept_first(&self) -> Result<QStream> {
+        match self.method {
+            ConnectionMethod::TCP => {
+                // Bind to the endpoint.
+                let listener = bind_tcp(self).await?;
+                // Listen to the endpoint.
+                loop {
+                    let (socket, address) = listener.accept().await?;
+                    // Continue to listen in case of error.
+                    if let Ok(qstream) = self.accept_tcp(socket, address).await {
+                        return Ok(qstream);
+                    }
+                }
+            }
+            ConnectionMethod::TLS => {
+                // Bind to the endpoint.
+                let listener = bind_tcp(self).await?;
+                // Build TLS acceptor from a server identity.
+                let tls_acceptor = TlsAcceptor::from_env().await?;
+                // Listen to the endpoint.
+                loop {
+                    let (socket, _) = listener.accept().await?;
+                    // Continue to listen in case of error.
+                    if let Ok(qstream) = self.accept_tls(&tls_acceptor, socket).await {
+                        return Ok(qstream);
+                    }
+                }
+            }
+            ConnectionMethod::UDS => {
+                // Bind to the file
+                let listener = bind_uds(self)?;
+                // Listen to the endpoint
+                loop {
+                    let (socket, _) = listener.accept().await?;
+                    // Continue to listen in case of error.
+                    if let Ok(qstream) = self.accept_uds(socket).await {
+                        return Ok(qstream);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Authenticate a client accepted by a TCP listener.
+    pub(crate) async fn accept_tcp(
+        &self,
+        mut socket: TcpStream,
+        address: SocketAddr,
+    ) -> Result<QStream> {
+        // Read untill null bytes and send back capacity.
+        self.authenticate(&mut socket, Some(address)).await?;
+        socket.set_nodelay(self.nodelay)?;
+        // Check if the connection is local
+        let is_local = address.ip() == IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        Ok(self.build(Box::new(socket), true, is_local))
+    }
+
+    /// Run TLS handshake and authenticate a client accepted by a TCP listener.
+    pub(crate) async fn accept_tls(
+        &self,
+        tls_acceptor: &TlsAcceptor,
+        socket: TcpStream,
+    ) -> Result<QStream> {
+        socket.set_nodelay(self.nodelay)?;
+        let address = socket.peer_addr()?;
+        let authenticator = self.shared_authenticator()?;
+        // TLS handshake and q handshake share the time limit.
+        let tls_socket = with_timeout("handshake", self.timeouts.handshake, async {
+            // TLS processing.
+            let mut tls_socket = tls_acceptor.accept(socket).await?;
+            // Read untill null bytes and send back a capacity.
+            read_client_input(&mut tls_socket, authenticator, Some(address)).await?;
+            Ok(tls_socket)
+        })
+        .await?;
+        // TLS is always a remote connection
+        let mut qstream = self.build(Box::new(tls_socket), true, false);
+        // In order to close the connection from the server side, it needs to tell a client to close the connection.
+        // The `kdbplus_close_tls_connection_` will be called from the server at shutdown.
+        qstream
+            .send_async_message(&".kdbplus.close_tls_connection_:{[] hclose .z.w;}")
+            .await?;
+        Ok(qstream)
+    }
+
+    /// Authenticate a client accepted by a Unix domain socket listener.
+    pub(crate) async fn accept_uds(&self, mut socket: UnixStream) -> Result<QStream> {
+        // Read untill null bytes and send back capacity.
+        self.authenticate(&mut socket, None).await?;
+        // UDS is always a local connection
+        Ok(self.build(Box::new(socket), true, true))
+    }
+
+    /// Connection method.
+    pub(crate) fn method(&self) -> ConnectionMethod {
+        self.method
+    }
+
+    /// Capability byte sent in the handshake.
+    fn capability_byte(&self) -> u8 {
+        match (self.capability, self.method) {
+            (Some(capability), _) => capability,
+            (None, ConnectionMethod::UDS) => 6,
+            (None, _) => 3,
+        }
+    }
+
+    /// Path of the Unix domain socket file.
+    fn sockfile_path(&self) -> Result<String> {
+        match &self.uds_path {
+            Some(path) => Ok(path.clone()),
+            None => create_sockfile_path(self.port),
+        }
+    }
+
+    /// Authenticate a client within the handshake time limit.
+    async fn authenticate<S>(&self, socket: &mut S, peer_addr: Option<SocketAddr>) -> Result<()>
+    where
+        S: Unpin + AsyncWriteExt + AsyncReadExt,
+    {
+        let authenticator = self.shared_authenticator()?;
+        with_timeout(
+            "handshake",
+            self.timeouts.handshake,
+            read_client_input(socket, authenticator, peer_addr),
+        )
+        .await
+    }
+
+    /// Authenticator set to the builder or the default one loaded from `KDBPLUS_ACCOUNT_FILE`.
+    fn shared_authenticator(&self) -> Result<Arc<dyn Authenticator>> {
+        match &self.authenticator {
+            Some(SharedAuthenticator(authenticator)) => Ok(authenticator.clone()),
+            None => Ok(Arc::new(AccountFile::from_env()?)),
+        }
+    }
+
+    /// Build `QStream` applying the compression policy and time limits.
+    fn build(&self, stream: Box<dyn QStreamInner>, is_listener: bool, is_local: bool) -> QStream {
+        let skips_compression = match self.compression {
+            Compression::Auto => is_local,
+            Compression::Always => false,
+            Compression::Never => true,
+        };
+        let mut qstream = QStream::new(stream, self.method, is_listener, skips_compression);
+        qstream.timeouts = self.timeouts;
+        qstream
+    }
+}
+
+//%% SharedAuthenticator %%//vvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl fmt::Debug for SharedAuthenticator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Authenticator")
+    }
 }
 
 //%% QStreamInner %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
@@ -711,10 +1179,18 @@ impl QStreamInner for TcpStream {
     async fn receive_message(&mut self) -> Result<(u8, K)> {
         receive_message(self).await
     }
+
+    async fn receive_first_byte(&mut self) -> Result<u8> {
+        receive_first_byte(self).await
+    }
+
+    async fn receive_message_after(&mut self, first: u8) -> Result<(u8, K)> {
+        receive_message_after(self, &[first]).await
+    }
 }
 
 #[async_trait]
-impl QStreamInner for TlsStream<TcpStream> {
+impl QStreamInner for TlsStream {
     async fn shutdown(&mut self, is_listener: bool) -> Result<()> {
         if is_listener {
             // Closing the handle from the server side by `self.get_mut().shutdown()` crashes due to 'assertion failed: !self.context.is_null()'.
@@ -723,8 +1199,7 @@ impl QStreamInner for TlsStream<TcpStream> {
                 .await
                 .into()
         } else {
-            self.get_mut().shutdown()?;
-            Ok(())
+            tls::shutdown(self).await
         }
     }
 
@@ -769,6 +1244,14 @@ impl QStreamInner for TlsStream<TcpStream> {
     async fn receive_message(&mut self) -> Result<(u8, K)> {
         receive_message(self).await
     }
+
+    async fn receive_first_byte(&mut self) -> Result<u8> {
+        receive_first_byte(self).await
+    }
+
+    async fn receive_message_after(&mut self, first: u8) -> Result<(u8, K)> {
+        receive_message_after(self, &[first]).await
+    }
 }
 
 #[async_trait]
@@ -821,38 +1304,13 @@ impl QStreamInner for UnixStream {
     async fn receive_message(&mut self) -> Result<(u8, K)> {
         receive_message(self).await
     }
-}
 
-//%% MessageHeader %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
-
-impl MessageHeader {
-    /// Constructor.
-    fn new(encoding: u8, message_type: u8, compressed: u8, length: u32) -> Self {
-        MessageHeader {
-            encoding: encoding,
-            message_type: message_type,
-            compressed: compressed,
-            _unused: 0,
-            length: length,
-        }
+    async fn receive_first_byte(&mut self) -> Result<u8> {
+        receive_first_byte(self).await
     }
 
-    /// Constructor from bytes.
-    fn from_bytes(bytes: [u8; 8]) -> Self {
-        let encoding = bytes[0];
-
-        let length = match encoding {
-            0 => u32::from_be_bytes(bytes[4..8].try_into().unwrap()),
-            _ => u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
-        };
-
-        // Build header
-        MessageHeader::new(encoding, bytes[1], bytes[2], length)
-    }
-
-    /// Length of bytes for a header.
-    fn size() -> usize {
-        8
+    async fn receive_message_after(&mut self, first: u8) -> Result<(u8, K)> {
+        receive_message_after(self, &[first]).await
     }
 }
 
@@ -869,53 +1327,70 @@ impl MessageHeader {
 /// Tries to connect to multiple resolved IP addresses until the first successful connection. Error is
 ///  returned if none of them are valid.
 /// # Parameters
-/// - `host`: Hostname or IP address of the target q/kdb+ process.
-/// - `port`: Port of the target q process
-async fn connect_tcp_impl(host: &str, port: u16) -> Result<TcpStream> {
+/// - `builder`: Builder holding the target host and port and socket options.
+async fn connect_tcp_impl(builder: &QStreamBuilder) -> Result<TcpStream> {
     // DNS system resolver (should not fail)
     let resolver =
         TokioAsyncResolver::tokio_from_system_conf().expect("failed to create a resolver");
 
     // Check if we were given an IP address
-    let ips;
-    if let Ok(ip) = host.parse::<IpAddr>() {
-        ips = vec![ip.to_string()]
-    } else {
+    let ips = match builder.host.parse::<IpAddr>() {
+        Ok(ip) => vec![ip],
         // Resolve the given hostname
-        ips = resolver
-            .ipv4_lookup(format!("{}.", host).as_str())
+        Err(_) => resolver
+            .ipv4_lookup(format!("{}.", builder.host).as_str())
             .await
-            .unwrap()
+            .map_err(|error| io::Error::new(io::ErrorKind::NotFound, error.to_string()))?
             .iter()
-            .map(|result| result.to_string())
-            .collect()
+            .map(|address| IpAddr::V4(*address))
+            .collect(),
     };
 
-    for answer in ips {
-        let host_port = format!("{}:{}", answer, port);
+    let mut last_error = None;
+    for ip in ips {
+        let address = SocketAddr::new(ip, builder.port);
         // Return if this IP address is valid
-        match TcpStream::connect(&host_port).await {
+        match configure_tcp_socket(builder, address)?
+            .connect(address)
+            .await
+        {
             Ok(socket) => {
-                println!("connected: {}", host_port);
+                socket.set_nodelay(builder.nodelay)?;
                 return Ok(socket);
             }
-            Err(_) => {
-                eprintln!("connection refused: {}. try next.", host_port);
-            }
+            // Try next address.
+            Err(error) => last_error = Some(error),
         }
     }
-    // All addresses failed.
-    Err(io::Error::new(io::ErrorKind::ConnectionRefused, "failed to connect").into())
+    // All addresses failed. Report the error of the last one.
+    Err(last_error
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::ConnectionRefused, "failed to connect"))
+        .into())
+}
+
+/// Create a TCP socket for an address with keepalive and buffer sizes specified by a builder.
+fn configure_tcp_socket(builder: &QStreamBuilder, address: SocketAddr) -> Result<TcpSocket> {
+    let socket = match address {
+        SocketAddr::V4(_) => TcpSocket::new_v4()?,
+        SocketAddr::V6(_) => TcpSocket::new_v6()?,
+    };
+    socket.set_keepalive(builder.keepalive)?;
+    if let Some(size) = builder.send_buffer_size {
+        socket.set_send_buffer_size(size)?;
+    }
+    if let Some(size) = builder.recv_buffer_size {
+        socket.set_recv_buffer_size(size)?;
+    }
+    Ok(socket)
 }
 
 /// Send a credential and receive a common capacity.
-async fn handshake<S>(socket: &mut S, credential_: &str, method_bytes: &str) -> Result<()>
+async fn handshake<S>(socket: &mut S, credential: &str, capability: u8) -> Result<()>
 where
     S: Unpin + AsyncWriteExt + AsyncReadExt,
 {
-    // Send credential
-    let credential = credential_.to_string() + method_bytes;
-    write_all_cancellation_safe(socket, credential.as_bytes()).await?;
+    // Send credential followed by a capability and a null byte.
+    write_all_cancellation_safe(socket, &handshake_request(credential, capability)).await?;
 
     // Placeholder of common capablility
     let mut cap = [0u8; 1];
@@ -927,166 +1402,173 @@ where
     }
 }
 
-/// Connect to q process running on a specified `host` and `port` via TCP with a credential `username:password`.
+/// Connect to q process via TCP with a credential `username:password`.
 /// # Parameters
-/// - `host`: Hostname or IP address of the target q process.
-/// - `port`: Port of the target q process.
-/// - `credential`: Credential in the form of `username:password` to connect to the target q process.
-async fn connect_tcp(host: &str, port: u16, credential: &str) -> Result<TcpStream> {
+/// - `builder`: Builder holding the target host, port, credential and options.
+async fn connect_tcp(builder: &QStreamBuilder) -> Result<TcpStream> {
+    let timeouts = &builder.timeouts;
     // Connect via TCP
-    let mut socket = connect_tcp_impl(host, port).await?;
+    let mut socket = with_timeout("connect", timeouts.connect, connect_tcp_impl(builder)).await?;
     // Handshake
-    handshake(&mut socket, credential, "\x03\x00").await?;
+    with_timeout(
+        "handshake",
+        timeouts.handshake,
+        handshake(&mut socket, &builder.credential, builder.capability_byte()),
+    )
+    .await?;
     Ok(socket)
 }
 
 /// TLS version of `connect_tcp`.
 /// # Parameters
-/// - `host`: Hostname or IP address of the target q process.
-/// - `port`: Port of the target q process.
-/// - `credential`: Credential in the form of `username:password` to connect to the target q process.
-async fn connect_tls(host: &str, port: u16, credential: &str) -> Result<TlsStream<TcpStream>> {
+/// - `builder`: Builder holding the target host, port, credential and options.
+async fn connect_tls(builder: &QStreamBuilder) -> Result<TlsStream> {
+    let timeouts = &builder.timeouts;
+    // Fail fast on an invalid certificate or key before connecting.
+    let connector = builder.tls.build_connector()?;
     // Connect via TCP
-    let socket_ = connect_tcp_impl(host, port).await?;
-    // Use TLS
-    let connector = TlsConnector::from(TlsConnectorInner::new().unwrap());
-    let mut socket = connector
-        .connect(host, socket_)
-        .await
-        .expect("failed to create TLS session");
-    // Handshake
-    handshake(&mut socket, credential, "\x03\x00").await?;
-    Ok(socket)
+    let socket_ = with_timeout("connect", timeouts.connect, connect_tcp_impl(builder)).await?;
+    // TLS handshake and q handshake share the time limit.
+    with_timeout("handshake", timeouts.handshake, async {
+        // Use TLS
+        let mut socket = connector
+            .connect(builder.tls.domain(&builder.host), socket_)
+            .await?;
+        // Handshake
+        handshake(&mut socket, &builder.credential, builder.capability_byte()).await?;
+        Ok(socket)
+    })
+    .await
 }
 
-/// Build a path of a socket file.
-fn create_sockfile_path(port: u16) -> Result<String> {
-    // Create file path
-    let udspath = match env::var("QUDSPATH") {
-        Ok(dir) => format!("{}/kx.{}", dir, port),
-        Err(_) => format!("/tmp/kx.{}", port),
-    };
+/// Bind a TCP listener to the host and port of a builder with its socket options.
+pub(crate) async fn bind_tcp(builder: &QStreamBuilder) -> Result<TcpListener> {
+    let address = tokio::net::lookup_host((builder.host.as_str(), builder.port))
+        .await?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "failed to resolve host"))?;
+    let socket = configure_tcp_socket(builder, address)?;
+    socket.set_reuseaddr(true)?;
+    socket.bind(address)?;
+    Ok(socket.listen(1024)?)
+}
 
-    Ok(udspath)
+/// Bind a Unix domain socket listener to the socket file of a builder in the abstract namespace.
+pub(crate) fn bind_uds(builder: &QStreamBuilder) -> Result<UnixListener> {
+    // Build a sockt file path.
+    let uds_path = builder.sockfile_path()?;
+    let abstract_sockfile_ = format!("\x00{}", uds_path);
+    let abstract_sockfile = Path::new(&abstract_sockfile_);
+    Ok(UnixListener::bind(abstract_sockfile)?)
 }
 
-/// Connect to q process running on the specified `port` via Unix domain socket with a credential `username:password`.
+/// Connect to q process via Unix domain socket with a credential `username:password`.
 /// # Parameters
-/// - `port`: Port of the target q process.
-/// - `credential`: Credential in the form of `username:password` to connect to the target q process.
+/// - `builder`: Builder holding the target port or socket path, credential and options.
 #[cfg(unix)]
-async fn connect_uds(port: u16, credential: &str) -> Result<UnixStream> {
+async fn connect_uds(builder: &QStreamBuilder) -> Result<UnixStream> {
+    let timeouts = &builder.timeouts;
     // Create a file path.
-    let uds_path = create_sockfile_path(port)?;
+    let uds_path = builder.sockfile_path()?;
     let abstract_sockfile_ = format!("\x00{}", uds_path);
     let abstract_sockfile = Path::new(&abstract_sockfile_);
     // Connect to kdb+.
-    let mut socket = UnixStream::connect(&abstract_sockfile).await?;
+    let mut socket = with_timeout("connect", timeouts.connect, async {
+        Ok(UnixStream::connect(&abstract_sockfile).await?)
+    })
+    .await?;
     // Handshake
-    handshake(&mut socket, credential, "\x06\x00").await?;
+    with_timeout(
+        "handshake",
+        timeouts.handshake,
+        handshake(&mut socket, &builder.credential, builder.capability_byte()),
+    )
+    .await?;
 
     Ok(socket)
 }
 
+/// Await a future within a time limit if any. `Error::Timeout(operation)` is returned when it expires.
+async fn with_timeout<T, F>(
+    operation: &'static str,
+    limit: Option<Duration>,
+    future: F,
+) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    match limit {
+        Some(limit) => time::timeout(limit, future)
+            .await
+            .unwrap_or_else(|_| Err(Error::timeout(operation))),
+        None => future.await,
+    }
+}
+
 //%% QStream Acceptor %%//vvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
 /// Read username, password, capacity and null byte from q client at the connection and does authentication.
 ///  Close the handle if the authentication fails.
-async fn read_client_input<S>(socket: &mut S) -> Result<()>
+/// # Parameters
+/// - `socket`: Socket accepted from a q client.
+/// - `authenticator`: Check of the credential.
+/// - `peer_addr`: Address of the client. `None` for Unix domain socket.
+async fn read_client_input<S>(
+    socket: &mut S,
+    authenticator: Arc<dyn Authenticator>,
+    peer_addr: Option<SocketAddr>,
+) -> Result<()>
 where
     S: Unpin + AsyncWriteExt + AsyncReadExt,
 {
     // Buffer to read inputs.
     let mut client_input = [0u8; 32];
     // credential will be built from small fractions of bytes.
-    let mut passed_credential = String::new();
-    loop {
+    let mut passed_input = Vec::new();
+    let null_location = loop {
         // Read a client credential input.
-        match socket.read(&mut client_input).await {
-            Ok(0) => {
-                // No bytes were read
+        match socket.read(&mut client_input).await? {
+            0 => {
+                // The client closed the connection before sending a null byte.
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "connection closed during handshake",
+                )
+                .into());
             }
-            Ok(_) => {
-                // Locate a byte denoting a capacity
-                if let Some(index) = client_input
-                    .iter()
-                    .position(|byte| *byte == 0x03 || *byte == 0x06)
-                {
-                    let capacity = client_input[index];
-                    passed_credential
-                        .push_str(str::from_utf8(&client_input[0..index]).expect("invalid bytes"));
-                    let credential = passed_credential.as_str().split(':').collect::<Vec<&str>>();
-                    if let Some(encoded) = ACCOUNTS.get(&credential[0].to_string()) {
-                        // User exists
-                        let mut hasher = Sha1::new();
-                        hasher.update(credential[1].as_bytes());
-                        let encoded_password = hasher.digest().to_string();
-                        if encoded == &encoded_password {
-                            // Client passed correct credential
-                            socket.write_all(&[capacity; 1]).await?;
-                            return Ok(());
-                        } else {
-                            // Authentication failure.
-                            // Close connection.
-                            socket.shutdown().await?;
-                            return Err(io::Error::new(
-                                io::ErrorKind::InvalidData,
-                                "authentication failed",
-                            )
-                            .into());
-                        }
-                    } else {
-                        // Authentication failure.
-                        // Close connection.
-                        socket.shutdown().await?;
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            "authentication failed",
-                        )
-                        .into());
-                    }
-                } else {
-                    // Append a fraction of credential
-                    passed_credential
-                        .push_str(str::from_utf8(&client_input).expect("invalid bytes"));
+            length => {
+                passed_input.extend_from_slice(&client_input[0..length]);
+                if let Some(index) = passed_input.iter().position(|byte| *byte == 0x00) {
+                    break index;
                 }
             }
-            Err(error) => {
-                return Err(error.into());
-            }
-        }
-    }
-}
-
-/// Check if server key exists and return teh contents.
-async fn build_identity_from_cert() -> Result<Identity> {
-    // Check if server key exists.
-    if let Ok(path) = env::var("KDBPLUS_TLS_KEY_FILE") {
-        if let Ok(password) = env::var("KDBPLUS_TLS_KEY_FILE_SECRET") {
-            let cert_file = tokio::fs::File::open(Path::new(&path)).await.unwrap();
-            let mut reader = BufReader::new(cert_file);
-            let mut der: Vec<u8> = Vec::new();
-            // Read the key file.
-            reader.read_to_end(&mut der).await?;
-            // Create identity.
-            if let Ok(identity) = Identity::from_pkcs12(&der, &password) {
-                return Ok(identity);
-            } else {
-                return Err(
-                    io::Error::new(io::ErrorKind::InvalidData, "authentication failed").into(),
-                );
-            }
-        } else {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "KDBPLUS_TLS_KEY_FILE_SECRET is not set",
-            )
-            .into());
         }
+    };
+    // Locate a byte denoting a capacity right before the null byte.
+    let (credential, capacity) = match passed_input[0..null_location].split_last() {
+        Some((capacity, credential)) if *capacity < 0x20 => (credential, *capacity),
+        _ => (&passed_input[0..null_location], 0),
+    };
+    let credential = String::from_utf8_lossy(credential).into_owned();
+    let (user, password) = credential
+        .split_once(':')
+        .map(|(user, password)| (user.to_string(), password.to_string()))
+        .unwrap_or((credential.clone(), String::new()));
+    // Authenticator may block on a file or hashing.
+    let accepted = tokio::task::spawn_blocking(move || {
+        authenticator.authenticate(&user, &password, peer_addr)
+    })
+    .await
+    .unwrap_or(false);
+    if accepted {
+        // Client passed correct credential
+        socket.write_all(&[capacity; 1]).await?;
+        Ok(())
     } else {
-        return Err(
-            io::Error::new(io::ErrorKind::NotFound, "KDBPLUS_TLS_KEY_FILE is not set").into(),
-        );
+        // Authentication failure.
+        // Close connection.
+        socket.shutdown().await?;
+        Err(io::Error::new(io::ErrorKind::InvalidData, "authentication failed").into())
     }
 }
 
@@ -1099,10 +1581,11 @@ where
 {
     let mut read_total = 0;
     let to_read = buffer.len();
-    loop {
-        read_total += socket.read(buffer).await?;
-        if read_total == to_read {
-            break;
+    while read_total < to_read {
+        match socket.read(&mut buffer[read_total..]).await? {
+            // The peer closed the connection.
+            0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            read => read_total += read,
         }
     }
     Ok(read_total)
@@ -1115,10 +1598,10 @@ where
 {
     let mut write_total = 0;
     let to_write = buffer.len();
-    loop {
-        write_total += socket.write(&buffer[write_total..]).await?;
-        if write_total == to_write {
-            break;
+    while write_total < to_write {
+        match socket.write(&buffer[write_total..]).await? {
+            0 => return Err(io::Error::from(io::ErrorKind::WriteZero).into()),
+            written => write_total += written,
         }
     }
     Ok(write_total)
@@ -1127,14 +1610,47 @@ where
 /// Receive a message from q process with decompression if necessary. The received message is parsed as `K` and message type is
 ///  stored in the first returned value.
 /// # Parameters
-/// - `socket`: Socket to communicate with a q process. Either of `TcpStream`, `TlsStream<TcpStream>` or `UnixStream`.
+/// - `socket`: Socket to communicate with a q process. Either of `TcpStream`, `TlsStream` or `UnixStream`.
 async fn receive_message<S>(socket: &mut S) -> Result<(u8, K)>
+where
+    S: Unpin + AsyncReadExt,
+{
+    receive_message_after(socket, &[]).await
+}
+
+/// Wait for the first byte of a message. A single `read` consumes nothing until it completes and so this
+///  can be cancelled safely.
+/// # Parameters
+/// - `socket`: Socket to communicate with a q process. Either of `TcpStream`, `TlsStream` or `UnixStream`.
+async fn receive_first_byte<S>(socket: &mut S) -> Result<u8>
+where
+    S: Unpin + AsyncReadExt,
+{
+    let mut byte = [0u8; 1];
+    match socket.read(&mut byte).await {
+        Ok(1) => Ok(byte[0]),
+        Ok(_) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection dropped").into()),
+        Err(err) => Err(io::Error::new(
+            io::ErrorKind::ConnectionAborted,
+            format!("Connection dropped: {}", err),
+        )
+        .into()),
+    }
+}
+
+/// Receive a message whose header starts with `prefix` which has been read already.
+/// # Parameters
+/// - `socket`: Socket to communicate with a q process. Either of `TcpStream`, `TlsStream` or `UnixStream`.
+/// - `prefix`: Bytes of the header read already.
+async fn receive_message_after<S>(socket: &mut S, prefix: &[u8]) -> Result<(u8, K)>
 where
     S: Unpin + AsyncReadExt,
 {
     // Read header
     let mut header_buffer = [0u8; 8];
-    if let Err(err) = read_exact_cancellation_safe(socket, &mut header_buffer).await {
+    header_buffer[..prefix.len()].copy_from_slice(prefix);
+    if let Err(err) = read_exact_cancellation_safe(socket, &mut header_buffer[prefix.len()..]).await
+    {
         // The expected message is header or EOF (close due to q process failure resulting from a bad query)
         return Err(io::Error::new(
             io::ErrorKind::ConnectionAborted,
@@ -1159,199 +1675,5 @@ where
         .into());
     }
 
-    // Decompress if necessary
-    if header.compressed == 0x01 {
-        body = decompress(body, header.encoding).await;
-    }
-
-    Ok((
-        header.message_type,
-        K::q_ipc_decode(&body, header.encoding).await,
-    ))
-}
-
-/// Compress body. The combination of serializing the data and compressing will result in
-/// the same output as shown in the q language by using the -18! function e.g.
-/// serializing 2000 bools set to true, then compressing, will have the same output as `-18!2000#1b`.
-/// # Parameter
-/// - `raw`: Serialized message.
-/// - `encode`: `0` if Big Endian; `1` if Little Endian.
-async fn compress(raw: Vec<u8>) -> (bool, Vec<u8>) {
-    let mut i = 0_u8;
-    let mut f = 0_u8;
-    let mut h0 = 0_usize;
-    let mut h = 0_usize;
-    let mut g: bool;
-    let mut compressed: Vec<u8> = Vec::with_capacity((raw.len()) / 2);
-    // Assure that vector is filled with 0
-    compressed.resize((raw.len()) / 2, 0_u8);
-
-    // Start index of compressed body
-    // 12 bytes are reserved for the header + size of raw bytes
-    let mut c = 12;
-    let mut d = c;
-    let e = compressed.len();
-    let mut p = 0_usize;
-    let mut q: usize;
-    let mut r: usize;
-    let mut s0 = 0_usize;
-
-    // Body starts from index 8
-    let mut s = 8_usize;
-    let t = raw.len();
-    let mut a = [0_i32; 256];
-
-    // Copy encode, message type, compressed and reserved
-    compressed[0..4].copy_from_slice(&raw[0..4]);
-    // Set compressed flag
-    compressed[2] = 1;
-
-    // Write size of raw bytes including a header
-    let raw_size = match ENCODING {
-        0 => (t as u32).to_be_bytes(),
-        _ => (t as u32).to_le_bytes(),
-    };
-    compressed[8..12].copy_from_slice(&raw_size);
-
-    while s < t {
-        if i == 0 {
-            if d > e - 17 {
-                // Early return when compressing to less than half failed
-                return (false, raw);
-            }
-            i = 1;
-            compressed[c] = f;
-            c = d;
-            d += 1;
-            f = 0;
-        }
-        g = s > t - 3;
-        if !g {
-            h = (raw[s] ^ raw[s + 1]) as usize;
-            p = a[h] as usize;
-            g = (0 == p) || (0 != (raw[s] ^ raw[p]));
-        }
-        if 0 < s0 {
-            a[h0] = s0 as i32;
-            s0 = 0;
-        }
-        if g {
-            h0 = h;
-            s0 = s;
-            compressed[d] = raw[s];
-            d += 1;
-            s += 1;
-        } else {
-            a[h] = s as i32;
-            f |= i;
-            p += 2;
-            s += 2;
-            r = s;
-            q = if s + 255 > t { t } else { s + 255 };
-            while (s < q) && (raw[p] == raw[s]) {
-                s += 1;
-                if s < q {
-                    p += 1;
-                }
-            }
-            compressed[d] = h as u8;
-            d += 1;
-            compressed[d] = (s - r) as u8;
-            d += 1;
-        }
-        i = i.wrapping_mul(2);
-    }
-    compressed[c] = f;
-    // Final compressed data size
-    let compressed_size = match ENCODING {
-        0 => (d as u32).to_be_bytes(),
-        _ => (d as u32).to_le_bytes(),
-    };
-    compressed[4..8].copy_from_slice(&compressed_size);
-    let _ = compressed.split_off(d);
-    (true, compressed)
-}
-
-/// Decompress body. The combination of decompressing and deserializing the data
-///  will result in the same output as shown in the q language by using the `-19!` function.
-/// # Parameter
-/// - `compressed`: Compressed serialized message.
-/// - `encoding`:
-///   - `0`: Big Endian
-///   - `1`: Little Endian.
-async fn decompress(compressed: Vec<u8>, encoding: u8) -> Vec<u8> {
-    let mut n = 0;
-    let mut r: usize;
-    let mut f = 0_usize;
-
-    // Header has already been removed.
-    // Start index of decompressed bytes is 0
-    let mut s = 0_usize;
-    let mut p = s;
-    let mut i = 0_usize;
-
-    // Subtract 8 bytes from decoded bytes size as 8 bytes have already been taken as header
-    let size = match encoding {
-        0 => {
-            i32::from_be_bytes(
-                compressed[0..4]
-                    .try_into()
-                    .expect("slice does not have length 4"),
-            ) - 8
-        }
-        _ => {
-            i32::from_le_bytes(
-                compressed[0..4]
-                    .try_into()
-                    .expect("slice does not have length 4"),
-            ) - 8
-        }
-    };
-    let mut decompressed: Vec<u8> = Vec::with_capacity(size as usize);
-    // Assure that vector is filled with 0
-    decompressed.resize(size as usize, 0_u8);
-
-    // Start index of compressed body.
-    // 8 bytes have already been removed as header
-    let mut d = 4;
-    let mut aa = [0_i32; 256];
-    while s < decompressed.len() {
-        if i == 0 {
-            f = (0xff & compressed[d]) as usize;
-            d += 1;
-            i = 1;
-        }
-        if (f & i) != 0 {
-            r = aa[(0xff & compressed[d]) as usize] as usize;
-            d += 1;
-            decompressed[s] = decompressed[r];
-            s += 1;
-            r += 1;
-            decompressed[s] = decompressed[r];
-            s += 1;
-            r += 1;
-            n = (0xff & compressed[d]) as usize;
-            d += 1;
-            for m in 0..n {
-                decompressed[s + m] = decompressed[r + m];
-            }
-        } else {
-            decompressed[s] = compressed[d];
-            s += 1;
-            d += 1;
-        }
-        while p < s - 1 {
-            aa[((0xff & decompressed[p]) ^ (0xff & decompressed[p + 1])) as usize] = p as i32;
-            p += 1;
-        }
-        if (f & i) != 0 {
-            s += n;
-            p = s;
-        }
-        i *= 2;
-        if i == 256 {
-            i = 0;
-        }
-    }
-    decompressed
+    Ok(decode_message(&header, body))
 }
@@ -3,7 +3,6 @@
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
 use super::*;
-use async_recursion::async_recursion;
 use std::convert::TryInto;
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -64,93 +63,84 @@ macro_rules! build_element {
     }};
 }
 
-/// Read given bytes with a given cursor and build a basic type list of the specified type.
+/// Read given bytes with a given header of attribute, size and cursor and build a basic type list of the specified
+///  type.
 macro_rules! build_list {
-    ($bytes:expr, $cursor:expr, $encode:expr, $qtype:expr, i16) => {
-        async {
-            let (attribute, size, cursor) = get_attribute_and_size($bytes, $cursor, $encode);
-            let list = match $encode {
-                0 => $bytes[cursor..cursor + 2 * size]
-                    .chunks(2)
-                    .map(|element| i16::from_be_bytes(element.try_into().unwrap()))
-                    .collect::<Vec<H>>(),
-                _ => $bytes[cursor..cursor + 2 * size]
-                    .chunks(2)
-                    .map(|element| i16::from_le_bytes(element.try_into().unwrap()))
-                    .collect::<Vec<H>>(),
-            };
-            let k = K::new($qtype, attribute, k0_inner::list(k0_list::new(list)));
-            (k, cursor + 2 * size)
-        }
-    };
-    ($bytes:expr, $cursor:expr, $encode:expr, $qtype:expr, i32) => {
-        async {
-            let (attribute, size, cursor) = get_attribute_and_size($bytes, $cursor, $encode);
-            let list = match $encode {
-                0 => $bytes[cursor..cursor + 4 * size]
-                    .chunks(4)
-                    .map(|element| i32::from_be_bytes(element.try_into().unwrap()))
-                    .collect::<Vec<I>>(),
-                _ => $bytes[cursor..cursor + 4 * size]
-                    .chunks(4)
-                    .map(|element| i32::from_le_bytes(element.try_into().unwrap()))
-                    .collect::<Vec<I>>(),
-            };
-            let k = K::new($qtype, attribute, k0_inner::list(k0_list::new(list)));
-            (k, cursor + 4 * size)
-        }
-    };
-    ($bytes:expr, $cursor:expr, $encode:expr, $qtype:expr, i64) => {
-        async {
-            let (attribute, size, cursor) = get_attribute_and_size($bytes, $cursor, $encode);
-            let list = match $encode {
-                0 => $bytes[cursor..cursor + 8 * size]
-                    .chunks(8)
-                    .map(|element| i64::from_be_bytes(element.try_into().unwrap()))
-                    .collect::<Vec<J>>(),
-                _ => $bytes[cursor..cursor + 8 * size]
-                    .chunks(8)
-                    .map(|element| i64::from_le_bytes(element.try_into().unwrap()))
-                    .collect::<Vec<J>>(),
-            };
-            let k = K::new($qtype, attribute, k0_inner::list(k0_list::new(list)));
-            (k, cursor + 8 * size)
-        }
-    };
-    ($bytes:expr, $cursor:expr, $encode:expr, $qtype:expr, f32) => {
-        async {
-            let (attribute, size, cursor) = get_attribute_and_size($bytes, $cursor, $encode);
-            let list = match $encode {
-                0 => $bytes[cursor..cursor + 4 * size]
-                    .chunks(4)
-                    .map(|element| f32::from_be_bytes(element.try_into().unwrap()))
-                    .collect::<Vec<E>>(),
-                _ => $bytes[cursor..cursor + 4 * size]
-                    .chunks(4)
-                    .map(|element| f32::from_le_bytes(element.try_into().unwrap()))
-                    .collect::<Vec<E>>(),
-            };
-            let k = K::new($qtype, attribute, k0_inner::list(k0_list::new(list)));
-            (k, cursor + 4 * size)
-        }
-    };
-    ($bytes:expr, $cursor:expr, $encode:expr, $qtype:expr, f64) => {
-        async {
-            let (attribute, size, cursor) = get_attribute_and_size($bytes, $cursor, $encode);
-            let list = match $encode {
-                0 => $bytes[cursor..cursor + 8 * size]
-                    .chunks(8)
-                    .map(|element| f64::from_be_bytes(element.try_into().unwrap()))
-                    .collect::<Vec<F>>(),
-                _ => $bytes[cursor..cursor + 8 * size]
-                    .chunks(8)
-                    .map(|element| f64::from_le_bytes(element.try_into().unwrap()))
-                    .collect::<Vec<F>>(),
-            };
-            let k = K::new($qtype, attribute, k0_inner::list(k0_list::new(list)));
-            (k, cursor + 8 * size)
-        }
-    };
+    ($bytes:expr, $header:expr, $encode:expr, $qtype:expr, i16) => {{
+        let (attribute, size, cursor) = $header;
+        let list = match $encode {
+            0 => $bytes[cursor..cursor + 2 * size]
+                .chunks(2)
+                .map(|element| i16::from_be_bytes(element.try_into().unwrap()))
+                .collect::<Vec<H>>(),
+            _ => $bytes[cursor..cursor + 2 * size]
+                .chunks(2)
+                .map(|element| i16::from_le_bytes(element.try_into().unwrap()))
+                .collect::<Vec<H>>(),
+        };
+        let k = K::new($qtype, attribute, k0_inner::list(k0_list::new(list)));
+        (k, cursor + 2 * size)
+    }};
+    ($bytes:expr, $header:expr, $encode:expr, $qtype:expr, i32) => {{
+        let (attribute, size, cursor) = $header;
+        let list = match $encode {
+            0 => $bytes[cursor..cursor + 4 * size]
+                .chunks(4)
+                .map(|element| i32::from_be_bytes(element.try_into().unwrap()))
+                .collect::<Vec<I>>(),
+            _ => $bytes[cursor..cursor + 4 * size]
+                .chunks(4)
+                .map(|element| i32::from_le_bytes(element.try_into().unwrap()))
+                .collect::<Vec<I>>(),
+        };
+        let k = K::new($qtype, attribute, k0_inner::list(k0_list::new(list)));
+        (k, cursor + 4 * size)
+    }};
+    ($bytes:expr, $header:expr, $encode:expr, $qtype:expr, i64) => {{
+        let (attribute, size, cursor) = $header;
+        let list = match $encode {
+            0 => $bytes[cursor..cursor + 8 * size]
+                .chunks(8)
+                .map(|element| i64::from_be_bytes(element.try_into().unwrap()))
+                .collect::<Vec<J>>(),
+            _ => $bytes[cursor..cursor + 8 * size]
+                .chunks(8)
+                .map(|element| i64::from_le_bytes(element.try_into().unwrap()))
+                .collect::<Vec<J>>(),
+        };
+        let k = K::new($qtype, attribute, k0_inner::list(k0_list::new(list)));
+        (k, cursor + 8 * size)
+    }};
+    ($bytes:expr, $header:expr, $encode:expr, $qtype:expr, f32) => {{
+        let (attribute, size, cursor) = $header;
+        let list = match $encode {
+            0 => $bytes[cursor..cursor + 4 * size]
+                .chunks(4)
+                .map(|element| f32::from_be_bytes(element.try_into().unwrap()))
+                .collect::<Vec<E>>(),
+            _ => $bytes[cursor..cursor + 4 * size]
+                .chunks(4)
+                .map(|element| f32::from_le_bytes(element.try_into().unwrap()))
+                .collect::<Vec<E>>(),
+        };
+        let k = K::new($qtype, attribute, k0_inner::list(k0_list::new(list)));
+        (k, cursor + 4 * size)
+    }};
+    ($bytes:expr, $header:expr, $encode:expr, $qtype:expr, f64) => {{
+        let (attribute, size, cursor) = $header;
+        let list = match $encode {
+            0 => $bytes[cursor..cursor + 8 * size]
+                .chunks(8)
+                .map(|element| f64::from_be_bytes(element.try_into().unwrap()))
+                .collect::<Vec<F>>(),
+            _ => $bytes[cursor..cursor + 8 * size]
+                .chunks(8)
+                .map(|element| f64::from_le_bytes(element.try_into().unwrap()))
+                .collect::<Vec<F>>(),
+        };
+        let k = K::new($qtype, attribute, k0_inner::list(k0_list::new(list)));
+        (k, cursor + 8 * size)
+    }};
 }
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -161,8 +151,21 @@ macro_rules! build_list {
 
 impl K {
     /// Deserialize bytes to q object in a manner of q function `-9!`.
-    pub(crate) async fn q_ipc_decode(bytes: &Vec<u8>, encode: u8) -> Self {
-        deserialize_bytes(bytes, 0, encode).await.0
+    pub(crate) fn q_ipc_decode(bytes: &[u8], encode: u8) -> Self {
+        deserialize_bytes(bytes, 0, encode).0
+    }
+
+    /// Deserialize elements of a simple list whose type, attribute and size are given apart, e.g., by the header
+    ///  of a file.
+    #[cfg(feature = "ipc-base")]
+    pub(crate) fn q_ipc_decode_list(
+        bytes: &[u8],
+        qtype: i8,
+        attribute: i8,
+        size: usize,
+        encode: u8,
+    ) -> Self {
+        deserialize_list(bytes, 0, encode, qtype, attribute, size).0
     }
 }
 
@@ -170,8 +173,7 @@ impl K {
 // >> Private Functions
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
-#[async_recursion]
-async fn deserialize_bytes(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usize) {
+fn deserialize_bytes(bytes: &[u8], cursor: usize, encode: u8) -> (K, usize) {
     match bytes[cursor] as i8 {
         qtype::BOOL_ATOM => deserialize_bool(bytes, cursor + 1, encode),
         qtype::GUID_ATOM => deserialize_guid(bytes, cursor + 1, encode),
@@ -197,34 +199,14 @@ async fn deserialize_bytes(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, us
         qtype::MINUTE_ATOM => build_element!(bytes, cursor + 1, encode, qtype::MINUTE_ATOM, i32),
         qtype::SECOND_ATOM => build_element!(bytes, cursor + 1, encode, qtype::SECOND_ATOM, i32),
         qtype::TIME_ATOM => build_element!(bytes, cursor + 1, encode, qtype::TIME_ATOM, i32),
-        qtype::COMPOUND_LIST => deserialize_compound_list(bytes, cursor + 1, encode).await,
-        qtype::BOOL_LIST => deserialize_bool_list(bytes, cursor + 1, encode),
-        qtype::GUID_LIST => deserialize_guid_list(bytes, cursor + 1, encode).await,
-        qtype::BYTE_LIST => deserialize_byte_list(bytes, cursor + 1, encode),
-        qtype::SHORT_LIST => build_list!(bytes, cursor + 1, encode, qtype::SHORT_LIST, i16).await,
-        qtype::INT_LIST => build_list!(bytes, cursor + 1, encode, qtype::INT_LIST, i32).await,
-        qtype::LONG_LIST => build_list!(bytes, cursor + 1, encode, qtype::LONG_LIST, i64).await,
-        qtype::REAL_LIST => build_list!(bytes, cursor + 1, encode, qtype::REAL_LIST, f32).await,
-        qtype::FLOAT_LIST => build_list!(bytes, cursor + 1, encode, qtype::FLOAT_LIST, f64).await,
-        qtype::STRING => deserialize_string(bytes, cursor + 1, encode),
-        qtype::SYMBOL_LIST => deserialize_symbol_list(bytes, cursor + 1, encode).await,
-        qtype::TIMESTAMP_LIST => {
-            build_list!(bytes, cursor + 1, encode, qtype::TIMESTAMP_LIST, i64).await
+        qtype::COMPOUND_LIST => deserialize_compound_list(bytes, cursor + 1, encode),
+        qtype @ qtype::BOOL_LIST..=qtype::TIME_LIST => {
+            let (attribute, size, cursor) = get_attribute_and_size(bytes, cursor + 1, encode);
+            deserialize_list(bytes, cursor, encode, qtype, attribute, size)
         }
-        qtype::MONTH_LIST => build_list!(bytes, cursor + 1, encode, qtype::MONTH_LIST, i32).await,
-        qtype::DATE_LIST => build_list!(bytes, cursor + 1, encode, qtype::DATE_LIST, i32).await,
-        qtype::DATETIME_LIST => {
-            build_list!(bytes, cursor + 1, encode, qtype::DATETIME_LIST, f64).await
-        }
-        qtype::TIMESPAN_LIST => {
-            build_list!(bytes, cursor + 1, encode, qtype::TIMESPAN_LIST, i64).await
-        }
-        qtype::MINUTE_LIST => build_list!(bytes, cursor + 1, encode, qtype::MINUTE_LIST, i32).await,
-        qtype::SECOND_LIST => build_list!(bytes, cursor + 1, encode, qtype::SECOND_LIST, i32).await,
-        qtype::TIME_LIST => build_list!(bytes, cursor + 1, encode, qtype::TIME_LIST, i32).await,
-        qtype::TABLE => deserialize_table(bytes, cursor + 1, encode).await,
-        qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
-            deserialize_dictionary(bytes, cursor + 1, encode).await
+        qtype::TABLE => deserialize_table(bytes, cursor + 1, encode),
+        qtype @ (qtype::DICTIONARY | qtype::SORTED_DICTIONARY) => {
+            deserialize_dictionary(bytes, cursor + 1, encode, qtype)
         }
         qtype::NULL => deserialize_null(bytes, cursor + 1, encode),
         qtype::ERROR => deserialize_error(bytes, cursor + 1, encode),
@@ -232,42 +214,74 @@ async fn deserialize_bytes(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, us
     }
 }
 
-fn deserialize_bool(bytes: &Vec<u8>, cursor: usize, _: u8) -> (K, usize) {
+/// Deserialize elements of a simple list whose type, attribute and size are read already.
+fn deserialize_list(
+    bytes: &[u8],
+    cursor: usize,
+    encode: u8,
+    qtype: i8,
+    attribute: i8,
+    size: usize,
+) -> (K, usize) {
+    let header = (attribute, size, cursor);
+    match qtype {
+        qtype::BOOL_LIST => deserialize_bool_list(bytes, cursor, attribute, size),
+        qtype::GUID_LIST => deserialize_guid_list(bytes, cursor, attribute, size),
+        qtype::BYTE_LIST => deserialize_byte_list(bytes, cursor, attribute, size),
+        qtype::SHORT_LIST => build_list!(bytes, header, encode, qtype::SHORT_LIST, i16),
+        qtype::INT_LIST => build_list!(bytes, header, encode, qtype::INT_LIST, i32),
+        qtype::LONG_LIST => build_list!(bytes, header, encode, qtype::LONG_LIST, i64),
+        qtype::REAL_LIST => build_list!(bytes, header, encode, qtype::REAL_LIST, f32),
+        qtype::FLOAT_LIST => build_list!(bytes, header, encode, qtype::FLOAT_LIST, f64),
+        qtype::STRING => deserialize_string(bytes, cursor, attribute, size),
+        qtype::SYMBOL_LIST => deserialize_symbol_list(bytes, cursor, attribute, size),
+        qtype::TIMESTAMP_LIST => build_list!(bytes, header, encode, qtype::TIMESTAMP_LIST, i64),
+        qtype::MONTH_LIST => build_list!(bytes, header, encode, qtype::MONTH_LIST, i32),
+        qtype::DATE_LIST => build_list!(bytes, header, encode, qtype::DATE_LIST, i32),
+        qtype::DATETIME_LIST => build_list!(bytes, header, encode, qtype::DATETIME_LIST, f64),
+        qtype::TIMESPAN_LIST => build_list!(bytes, header, encode, qtype::TIMESPAN_LIST, i64),
+        qtype::MINUTE_LIST => build_list!(bytes, header, encode, qtype::MINUTE_LIST, i32),
+        qtype::SECOND_LIST => build_list!(bytes, header, encode, qtype::SECOND_LIST, i32),
+        qtype::TIME_LIST => build_list!(bytes, header, encode, qtype::TIME_LIST, i32),
+        _ => unreachable!(),
+    }
+}
+
+fn deserialize_bool(bytes: &[u8], cursor: usize, _: u8) -> (K, usize) {
     (K::new_bool(bytes[cursor] != 0), cursor + 1)
 }
 
-fn deserialize_guid(bytes: &Vec<u8>, cursor: usize, _: u8) -> (K, usize) {
+fn deserialize_guid(bytes: &[u8], cursor: usize, _: u8) -> (K, usize) {
     (
         K::new_guid(bytes[cursor..cursor + 16].try_into().unwrap()),
         cursor + 16,
     )
 }
 
-fn deserialize_byte(bytes: &Vec<u8>, cursor: usize, _: u8) -> (K, usize) {
+fn deserialize_byte(bytes: &[u8], cursor: usize, _: u8) -> (K, usize) {
     (K::new_byte(bytes[cursor]), cursor + 1)
 }
 
-fn deserialize_char(bytes: &Vec<u8>, cursor: usize, _: u8) -> (K, usize) {
+fn deserialize_char(bytes: &[u8], cursor: usize, _: u8) -> (K, usize) {
     (
         K::new(qtype::CHAR, qattribute::NONE, k0_inner::byte(bytes[cursor])),
         cursor + 1,
     )
 }
 
-fn deserialize_symbol(bytes: &Vec<u8>, cursor: usize, _: u8) -> (K, usize) {
+fn deserialize_symbol(bytes: &[u8], cursor: usize, _: u8) -> (K, usize) {
     let null_location = bytes
         .split_at(cursor)
         .1
         .iter()
         .position(|b| *b == 0x00)
         .unwrap();
-    let k =
-        K::new_symbol(String::from_utf8(bytes[cursor..cursor + null_location].to_vec()).unwrap());
+    let k = K::new_symbol(std::str::from_utf8(&bytes[cursor..cursor + null_location]).unwrap());
     (k, cursor + null_location + 1)
 }
 
 /// Extract attribute and list length and then proceed the cursor.
-fn get_attribute_and_size(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (i8, usize, usize) {
+fn get_attribute_and_size(bytes: &[u8], cursor: usize, encode: u8) -> (i8, usize, usize) {
     let size = match encode {
         0 => u32::from_be_bytes(bytes[cursor + 1..cursor + 5].try_into().unwrap()),
         _ => u32::from_le_bytes(bytes[cursor + 1..cursor + 5].try_into().unwrap()),
@@ -275,8 +289,7 @@ fn get_attribute_and_size(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (i8, us
     (bytes[cursor] as i8, size as usize, cursor + 5)
 }
 
-fn deserialize_bool_list(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usize) {
-    let (attribute, size, cursor) = get_attribute_and_size(bytes, cursor, encode);
+fn deserialize_bool_list(bytes: &[u8], cursor: usize, attribute: i8, size: usize) -> (K, usize) {
     let list = bytes[cursor..cursor + size].to_vec();
     (
         K::new(
@@ -288,8 +301,7 @@ fn deserialize_bool_list(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usiz
     )
 }
 
-async fn deserialize_guid_list(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usize) {
-    let (attribute, size, cursor) = get_attribute_and_size(bytes, cursor, encode);
+fn deserialize_guid_list(bytes: &[u8], cursor: usize, attribute: i8, size: usize) -> (K, usize) {
     let list = bytes[cursor..cursor + 16 * size]
         .chunks(16)
         .map(|guid| guid.try_into().unwrap())
@@ -297,14 +309,12 @@ async fn deserialize_guid_list(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K
     (K::new_guid_list(list, attribute), cursor + 16 * size)
 }
 
-fn deserialize_byte_list(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usize) {
-    let (attribute, size, cursor) = get_attribute_and_size(bytes, cursor, encode);
+fn deserialize_byte_list(bytes: &[u8], cursor: usize, attribute: i8, size: usize) -> (K, usize) {
     let list = bytes[cursor..cursor + size].to_vec();
     (K::new_byte_list(list, attribute), cursor + size)
 }
 
-fn deserialize_string(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usize) {
-    let (attribute, size, cursor) = get_attribute_and_size(bytes, cursor, encode);
+fn deserialize_string(bytes: &[u8], cursor: usize, attribute: i8, size: usize) -> (K, usize) {
     (
         K::new_string(
             String::from_utf8(bytes[cursor..cursor + size].to_vec()).unwrap(),
@@ -314,61 +324,85 @@ fn deserialize_string(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usize)
     )
 }
 
-async fn deserialize_symbol_list(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usize) {
-    let (attribute, size, mut cursor) = get_attribute_and_size(bytes, cursor, encode);
-    let mut list = Vec::<String>::new();
-    for _ in 0..size {
-        let null_location = bytes
-            .split_at(cursor)
-            .1
-            .iter()
-            .position(|b| *b == 0x00)
-            .unwrap();
-        list.push(String::from_utf8(bytes[cursor..cursor + null_location].to_vec()).unwrap());
-        cursor += null_location + 1;
-    }
-    (K::new_symbol_list(list, attribute), cursor)
+fn deserialize_symbol_list(
+    bytes: &[u8],
+    mut cursor: usize,
+    attribute: i8,
+    size: usize,
+) -> (K, usize) {
+    // Intern symbols so that each distinct symbol is allocated only once.
+    let list = SymbolPool::intern_list(|pool| {
+        let mut list = Vec::<S>::with_capacity(size);
+        for _ in 0..size {
+            let null_location = bytes
+                .split_at(cursor)
+                .1
+                .iter()
+                .position(|b| *b == 0x00)
+                .unwrap();
+            list.push(
+                pool.intern(std::str::from_utf8(&bytes[cursor..cursor + null_location]).unwrap()),
+            );
+            cursor += null_location + 1;
+        }
+        list
+    });
+    (
+        K::new(
+            qtype::SYMBOL_LIST,
+            attribute,
+            k0_inner::list(k0_list::new(list)),
+        ),
+        cursor,
+    )
 }
 
-async fn deserialize_compound_list(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usize) {
+fn deserialize_compound_list(bytes: &[u8], cursor: usize, encode: u8) -> (K, usize) {
     let (_, size, cursor) = get_attribute_and_size(bytes, cursor, encode);
     let mut list = Vec::<K>::new();
     let mut cursor_ = cursor;
     for _ in 0..size {
-        let (element, cursor) = deserialize_bytes(bytes, cursor_, encode).await;
+        let (element, cursor) = deserialize_bytes(bytes, cursor_, encode);
         list.push(element);
         cursor_ = cursor;
     }
     (K::new_compound_list(list), cursor_)
 }
 
-async fn deserialize_table(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usize) {
+fn deserialize_table(bytes: &[u8], cursor: usize, encode: u8) -> (K, usize) {
     // Skip table attribute 0x00, dictionary indicator 99 and symbol list indicator 11
-    let (headers, cursor) = deserialize_symbol_list(bytes, cursor + 3, encode).await;
+    let (attribute, size, cursor) = get_attribute_and_size(bytes, cursor + 3, encode);
+    let (headers, cursor) = deserialize_symbol_list(bytes, cursor, attribute, size);
     // Skip compound list indicator 0
-    let (columns, cursor) = deserialize_compound_list(bytes, cursor + 1, encode).await;
+    let (columns, cursor) = deserialize_compound_list(bytes, cursor + 1, encode);
     // Trust kdb+. Should not fail.
     let dictionary = K::new_dictionary(headers, columns).expect("failed to build a dictionary");
     (
-        K::new(qtype::TABLE, qattribute::NONE, k0_inner::table(dictionary)),
+        K::new(
+            qtype::TABLE,
+            qattribute::NONE,
+            k0_inner::table(Box::new(dictionary)),
+        ),
         cursor,
     )
 }
 
-async fn deserialize_dictionary(bytes: &Vec<u8>, cursor: usize, encode: u8) -> (K, usize) {
-    let (keys, cursor) = deserialize_bytes(bytes, cursor, encode).await;
-    let (values, cursor) = deserialize_bytes(bytes, cursor, encode).await;
-    (
-        K::new_dictionary(keys, values).expect("failed to build a dictionary"),
-        cursor,
-    )
+fn deserialize_dictionary(bytes: &[u8], cursor: usize, encode: u8, qtype: i8) -> (K, usize) {
+    let (keys, cursor) = deserialize_bytes(bytes, cursor, encode);
+    let (values, cursor) = deserialize_bytes(bytes, cursor, encode);
+    let mut dictionary = K::new_dictionary(keys, values).expect("failed to build a dictionary");
+    if qtype == qtype::SORTED_DICTIONARY {
+        // Keep the type on the wire even if the keys came without the sorted attribute.
+        dictionary.set_sorted_keys(true);
+    }
+    (dictionary, cursor)
 }
 
-fn deserialize_null(_: &Vec<u8>, cursor: usize, _: u8) -> (K, usize) {
+fn deserialize_null(_: &[u8], cursor: usize, _: u8) -> (K, usize) {
     (K::new_null(), cursor + 1)
 }
 
-fn deserialize_error(bytes: &Vec<u8>, cursor: usize, _: u8) -> (K, usize) {
+fn deserialize_error(bytes: &[u8], cursor: usize, _: u8) -> (K, usize) {
     let null_location = bytes
         .split_at(cursor)
         .1
@@ -0,0 +1,739 @@
+//! This module provides lookup, upsert and removal by key on q dictionary and keyed table.
+//!
+//! Keys are looked up through a hash index from a key to its position which is built on the first lookup
+//!  and cached in the dictionary. The index is maintained on upsert and dropped when the dictionary is
+//!  accessed mutably in other ways, e.g., with `as_mut_vec` or `remove_key`.
+//!
+//! Keys are compared with q's match semantics, i.e., types must be identical (`1i` does not match `1j`)
+//!  and null matches null.
+//!
+//! # Example
+//! ```
+//! use kdbplus::qattribute;
+//! use kdbplus::ipc::*;
+//!
+//! fn main() {
+//!     let mut q_dictionary = K::new_dictionary(
+//!         K::new_symbol_list(vec![String::from("a"), String::from("b")], qattribute::NONE),
+//!         K::new_long_list(vec![1, 2], qattribute::NONE),
+//!     )
+//!     .unwrap();
+//!     assert_eq!(q_dictionary.get(&K::new_symbol("b")).unwrap().get_long(), Ok(2));
+//!
+//!     q_dictionary.upsert(K::new_symbol("c"), K::new_long(3)).unwrap();
+//!     q_dictionary.upsert(K::new_symbol("a"), K::new_long(10)).unwrap();
+//!     assert_eq!(format!("{}", q_dictionary), String::from("`a`b`c!10 2 3"));
+//!
+//!     q_dictionary.remove_key(&K::new_symbol("b")).unwrap();
+//!     assert!(!q_dictionary.contains_key(&K::new_symbol("b")).unwrap());
+//! }
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Evaluate an expression with the element type of a simple list (except string) bound to `$element` and
+///  the variant of `k0_inner` holding its atom bound to `$wrap`. `$otherwise` is evaluated for other types.
+macro_rules! with_simple_list_type {
+    ($qtype: expr, |$element: ident, $wrap: ident| $body: expr, $otherwise: expr) => {
+        match $qtype {
+            qtype::BOOL_LIST | qtype::BYTE_LIST => {
+                type $element = G;
+                let $wrap: fn(G) -> k0_inner = k0_inner::byte;
+                $body
+            }
+            qtype::GUID_LIST => {
+                type $element = U;
+                let $wrap: fn(U) -> k0_inner = k0_inner::guid;
+                $body
+            }
+            qtype::SHORT_LIST => {
+                type $element = H;
+                let $wrap: fn(H) -> k0_inner = k0_inner::short;
+                $body
+            }
+            qtype::INT_LIST
+            | qtype::MONTH_LIST
+            | qtype::DATE_LIST
+            | qtype::MINUTE_LIST
+            | qtype::SECOND_LIST
+            | qtype::TIME_LIST => {
+                type $element = I;
+                let $wrap: fn(I) -> k0_inner = k0_inner::int;
+                $body
+            }
+            qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+                type $element = J;
+                let $wrap: fn(J) -> k0_inner = k0_inner::long;
+                $body
+            }
+            qtype::REAL_LIST => {
+                type $element = E;
+                let $wrap: fn(E) -> k0_inner = k0_inner::real;
+                $body
+            }
+            qtype::FLOAT_LIST | qtype::DATETIME_LIST => {
+                type $element = F;
+                let $wrap: fn(F) -> k0_inner = k0_inner::float;
+                $body
+            }
+            qtype::SYMBOL_LIST => {
+                type $element = S;
+                let $wrap: fn(S) -> k0_inner = k0_inner::symbol;
+                $body
+            }
+            _ => $otherwise,
+        }
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% KeyValue %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Hashable value of a key element.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum KeyValue {
+    byte(G),
+    guid(U),
+    short(H),
+    int(I),
+    long(J),
+    /// Bits of a real value. All nulls have the same bits.
+    real(u32),
+    /// Bits of a float value. All nulls have the same bits.
+    float(u64),
+    symbol(S),
+    /// Non-atom object is compared by its IPC encoding.
+    other(Vec<u8>),
+}
+
+/// Key of a dictionary or a tuple of key columns of a keyed table. Each element holds its type.
+type Key = Vec<(i8, KeyValue)>;
+
+//%% KeyIndex %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Cache of a hash index from a key to its first position in a dictionary.
+#[derive(Clone, Default)]
+pub(crate) struct KeyIndex(OnceLock<Arc<HashMap<Key, usize>>>);
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% KeyIndex %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl KeyIndex {
+    /// Drop the index.
+    pub(crate) fn clear(&mut self) {
+        self.0 = OnceLock::new();
+    }
+}
+
+impl fmt::Debug for KeyIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.get() {
+            Some(index) => write!(f, "KeyIndex({} keys)", index.len()),
+            None => write!(f, "KeyIndex(not built)"),
+        }
+    }
+}
+
+//%% K %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl K {
+    /// Get a value associated with a key from a q dictionary. For a keyed table, the key is a tuple of
+    ///  key columns (an atom for a single key column) and a row of the value table is returned as a dictionary.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let q_table = K::new_dictionary(
+    ///         K::new_symbol_list(
+    ///             vec![String::from("sym"), String::from("date"), String::from("price")],
+    ///             qattribute::NONE,
+    ///         ),
+    ///         K::new_compound_list(vec![
+    ///             K::new_symbol_list(vec![String::from("AAPL"), String::from("AAPL")], qattribute::NONE),
+    ///             K::new_temporal_list(vec![QDate::from_raw(0), QDate::from_raw(1)], qattribute::NONE),
+    ///             K::new_float_list(vec![101.5, 102.25], qattribute::NONE),
+    ///         ]),
+    ///     )
+    ///     .unwrap()
+    ///     .flip()
+    ///     .unwrap();
+    ///     let q_keyed_table = q_table.enkey(2).unwrap();
+    ///
+    ///     let key = K::new_compound_list(vec![K::new_symbol("AAPL"), K::from(QDate::from_raw(1))]);
+    ///     assert_eq!(
+    ///         format!("{}", q_keyed_table.get(&key).unwrap()),
+    ///         String::from(",`price!,102.25")
+    ///     );
+    /// }
+    /// ```
+    pub fn get(&self, key: &K) -> Result<K> {
+        let index = self
+            .find_key("get", key)?
+            .ok_or_else(|| Error::no_such_key(key))?;
+        Ok(element_at(&self.as_vec::<K>().unwrap()[1], index))
+    }
+
+    /// Get a mutable value associated with a key from a q dictionary whose values are a compound list.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let mut q_dictionary = K::new_dictionary(
+    ///         K::new_symbol_list(vec![String::from("a"), String::from("b")], qattribute::NONE),
+    ///         K::new_compound_list(vec![
+    ///             K::new_long_list(vec![1, 2], qattribute::NONE),
+    ///             K::new_string(String::from("text"), qattribute::NONE),
+    ///         ]),
+    ///     )
+    ///     .unwrap();
+    ///     q_dictionary.get_mut(&K::new_symbol("a")).unwrap().push(&3_i64).unwrap();
+    ///     assert_eq!(format!("{}", q_dictionary), String::from("`a`b!(1 2 3;\"text\")"));
+    /// }
+    /// ```
+    pub fn get_mut(&mut self, key: &K) -> Result<&mut K> {
+        let index = self
+            .find_key("get_mut", key)?
+            .ok_or_else(|| Error::no_such_key(key))?;
+        // Values can be modified without dropping the index of keys.
+        let values = &mut self.pair_mut()[1];
+        match values.0.qtype {
+            qtype::COMPOUND_LIST => Ok(&mut values.as_mut_vec::<K>().unwrap()[index]),
+            _ => Err(Error::invalid_operation(
+                "get_mut",
+                values.0.qtype,
+                Some(qtype::COMPOUND_LIST),
+            )),
+        }
+    }
+
+    /// Check if a q dictionary or a keyed table has a key.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let q_dictionary = K::new_dictionary(
+    ///         K::new_int_list(vec![1, 2], qattribute::NONE),
+    ///         K::new_symbol_list(vec![String::from("a"), String::from("b")], qattribute::NONE),
+    ///     )
+    ///     .unwrap();
+    ///     assert_eq!(q_dictionary.contains_key(&K::new_int(2)), Ok(true));
+    ///     // Type must match.
+    ///     assert_eq!(q_dictionary.contains_key(&K::new_long(2)), Ok(false));
+    /// }
+    /// ```
+    pub fn contains_key(&self, key: &K) -> Result<bool> {
+        Ok(self.find_key("contains_key", key)?.is_some())
+    }
+
+    /// Update a value associated with a key or add the key-value pair if the key does not exist, as `upsert`
+    ///  does in q. Sorted dictionary becomes a normal dictionary if the keys are no longer sorted.
+    /// # Parameters
+    /// - `key`: Key atom of a dictionary. For a keyed table, a tuple of key columns as a list or an atom for a single
+    ///   key column.
+    /// - `value`: Value atom of a dictionary. For a keyed table, a row of the value table as one of:
+    ///   - dictionary from column names to values. For a new row, missing columns are filled with nulls.
+    ///   - list of values in the order of value columns.
+    ///   - atom for a single value column.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let mut q_keyed_table = K::new_dictionary(
+    ///         K::new_symbol_list(
+    ///             vec![String::from("sym"), String::from("price"), String::from("size")],
+    ///             qattribute::NONE,
+    ///         ),
+    ///         K::new_compound_list(vec![
+    ///             K::new_symbol_list(vec![String::from("AAPL")], qattribute::NONE),
+    ///             K::new_float_list(vec![101.5], qattribute::NONE),
+    ///             K::new_long_list(vec![100], qattribute::NONE),
+    ///         ]),
+    ///     )
+    ///     .unwrap()
+    ///     .flip()
+    ///     .unwrap()
+    ///     .enkey(1)
+    ///     .unwrap();
+    ///
+    ///     // Update
+    ///     let row = K::new_dictionary(
+    ///         K::new_symbol_list(vec![String::from("price")], qattribute::NONE),
+    ///         K::new_float_list(vec![102.0], qattribute::NONE),
+    ///     )
+    ///     .unwrap();
+    ///     q_keyed_table.upsert(K::new_symbol("AAPL"), row.clone()).unwrap();
+    ///     // Insert
+    ///     q_keyed_table.upsert(K::new_symbol("MSFT"), row).unwrap();
+    ///     assert_eq!(
+    ///         format!("{}", q_keyed_table),
+    ///         String::from("(+,`sym!,`AAPL`MSFT)!(+`price`size!(102 102;100 0N))")
+    ///     );
+    /// }
+    /// ```
+    pub fn upsert(&mut self, key: K, value: K) -> Result<()> {
+        let position = self.find_key("upsert", &key)?;
+        let key_hash = self.key_of(&key)?;
+        let pair = self.pair_mut();
+        match position {
+            Some(index) => match pair[1].0.qtype {
+                qtype::TABLE => set_row(&mut pair[1], index, &value),
+                _ => set_element(&mut pair[1], index, value),
+            },
+            None => {
+                let index = pair[0].len();
+                // Validate both key and value before modifying the dictionary.
+                let key_row = match pair[0].0.qtype {
+                    qtype::TABLE => {
+                        let row = (0..key_hash.len())
+                            .map(|i| match key.0.qtype {
+                                _t @ qtype::COMPOUND_LIST..=qtype::TIME_LIST => {
+                                    Some(element_at(&key, i))
+                                }
+                                _ => Some(key.clone()),
+                            })
+                            .collect::<Vec<Option<K>>>();
+                        row.iter()
+                            .zip(table_columns(&pair[0]).iter())
+                            .try_for_each(|(element, column)| {
+                                check_element(column, element.as_ref().unwrap())
+                            })?;
+                        Some(row)
+                    }
+                    _ => {
+                        check_element(&pair[0], &key)?;
+                        None
+                    }
+                };
+                if pair[1].0.qtype == qtype::TABLE {
+                    check_row(&pair[1], &value)?;
+                } else {
+                    check_element(&pair[1], &value)?;
+                }
+                match key_row {
+                    Some(row) => push_row(&mut pair[0], row)?,
+                    None => push_element(&mut pair[0], key)?,
+                }
+                match pair[1].0.qtype {
+                    qtype::TABLE => {
+                        let row = row_elements(&pair[1], &value)?;
+                        push_row(&mut pair[1], row)?;
+                    }
+                    _ => push_element(&mut pair[1], value)?,
+                }
+                if self.0.qtype == qtype::SORTED_DICTIONARY {
+                    let keys = &self.as_vec::<K>().unwrap()[0];
+                    let sorted = match keys.0.qtype {
+                        qtype::TABLE => rows_are_sorted(keys, index),
+                        _ => has_sorted_keys(keys),
+                    };
+                    if !sorted {
+                        self.set_sorted_keys(false);
+                    }
+                }
+                // Maintain the index if it was already built.
+                if let Some(index_map) = self.key_index_mut().0.get_mut() {
+                    Arc::make_mut(index_map).insert(key_hash, index);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove a key and its value from a q dictionary or a keyed table and return the removed value.
+    ///  For a keyed table, the removed row of the value table is returned as a dictionary.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let mut q_dictionary = K::new_dictionary(
+    ///         K::new_long_list(vec![1, 2, 3], qattribute::NONE),
+    ///         K::new_float_list(vec![0.5, 1.5, 2.5], qattribute::NONE),
+    ///     )
+    ///     .unwrap();
+    ///     let removed = q_dictionary.remove_key(&K::new_long(2)).unwrap();
+    ///     assert_eq!(removed.get_float(), Ok(1.5));
+    ///     assert_eq!(format!("{}", q_dictionary), String::from("1 3!0.5 2.5"));
+    /// }
+    /// ```
+    pub fn remove_key(&mut self, key: &K) -> Result<K> {
+        let index = self
+            .find_key("remove_key", key)?
+            .ok_or_else(|| Error::no_such_key(key))?;
+        // Positions of the following keys are shifted.
+        let pair = self.as_mut_vec::<K>().unwrap();
+        match pair[0].0.qtype {
+            qtype::TABLE => {
+                remove_row(&mut pair[0], index)?;
+            }
+            _ => {
+                pair[0].remove(index)?;
+            }
+        }
+        match pair[1].0.qtype {
+            qtype::TABLE => remove_row(&mut pair[1], index),
+            _ => pair[1].remove(index),
+        }
+    }
+
+    /// Find a position of a key in a dictionary building the index if it has not been built yet.
+    fn find_key(&self, operator: &'static str, key: &K) -> Result<Option<usize>> {
+        match self.0.qtype {
+            qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+                let key = self.key_of(key)?;
+                let index = self.key_index().0.get_or_init(|| {
+                    let keys = &self.as_vec::<K>().unwrap()[0];
+                    let mut index = HashMap::with_capacity(keys.len());
+                    for i in 0..keys.len() {
+                        // Keep the first position as q does.
+                        index.entry(key_at(keys, i)).or_insert(i);
+                    }
+                    Arc::new(index)
+                });
+                Ok(index.get(&key).copied())
+            }
+            _ => Err(Error::invalid_operation(
+                operator,
+                self.0.qtype,
+                Some(qtype::DICTIONARY),
+            )),
+        }
+    }
+
+    /// Get the index of keys of a dictionary.
+    fn key_index(&self) -> &KeyIndex {
+        match &self.0.value {
+            k0_inner::list(k0_list::dictionary(_, index)) => index,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Get the mutable index of keys of a dictionary.
+    fn key_index_mut(&mut self) -> &mut KeyIndex {
+        match &mut self.0.value {
+            k0_inner::list(k0_list::dictionary(_, index)) => index,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Build a hashable key from a key given to a dictionary or a keyed table.
+    fn key_of(&self, key: &K) -> Result<Key> {
+        let keys = &self.as_vec::<K>().unwrap()[0];
+        match keys.0.qtype {
+            qtype::TABLE => {
+                let width = keys.get_dictionary().unwrap().as_vec::<K>().unwrap()[0].len();
+                match key.0.qtype {
+                    _t @ qtype::COMPOUND_LIST..=qtype::TIME_LIST => {
+                        if key.len() == width {
+                            Ok((0..width)
+                                .map(|i| key_element(&element_at(key, i)))
+                                .collect())
+                        } else {
+                            Err(Error::length_mismatch(width, key.len()))
+                        }
+                    }
+                    _ if width == 1 => Ok(vec![key_element(key)]),
+                    _ => Err(Error::length_mismatch(width, 1)),
+                }
+            }
+            _ => Ok(vec![key_element(key)]),
+        }
+    }
+
+    /// Get a mutable pair of keys and values keeping the index.
+    fn pair_mut(&mut self) -> &mut Vec<K> {
+        match &mut self.0.value {
+            k0_inner::list(list) => list.as_mut_vec::<K>().unwrap(),
+            _ => unreachable!(),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Key %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Build a hashable key element from a q object.
+fn key_element(object: &K) -> (i8, KeyValue) {
+    let value = match &object.0.value {
+        _ if object.0.qtype >= 0 => KeyValue::other(object.q_ipc_encode()),
+        k0_inner::byte(byte) => KeyValue::byte(*byte),
+        k0_inner::guid(guid) => KeyValue::guid(*guid),
+        k0_inner::short(short) => KeyValue::short(*short),
+        k0_inner::int(int) => KeyValue::int(*int),
+        k0_inner::long(long) => KeyValue::long(*long),
+        k0_inner::real(real) => KeyValue::real(match real.is_nan() {
+            true => E::NAN.to_bits(),
+            false => (real + 0.0).to_bits(),
+        }),
+        k0_inner::float(float) => KeyValue::float(match float.is_nan() {
+            true => F::NAN.to_bits(),
+            false => (float + 0.0).to_bits(),
+        }),
+        k0_inner::symbol(symbol) => KeyValue::symbol(symbol.clone()),
+        _ => KeyValue::other(object.q_ipc_encode()),
+    };
+    (object.0.qtype, value)
+}
+
+/// Build a hashable key of `index`-th element of keys.
+fn key_at(keys: &K, index: usize) -> Key {
+    match keys.0.qtype {
+        qtype::TABLE => table_columns(keys)
+            .iter()
+            .map(|column| key_element(&element_at(column, index)))
+            .collect(),
+        _ => vec![key_element(&element_at(keys, index))],
+    }
+}
+
+//%% Element %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Get `index`-th element of a list as `K`. A row of a table is returned as a dictionary.
+fn element_at(list: &K, index: usize) -> K {
+    with_simple_list_type!(
+        list.0.qtype,
+        |T, wrap| K::new(
+            -list.0.qtype,
+            qattribute::NONE,
+            wrap(list.as_vec::<T>().unwrap().get(index).cloned().unwrap())
+        ),
+        match list.0.qtype {
+            qtype::STRING => K::new_char(list.as_string().unwrap().as_bytes()[index] as char),
+            qtype::COMPOUND_LIST => list.as_vec::<K>().unwrap()[index].clone(),
+            qtype::TABLE => {
+                let dictionary = list.get_dictionary().unwrap().as_vec::<K>().unwrap();
+                let values = dictionary[1]
+                    .as_vec::<K>()
+                    .unwrap()
+                    .iter()
+                    .map(|column| element_at(column, index))
+                    .collect();
+                K::new_dictionary(dictionary[0].clone(), K::new_compound_list(values)).unwrap()
+            }
+            _ => unreachable!(),
+        }
+    )
+}
+
+/// Get an atom value of a type `T` from an atom.
+fn atom_value<T>(atom: &K) -> Option<T>
+where
+    T: Clone + 'static,
+{
+    let value: &dyn Any = match &atom.0.value {
+        k0_inner::byte(value) => value,
+        k0_inner::guid(value) => value,
+        k0_inner::short(value) => value,
+        k0_inner::int(value) => value,
+        k0_inner::long(value) => value,
+        k0_inner::real(value) => value,
+        k0_inner::float(value) => value,
+        k0_inner::symbol(value) => value,
+        _ => return None,
+    };
+    value.downcast_ref::<T>().cloned()
+}
+
+/// Check if an element can be stored in a list.
+fn check_element(list: &K, element: &K) -> Result<()> {
+    match list.0.qtype {
+        qtype::COMPOUND_LIST => Ok(()),
+        _ if element.0.qtype == -list.0.qtype => Ok(()),
+        _ => Err(Error::invalid_cast(element.0.qtype, -list.0.qtype)),
+    }
+}
+
+/// Add an element to the tail of a list.
+fn push_element(list: &mut K, element: K) -> Result<()> {
+    check_element(list, &element)?;
+    let index = list.len();
+    with_simple_list_type!(
+        list.0.qtype,
+        |T, _wrap| list
+            .as_mut_vec::<T>()
+            .unwrap()
+            .push(atom_value::<T>(&element).unwrap()),
+        match list.0.qtype {
+            qtype::STRING => list
+                .as_mut_string()
+                .unwrap()
+                .push(element.get_char().unwrap()),
+            qtype::COMPOUND_LIST => list.as_mut_vec::<K>().unwrap().push(element),
+            _ => return Err(Error::invalid_operation("upsert", list.0.qtype, None)),
+        }
+    );
+    list.update_attribute_at(index);
+    Ok(())
+}
+
+/// Replace `index`-th element of a list.
+fn set_element(list: &mut K, index: usize, element: K) -> Result<()> {
+    check_element(list, &element)?;
+    with_simple_list_type!(
+        list.0.qtype,
+        |T, _wrap| list.as_mut_vec::<T>().unwrap()[index] = atom_value::<T>(&element).unwrap(),
+        match list.0.qtype {
+            qtype::STRING => {
+                let string = list.as_mut_string().unwrap();
+                let character = element.get_char().unwrap().to_string();
+                string.replace_range(index..index + 1, &character);
+            }
+            qtype::COMPOUND_LIST => list.as_mut_vec::<K>().unwrap()[index] = element,
+            _ => return Err(Error::invalid_operation("upsert", list.0.qtype, None)),
+        }
+    );
+    list.update_attribute_at(index);
+    Ok(())
+}
+
+/// Add a null element to the tail of a list.
+fn push_null(list: &mut K) -> Result<()> {
+    let null = match list.0.qtype {
+        qtype::BOOL_LIST | qtype::BYTE_LIST => k0_inner::byte(0),
+        qtype::GUID_LIST => k0_inner::guid(qnull_base::U),
+        qtype::SHORT_LIST => k0_inner::short(qnull_base::H),
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => k0_inner::int(qnull_base::I),
+        qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+            k0_inner::long(qnull_base::J)
+        }
+        qtype::REAL_LIST => k0_inner::real(qnull_base::E),
+        qtype::FLOAT_LIST | qtype::DATETIME_LIST => k0_inner::float(qnull_base::F),
+        qtype::SYMBOL_LIST => k0_inner::symbol(Symbol::default()),
+        qtype::STRING => k0_inner::byte(b' '),
+        _ => return push_element(list, K::new_null()),
+    };
+    push_element(list, K::new(-list.0.qtype, qattribute::NONE, null))
+}
+
+//%% Table %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Get columns of a table.
+fn table_columns(table: &K) -> &Vec<K> {
+    table.get_dictionary().unwrap().as_vec::<K>().unwrap()[1]
+        .as_vec::<K>()
+        .unwrap()
+}
+
+/// Get mutable columns of a table.
+fn table_columns_mut(table: &mut K) -> &mut Vec<K> {
+    table
+        .get_mut_dictionary()
+        .unwrap()
+        .as_mut_vec::<K>()
+        .unwrap()[1]
+        .as_mut_vec::<K>()
+        .unwrap()
+}
+
+/// Arrange a row given to a table in the order of columns. `None` denotes a missing column.
+fn row_elements(table: &K, row: &K) -> Result<Vec<Option<K>>> {
+    let dictionary = table.get_dictionary().unwrap().as_vec::<K>().unwrap();
+    let width = dictionary[0].len();
+    match row.0.qtype {
+        qtype::DICTIONARY => {
+            let names = dictionary[0].as_vec::<S>().unwrap();
+            let pair = row.as_vec::<K>().unwrap();
+            let given = pair[0]
+                .as_vec::<S>()
+                .map_err(|_| Error::invalid_cast_list(pair[0].0.qtype))?;
+            if let Some(unknown) = given.iter().find(|name| !names.contains(name)) {
+                return Err(Error::no_such_column(unknown.to_string()));
+            }
+            Ok(names
+                .iter()
+                .map(|name| {
+                    given
+                        .iter()
+                        .position(|given_name| given_name == name)
+                        .map(|i| element_at(&pair[1], i))
+                })
+                .collect())
+        }
+        _t @ qtype::COMPOUND_LIST..=qtype::TIME_LIST => {
+            if row.len() == width {
+                Ok((0..width).map(|i| Some(element_at(row, i))).collect())
+            } else {
+                Err(Error::length_mismatch(width, row.len()))
+            }
+        }
+        _ if width == 1 => Ok(vec![Some(row.clone())]),
+        _ => Err(Error::length_mismatch(width, 1)),
+    }
+}
+
+/// Check if a row can be added to a table.
+fn check_row(table: &K, row: &K) -> Result<()> {
+    row_elements(table, row)?
+        .iter()
+        .zip(table_columns(table).iter())
+        .try_for_each(|(element, column)| match element {
+            Some(element) => check_element(column, element),
+            None => Ok(()),
+        })
+}
+
+/// Add a row to the tail of a table. Missing columns are filled with nulls.
+fn push_row(table: &mut K, row: Vec<Option<K>>) -> Result<()> {
+    for (element, column) in row.into_iter().zip(table_columns_mut(table).iter_mut()) {
+        match element {
+            Some(element) => push_element(column, element)?,
+            None => push_null(column)?,
+        }
+    }
+    Ok(())
+}
+
+/// Replace `index`-th row of a table. Missing columns are kept.
+fn set_row(table: &mut K, index: usize, row: &K) -> Result<()> {
+    check_row(table, row)?;
+    let row = row_elements(table, row)?;
+    for (element, column) in row.into_iter().zip(table_columns_mut(table).iter_mut()) {
+        if let Some(element) = element {
+            set_element(column, index, element)?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove `index`-th row from a table and return it as a dictionary.
+fn remove_row(table: &mut K, index: usize) -> Result<K> {
+    let removed = element_at(table, index);
+    for column in table_columns_mut(table).iter_mut() {
+        column.remove(index)?;
+    }
+    Ok(removed)
+}
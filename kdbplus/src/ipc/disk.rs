@@ -0,0 +1,1145 @@
+//! This module provides functions to read objects which q writes to the disk, i.e., files written by
+//!  `` `:file set x ``, splayed tables and partitions of a partitioned database, and to write tables which q
+//!  can load with `\l`, without a q process.
+//!
+//! Files are memory-mapped when read and the following formats of kdb+ 3.x or later are supported:
+//! - A file written by `set` for a non-mappable object, e.g., a symbol list, a dictionary or a general list,
+//!   which is a serialized object following `0xff01`.
+//! - A file of a simple list, which is a 16-byte header starting with `0xfe20` and the elements.
+//! - A file of an enumerated symbol list, which is resolved with its domain file, e.g., `sym`.
+//! - A nested column of a splayed table stored as two files `col` and `col#`.
+//! - A compressed file of any of the above written by `-19!` or with `.z.zd`, which is decompressed when read.
+//!
+//! Data of attributes on the disk, e.g., the index of `p#`, are ignored.
+//!
+//! Reading, decoding, encoding and writing files, including waiting for the lock of a sym file, run on the thread
+//!  pool of tokio for blocking tasks so that they do not stall other tasks of the runtime.
+//!
+//! A compressed file starts with `kxzipped` followed by blocks, each of which is compressed data of a logical
+//!  block of `2^n` bytes of the original file, and ends with a trailer of the compressed size of each block as
+//!  a 32-bit integer and 16 bytes of the size of the original file as a 64-bit integer, the number of blocks as
+//!  a 32-bit integer, `n`, the algorithm, the level and a reserved byte. A block as large as its logical block
+//!  is stored without compression. Algorithms other than q IPC compression need a feature:
+//!
+//! | Algorithm   | Number | Feature       |
+//! |-------------|--------|---------------|
+//! | q IPC       | 1      | -             |
+//! | gzip        | 2      | `disk-gzip`   |
+//! | snappy      | 3      | `disk-snappy` |
+//! | lz4hc       | 4      | `disk-lz4`    |
+//! | zstd        | 5      | `disk-zstd`   |
+//!
+//! lz4hc blocks are written with the fast LZ4 encoder. See [`CompressionAlgorithm::Lz4hc`].
+//! # Example
+//! ```no_run
+//! use chrono::NaiveDate;
+//! use kdbplus::ipc::*;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     // q)`:/data/db/quote/ set .Q.en[`:/data/db] quote
+//!     let quote = disk::read_splayed("/data/db/quote").await?;
+//!     println!("{}", quote);
+//!
+//!     // q)select from trade where date=2024.01.02
+//!     let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+//!     let trade = disk::read_partition("/data/hdb", date, "trade").await?;
+//!     println!("{}", trade);
+//!
+//!     // q).Q.dpft[`:/data/hdb; 2024.01.03; `sym; `trade]
+//!     let date = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+//!     disk::write_partition("/data/hdb", date, "trade", &trade, Some("sym")).await?;
+//!
+//!     // q)-19!(`:/data/hdb/2024.01.03/trade/price; `:/data/hdb/2024.01.03/trade/price; 17; 1; 0)
+//!     let parameters = disk::CompressionParameters {
+//!         logical_block_size: 17,
+//!         algorithm: disk::CompressionAlgorithm::Q,
+//!         level: 0,
+//!     };
+//!     let price = "/data/hdb/2024.01.03/trade/price";
+//!     disk::compress_file(price, price, parameters).await?;
+//!     Ok(())
+//! }
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::codec::{compress, try_decompress};
+use super::serialize::ENCODING;
+use super::{ascending_rows, k0_inner, k0_list, qattribute, qtype, select_rows, Result, K, S};
+use chrono::NaiveDate;
+use memmap2::Mmap;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Deref;
+use std::path::Path;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Magic bytes of a file of a non-mappable object.
+const OBJECT_MAGIC: [u8; 2] = [0xff, 0x01];
+
+/// Magic bytes of a file of a mappable list.
+const LIST_MAGIC: [u8; 2] = [0xfe, 0x20];
+
+/// Magic bytes of a compressed file.
+const COMPRESSED_MAGIC: &[u8; 8] = b"kxzipped";
+
+/// Length of the trailer of a compressed file following the sizes of blocks.
+const COMPRESSED_TRAILER_LENGTH: usize = 16;
+
+/// Range of the logical block size of a compressed file as a power of 2.
+const LOGICAL_BLOCK_SIZES: std::ops::RangeInclusive<u8> = 12..=20;
+
+/// Block shorter than this is not compressed with q IPC compression, which needs some room for its header.
+const MIN_Q_COMPRESSED_BLOCK: usize = 64;
+
+/// Length of the header of a mappable list.
+const LIST_HEADER_LENGTH: usize = 16;
+
+/// Offset of the type of a nested column from the type of its elements.
+const NESTED_TYPE_OFFSET: i8 = 77;
+
+/// Name of the file of the column order of a splayed table.
+const COLUMN_ORDER_FILE: &str = ".d";
+
+/// Domain of an enumerated list which does not record its domain.
+const DEFAULT_DOMAIN: &str = "sym";
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% CompressionAlgorithm %%//vvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Algorithm of a compressed file. The discriminant is the number used by q.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    /// q IPC compression. Level must be 0.
+    Q = 1,
+    /// gzip of level from 0 to 9. Needs `disk-gzip` feature.
+    Gzip = 2,
+    /// snappy. Level must be 0. Needs `disk-snappy` feature.
+    Snappy = 3,
+    /// lz4hc of level from 0 to 16. Needs `disk-lz4` feature.
+    ///
+    /// Blocks are written with the fast LZ4 encoder, not the high compression one, so the level is recorded
+    ///  but ignored. q reads them since both encoders produce the LZ4 block format.
+    Lz4hc = 4,
+    /// zstd of level from 0 to 22. Needs `disk-zstd` feature.
+    Zstd = 5,
+}
+
+//%% CompressionParameters %%//vvvvvvvvvvvvvvvvvvvvvv/
+
+/// Parameters of [`compress_file`], which are the last three arguments of `-19!`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressionParameters {
+    /// Size of a logical block as a power of 2 from 12 to 20.
+    pub logical_block_size: u8,
+    /// Compression algorithm.
+    pub algorithm: CompressionAlgorithm,
+    /// Compression level whose range depends on the algorithm.
+    pub level: u8,
+}
+
+//%% FileBytes %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Content of a file which is mapped to memory or decompressed.
+enum FileBytes {
+    Mapped(Mmap),
+    Decompressed(Vec<u8>),
+}
+
+//%% Column %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Column of a table to write in the layout of the disk.
+enum Column {
+    /// Simple list except for symbol list. Holds the elements in the layout of the disk.
+    Simple {
+        qtype: i8,
+        attribute: i8,
+        element_size: usize,
+        data: Vec<u8>,
+    },
+    /// Symbol list enumerated when written.
+    Symbol { attribute: i8, symbols: Vec<S> },
+    /// List of simple lists of the same type. Holds the elements of each item.
+    Nested {
+        qtype: i8,
+        element_size: usize,
+        items: Vec<Vec<u8>>,
+    },
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% CompressionAlgorithm %%//vvvvvvvvvvvvvvvvvvvvvvv/
+
+impl CompressionAlgorithm {
+    /// Get an algorithm from the number used by q.
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(CompressionAlgorithm::Q),
+            2 => Some(CompressionAlgorithm::Gzip),
+            3 => Some(CompressionAlgorithm::Snappy),
+            4 => Some(CompressionAlgorithm::Lz4hc),
+            5 => Some(CompressionAlgorithm::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Get the feature needed by an algorithm.
+    fn feature(self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Q => "ipc",
+            CompressionAlgorithm::Gzip => "disk-gzip",
+            CompressionAlgorithm::Snappy => "disk-snappy",
+            CompressionAlgorithm::Lz4hc => "disk-lz4",
+            CompressionAlgorithm::Zstd => "disk-zstd",
+        }
+    }
+
+    /// Get the maximum compression level of an algorithm.
+    fn max_level(self) -> u8 {
+        match self {
+            CompressionAlgorithm::Q | CompressionAlgorithm::Snappy => 0,
+            CompressionAlgorithm::Gzip => 9,
+            CompressionAlgorithm::Lz4hc => 16,
+            CompressionAlgorithm::Zstd => 22,
+        }
+    }
+}
+
+//%% FileBytes %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Deref for FileBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileBytes::Mapped(bytes) => bytes,
+            FileBytes::Decompressed(bytes) => bytes,
+        }
+    }
+}
+
+//%% Column %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Column {
+    /// Convert a column of a table.
+    fn new(column: &K, header: &S) -> Result<Self> {
+        let unsupported = || invalid_input(&format!("column cannot be splayed: {}", header));
+        match column.get_type() {
+            qtype::SYMBOL_LIST => Ok(Column::Symbol {
+                attribute: disk_attribute(column.get_attribute()),
+                symbols: column.as_vec::<S>()?.clone(),
+            }),
+            qtype::COMPOUND_LIST => {
+                let list = column.as_vec::<K>()?;
+                // An empty nested column is regarded as a string column.
+                let qtype = list.first().map_or(qtype::STRING, |item| item.get_type());
+                let element_size = element_size(qtype).map_err(|_| unsupported())?;
+                let items = list
+                    .iter()
+                    .map(|item| match item.get_type() == qtype {
+                        true => Ok(item.q_ipc_encode().split_off(6)),
+                        false => Err(unsupported()),
+                    })
+                    .collect::<io::Result<Vec<Vec<u8>>>>()?;
+                Ok(Column::Nested {
+                    qtype,
+                    element_size,
+                    items,
+                })
+            }
+            qtype => Ok(Column::Simple {
+                qtype,
+                attribute: disk_attribute(column.get_attribute()),
+                element_size: element_size(qtype).map_err(|_| unsupported())?,
+                // Skip the type, the attribute and the length.
+                data: column.q_ipc_encode().split_off(6),
+            }),
+        }
+    }
+
+    /// Set an attribute written to the header. A nested column has no attribute.
+    fn set_attribute(&mut self, new_attribute: i8) {
+        match self {
+            Column::Simple { attribute, .. } | Column::Symbol { attribute, .. } => {
+                *attribute = new_attribute
+            }
+            Column::Nested { .. } => {}
+        }
+    }
+
+    /// Write the column to `path`. A nested column also writes `path#`.
+    fn write(self, path: &Path, domain: &str, enumeration: &HashMap<S, i32>) -> io::Result<()> {
+        match self {
+            Column::Simple {
+                qtype,
+                attribute,
+                element_size,
+                data,
+            } => {
+                let mut bytes = list_header(qtype, attribute, data.len() / element_size);
+                bytes.extend_from_slice(&data);
+                fs::write(path, bytes)
+            }
+            Column::Symbol { attribute, symbols } => {
+                // The domain name is padded to 8 bytes with at least one null.
+                let mut name = domain.as_bytes().to_vec();
+                name.resize((name.len() / 8 + 1) * 8, 0);
+                let mut bytes = list_header(qtype::ENUM_LIST, attribute, symbols.len());
+                bytes.splice(8..8, name);
+                for symbol in symbols.iter() {
+                    bytes.extend_from_slice(&enumeration[symbol].to_ne_bytes());
+                }
+                fs::write(path, bytes)
+            }
+            Column::Nested {
+                qtype,
+                element_size,
+                items,
+            } => {
+                let mut bytes =
+                    list_header(qtype + NESTED_TYPE_OFFSET, qattribute::NONE, items.len());
+                let mut end = 0;
+                for item in items.iter() {
+                    end += item.len() / element_size;
+                    bytes.extend_from_slice(&(end as i64).to_ne_bytes());
+                }
+                fs::write(path, bytes)?;
+                let mut bytes = list_header(qtype, qattribute::NONE, end);
+                for item in items.iter() {
+                    bytes.extend_from_slice(item);
+                }
+                let mut data_path = path.as_os_str().to_owned();
+                data_path.push("#");
+                fs::write(data_path, bytes)
+            }
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Read an object written by `` `:file set x ``.
+/// # Errors
+/// - IO error if the file cannot be read.
+/// - IO error of `InvalidData` if the file is not a q object or is an enumerated list.
+/// - IO error of `Unsupported` if the file is compressed with an algorithm whose feature is not enabled.
+///
+/// An enumerated list needs its domain and so read it as a column with [`read_splayed`].
+/// # Example
+/// ```no_run
+/// use kdbplus::ipc::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     // q)`:/data/prices set 101.5 102 103.5
+///     let prices = disk::read_object("/data/prices").await?;
+///     assert_eq!(prices.len(), 3);
+///     Ok(())
+/// }
+/// ```
+pub async fn read_object<P>(path: P) -> Result<K>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref().to_path_buf();
+    run_blocking(move || load_object(&path)).await
+}
+
+/// Read a splayed table. Enumerated columns are resolved with domain files, e.g., `sym`, in the parent
+///  directory of the table.
+/// # Parameters
+/// - `directory`: Directory of the table containing `.d` and column files.
+/// # Errors
+/// - IO error if a file cannot be read.
+/// - IO error of `InvalidData` if a file has an unsupported format.
+/// - IO error of `Unsupported` if a file is compressed with an algorithm whose feature is not enabled.
+/// - Error of building a table if the lengths of columns do not match.
+/// # Example
+/// See the example of [`disk`](index.html).
+pub async fn read_splayed<P>(directory: P) -> Result<K>
+where
+    P: AsRef<Path>,
+{
+    let directory = directory.as_ref().to_path_buf();
+    run_blocking(move || {
+        let database = directory.parent().unwrap_or_else(|| Path::new("."));
+        let (headers, columns) = read_columns(&directory, database)?;
+        K::new_dictionary(headers, K::new_compound_list(columns))?.flip()
+    })
+    .await
+}
+
+/// Read a table of a date partition of a partitioned database, adding the virtual column `date` at the head
+///  as `select from table where date=d` does.
+/// # Parameters
+/// - `database`: Root directory of the database containing `sym` and date directories.
+/// - `date`: Date of the partition.
+/// - `table`: Name of the table.
+/// # Errors
+/// The same errors as [`read_splayed`].
+/// # Example
+/// See the example of [`disk`](index.html).
+pub async fn read_partition<P>(database: P, date: NaiveDate, table: &str) -> Result<K>
+where
+    P: AsRef<Path>,
+{
+    let database = database.as_ref().to_path_buf();
+    let directory = database
+        .join(date.format("%Y.%m.%d").to_string())
+        .join(table);
+    let (headers, mut columns) = run_blocking(move || read_columns(&directory, &database)).await?;
+    let mut headers = headers.as_vec::<S>()?.clone();
+    headers.insert(0, S::from("date"));
+    let length = columns.first().map_or(0, |column| column.len());
+    columns.insert(0, K::new_date_list(vec![date; length], qattribute::NONE));
+    let headers = K::new(
+        qtype::SYMBOL_LIST,
+        qattribute::NONE,
+        k0_inner::list(k0_list::new(headers)),
+    );
+    K::new_dictionary(headers, K::new_compound_list(columns))?.flip()
+}
+
+/// Write a table as a splayed table. Symbol columns are enumerated against `sym_file` which is locked while
+///  new symbols are appended to it, so that multiple writers can share it. The domain of enumerated columns
+///  is the file name of `sym_file`, which q looks up in the parent directory of the table.
+/// # Parameters
+/// - `directory`: Directory of the table. Created if it does not exist and existing column files are overwritten.
+/// - `table`: Table to write. Columns must be simple lists or lists of simple lists of the same type, e.g., strings.
+///
+/// Only the sorted attribute of a column is written because the others need an index built by q.
+/// - `sym_file`: Domain file of enumerated symbol columns. Created if it does not exist.
+/// # Errors
+/// - IO error if a file cannot be written.
+/// - IO error of `InvalidInput` if `table` is not a table or has a column which cannot be splayed.
+/// # Example
+/// ```no_run
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let table = K::new_dictionary(
+///         K::new_symbol_list(vec![String::from("sym"), String::from("price")], qattribute::NONE),
+///         K::new_compound_list(vec![
+///             K::new_symbol_list(vec![String::from("AAPL"), String::from("MSFT")], qattribute::NONE),
+///             K::new_float_list(vec![185.5, 371.25], qattribute::NONE),
+///         ]),
+///     )?
+///     .flip()?;
+///     // q)`:/data/db/quote/ set .Q.en[`:/data/db] table
+///     disk::write_splayed("/data/db/quote", &table, "/data/db/sym").await
+/// }
+/// ```
+pub async fn write_splayed<P, Q>(directory: P, table: &K, sym_file: Q) -> Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let (headers, columns) = splay(table)?;
+    let directory = directory.as_ref().to_path_buf();
+    let sym_file = sym_file.as_ref().to_path_buf();
+    run_blocking(move || write_columns(&directory, &headers, columns, &sym_file)).await
+}
+
+/// Write a table to a date partition of a partitioned database, i.e., `` `:database/date/name/ ``. Symbol
+///  columns are enumerated against `sym` in the root directory as [`write_splayed`] does.
+/// # Parameters
+/// - `database`: Root directory of the database.
+/// - `date`: Date of the partition.
+/// - `name`: Name of the table.
+/// - `table`: Table to write.
+/// - `parted`: Column to which the parted attribute is applied as `.Q.dpft` does, i.e., rows are sorted by the
+///   column keeping the order of equal values, the column is written with `p#` and comes first in `.d`. The
+///   other columns lose their attributes. `None` writes rows and columns in the order of `table` keeping only
+///   the sorted attribute.
+///
+/// Only the attribute in the header of the `p#` column is written, not the index which q appends after the
+///  elements. Apply `` @[`:/data/hdb/2024.01.02/trade/; `sym; `p#] `` in q to write the index if necessary.
+/// # Errors
+/// - The same errors as [`write_splayed`].
+/// - IO error of `InvalidInput` if `parted` is not a column of `table` or is not a simple list.
+/// # Example
+/// See the example of [`disk`](index.html).
+pub async fn write_partition<P>(
+    database: P,
+    date: NaiveDate,
+    name: &str,
+    table: &K,
+    parted: Option<&str>,
+) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let database = database.as_ref();
+    let (headers, columns) = match parted {
+        Some(column) => {
+            let (headers, mut columns) = splay(&part(table, column)?)?;
+            columns[0].set_attribute(qattribute::PARTED);
+            (headers, columns)
+        }
+        None => splay(table)?,
+    };
+    let directory = database
+        .join(date.format("%Y.%m.%d").to_string())
+        .join(name);
+    let sym_file = database.join(DEFAULT_DOMAIN);
+    run_blocking(move || write_columns(&directory, &headers, columns, &sym_file)).await
+}
+
+/// Compress a file in the same layout as `` -19!(`:source; `:destination; n; algorithm; level) `` does. The source
+///  file is read whole before the destination is written, so that a file can be compressed in place.
+/// # Parameters
+/// - `source`: File to compress.
+/// - `destination`: Compressed file. Overwritten if it exists.
+/// - `parameters`: Logical block size, algorithm and level.
+/// # Errors
+/// - IO error if a file cannot be read or written.
+/// - IO error of `InvalidInput` if `source` is compressed already or a parameter is out of range.
+/// - IO error of `Unsupported` if the feature of the algorithm is not enabled.
+/// # Example
+/// See the example of [`disk`](index.html).
+pub async fn compress_file<P, Q>(
+    source: P,
+    destination: Q,
+    parameters: CompressionParameters,
+) -> Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    if !LOGICAL_BLOCK_SIZES.contains(&parameters.logical_block_size) {
+        return Err(invalid_input("logical block size must be from 12 to 20").into());
+    }
+    if parameters.level > parameters.algorithm.max_level() {
+        return Err(invalid_input(&format!(
+            "level of {:?} must be from 0 to {}",
+            parameters.algorithm,
+            parameters.algorithm.max_level()
+        ))
+        .into());
+    }
+    let source = source.as_ref().to_path_buf();
+    let destination = destination.as_ref().to_path_buf();
+    run_blocking(move || {
+        let bytes = fs::read(&source)?;
+        if bytes.starts_with(COMPRESSED_MAGIC) {
+            return Err(invalid_input("file is compressed already").into());
+        }
+
+        let block_size = 1_usize << parameters.logical_block_size;
+        let mut compressed = COMPRESSED_MAGIC.to_vec();
+        let mut sizes = Vec::new();
+        for block in bytes.chunks(block_size) {
+            let start = compressed.len();
+            match compress_block(block, &parameters)? {
+                data if data.len() < block.len() => compressed.extend(data),
+                _ => compressed.extend_from_slice(block),
+            }
+            sizes.push((compressed.len() - start) as u32);
+        }
+        for size in sizes.iter() {
+            compressed.extend_from_slice(&size.to_ne_bytes());
+        }
+        compressed.extend_from_slice(&(bytes.len() as u64).to_ne_bytes());
+        compressed.extend_from_slice(&(sizes.len() as u32).to_ne_bytes());
+        compressed.extend_from_slice(&[
+            parameters.logical_block_size,
+            parameters.algorithm as u8,
+            parameters.level,
+            0,
+        ]);
+        fs::write(&destination, compressed)?;
+        Ok(())
+    })
+    .await
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Run blocking file IO and decoding on the thread pool of tokio for blocking tasks. A panic is propagated.
+async fn run_blocking<T, F>(function: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::task::spawn_blocking(function).await {
+        Ok(result) => result,
+        Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+        Err(error) => Err(io::Error::other(error).into()),
+    }
+}
+
+/// Read an object written by `set`.
+fn load_object(path: &Path) -> Result<K> {
+    let bytes = load_file(path)?;
+    match list_type(&bytes)? {
+        None => Ok(decode_object(&bytes)),
+        Some(qtype::ENUM_LIST) => Err(invalid_data(path, "enumerated list").into()),
+        Some(_) => decode_list(&bytes),
+    }
+}
+
+/// Read the column names and columns of a splayed table.
+fn read_columns(directory: &Path, database: &Path) -> Result<(K, Vec<K>)> {
+    let headers = load_object(&directory.join(COLUMN_ORDER_FILE))?;
+    let mut columns = Vec::new();
+    for header in headers.as_vec::<S>()? {
+        columns.push(read_column(&directory.join(header.as_str()), database)?);
+    }
+    Ok((headers, columns))
+}
+
+/// Read a column file resolving an enumeration and a nested column.
+fn read_column(path: &Path, database: &Path) -> Result<K> {
+    let bytes = load_file(path)?;
+    match list_type(&bytes)? {
+        None => Ok(decode_object(&bytes)),
+        Some(qtype::ENUM_LIST) => {
+            let (domain, attribute, indices) = enum_indices(&bytes, path)?;
+            let domain = load_object(&database.join(domain))?;
+            let domain = domain.as_vec::<S>()?;
+            let symbols = indices
+                .map(|index| {
+                    usize::try_from(index)
+                        .ok()
+                        .and_then(|index| domain.get(index))
+                        .cloned()
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<S>>();
+            Ok(K::new(
+                qtype::SYMBOL_LIST,
+                attribute,
+                k0_inner::list(k0_list::new(symbols)),
+            ))
+        }
+        Some(qtype) if (NESTED_TYPE_OFFSET..qtype::TABLE).contains(&qtype) => {
+            let mut data_path = path.as_os_str().to_owned();
+            data_path.push("#");
+            let data = load_file(Path::new(&data_path))?;
+            decode_nested(&bytes, &data, qtype - NESTED_TYPE_OFFSET, path)
+        }
+        Some(_) => decode_list(&bytes),
+    }
+}
+
+/// Split a table into column names and columns in the layout of the disk.
+fn splay(table: &K) -> Result<(Vec<S>, Vec<Column>)> {
+    if table.get_type() != qtype::TABLE {
+        return Err(invalid_input("not a table").into());
+    }
+    let dictionary = table.get_dictionary()?.as_vec::<K>()?;
+    let headers = dictionary[0].as_vec::<S>()?.clone();
+    let columns = dictionary[1]
+        .as_vec::<K>()?
+        .iter()
+        .zip(headers.iter())
+        .map(|(column, header)| Column::new(column, header))
+        .collect::<Result<Vec<Column>>>()?;
+    Ok((headers, columns))
+}
+
+/// Sort rows of a table by a column keeping the order of equal values and move the column to the head as
+///  `.Q.dpft` does.
+fn part(table: &K, column: &str) -> Result<K> {
+    let dictionary = table
+        .get_dictionary()
+        .map_err(|_| invalid_input("not a table"))?
+        .as_vec::<K>()?;
+    let headers = dictionary[0].as_vec::<S>()?;
+    let columns = dictionary[1].as_vec::<K>()?;
+    let position = headers
+        .iter()
+        .position(|header| header.as_str() == column)
+        .ok_or_else(|| invalid_input(&format!("no such column: {}", column)))?;
+    let rows = ascending_rows(&columns[position])
+        .ok_or_else(|| invalid_input(&format!("column cannot be parted: {}", column)))?;
+    let mut order = vec![position];
+    order.extend((0..headers.len()).filter(|&i| i != position));
+    let headers = K::new(
+        qtype::SYMBOL_LIST,
+        qattribute::NONE,
+        k0_inner::list(k0_list::new(
+            order
+                .iter()
+                .map(|&i| headers[i].clone())
+                .collect::<Vec<S>>(),
+        )),
+    );
+    let columns = order
+        .iter()
+        .map(|&i| select_rows(&columns[i], &rows))
+        .collect();
+    K::new_dictionary(headers, K::new_compound_list(columns))?.flip()
+}
+
+/// Write column files and then `.d` enumerating symbol columns against `sym_file`.
+fn write_columns(
+    directory: &Path,
+    headers: &[S],
+    columns: Vec<Column>,
+    sym_file: &Path,
+) -> Result<()> {
+    let domain = sym_file
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| invalid_input("invalid sym file"))?;
+    let symbols = columns
+        .iter()
+        .filter_map(|column| match column {
+            Column::Symbol { symbols, .. } => Some(symbols.iter()),
+            _ => None,
+        })
+        .flatten();
+    let enumeration = enumerate(sym_file, symbols)?;
+    fs::create_dir_all(directory)?;
+    for (header, column) in headers.iter().zip(columns) {
+        column.write(&directory.join(header.as_str()), domain, &enumeration)?;
+    }
+    let headers = K::new(
+        qtype::SYMBOL_LIST,
+        qattribute::NONE,
+        k0_inner::list(k0_list::new(headers.to_vec())),
+    );
+    let mut bytes = OBJECT_MAGIC.to_vec();
+    bytes.extend_from_slice(&headers.q_ipc_encode());
+    fs::write(directory.join(COLUMN_ORDER_FILE), bytes)?;
+    Ok(())
+}
+
+/// Append symbols which are not in a sym file to it under an exclusive lock and return indices of symbols.
+fn enumerate<'a, I>(sym_file: &Path, symbols: I) -> io::Result<HashMap<S, i32>>
+where
+    I: Iterator<Item = &'a S>,
+{
+    if let Some(parent) = sym_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(sym_file)?;
+    // Released when the file is closed.
+    file.lock()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    if bytes.is_empty() {
+        bytes = OBJECT_MAGIC.to_vec();
+        bytes.extend_from_slice(&K::new_symbol_list(Vec::new(), qattribute::NONE).q_ipc_encode());
+        file.write_all(&bytes)?;
+    }
+    if !bytes.starts_with(&OBJECT_MAGIC)
+        || bytes.get(2) != Some(&(qtype::SYMBOL_LIST as u8))
+        || bytes.len() < 8
+    {
+        return Err(invalid_data(sym_file, "not a symbol list"));
+    }
+    let count = u32::from_ne_bytes(bytes[4..8].try_into().unwrap()) as usize;
+    let mut enumeration = HashMap::with_capacity(count);
+    // End of the `count`-th symbol. Bytes after it, e.g., left by an interrupted write, are overwritten.
+    let mut end = 8;
+    for index in 0..count {
+        let length = bytes[end..]
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| invalid_data(sym_file, "symbol list is truncated"))?;
+        let symbol = String::from_utf8_lossy(&bytes[end..end + length]);
+        // q finds the first occurrence.
+        enumeration
+            .entry(S::from(symbol.as_ref()))
+            .or_insert(index as i32);
+        end += length + 1;
+    }
+    let mut appended = Vec::new();
+    let mut total = count;
+    for symbol in symbols {
+        if !enumeration.contains_key(symbol) {
+            enumeration.insert(symbol.clone(), total as i32);
+            total += 1;
+            appended.extend_from_slice(symbol.as_bytes());
+            appended.push(0);
+        }
+    }
+    if !appended.is_empty() {
+        file.seek(SeekFrom::Start(end as u64))?;
+        file.write_all(&appended)?;
+        file.set_len((end + appended.len()) as u64)?;
+        file.seek(SeekFrom::Start(4))?;
+        file.write_all(&(total as u32).to_ne_bytes())?;
+        file.sync_data()?;
+    }
+    Ok(enumeration)
+}
+
+/// Map a file to memory and decompress it if it is compressed.
+fn load_file(path: &Path) -> Result<FileBytes> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Err(invalid_data(path, "empty file").into());
+    }
+    // The file is not expected to be modified while it is read, as q assumes.
+    let bytes = unsafe { Mmap::map(&file) }?;
+    if bytes.starts_with(COMPRESSED_MAGIC) {
+        Ok(FileBytes::Decompressed(decompress_file(&bytes, path)?))
+    } else {
+        Ok(FileBytes::Mapped(bytes))
+    }
+}
+
+/// Decompress the content of a compressed file.
+fn decompress_file(bytes: &[u8], path: &Path) -> io::Result<Vec<u8>> {
+    let corrupted = || invalid_data(path, "compressed file is corrupted");
+    let trailer = bytes
+        .len()
+        .checked_sub(COMPRESSED_TRAILER_LENGTH)
+        .filter(|&trailer| trailer >= COMPRESSED_MAGIC.len())
+        .ok_or_else(corrupted)?;
+    let length = read_length(bytes, trailer).map_err(|_| corrupted())?;
+    let blocks = u32::from_ne_bytes(bytes[trailer + 8..trailer + 12].try_into().unwrap()) as usize;
+    let logical_block_size = bytes[trailer + 12];
+    if !LOGICAL_BLOCK_SIZES.contains(&logical_block_size) {
+        return Err(corrupted());
+    }
+    let block_size = 1_usize << logical_block_size;
+    let algorithm = CompressionAlgorithm::from_code(bytes[trailer + 13]).ok_or_else(|| {
+        invalid_data(
+            path,
+            &format!("unknown compression algorithm: {}", bytes[trailer + 13]),
+        )
+    })?;
+    if blocks != length.div_ceil(block_size) {
+        return Err(corrupted());
+    }
+    let sizes = blocks
+        .checked_mul(4)
+        .and_then(|size| trailer.checked_sub(size))
+        .filter(|&sizes| sizes >= COMPRESSED_MAGIC.len())
+        .ok_or_else(corrupted)?;
+
+    let mut decompressed = Vec::with_capacity(length);
+    let mut start = COMPRESSED_MAGIC.len();
+    for (i, size) in bytes[sizes..trailer].chunks(4).enumerate() {
+        let size = u32::from_ne_bytes(size.try_into().unwrap()) as usize;
+        let block = bytes
+            .get(start..start + size)
+            .filter(|_| start + size <= sizes)
+            .ok_or_else(corrupted)?;
+        let block_length = block_size.min(length - i * block_size);
+        if size == block_length {
+            decompressed.extend_from_slice(block);
+        } else {
+            decompressed.extend(decompress_block(block, algorithm, block_length).map_err(
+                |error| match error.kind() {
+                    io::ErrorKind::Unsupported => error,
+                    _ => corrupted(),
+                },
+            )?);
+        }
+        start += size;
+    }
+    if start != sizes {
+        return Err(corrupted());
+    }
+    Ok(decompressed)
+}
+
+/// Compress a logical block. A result as large as the block is discarded by the caller.
+fn compress_block(block: &[u8], parameters: &CompressionParameters) -> io::Result<Vec<u8>> {
+    match parameters.algorithm {
+        CompressionAlgorithm::Q => {
+            if block.len() < MIN_Q_COMPRESSED_BLOCK {
+                return Ok(block.to_vec());
+            }
+            // Compress as a message whose body is the block and drop the header of the message. The rest
+            //  starts with the size of the original message.
+            let mut raw = Vec::with_capacity(8 + block.len());
+            raw.extend_from_slice(&[ENCODING, 0, 0, 0, 0, 0, 0, 0]);
+            raw.extend_from_slice(block);
+            match compress(raw) {
+                (true, compressed) => Ok(compressed[8..].to_vec()),
+                _ => Ok(block.to_vec()),
+            }
+        }
+        #[cfg(feature = "disk-gzip")]
+        CompressionAlgorithm::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::new(parameters.level as u32),
+            );
+            encoder.write_all(block)?;
+            encoder.finish()
+        }
+        #[cfg(feature = "disk-snappy")]
+        CompressionAlgorithm::Snappy => snap::raw::Encoder::new()
+            .compress_vec(block)
+            .map_err(io::Error::other),
+        #[cfg(feature = "disk-lz4")]
+        CompressionAlgorithm::Lz4hc => Ok(lz4_flex::block::compress(block)),
+        #[cfg(feature = "disk-zstd")]
+        CompressionAlgorithm::Zstd => zstd::bulk::compress(block, parameters.level as i32),
+        #[allow(unreachable_patterns)]
+        algorithm => Err(unsupported(algorithm)),
+    }
+}
+
+/// Decompress a block into `length` bytes.
+fn decompress_block(
+    block: &[u8],
+    algorithm: CompressionAlgorithm,
+    length: usize,
+) -> io::Result<Vec<u8>> {
+    let decompressed = match algorithm {
+        CompressionAlgorithm::Q => {
+            // The size of the original message includes the header of 8 bytes.
+            let size = block
+                .get(0..4)
+                .map(|size| u32::from_ne_bytes(size.try_into().unwrap()) as usize);
+            if size != Some(length + 8) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid size of a block",
+                ));
+            }
+            try_decompress(block, ENCODING).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "invalid data of a block")
+            })?
+        }
+        #[cfg(feature = "disk-gzip")]
+        CompressionAlgorithm::Gzip => {
+            let mut decompressed = Vec::with_capacity(length);
+            flate2::read::GzDecoder::new(block).read_to_end(&mut decompressed)?;
+            decompressed
+        }
+        #[cfg(feature = "disk-snappy")]
+        CompressionAlgorithm::Snappy => snap::raw::Decoder::new()
+            .decompress_vec(block)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?,
+        #[cfg(feature = "disk-lz4")]
+        CompressionAlgorithm::Lz4hc => lz4_flex::block::decompress(block, length)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?,
+        #[cfg(feature = "disk-zstd")]
+        CompressionAlgorithm::Zstd => zstd::bulk::decompress(block, length)?,
+        #[allow(unreachable_patterns)]
+        algorithm => return Err(unsupported(algorithm)),
+    };
+    if decompressed.len() != length {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid size of a block",
+        ));
+    }
+    Ok(decompressed)
+}
+
+/// Get the type of a mappable list. `None` for a non-mappable object.
+fn list_type(bytes: &[u8]) -> io::Result<Option<i8>> {
+    if bytes.starts_with(&OBJECT_MAGIC) {
+        Ok(None)
+    } else if bytes.starts_with(&LIST_MAGIC) && bytes.len() >= LIST_HEADER_LENGTH {
+        Ok(Some(bytes[2] as i8))
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "not a q object"))
+    }
+}
+
+/// Deserialize a non-mappable object.
+fn decode_object(bytes: &[u8]) -> K {
+    K::q_ipc_decode(&bytes[OBJECT_MAGIC.len()..], ENCODING)
+}
+
+/// Deserialize a mappable simple list from the elements following the header.
+fn decode_list(bytes: &[u8]) -> Result<K> {
+    let length = read_length(bytes, 8)?;
+    let element_size = element_size(bytes[2] as i8)?;
+    let data = length
+        .checked_mul(element_size)
+        .and_then(|size| size.checked_add(LIST_HEADER_LENGTH))
+        .and_then(|end| bytes.get(LIST_HEADER_LENGTH..end))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "list is truncated"))?;
+    Ok(K::q_ipc_decode_list(
+        data,
+        bytes[2] as i8,
+        bytes[3] as i8,
+        length,
+        ENCODING,
+    ))
+}
+
+/// Deserialize a nested column from offsets of the ends of elements in `col` and elements in `col#`.
+fn decode_nested(bytes: &[u8], data: &[u8], qtype: i8, path: &Path) -> Result<K> {
+    let length = read_length(bytes, 8)?;
+    let element_size = element_size(qtype)?;
+    let truncated = || invalid_data(path, "nested column is truncated");
+    // `col#` is a simple list.
+    if !data.starts_with(&LIST_MAGIC) || data.len() < LIST_HEADER_LENGTH {
+        return Err(truncated().into());
+    }
+    // Offsets must be in `col` before the length is trusted.
+    length
+        .checked_mul(8)
+        .and_then(|size| size.checked_add(LIST_HEADER_LENGTH))
+        .filter(|&end| end <= bytes.len())
+        .ok_or_else(truncated)?;
+    let data = &data[LIST_HEADER_LENGTH..];
+    let mut items = Vec::with_capacity(length);
+    let mut start = 0_usize;
+    for i in 0..length {
+        let end = read_length(bytes, LIST_HEADER_LENGTH + 8 * i).map_err(|_| truncated())?;
+        // A decreasing offset is rejected as an invalid range.
+        let element = start
+            .checked_mul(element_size)
+            .zip(end.checked_mul(element_size))
+            .and_then(|(from, to)| data.get(from..to))
+            .ok_or_else(truncated)?;
+        items.push(K::q_ipc_decode_list(
+            element,
+            qtype,
+            qattribute::NONE,
+            end - start,
+            ENCODING,
+        ));
+        start = end;
+    }
+    Ok(K::new_compound_list(items))
+}
+
+/// Get the domain, the attribute and indices of an enumerated list. The domain name is stored between the
+///  magic bytes and the length if any.
+fn enum_indices<'a>(
+    bytes: &'a [u8],
+    path: &Path,
+) -> io::Result<(String, i8, impl Iterator<Item = i32> + 'a)> {
+    // Find the header whose length is consistent with the file size. Data of an attribute may follow the
+    //  indices and so an exact match is preferred.
+    let end = |header_length: usize| {
+        read_length(bytes, header_length - 8)
+            .ok()
+            .and_then(|length| length.checked_mul(4))
+            .and_then(|size| size.checked_add(header_length))
+    };
+    let mut candidates = (LIST_HEADER_LENGTH..=bytes.len()).step_by(8);
+    let header_length = candidates
+        .clone()
+        .find(|&header_length| end(header_length) == Some(bytes.len()))
+        .or_else(|| {
+            candidates
+                .find(|&header_length| end(header_length).is_some_and(|end| end <= bytes.len()))
+        })
+        .ok_or_else(|| invalid_data(path, "enumerated list is truncated"))?;
+    let length = read_length(bytes, header_length - 8)?;
+    let domain = bytes[8..header_length - 8]
+        .split(|byte| *byte == 0)
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .unwrap_or_else(|| String::from(DEFAULT_DOMAIN));
+    let indices = bytes[header_length..header_length + 4 * length]
+        .chunks(4)
+        .map(|index| i32::from_ne_bytes(index.try_into().unwrap()));
+    Ok((domain, bytes[3] as i8, indices))
+}
+
+/// Read a length stored as a 64-bit integer at `offset`.
+fn read_length(bytes: &[u8], offset: usize) -> io::Result<usize> {
+    let length = bytes
+        .get(offset..offset + 8)
+        .map(|length| i64::from_ne_bytes(length.try_into().unwrap()))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "header is truncated"))?;
+    usize::try_from(length)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid length of a list"))
+}
+
+/// Get the size of an element of a simple list.
+fn element_size(qtype: i8) -> io::Result<usize> {
+    match qtype {
+        qtype::BOOL_LIST | qtype::BYTE_LIST | qtype::STRING => Ok(1),
+        qtype::GUID_LIST => Ok(16),
+        qtype::SHORT_LIST => Ok(2),
+        qtype::INT_LIST
+        | qtype::REAL_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => Ok(4),
+        qtype::LONG_LIST
+        | qtype::FLOAT_LIST
+        | qtype::TIMESTAMP_LIST
+        | qtype::DATETIME_LIST
+        | qtype::TIMESPAN_LIST => Ok(8),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported type of a list: {}", qtype),
+        )),
+    }
+}
+
+/// Attribute written to the disk. Attributes other than sorted are dropped.
+fn disk_attribute(attribute: i8) -> i8 {
+    match attribute {
+        qattribute::SORTED => qattribute::SORTED,
+        _ => qattribute::NONE,
+    }
+}
+
+/// Header of a mappable list.
+fn list_header(qtype: i8, attribute: i8, length: usize) -> Vec<u8> {
+    let mut header = vec![
+        LIST_MAGIC[0],
+        LIST_MAGIC[1],
+        qtype as u8,
+        attribute as u8,
+        0,
+        0,
+        0,
+        0,
+    ];
+    header.extend_from_slice(&(length as i64).to_ne_bytes());
+    header
+}
+
+/// Construct an IO error of `Unsupported` for an algorithm whose feature is not enabled.
+fn unsupported(algorithm: CompressionAlgorithm) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "compression algorithm {:?} needs `{}` feature",
+            algorithm,
+            algorithm.feature()
+        ),
+    )
+}
+
+/// Construct an IO error of `InvalidInput`.
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_string())
+}
+
+/// Construct an IO error of `InvalidData` with a path.
+fn invalid_data(path: &Path, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), message),
+    )
+}
@@ -4,11 +4,15 @@
 // >> Load Libraries
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
+#[cfg(feature = "ipc-base")]
+use super::ReuniteError;
 use super::K;
-use crate::qtype;
+use crate::{qattribute, qtype};
 use std::error::Error as StdError;
 use std::fmt;
 use std::io::Error as IOError;
+#[cfg(feature = "ipc-base")]
+use std::io::ErrorKind;
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >>  Structs
@@ -20,6 +24,8 @@ pub enum Error {
     InvalidDateTime,
     /// Network error.
     IO(IOError),
+    /// Operation did not complete within a time limit. Holds the name of the operation.
+    Timeout(&'static str),
     /// Tried to cast to wrong type.
     InvalidCast {
         from: &'static str,
@@ -40,8 +46,14 @@ pub enum Error {
         key_length: usize,
         value_length: usize,
     },
+    /// Lengths of operands of an arithmetic operator do not match.
+    OperandLengthMismatch { left: usize, right: usize },
     /// Tried to get non-existing column.
     NoSuchColumn(String),
+    /// Tried to get non-existing key of dictionary.
+    NoSuchKey(String),
+    /// Table does not have an expected schema.
+    SchemaMismatch(String),
     /// Tried to insert or push wrong element.
     InsertWrongElement {
         is_insert: bool,
@@ -50,6 +62,11 @@ pub enum Error {
     },
     /// Tried to pop from empty list.
     PopFromEmptyList,
+    /// Tried to set an attribute whose precondition is not satisfied by the object.
+    InvalidAttribute {
+        attribute: &'static str,
+        qtype: &'static str,
+    },
     /// Tried to convert but coluld not.
     Object(K),
 }
@@ -59,6 +76,11 @@ pub enum Error {
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
 impl Error {
+    /// Construct `Timeout` error.
+    pub(crate) fn timeout(operation: &'static str) -> Self {
+        Self::Timeout(operation)
+    }
+
     /// Construct `InvalidCast` error.
     pub(crate) fn invalid_cast(from: i8, to: i8) -> Self {
         Self::InvalidCast {
@@ -98,11 +120,26 @@ impl Error {
         }
     }
 
+    /// Construct `OperandLengthMismatch` error.
+    pub(crate) fn operand_length_mismatch(left: usize, right: usize) -> Self {
+        Self::OperandLengthMismatch { left, right }
+    }
+
     /// Construct `NoSuchColumn` error.
     pub(crate) fn no_such_column(column: String) -> Self {
         Self::NoSuchColumn(column)
     }
 
+    /// Construct `NoSuchKey` error.
+    pub(crate) fn no_such_key(key: &K) -> Self {
+        Self::NoSuchKey(key.to_string())
+    }
+
+    /// Construct `SchemaMismatch` error.
+    pub(crate) fn schema_mismatch(message: String) -> Self {
+        Self::SchemaMismatch(message)
+    }
+
     /// Construct `InsertWrongElement` error.
     pub(crate) fn insert_wrong_element(
         is_insert: bool,
@@ -121,6 +158,14 @@ impl Error {
         Self::PopFromEmptyList
     }
 
+    /// Construct `InvalidAttribute` error.
+    pub(crate) fn invalid_attribute(attribute: i8, qtype: i8) -> Self {
+        Self::InvalidAttribute {
+            attribute: attribute_to_string(attribute),
+            qtype: type_to_string(qtype),
+        }
+    }
+
     /// Construct returned object as a result of an error.
     pub(crate) fn object(returned: K) -> Self {
         Self::Object(returned)
@@ -156,11 +201,20 @@ impl From<IOError> for Error {
     }
 }
 
+#[cfg(feature = "ipc-base")]
+impl From<ReuniteError> for Error {
+    /// Convert into an IO error of `InvalidInput`. The halves are dropped.
+    fn from(error: ReuniteError) -> Self {
+        Self::IO(IOError::new(ErrorKind::InvalidInput, error.to_string()))
+    }
+}
+
 impl PartialEq<Self> for Error {
     fn eq(&self, other: &Error) -> bool {
         match (self, other) {
             (Self::IO(left), Self::IO(right)) => left.to_string() == right.to_string(),
             (Self::IO(_), _) => false,
+            (Self::Timeout(left), Self::Timeout(right)) => left == right,
             (Self::InvalidCast { from: f, to: t }, Self::InvalidCast { from: f2, to: t2 }) => {
                 f == f2 && t == t2
             }
@@ -197,7 +251,16 @@ impl PartialEq<Self> for Error {
                     value_length: l2,
                 },
             ) => k == k2 && l == l2,
+            (
+                Self::OperandLengthMismatch { left: l, right: r },
+                Self::OperandLengthMismatch {
+                    left: l2,
+                    right: r2,
+                },
+            ) => l == l2 && r == r2,
             (Self::NoSuchColumn(left), Self::NoSuchColumn(right)) => left == right,
+            (Self::NoSuchKey(left), Self::NoSuchKey(right)) => left == right,
+            (Self::SchemaMismatch(left), Self::SchemaMismatch(right)) => left == right,
             (
                 Self::InsertWrongElement {
                     is_insert: i,
@@ -214,6 +277,16 @@ impl PartialEq<Self> for Error {
                 left.0.qtype == right.0.qtype && left.0.attribute == right.0.attribute
             }
             (Self::PopFromEmptyList, Self::PopFromEmptyList) => true,
+            (
+                Self::InvalidAttribute {
+                    attribute: a,
+                    qtype: q,
+                },
+                Self::InvalidAttribute {
+                    attribute: a2,
+                    qtype: q2,
+                },
+            ) => a == a2 && q == q2,
             _ => false,
         }
     }
@@ -224,6 +297,7 @@ impl fmt::Display for Error {
         match self {
             Self::InvalidDateTime => write!(f, "invalid datetime"),
             Self::IO(error) => write!(f, "IO error: {}", error),
+            Self::Timeout(operation) => write!(f, "{} timed out", operation),
             Self::InvalidCast { from, to } => write!(f, "invalid cast from {} to {}", from, to),
             Self::InvalidCastList(from) => {
                 write!(f, "invalid cast from {} to list of generics T", from)
@@ -253,7 +327,12 @@ impl fmt::Display for Error {
                 "key-value length mismatch: {} and {}",
                 key_length, value_length
             ),
+            Self::OperandLengthMismatch { left, right } => {
+                write!(f, "operand length mismatch: {} and {}", left, right)
+            }
             Self::NoSuchColumn(column) => write!(f, "no such column: {}", column),
+            Self::NoSuchKey(key) => write!(f, "no such key: {}", key),
+            Self::SchemaMismatch(message) => write!(f, "schema mismatch: {}", message),
             Self::InsertWrongElement {
                 is_insert,
                 destination,
@@ -271,6 +350,9 @@ impl fmt::Display for Error {
             }
             Self::Object(object) => write!(f, "{}", object),
             Self::PopFromEmptyList => write!(f, "pop from empty list"),
+            Self::InvalidAttribute { attribute, qtype } => {
+                write!(f, "invalid attribute {} on {}", attribute, qtype)
+            }
         }
     }
 }
@@ -280,6 +362,7 @@ impl fmt::Debug for Error {
         match self {
             Self::InvalidDateTime => write!(f, "invalid datetime"),
             Self::IO(error) => write!(f, "IO error: {:?}", error),
+            Self::Timeout(operation) => write!(f, "{} timed out", operation),
             Self::InvalidCast { from, to } => write!(f, "invalid cast from {} to {}", from, to),
             Self::InvalidCastList(from) => {
                 write!(f, "invalid cast from {} to list of generics T", from)
@@ -309,7 +392,12 @@ impl fmt::Debug for Error {
                 "key-value length mismatch: {} and {}",
                 key_length, value_length
             ),
+            Self::OperandLengthMismatch { left, right } => {
+                write!(f, "operand length mismatch: {} and {}", left, right)
+            }
             Self::NoSuchColumn(column) => write!(f, "no such column: {}", column),
+            Self::NoSuchKey(key) => write!(f, "no such key: {}", key),
+            Self::SchemaMismatch(message) => write!(f, "schema mismatch: {}", message),
             Self::InsertWrongElement {
                 is_insert,
                 destination,
@@ -327,6 +415,9 @@ impl fmt::Debug for Error {
             }
             Self::Object(object) => write!(f, "{}", object),
             Self::PopFromEmptyList => write!(f, "pop from empty list"),
+            Self::InvalidAttribute { attribute, qtype } => {
+                write!(f, "invalid attribute {} on {}", attribute, qtype)
+            }
         }
     }
 }
@@ -385,3 +476,15 @@ fn type_to_string(qtype: i8) -> &'static str {
         _ => "not supported",
     }
 }
+
+/// Return a q notation of a given attribute.
+fn attribute_to_string(attribute: i8) -> &'static str {
+    match attribute {
+        qattribute::NONE => "none",
+        qattribute::SORTED => "`s#",
+        qattribute::UNIQUE => "`u#",
+        qattribute::PARTED => "`p#",
+        qattribute::GROUPED => "`g#",
+        _ => "not supported",
+    }
+}
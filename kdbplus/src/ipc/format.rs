@@ -28,6 +28,87 @@ impl fmt::Display for K {
     }
 }
 
+//%% Temporal %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl fmt::Display for QTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut stream = String::new();
+        if put_timestamp(self.raw(), &mut stream) {
+            stream.push('p');
+        }
+        write!(f, "{}", stream)
+    }
+}
+
+impl fmt::Display for QMonth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut stream = String::new();
+        put_month(self.raw(), &mut stream);
+        stream.push('m');
+        write!(f, "{}", stream)
+    }
+}
+
+impl fmt::Display for QDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut stream = String::new();
+        if put_date(self.raw(), &mut stream) {
+            stream.push('d');
+        }
+        write!(f, "{}", stream)
+    }
+}
+
+impl fmt::Display for QDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut stream = String::new();
+        if put_datetime(self.raw(), &mut stream) {
+            stream.push('z');
+        }
+        write!(f, "{}", stream)
+    }
+}
+
+impl fmt::Display for QTimespan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut stream = String::new();
+        if put_timespan(self.raw(), &mut stream) {
+            stream.push('n');
+        }
+        write!(f, "{}", stream)
+    }
+}
+
+impl fmt::Display for QMinute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut stream = String::new();
+        if put_minute(self.raw(), &mut stream) {
+            stream.push('u');
+        }
+        write!(f, "{}", stream)
+    }
+}
+
+impl fmt::Display for QSecond {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut stream = String::new();
+        if put_second(self.raw(), &mut stream) {
+            stream.push('v');
+        }
+        write!(f, "{}", stream)
+    }
+}
+
+impl fmt::Display for QTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut stream = String::new();
+        if put_time(self.raw(), &mut stream) {
+            stream.push('t');
+        }
+        write!(f, "{}", stream)
+    }
+}
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Private Functions
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -164,16 +245,14 @@ fn put_month(months: I, stream: &mut String) {
     } else if months == qninf_base::I {
         stream.push_str("-0W")
     } else {
-        match months.signum() {
-            -1 => {
-                let year = 2000 + months.signum() * (1 + months.abs() / 12);
-                let month = 12 + (1 + months % 12);
-                stream.push_str(format!("{}.{:02}", year, month).as_str())
-            }
-            _ => {
-                stream.push_str(format!("{}.{:02}", 2000 + months / 12, 1 + (months % 12)).as_str())
-            }
-        }
+        stream.push_str(
+            format!(
+                "{}.{:02}",
+                2000 + months.div_euclid(12),
+                1 + months.rem_euclid(12)
+            )
+            .as_str(),
+        )
     }
 }
 
@@ -189,17 +268,31 @@ fn put_date(days: I, stream: &mut String) -> bool {
         stream.push_str("-0W");
         true
     } else {
-        stream.push_str(
-            Utc.timestamp_nanos(ONE_DAY_NANOS * (days + KDB_DAY_OFFSET) as i64)
-                .date_naive()
-                .format("%Y.%m.%d")
-                .to_string()
-                .as_str(),
-        );
+        let (year, month, day) = q_date_to_civil(days);
+        let sign = if year < 0 { "-" } else { "" };
+        stream.push_str(format!("{}{:04}.{:02}.{:02}", sign, year.abs(), month, day).as_str());
         false
     }
 }
 
+/// Convert days since `2000.01.01` into year, month and day of the proleptic Gregorian calendar. Unlike
+///  `NaiveDate`, every `i32` is in the range.
+fn q_date_to_civil(days: I) -> (i64, i64, i64) {
+    // Days since `0000.03.01`, the start of a 400-year cycle with the leap day at the end of a year.
+    let days = days as i64 + 730425;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Month counted from March.
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + (month <= 2) as i64;
+    (year, month, day)
+}
+
 /// Put formatted datetime value to a stream and return if 'z' suffix is necessaery in case of atom.
 fn put_datetime(days: F, stream: &mut String) -> bool {
     if days.is_nan() {
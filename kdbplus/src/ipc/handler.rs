@@ -0,0 +1,148 @@
+//! This module provides a framework to serve q clients from Rust like a q process.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::error::Error;
+use super::{qmsg_type, QStream, Result, K};
+use async_trait::async_trait;
+use std::io;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% QHandler %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Callbacks invoked on messages from a q client, i.e., the Rust equivalent of `.z.pg`, `.z.ps`, `.z.po` and
+///  `.z.pc`. Run a handler over an accepted connection with [`QStream::serve`](struct.QStream.html#method.serve)
+///  or over all clients of a listener with [`QListener::serve`](struct.QListener.html#method.serve).
+///
+/// A synchronous message is answered with the returned object. An error returned by `on_sync` is sent back as
+///  a q error (type -128), which the q client sees as a signal. A q error object can be also returned
+///  explicitly by `Ok(K::new_error(...))`.
+///
+/// Implement the trait with [`async_trait`](https://docs.rs/async-trait) in the same way as `Query`.
+/// # Example
+/// ```no_run
+/// use kdbplus::ipc::*;
+/// use std::sync::Arc;
+///
+/// struct Calculator;
+///
+/// #[async_trait::async_trait]
+/// impl QHandler for Calculator {
+///     // q)h (`add; 1; 2)
+///     async fn on_sync(&self, message: &K) -> Result<K> {
+///         let list = message.as_vec::<K>()?;
+///         match list[0].get_symbol()? {
+///             "add" => Ok(K::new_long(list[1].get_long()? + list[2].get_long()?)),
+///             _ => Ok(K::new_error(String::from("nyi"))),
+///         }
+///     }
+///
+///     // q)neg[h] "hello"
+///     async fn on_async(&self, message: &K) {
+///         println!("{}", message);
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let builder = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 7000);
+///     let listener = QListener::bind(builder, 16).await?;
+///     listener.serve(Arc::new(Calculator)).await
+/// }
+/// ```
+#[async_trait]
+pub trait QHandler: Send + Sync {
+    /// Answer a synchronous message. Equivalent of `.z.pg`.
+    async fn on_sync(&self, message: &K) -> Result<K>;
+
+    /// Process an asynchronous message. Equivalent of `.z.ps`. Default: ignore the message.
+    async fn on_async(&self, _message: &K) {}
+
+    /// Called when a client is connected. Equivalent of `.z.po`. Default: do nothing.
+    async fn on_open(&self) {}
+
+    /// Called when a client is disconnected. Equivalent of `.z.pc`. Default: do nothing.
+    async fn on_close(&self) {}
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% QStream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QStream {
+    /// Dispatch messages from a client to a handler until the client disconnects.
+    /// # Parameters
+    /// - `handler`: Callbacks of messages.
+    /// # Errors
+    /// IO error other than the disconnection of the client, after which `on_close` is still called.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::ipc::*;
+    ///
+    /// struct Echo;
+    ///
+    /// #[async_trait::async_trait]
+    /// impl QHandler for Echo {
+    ///     async fn on_sync(&self, message: &K) -> Result<K> {
+    ///         Ok(message.clone())
+    ///     }
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut socket = QStream::accept(ConnectionMethod::TCP, "127.0.0.1", 7000).await?;
+    ///     socket.serve(&Echo).await
+    /// }
+    /// ```
+    pub async fn serve<H>(&mut self, handler: &H) -> Result<()>
+    where
+        H: QHandler + ?Sized,
+    {
+        handler.on_open().await;
+        let result = loop {
+            match self.receive_message().await {
+                Ok((qmsg_type::synchronous, message)) => {
+                    let response = handler
+                        .on_sync(&message)
+                        .await
+                        .unwrap_or_else(|error| K::new_error(error.to_string()));
+                    if let Err(error) = self.send_message(&response, qmsg_type::response).await {
+                        break Err(error);
+                    }
+                }
+                Ok((qmsg_type::asynchronous, message)) => handler.on_async(&message).await,
+                // A client does not send a response without a request.
+                Ok(_) => {}
+                Err(error) if is_disconnection(&error) => break Ok(()),
+                Err(error) => break Err(error),
+            }
+        };
+        handler.on_close().await;
+        result
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Check if an error means that the peer closed the connection.
+pub(crate) fn is_disconnection(error: &Error) -> bool {
+    match error {
+        Error::IO(error) => matches!(
+            error.kind(),
+            io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+        ),
+        _ => false,
+    }
+}
@@ -0,0 +1,508 @@
+//! This module provides a reader and a writer of a tickerplant journal (log file), i.e., a file replayed by
+//!  q with `-11!`.
+//!
+//! A journal starts with a header of a general list as `` `:log set () `` writes and each chunk following
+//!  the header is one serialized message, typically `` (`upd; `table; data) ``. [`Writer`] appends chunks in
+//!  the same encoding as q and [`Reader`] iterates them without loading the whole file.
+//!
+//! A journal whose last chunk was partially written, e.g., by a crash of a tickerplant, can be detected with
+//!  [`count_chunks`] (`-11!(-2;file)`) and fixed with [`repair`]. A chunk of a type which cannot be
+//!  deserialized, e.g., a function, is not regarded as a corrupt tail but reported as an error.
+//! # Example
+//! ```
+//! use kdbplus::qattribute;
+//! use kdbplus::ipc::*;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     let path = std::env::temp_dir().join("kdbplus_journal_doc");
+//!
+//!     let mut writer = journal::Writer::create(&path).await?;
+//!     writer
+//!         .append_update("trade", K::new_long_list(vec![100, 200], qattribute::NONE))
+//!         .await?;
+//!     writer
+//!         .append_update("trade", K::new_long_list(vec![300], qattribute::NONE))
+//!         .await?;
+//!     assert_eq!(
+//!         journal::count_chunks(&path).await?,
+//!         journal::ChunkCount::Complete(2)
+//!     );
+//!
+//!     let mut reader = journal::Reader::open(&path).await?;
+//!     while let Some(message) = reader.next_message().await? {
+//!         println!("{}", message);
+//!     }
+//!     assert_eq!(reader.chunks(), 2);
+//!     Ok(())
+//! }
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::serialize::ENCODING;
+use super::{qtype, Result, K};
+use std::io::{self, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Magic bytes of a file written by `set` followed by type and attribute of a general list.
+const MAGIC: [u8; 4] = [0xff, 0x01, 0x00, 0x00];
+
+/// Length of the header: magic bytes and the number of chunks.
+const HEADER_LENGTH: u64 = 8;
+
+/// Minimum number of bytes read from a file at once.
+const BLOCK_SIZE: usize = 1 << 16;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% ChunkCount %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Result of [`count_chunks`], which corresponds to the result of `-11!(-2;file)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkCount {
+    /// All chunks are valid. Holds the number of chunks.
+    Complete(usize),
+    /// The file ends with a partial or invalid chunk. Holds the number of valid chunks and the length of the
+    ///  valid part of the file in bytes.
+    Truncated { chunks: usize, valid_length: u64 },
+}
+
+//%% Reader %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Reader of chunks of a journal. Only a block of the file and the current chunk are kept in memory.
+///
+/// A chunk which is cut by the end of the file is regarded as the start of a corrupt tail. A chunk which has a
+///  type which cannot be deserialized, e.g., a function, is an error.
+/// # Example
+/// See the example of [`journal`](index.html).
+pub struct Reader {
+    /// Journal file.
+    file: File,
+    /// Bytes read from the file and not consumed yet.
+    buffer: Vec<u8>,
+    /// Position of the next chunk in `buffer`.
+    cursor: usize,
+    /// Position of `buffer` in the file.
+    offset: u64,
+    /// Number of chunks read so far.
+    chunks: usize,
+    /// Whether the end of the file was reached.
+    eof: bool,
+}
+
+//%% ScanError %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Reason why the end of a serialized object was not found.
+enum ScanError {
+    /// Bytes end before the object does.
+    Incomplete,
+    /// The object contains a type which cannot be deserialized.
+    Unsupported(i8),
+}
+
+//%% Writer %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Writer appending messages to a journal in the same encoding as a tickerplant does with `` h enlist x ``.
+/// # Example
+/// See the example of [`journal`](index.html).
+pub struct Writer {
+    /// Journal file.
+    file: File,
+    /// Number of chunks in the file.
+    chunks: usize,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Reader %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Reader {
+    /// Open a journal.
+    /// # Errors
+    /// - IO error if the file cannot be read.
+    /// - IO error of `InvalidData` if the file does not start with the header of a journal.
+    pub async fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut file = File::open(path).await?;
+        let mut header = [0_u8; HEADER_LENGTH as usize];
+        file.read_exact(&mut header)
+            .await
+            .map_err(|_| invalid_data("not a journal"))?;
+        if header[0..4] != MAGIC {
+            return Err(invalid_data("not a journal").into());
+        }
+        Ok(Reader {
+            file,
+            buffer: Vec::new(),
+            cursor: 0,
+            offset: HEADER_LENGTH,
+            chunks: 0,
+            eof: false,
+        })
+    }
+
+    /// Read the next message. `None` at the end of the journal.
+    /// # Errors
+    /// - IO error if the file cannot be read.
+    /// - IO error of `InvalidData` if the rest of the file is a corrupt tail (see [`repair`]) or the next chunk
+    ///   has a type which cannot be deserialized.
+    pub async fn next_message(&mut self) -> Result<Option<K>> {
+        match self.next_chunk().await? {
+            Some(range) => Ok(Some(K::q_ipc_decode(&self.buffer[range], ENCODING))),
+            None if self.is_truncated() => Err(invalid_data(&format!(
+                "journal is truncated after {} chunks at byte {}",
+                self.chunks,
+                self.position()
+            ))
+            .into()),
+            None => Ok(None),
+        }
+    }
+
+    /// Skip `n` chunks without deserializing them, e.g., to resume a replay as `-11!(n;file)` stops at.
+    ///  Returns the number of chunks actually skipped, which is less than `n` at the end of the journal.
+    /// # Errors
+    /// - IO error if the file cannot be read.
+    /// - IO error of `InvalidData` if a chunk has a type which cannot be deserialized.
+    pub async fn skip(&mut self, n: usize) -> Result<usize> {
+        for skipped in 0..n {
+            if self.next_chunk().await?.is_none() {
+                return Ok(skipped);
+            }
+        }
+        Ok(n)
+    }
+
+    /// Get the number of chunks read or skipped so far.
+    pub fn chunks(&self) -> usize {
+        self.chunks
+    }
+
+    /// Get the position of the next chunk in the file in bytes.
+    pub fn position(&self) -> u64 {
+        self.offset + self.cursor as u64
+    }
+
+    /// Find the next chunk reading the file as needed. `None` at the end of the file or the start of a corrupt
+    ///  tail.
+    async fn next_chunk(&mut self) -> Result<Option<Range<usize>>> {
+        loop {
+            match serialized_end(&self.buffer, self.cursor, ENCODING) {
+                Ok(end) => {
+                    let start = self.cursor;
+                    self.cursor = end;
+                    self.chunks += 1;
+                    return Ok(Some(start..end));
+                }
+                Err(ScanError::Unsupported(qtype)) => {
+                    return Err(invalid_data(&format!(
+                        "journal has a chunk of unsupported type {} at byte {}",
+                        qtype,
+                        self.position()
+                    ))
+                    .into())
+                }
+                Err(ScanError::Incomplete) => (),
+            }
+            if self.eof {
+                return Ok(None);
+            }
+            // Drop consumed bytes and read at least as many bytes as held so that a large chunk is not
+            //  scanned too many times.
+            self.offset += self.cursor as u64;
+            self.buffer.drain(..self.cursor);
+            self.cursor = 0;
+            let length = self.buffer.len();
+            self.buffer.resize(length + BLOCK_SIZE.max(length), 0);
+            let read = self.file.read(&mut self.buffer[length..]).await?;
+            self.buffer.truncate(length + read);
+            self.eof = read == 0;
+        }
+    }
+
+    /// Check if bytes are left after the last valid chunk.
+    fn is_truncated(&self) -> bool {
+        self.eof && self.cursor < self.buffer.len()
+    }
+}
+
+//%% Writer %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Writer {
+    /// Create an empty journal as `` `:log set () `` does. An existing file is overwritten.
+    /// # Errors
+    /// IO error if the file cannot be written.
+    pub async fn create<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .await?;
+        let mut writer = Writer { file, chunks: 0 };
+        writer.file.write_all(&MAGIC).await?;
+        writer.write_count().await?;
+        Ok(writer)
+    }
+
+    /// Open an existing journal to append messages.
+    /// # Errors
+    /// - IO error if the file cannot be read or written.
+    /// - IO error of `InvalidData` if the file is not a journal, has a corrupt tail (see [`repair`]) or has a chunk
+    ///   of a type which cannot be deserialized.
+    pub async fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let chunks = match count_chunks(&path).await? {
+            ChunkCount::Complete(chunks) => chunks,
+            ChunkCount::Truncated { chunks, .. } => {
+                return Err(
+                    invalid_data(&format!("journal is truncated after {} chunks", chunks)).into(),
+                )
+            }
+        };
+        let mut file = OpenOptions::new().read(true).write(true).open(path).await?;
+        file.seek(SeekFrom::End(0)).await?;
+        Ok(Writer { file, chunks })
+    }
+
+    /// Append a message.
+    /// # Errors
+    /// IO error if the file cannot be written.
+    pub async fn append(&mut self, message: &K) -> Result<()> {
+        self.file.write_all(&message.q_ipc_encode()).await?;
+        self.chunks += 1;
+        self.write_count().await
+    }
+
+    /// Append an update of a table in the form of `` (`upd; `table; data) ``.
+    /// # Errors
+    /// IO error if the file cannot be written.
+    pub async fn append_update(&mut self, table: &str, data: K) -> Result<()> {
+        let message = K::new_compound_list(vec![
+            K::new_symbol(String::from("upd")),
+            K::new_symbol(String::from(table)),
+            data,
+        ]);
+        self.append(&message).await
+    }
+
+    /// Get the number of chunks in the journal.
+    pub fn chunks(&self) -> usize {
+        self.chunks
+    }
+
+    /// Flush written chunks to the disk.
+    /// # Errors
+    /// IO error if the file cannot be synchronized.
+    pub async fn sync(&mut self) -> Result<()> {
+        self.file.sync_data().await?;
+        Ok(())
+    }
+
+    /// Update the number of chunks in the header and move back to the end of the file.
+    async fn write_count(&mut self) -> Result<()> {
+        write_count(&mut self.file, self.chunks).await?;
+        self.file.seek(SeekFrom::End(0)).await?;
+        Ok(())
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Count valid chunks of a journal in a manner of `-11!(-2;file)`.
+/// # Errors
+/// - IO error if the file cannot be read.
+/// - IO error of `InvalidData` if the file is not a journal or has a chunk of a type which cannot be
+///   deserialized.
+/// # Example
+/// See the example of [`journal`](index.html).
+pub async fn count_chunks<P>(path: P) -> Result<ChunkCount>
+where
+    P: AsRef<Path>,
+{
+    let mut reader = Reader::open(path).await?;
+    while reader.next_chunk().await?.is_some() {}
+    if reader.is_truncated() {
+        Ok(ChunkCount::Truncated {
+            chunks: reader.chunks,
+            valid_length: reader.position(),
+        })
+    } else {
+        Ok(ChunkCount::Complete(reader.chunks))
+    }
+}
+
+/// Cut off a corrupt tail of a journal so that a tickerplant or [`Writer`] can append to it again. Returns the
+///  count before the repair. A journal with a chunk of a type which cannot be deserialized is left as it is.
+/// # Errors
+/// - IO error if the file cannot be read or written.
+/// - IO error of `InvalidData` if the file is not a journal or has a chunk of a type which cannot be
+///   deserialized.
+pub async fn repair<P>(path: P) -> Result<ChunkCount>
+where
+    P: AsRef<Path>,
+{
+    let count = count_chunks(&path).await?;
+    if let ChunkCount::Truncated {
+        chunks,
+        valid_length,
+    } = count
+    {
+        let mut file = OpenOptions::new().write(true).open(path).await?;
+        file.set_len(valid_length).await?;
+        write_count(&mut file, chunks).await?;
+        file.sync_all().await?;
+    }
+    Ok(count)
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Write the number of chunks to the header.
+async fn write_count(file: &mut File, chunks: usize) -> io::Result<()> {
+    file.seek(SeekFrom::Start(MAGIC.len() as u64)).await?;
+    file.write_all(&(chunks as u32).to_ne_bytes()).await?;
+    file.flush().await
+}
+
+/// Construct an IO error of `InvalidData`.
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+//%% Chunk Scanner %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Find the end of a serialized object starting at `cursor` without building it.
+fn serialized_end(
+    bytes: &[u8],
+    cursor: usize,
+    encode: u8,
+) -> std::result::Result<usize, ScanError> {
+    let qtype = *bytes.get(cursor).ok_or(ScanError::Incomplete)? as i8;
+    let cursor = cursor + 1;
+    let end = match qtype {
+        qtype::BOOL_ATOM | qtype::BYTE_ATOM | qtype::CHAR => cursor + 1,
+        qtype::GUID_ATOM => cursor + 16,
+        qtype::SHORT_ATOM => cursor + 2,
+        qtype::INT_ATOM
+        | qtype::REAL_ATOM
+        | qtype::MONTH_ATOM
+        | qtype::DATE_ATOM
+        | qtype::MINUTE_ATOM
+        | qtype::SECOND_ATOM
+        | qtype::TIME_ATOM => cursor + 4,
+        qtype::LONG_ATOM
+        | qtype::FLOAT_ATOM
+        | qtype::TIMESTAMP_ATOM
+        | qtype::DATETIME_ATOM
+        | qtype::TIMESPAN_ATOM => cursor + 8,
+        qtype::SYMBOL_ATOM | qtype::ERROR => null_terminated_end(bytes, cursor)?,
+        qtype::NULL => cursor + 1,
+        qtype::BOOL_LIST | qtype::BYTE_LIST | qtype::STRING => {
+            fixed_list_end(bytes, cursor, encode, 1)?
+        }
+        qtype::GUID_LIST => fixed_list_end(bytes, cursor, encode, 16)?,
+        qtype::SHORT_LIST => fixed_list_end(bytes, cursor, encode, 2)?,
+        qtype::INT_LIST
+        | qtype::REAL_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => fixed_list_end(bytes, cursor, encode, 4)?,
+        qtype::LONG_LIST
+        | qtype::FLOAT_LIST
+        | qtype::TIMESTAMP_LIST
+        | qtype::DATETIME_LIST
+        | qtype::TIMESPAN_LIST => fixed_list_end(bytes, cursor, encode, 8)?,
+        qtype::SYMBOL_LIST => {
+            let (size, mut cursor) = list_size(bytes, cursor, encode)?;
+            for _ in 0..size {
+                cursor = null_terminated_end(bytes, cursor)?;
+            }
+            cursor
+        }
+        qtype::COMPOUND_LIST => {
+            let (size, mut cursor) = list_size(bytes, cursor, encode)?;
+            for _ in 0..size {
+                cursor = serialized_end(bytes, cursor, encode)?;
+            }
+            cursor
+        }
+        // Skip table attribute.
+        qtype::TABLE => serialized_end(bytes, cursor + 1, encode)?,
+        qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+            let cursor = serialized_end(bytes, cursor, encode)?;
+            serialized_end(bytes, cursor, encode)?
+        }
+        qtype => return Err(ScanError::Unsupported(qtype)),
+    };
+    match end <= bytes.len() {
+        true => Ok(end),
+        false => Err(ScanError::Incomplete),
+    }
+}
+
+/// Get the length of a list and the cursor after the attribute and the length.
+fn list_size(
+    bytes: &[u8],
+    cursor: usize,
+    encode: u8,
+) -> std::result::Result<(usize, usize), ScanError> {
+    let size: [u8; 4] = bytes
+        .get(cursor + 1..cursor + 5)
+        .ok_or(ScanError::Incomplete)?
+        .try_into()
+        .unwrap();
+    let size = match encode {
+        0 => u32::from_be_bytes(size),
+        _ => u32::from_le_bytes(size),
+    };
+    Ok((size as usize, cursor + 5))
+}
+
+/// Find the end of a list whose elements have a fixed size.
+fn fixed_list_end(
+    bytes: &[u8],
+    cursor: usize,
+    encode: u8,
+    element_size: usize,
+) -> std::result::Result<usize, ScanError> {
+    let (size, cursor) = list_size(bytes, cursor, encode)?;
+    Ok(cursor + element_size * size)
+}
+
+/// Find the end of a null terminated string.
+fn null_terminated_end(bytes: &[u8], cursor: usize) -> std::result::Result<usize, ScanError> {
+    let null_location = bytes
+        .get(cursor..)
+        .and_then(|bytes| bytes.iter().position(|b| *b == 0x00))
+        .ok_or(ScanError::Incomplete)?;
+    Ok(cursor + null_location + 1)
+}
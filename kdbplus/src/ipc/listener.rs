@@ -0,0 +1,293 @@
+//! This module provides a listener which keeps its socket bound and serves multiple q clients.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::connection::{bind_tcp, bind_uds};
+use super::tls::TlsAcceptor;
+use super::{ConnectionMethod, QHandler, QStream, QStreamBuilder, Result};
+use futures_core::Stream;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Time limit of the handshake of a client applied by [`QListener::bind`] unless the builder sets one.
+const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% QListener %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Listener which binds once and yields authenticated q clients. Unlike [`QStream::accept`](struct.QStream.html#method.accept),
+///  the socket stays bound until the listener is dropped.
+///
+/// Clients are accepted and authenticated in the background. At most `max_connections` clients are served at
+///  the same time; a slot is released when the `QStream` of a client is dropped and further clients wait in the
+///  backlog of the socket until then. A client failing authentication is disconnected and not reported.
+///
+/// `QListener` also implements [`Stream`](https://docs.rs/futures-core/latest/futures_core/stream/trait.Stream.html)
+///  of `Result<QStream>`.
+/// # Example
+/// ```no_run
+/// use kdbplus::ipc::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     // Serve at most 16 clients at once.
+///     let builder = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 7000);
+///     let mut listener = QListener::bind(builder, 16).await?;
+///     loop {
+///         let mut socket = listener.accept().await?;
+///         tokio::spawn(async move {
+///             while let Ok((_, message)) = socket.receive_message().await {
+///                 println!("{}", message);
+///             }
+///         });
+///     }
+/// }
+/// ```
+/// q processes can connect at the same time.
+/// ```q
+/// q)h:hopen `::7000:reluctant:slowday
+/// q)neg[h] (`pizza; 125)
+/// ```
+pub struct QListener {
+    /// Authenticated clients and errors of the socket.
+    receiver: mpsc::Receiver<Result<QStream>>,
+    /// Slots of clients served at the same time.
+    slots: Arc<Semaphore>,
+    /// Maximum number of clients served at the same time.
+    max_connections: usize,
+    /// Bound address of a TCP or TLS listener.
+    local_addr: Option<SocketAddr>,
+    /// Task accepting connections.
+    task: JoinHandle<()>,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% QListener %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QListener {
+    /// Bind a listener and start accepting clients.
+    /// # Parameters
+    /// - `builder`: Connection method, host, port and options of accepted streams. Port 0 picks a free port. If
+    ///   `timeouts.handshake` is not set, a client must finish the TLS handshake and authentication within 10
+    ///   seconds so that it does not hold a slot forever.
+    /// - `max_connections`: Maximum number of clients served at the same time. 0 is treated as 1.
+    /// # Errors
+    /// - IO error if the socket cannot be bound.
+    /// - Error of the server identity for TLS. See [Environmental Variables](../ipc/index.html#environmentl-variables).
+    /// # Example
+    /// See the example of [`QListener`](struct.QListener.html).
+    pub async fn bind(builder: QStreamBuilder, max_connections: usize) -> Result<Self> {
+        let builder = builder
+            .with_default_authenticator()?
+            .with_default_handshake_timeout(DEFAULT_HANDSHAKE_TIMEOUT);
+        let max_connections = max_connections.max(1);
+        let slots = Arc::new(Semaphore::new(max_connections));
+        let (sender, receiver) = mpsc::channel(max_connections);
+        let (task, local_addr) = match builder.method() {
+            ConnectionMethod::TCP => {
+                let listener = bind_tcp(&builder).await?;
+                let local_addr = listener.local_addr()?;
+                let task = tokio::spawn(serve_tcp(
+                    listener,
+                    Arc::new(builder),
+                    None,
+                    slots.clone(),
+                    sender,
+                ));
+                (task, Some(local_addr))
+            }
+            ConnectionMethod::TLS => {
+                let listener = bind_tcp(&builder).await?;
+                let local_addr = listener.local_addr()?;
+                let tls_acceptor = Arc::new(TlsAcceptor::from_env().await?);
+                let task = tokio::spawn(serve_tcp(
+                    listener,
+                    Arc::new(builder),
+                    Some(tls_acceptor),
+                    slots.clone(),
+                    sender,
+                ));
+                (task, Some(local_addr))
+            }
+            ConnectionMethod::UDS => {
+                let listener = bind_uds(&builder)?;
+                let task = tokio::spawn(serve_uds(
+                    listener,
+                    Arc::new(builder),
+                    slots.clone(),
+                    sender,
+                ));
+                (task, None)
+            }
+        };
+        Ok(QListener {
+            receiver,
+            slots,
+            max_connections,
+            local_addr,
+            task,
+        })
+    }
+
+    /// Wait for the next authenticated client.
+    /// # Errors
+    /// IO error if accepting a connection failed. The listener keeps listening afterwards.
+    /// # Example
+    /// See the example of [`QListener`](struct.QListener.html).
+    pub async fn accept(&mut self) -> Result<QStream> {
+        match self.receiver.recv().await {
+            Some(result) => result,
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "listener is closed").into()),
+        }
+    }
+
+    /// Wait for the next result of accepting a client. `None` after the listener is closed.
+    pub(crate) async fn next_client(&mut self) -> Option<Result<QStream>> {
+        self.receiver.recv().await
+    }
+
+    /// Serve every accepted client with a handler in its own task until the listener is dropped. Errors of
+    ///  accepting or serving one client do not affect the others.
+    /// # Parameters
+    /// - `handler`: Callbacks shared by all clients.
+    /// # Example
+    /// See the example of [`QHandler`](trait.QHandler.html).
+    pub async fn serve<H>(mut self, handler: Arc<H>) -> Result<()>
+    where
+        H: QHandler + 'static,
+    {
+        while let Some(result) = self.receiver.recv().await {
+            if let Ok(mut socket) = result {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let _ = socket.serve(handler.as_ref()).await;
+                });
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::NotConnected, "listener is closed").into())
+    }
+
+    /// Get the bound address of a TCP or TLS listener, e.g., to find the port picked for port 0. `None` for
+    ///  Unix domain socket.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Get the number of clients being authenticated or served, including ones not taken by `accept` yet.
+    pub fn active_connections(&self) -> usize {
+        self.max_connections - self.slots.available_permits()
+    }
+}
+
+impl Stream for QListener {
+    type Item = Result<QStream>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for QListener {
+    /// Stop accepting clients. Streams already accepted stay connected.
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Accept TCP connections and authenticate each of them in its own task. TLS handshake is run beforehand if
+///  `tls_acceptor` is given.
+async fn serve_tcp(
+    listener: TcpListener,
+    builder: Arc<QStreamBuilder>,
+    tls_acceptor: Option<Arc<TlsAcceptor>>,
+    slots: Arc<Semaphore>,
+    sender: mpsc::Sender<Result<QStream>>,
+) {
+    loop {
+        // The semaphore is never closed.
+        let slot = slots.clone().acquire_owned().await.unwrap();
+        match listener.accept().await {
+            Ok((socket, address)) => {
+                let builder = builder.clone();
+                let tls_acceptor = tls_acceptor.clone();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    let result = match tls_acceptor {
+                        Some(tls_acceptor) => builder.accept_tls(&tls_acceptor, socket).await,
+                        None => builder.accept_tcp(socket, address).await,
+                    };
+                    deliver(result, slot, &sender).await;
+                });
+            }
+            Err(error) => {
+                if sender.send(Err(error.into())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Accept Unix domain socket connections and authenticate each of them in its own task.
+async fn serve_uds(
+    listener: UnixListener,
+    builder: Arc<QStreamBuilder>,
+    slots: Arc<Semaphore>,
+    sender: mpsc::Sender<Result<QStream>>,
+) {
+    loop {
+        // The semaphore is never closed.
+        let slot = slots.clone().acquire_owned().await.unwrap();
+        match listener.accept().await {
+            Ok((socket, _)) => {
+                let builder = builder.clone();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    let result = builder.accept_uds(socket).await;
+                    deliver(result, slot, &sender).await;
+                });
+            }
+            Err(error) => {
+                if sender.send(Err(error.into())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Pass an authenticated client to `QListener` holding its slot. A failed client releases the slot.
+async fn deliver(
+    result: Result<QStream>,
+    slot: OwnedSemaphorePermit,
+    sender: &mpsc::Sender<Result<QStream>>,
+) {
+    if let Ok(mut qstream) = result {
+        qstream.hold_slot(slot);
+        // The listener may be dropped already.
+        let _ = sender.send(Ok(qstream)).await;
+    }
+}
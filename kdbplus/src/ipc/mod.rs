@@ -36,13 +36,20 @@
 //!       0xd03f5cc1cdb11a77410ee34e26ca1102e67a893c
 //!      ```
 //!
+//!     This file is used only if no [`Authenticator`](trait.Authenticator.html) is set to the acceptor. bcrypt and Argon2 hashes are also
+//!      accepted with `bcrypt` and `argon2` features. See [`AccountFile`](struct.AccountFile.html).
+//!
 //! - `KDBPLUS_TLS_KEY_FILE` and `KDBPLUS_TLS_KEY_FILE_SECRET`: The pkcs12 file and its password which TLS acceptor uses.
+//! - `KDBPLUS_TLS_CERT_PEM_FILE` and `KDBPLUS_TLS_KEY_PEM_FILE`: The PEM encoded certificate chain and PKCS#8 private key which TLS acceptor
+//!  uses instead of the pkcs12 file if `KDBPLUS_TLS_CERT_PEM_FILE` is set.
 //! - `QUDSPATH` (optional): q-native environmental variable to define an astract namespace. This environmental variable is used by UDS acceptor too.
 //!  The abstract nameapace will be `@${QUDSPATH}/kx.[server process port]` if this environmental variable is defined. Otherwise it will be `@/tmp/kx.[server process port]`.
 //!
 //! *Notes:*
 //!
 //! - Messages will be sent with OS native endian.
+//! - TLS is provided by native-tls with `ipc` feature and by rustls with `ipc-rustls` feature. rustls does not read a pkcs12 file.
+//! - `blocking` feature provides a client without tokio in [`blocking`](blocking/index.html). `q` objects are available with this feature alone but the asynchronous stream, the listener, `disk` and `journal` require `ipc` or `ipc-rustls` feature.
 //! - When using this crate for a TLS client you need to set two environmental variables `KX_SSL_CERT_FILE` and `KX_SSL_KEY_FILE` on q side to make q/kdb+
 //!  to work as a TLS server. For details, see [the KX website](https://code.kx.com/q/kb/ssl/).
 //!
@@ -79,6 +86,13 @@
 //! | `dictionary`     | `Vec<K>`                                          |
 //! | `null`           | `()`                                              |
 //!
+//! Temporal types can also be handled as typed q values (`QTimestamp`, `QMonth`, `QDate`, `QDateTime`, `QTimespan`, `QMinute`, `QSecond`
+//!  and `QTime`) which keep null and infinity and support arithmetic, parsing and formatting in the same way as q.
+//!  They are converted into `K` with `K::from` or `K::new_temporal_list` and taken back with `get_temporal` or `get_temporal_list`.
+//!
+//! Symbols are stored as interned `Symbol` (aliased as `S`) so that repeated symbols in a list share one allocation.
+//!  See [`SymbolPool`](struct.SymbolPool.html) for a global pool shared across messages.
+//!
 //! ## Examples
 //!
 //! ### Client
@@ -169,14 +183,21 @@
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
 pub mod error;
-
-use super::{qattribute, qinf_base, qninf_base, qnull_base, qtype};
+#[cfg(feature = "ipc-base")]
+pub mod disk;
+#[cfg(feature = "ipc-base")]
+pub mod journal;
+pub mod ops;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+use super::{attribute, qattribute, qinf_base, qninf_base, qnull_base, qtype};
 use chrono::prelude::*;
 use chrono::Duration;
 use error::Error;
-use std::any::Any;
-use std::fmt;
+use std::any::{Any, TypeId};
 use std::result::Result as StdResult;
+use std::sync::Arc;
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Structs
@@ -794,14 +815,91 @@ pub mod qninf {
     pub const TIME: Lazy<Duration> = Lazy::new(|| Duration::milliseconds(qninf_base::I as i64));
 }
 
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Evaluate an expression with the underlying `Arc` of any variant of `k0_list` bound to `$vector`.
+macro_rules! map_k0_list {
+    ($list: expr, |$vector: ident| $body: expr) => {
+        match $list {
+            k0_list::byte($vector) => $body,
+            k0_list::guid($vector) => $body,
+            k0_list::short($vector) => $body,
+            k0_list::int($vector) => $body,
+            k0_list::long($vector) => $body,
+            k0_list::real($vector) => $body,
+            k0_list::float($vector) => $body,
+            k0_list::symbol($vector) => $body,
+            k0_list::compound($vector) => $body,
+            k0_list::dictionary($vector, _) => $body,
+        }
+    };
+}
+
+/// Evaluate an expression with the underlying vector of a simple list (except string) bound to `$list` and
+///  a function comparing its elements in q order bound to `$compare`. `$otherwise` is evaluated for other types.
+macro_rules! with_simple_list {
+    ($object: expr, $as_vec: ident, |$list: ident, $compare: ident| $body: expr, $otherwise: expr) => {
+        match $object.0.qtype {
+            qtype::BOOL_LIST | qtype::BYTE_LIST => {
+                let $list = $object.$as_vec::<G>().unwrap();
+                let $compare = <G as Ord>::cmp;
+                $body
+            }
+            qtype::GUID_LIST => {
+                let $list = $object.$as_vec::<U>().unwrap();
+                let $compare = <U as Ord>::cmp;
+                $body
+            }
+            qtype::SHORT_LIST => {
+                let $list = $object.$as_vec::<H>().unwrap();
+                let $compare = <H as Ord>::cmp;
+                $body
+            }
+            qtype::INT_LIST
+            | qtype::MONTH_LIST
+            | qtype::DATE_LIST
+            | qtype::MINUTE_LIST
+            | qtype::SECOND_LIST
+            | qtype::TIME_LIST => {
+                let $list = $object.$as_vec::<I>().unwrap();
+                let $compare = <I as Ord>::cmp;
+                $body
+            }
+            qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => {
+                let $list = $object.$as_vec::<J>().unwrap();
+                let $compare = <J as Ord>::cmp;
+                $body
+            }
+            qtype::REAL_LIST => {
+                let $list = $object.$as_vec::<E>().unwrap();
+                let $compare = attribute::compare_real;
+                $body
+            }
+            qtype::FLOAT_LIST | qtype::DATETIME_LIST => {
+                let $list = $object.$as_vec::<F>().unwrap();
+                let $compare = attribute::compare_float;
+                $body
+            }
+            qtype::SYMBOL_LIST => {
+                let $list = $object.$as_vec::<S>().unwrap();
+                let $compare = <S as Ord>::cmp;
+                $body
+            }
+            _ => $otherwise,
+        }
+    };
+}
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >>  Structs
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
 //%% Alias %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
-/// q type denoting symbol and string.
-pub type S = String;
+/// q type denoting symbol.
+pub type S = Symbol;
 /// q type denoting char.
 pub type C = i8;
 /// q type denoting bool and byte.
@@ -819,45 +917,42 @@ pub type F = f64;
 /// q type denoting GUID.
 pub type U = [G; 16];
 
-//%% AsAny %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
-
-/// Feature of q list object to be cast to concrete type internally.
-pub(crate) trait AsAny {
-    /// Return as Any type.
-    fn as_any(&self) -> &dyn Any;
-    /// Return as mutable Any type.
-    fn as_any_mut(&mut self) -> &mut dyn Any;
-}
-
-//%% Klone %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
-
-/// Trait to clone `k0_list`.
-pub(crate) trait Klone {
-    fn clone_box(&self) -> Box<dyn k0_list_inner>;
-}
-
 //%% k0_list_inner %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
-/// Feature of q list.
-pub(crate) trait k0_list_inner: Klone + fmt::Debug + AsAny + Send + Sync + 'static {
-    /// Get a length of inner vector.
-    fn len(&self) -> usize;
+/// Feature of a vector which can be stored as q list.
+pub(crate) trait k0_list_inner {
+    /// Wrap the vector into a list holder.
+    fn into_list(self) -> k0_list;
 }
 
 //%% k0_list %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
 /// Underlying list value of q object.
 /// # Note
-/// Usually this struct does not need to be accessed this struct directly unless user wants to
-///  access via a raw pointer for non-trivial stuff.
-#[derive(Debug)]
-pub(crate) struct k0_list {
-    /// Length of the list.
-    n: J,
-    /// Pointer referring to the head of the list. This pointer will be interpreted
-    ///  as various types when accessing `K` object to edit the list with
-    ///  [`as_mut_slice`](trait.KUtility.html#tymethod.as_mut_slice).
-    G0: Box<dyn k0_list_inner>,
+/// The vector is shared among clones of a `K` object and copied only when one of them is modified
+///  (copy-on-write). Usually this enum does not need to be accessed directly.
+#[derive(Clone, Debug)]
+pub(crate) enum k0_list {
+    /// Bool list and byte list holder.
+    byte(Arc<Vec<G>>),
+    /// GUID list holder.
+    guid(Arc<Vec<U>>),
+    /// Short list holder.
+    short(Arc<Vec<H>>),
+    /// Int list and its compatible lists holder.
+    int(Arc<Vec<I>>),
+    /// Long list and its compatible lists holder.
+    long(Arc<Vec<J>>),
+    /// Real list holder.
+    real(Arc<Vec<E>>),
+    /// Float list and datetime list holder.
+    float(Arc<Vec<F>>),
+    /// Symbol list holder.
+    symbol(Arc<Vec<S>>),
+    /// Compound list holder.
+    compound(Arc<Vec<K>>),
+    /// Dictionary holder of keys and values with the index of keys built on demand.
+    dictionary(Arc<Vec<K>>, KeyIndex),
 }
 
 //%% k0_inner %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
@@ -883,11 +978,13 @@ pub(crate) enum k0_inner {
     /// Float type holder.
     float(F),
     /// Symbol type holder.
-    /// # Note
-    /// String type is also stored here.
     symbol(S),
+    /// String type holder.
+    /// # Note
+    /// Error is also stored here.
+    string(String),
     /// Table type holder.
-    table(K),
+    table(Box<K>),
     /// List type holder.
     list(k0_list),
     /// Null type holder.
@@ -911,174 +1008,65 @@ pub(crate) struct k0 {
 
 /// Struct representing q object.
 #[derive(Clone, Debug)]
-pub struct K(pub(crate) Box<k0>);
+pub struct K(pub(crate) k0);
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Implementation
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
-//%% AsAny %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
-
-impl AsAny for Vec<G> {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
-
-impl AsAny for Vec<U> {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
-
-impl AsAny for Vec<H> {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
-
-impl AsAny for Vec<I> {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
-
-impl AsAny for Vec<J> {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
-
-impl AsAny for Vec<E> {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
-
-impl AsAny for Vec<F> {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
-
-impl AsAny for String {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
-
-impl AsAny for Vec<S> {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
-
-impl AsAny for Vec<K> {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
-
-//%% Klone %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
-
-impl<T> Klone for T
-where
-    T: k0_list_inner + Clone,
-{
-    fn clone_box(&self) -> Box<dyn k0_list_inner> {
-        Box::new(self.clone())
-    }
-}
-
 //%% k0_list_inner %%vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
 impl k0_list_inner for Vec<G> {
-    fn len(&self) -> usize {
-        self.len()
+    fn into_list(self) -> k0_list {
+        k0_list::byte(Arc::new(self))
     }
 }
 
 impl k0_list_inner for Vec<U> {
-    fn len(&self) -> usize {
-        self.len()
+    fn into_list(self) -> k0_list {
+        k0_list::guid(Arc::new(self))
     }
 }
 
 impl k0_list_inner for Vec<H> {
-    fn len(&self) -> usize {
-        self.len()
+    fn into_list(self) -> k0_list {
+        k0_list::short(Arc::new(self))
     }
 }
 
 impl k0_list_inner for Vec<I> {
-    fn len(&self) -> usize {
-        self.len()
+    fn into_list(self) -> k0_list {
+        k0_list::int(Arc::new(self))
     }
 }
 
 impl k0_list_inner for Vec<J> {
-    fn len(&self) -> usize {
-        self.len()
+    fn into_list(self) -> k0_list {
+        k0_list::long(Arc::new(self))
     }
 }
 
 impl k0_list_inner for Vec<E> {
-    fn len(&self) -> usize {
-        self.len()
+    fn into_list(self) -> k0_list {
+        k0_list::real(Arc::new(self))
     }
 }
 
 impl k0_list_inner for Vec<F> {
-    fn len(&self) -> usize {
-        self.len()
-    }
-}
-
-impl k0_list_inner for String {
-    fn len(&self) -> usize {
-        self.len()
+    fn into_list(self) -> k0_list {
+        k0_list::float(Arc::new(self))
     }
 }
 
 impl k0_list_inner for Vec<S> {
-    fn len(&self) -> usize {
-        self.len()
+    fn into_list(self) -> k0_list {
+        k0_list::symbol(Arc::new(self))
     }
 }
 
 impl k0_list_inner for Vec<K> {
-    fn len(&self) -> usize {
-        self.len()
+    fn into_list(self) -> k0_list {
+        k0_list::compound(Arc::new(self))
     }
 }
 
@@ -1089,20 +1077,30 @@ impl k0_list {
     where
         T: k0_list_inner,
     {
-        k0_list {
-            n: array.len() as J,
-            G0: Box::new(array),
-        }
+        array.into_list()
     }
-}
 
-impl Clone for k0_list {
-    fn clone(&self) -> Self {
-        // Deref happens here
-        k0_list {
-            n: self.n,
-            G0: self.G0.clone_box(),
-        }
+    /// Get a length of the underlying vector.
+    pub(crate) fn len(&self) -> usize {
+        map_k0_list!(self, |vector| vector.len())
+    }
+
+    /// Get the underlying vector if its element type is `T`.
+    pub(crate) fn as_vec<T>(&self) -> Option<&Vec<T>>
+    where
+        T: 'static,
+    {
+        map_k0_list!(self, |vector| (&**vector as &dyn Any)
+            .downcast_ref::<Vec<T>>())
+    }
+
+    /// Get the underlying mutable vector if its element type is `T`. The vector is copied here
+    ///  if it is shared with another object.
+    pub(crate) fn as_mut_vec<T>(&mut self) -> Option<&mut Vec<T>>
+    where
+        T: 'static,
+    {
+        map_k0_list!(self, |vector| make_mut_as::<_, T>(vector))
     }
 }
 
@@ -1113,11 +1111,11 @@ impl K {
 
     /// Base constructor of `K`.
     pub(crate) fn new(qtype: i8, attribute: i8, inner: k0_inner) -> Self {
-        K(Box::new(k0 {
+        K(k0 {
             qtype: qtype,
             attribute: attribute,
             value: inner,
-        }))
+        })
     }
 
     /// Construct q bool from `bool`.
@@ -1257,7 +1255,7 @@ impl K {
         )
     }
 
-    /// Construct q symbol from `String`.
+    /// Construct q symbol from `String`, `&str` or `Symbol`.
     /// # Example
     /// ```
     /// use kdbplus::ipc::*;
@@ -1267,11 +1265,14 @@ impl K {
     ///     assert_eq!(format!("{}", q_symbol), String::from("`Jordan"));
     /// }
     /// ```
-    pub fn new_symbol(symbol: String) -> Self {
+    pub fn new_symbol<T>(symbol: T) -> Self
+    where
+        T: Into<S>,
+    {
         K::new(
             qtype::SYMBOL_ATOM,
             qattribute::NONE,
-            k0_inner::symbol(symbol),
+            k0_inner::symbol(symbol.into()),
         )
     }
 
@@ -1659,7 +1660,7 @@ impl K {
     /// # Note
     /// q string must be accessed with `as_string` or `as_mut_string`.
     pub fn new_string(string: String, attribute: i8) -> Self {
-        K::new(qtype::STRING, attribute, k0_inner::symbol(string))
+        K::new(qtype::STRING, attribute, k0_inner::string(string))
     }
 
     /// Construct q symbol list from `Vec<String>`.
@@ -1681,7 +1682,15 @@ impl K {
     ///     assert_eq!(format!("{}", q_symbol_list), String::from("`g#`a`b`a`c"));
     /// }
     /// ```
+    /// # Note
+    /// Symbols are interned within the list, i.e., each distinct symbol is allocated only once. If the global
+    ///  pool is enabled, symbols are interned by the global pool (see [`SymbolPool`](struct.SymbolPool.html)).
     pub fn new_symbol_list(list: Vec<String>, attribute: i8) -> Self {
+        let list = SymbolPool::intern_list(|pool| {
+            list.iter()
+                .map(|symbol| pool.intern(symbol))
+                .collect::<Vec<S>>()
+        });
         K::new(
             qtype::SYMBOL_LIST,
             attribute,
@@ -2031,15 +2040,18 @@ impl K {
         if keys.len() != values.len() {
             Err(Error::length_mismatch(keys.len(), values.len()))
         } else {
-            let qtype = if keys.0.attribute == qattribute::SORTED {
-                qtype::SORTED_DICTIONARY
+            let (qtype, attribute) = if has_sorted_keys(&keys) {
+                (qtype::SORTED_DICTIONARY, qattribute::SORTED)
             } else {
-                qtype::DICTIONARY
+                (qtype::DICTIONARY, qattribute::NONE)
             };
             Ok(K::new(
                 qtype,
-                qattribute::NONE,
-                k0_inner::list(k0_list::new(vec![keys, values])),
+                attribute,
+                k0_inner::list(k0_list::dictionary(
+                    Arc::new(vec![keys, values]),
+                    KeyIndex::default(),
+                )),
             ))
         }
     }
@@ -2069,7 +2081,7 @@ impl K {
     /// }
     /// ```
     pub fn new_error(error: String) -> Self {
-        K::new(qtype::ERROR, qattribute::NONE, k0_inner::symbol(error))
+        K::new(qtype::ERROR, qattribute::NONE, k0_inner::string(error))
     }
 
     // Getter //---------------------------------/
@@ -2289,7 +2301,7 @@ impl K {
     pub fn get_symbol(&self) -> Result<&str> {
         match self.0.qtype {
             qtype::SYMBOL_ATOM => match &self.0.value {
-                k0_inner::symbol(symbol) => Ok(symbol),
+                k0_inner::symbol(symbol) => Ok(symbol.as_str()),
                 _ => unreachable!(),
             },
             _ => Err(Error::invalid_cast(self.0.qtype, qtype::SYMBOL_ATOM)),
@@ -2611,7 +2623,7 @@ impl K {
     pub fn get_error_string(&self) -> Result<&str> {
         match self.0.qtype {
             qtype::ERROR => match &self.0.value {
-                k0_inner::symbol(error) => Ok(error),
+                k0_inner::string(error) => Ok(error),
                 _ => unreachable!(),
             },
             _ => Err(Error::invalid_cast(self.0.qtype, qtype::ERROR)),
@@ -2632,7 +2644,7 @@ impl K {
     pub fn as_string(&self) -> Result<&str> {
         match self.0.qtype {
             qtype::STRING => match &self.0.value {
-                k0_inner::symbol(string) => Ok(string),
+                k0_inner::string(string) => Ok(string),
                 _ => unreachable!(),
             },
             _ => Err(Error::invalid_cast(self.0.qtype, qtype::STRING)),
@@ -2654,7 +2666,7 @@ impl K {
     pub fn as_mut_string(&mut self) -> Result<&mut String> {
         match self.0.qtype {
             qtype::STRING => match &mut self.0.value {
-                k0_inner::symbol(string) => Ok(string),
+                k0_inner::string(string) => Ok(string),
                 _ => unreachable!(),
             },
             _ => Err(Error::invalid_cast(self.0.qtype, qtype::STRING)),
@@ -2662,6 +2674,8 @@ impl K {
     }
 
     /// Get the underlying mutable vector. If the specified type is wrong, it returns an empty vector.
+    /// # Note
+    /// A list is shared among clones of a `K` object. The list is copied on this call if it is shared.
     /// # Example
     /// ```
     /// use kdbplus::qattribute;
@@ -2687,6 +2701,10 @@ impl K {
     where
         T: 'static,
     {
+        // Keys of a dictionary may be modified.
+        if let k0_inner::list(k0_list::dictionary(_, index)) = &mut self.0.value {
+            index.clear();
+        }
         match self.0.qtype {
             qtype::COMPOUND_LIST
             | qtype::BOOL_LIST
@@ -2708,7 +2726,7 @@ impl K {
             | qtype::TIME_LIST
             | qtype::DICTIONARY
             | qtype::SORTED_DICTIONARY => match &mut self.0.value {
-                k0_inner::list(list) => match list.G0.as_any_mut().downcast_mut::<Vec<T>>() {
+                k0_inner::list(list) => match list.as_mut_vec::<T>() {
                     Some(vector) => Ok(vector),
                     _ => Err(Error::invalid_cast_list(self.0.qtype)),
                 },
@@ -2754,7 +2772,7 @@ impl K {
             | qtype::TIME_LIST
             | qtype::DICTIONARY
             | qtype::SORTED_DICTIONARY => match &self.0.value {
-                k0_inner::list(list) => match list.G0.as_any().downcast_ref::<Vec<T>>() {
+                k0_inner::list(list) => match list.as_vec::<T>() {
                     Some(vector) => Ok(vector),
                     _ => Err(Error::invalid_cast_list(self.0.qtype)),
                 },
@@ -2813,7 +2831,7 @@ impl K {
                     _ => Err(Error::no_such_column(column.to_string())),
                 }
             }
-            qtype::DICTIONARY => {
+            qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
                 let key_value = self.as_vec::<K>().unwrap();
                 if key_value[0].0.qtype == qtype::TABLE {
                     // Keyed table
@@ -2897,7 +2915,7 @@ impl K {
                     _ => Err(Error::no_such_column(column.to_string())),
                 }
             }
-            qtype::DICTIONARY => {
+            qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
                 let key_value = self.as_vec::<K>().unwrap();
                 if key_value[0].0.qtype == qtype::TABLE {
                     // Keyed table
@@ -3001,7 +3019,17 @@ impl K {
 
     // Setter //---------------------------------/
 
-    /// Set an attribute to the underlying q object.
+    /// Set an attribute to the underlying q object. The precondition of the attribute is validated and
+    ///  `Error::InvalidAttribute` is returned if the object does not satisfy it:
+    /// - sorted (`s#`): elements are in ascending order.
+    /// - unique (`u#`): no element appears twice.
+    /// - parted (`p#`): equal elements are contiguous.
+    /// - grouped (`g#`): no precondition.
+    ///
+    /// Attributes other than none can be set only to a simple list, except that sorted attribute can be set to
+    ///  a dictionary whose keys are sorted and to a keyed table whose rows of keys are sorted. As q does, the
+    ///  sorted attribute of a dictionary is held by the keys (the first key column for a keyed table) and the
+    ///  dictionary becomes `qtype::SORTED_DICTIONARY`.
     /// # Example
     /// ```
     /// use kdbplus::qattribute;
@@ -3018,32 +3046,171 @@ impl K {
     ///     timestamp_list
     ///         .push(&Utc.ymd(2021, 3, 13).and_hms_nano(5, 47, 2, 260484387))
     ///         .unwrap();
-    ///     timestamp_list.set_attribute(qattribute::SORTED);
+    ///     timestamp_list.set_attribute(qattribute::SORTED).unwrap();
     ///     assert_eq!(timestamp_list.get_attribute(), qattribute::SORTED);
+    ///
+    ///     let mut symbol_list = K::new_symbol_list(
+    ///         vec![String::from("b"), String::from("a"), String::from("b")],
+    ///         qattribute::NONE,
+    ///     );
+    ///     assert!(symbol_list.set_attribute(qattribute::SORTED).is_err());
+    ///     assert!(symbol_list.set_attribute(qattribute::UNIQUE).is_err());
+    ///     assert!(symbol_list.set_attribute(qattribute::PARTED).is_err());
+    ///     assert!(symbol_list.set_attribute(qattribute::GROUPED).is_ok());
     /// }
     /// ```
-    /// # Note
-    /// The validity of the attribute is not checked. For example, if you set a sorted attribute to
-    ///  an unsorted list, it does not return an error. It will fail in q process.
-    pub fn set_attribute(&mut self, attribute: i8) {
+    pub fn set_attribute(&mut self, attribute: i8) -> Result<()> {
+        if self.satisfies_attribute(attribute) {
+            if let qtype::DICTIONARY | qtype::SORTED_DICTIONARY = self.0.qtype {
+                self.set_sorted_keys(attribute == qattribute::SORTED);
+            }
+            self.0.attribute = attribute;
+            Ok(())
+        } else {
+            Err(Error::invalid_attribute(attribute, self.0.qtype))
+        }
+    }
+
+    /// Rearrange the underlying q list so that it satisfies an attribute and set the attribute.
+    /// - sorted (`s#`): sort the list in ascending order.
+    /// - unique (`u#`): remove duplicates keeping the first occurrence as `distinct` of q does.
+    /// - parted (`p#`): gather equal elements in order of their first occurrence.
+    /// - grouped (`g#`): leave the list as it is.
+    ///
+    /// Nulls are regarded as smaller than any other value as q does.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let mut long_list = K::new_long_list(vec![3, 1, 3, qnull::LONG, 1], qattribute::NONE);
+    ///     long_list.apply_attribute(qattribute::PARTED).unwrap();
+    ///     assert_eq!(format!("{}", long_list), String::from("`p#3 3 1 1 0N"));
+    ///
+    ///     long_list.apply_attribute(qattribute::UNIQUE).unwrap();
+    ///     assert_eq!(format!("{}", long_list), String::from("`u#3 1 0N"));
+    ///
+    ///     long_list.apply_attribute(qattribute::SORTED).unwrap();
+    ///     assert_eq!(format!("{}", long_list), String::from("`s#0N 1 3"));
+    ///     assert_eq!(long_list.len(), 3);
+    /// }
+    /// ```
+    pub fn apply_attribute(&mut self, attribute: i8) -> Result<()> {
+        if !(qattribute::NONE..=qattribute::GROUPED).contains(&attribute) {
+            return Err(Error::invalid_attribute(attribute, self.0.qtype));
+        }
+        match self.0.qtype {
+            qtype::STRING => {
+                let string = self.as_mut_string().unwrap();
+                let mut characters = string.chars().collect::<Vec<char>>();
+                attribute::apply(&mut characters, attribute, char::cmp);
+                *string = characters.into_iter().collect();
+            }
+            _ => with_simple_list!(
+                self,
+                as_mut_vec,
+                |list, compare| attribute::apply(list, attribute, compare),
+                if attribute != qattribute::NONE {
+                    return Err(Error::invalid_attribute(attribute, self.0.qtype));
+                }
+            ),
+        }
         self.0.attribute = attribute;
+        Ok(())
     }
 
-    // Push/Pop //-------------------------------/
+    /// Check if the underlying q object satisfies a precondition of an attribute.
+    fn satisfies_attribute(&self, attribute: i8) -> bool {
+        match self.0.qtype {
+            qtype::DICTIONARY | qtype::SORTED_DICTIONARY => match attribute {
+                qattribute::NONE => true,
+                qattribute::SORTED => {
+                    let keys = &self.as_vec::<K>().unwrap()[0];
+                    match keys.0.qtype {
+                        qtype::TABLE => rows_are_sorted(keys, 0),
+                        _ => keys.satisfies_attribute(attribute),
+                    }
+                }
+                _ => false,
+            },
+            qtype::STRING => {
+                attribute::satisfies(self.as_string().unwrap().as_bytes(), attribute, u8::cmp)
+            }
+            _ => with_simple_list!(
+                self,
+                as_vec,
+                |list, compare| attribute::satisfies(list, attribute, compare),
+                attribute == qattribute::NONE
+            ),
+        }
+    }
 
-    /// Increment `n` of `k0_list`.
-    fn increment(&mut self) {
-        match &mut self.0.value {
-            k0_inner::list(list) => list.n += 1,
+    /// Set or drop the sorted attribute of keys of a dictionary or the first key column of a keyed table and
+    ///  switch the type of the dictionary accordingly.
+    fn set_sorted_keys(&mut self, sorted: bool) {
+        let (qtype, attribute) = if sorted {
+            (qtype::SORTED_DICTIONARY, qattribute::SORTED)
+        } else {
+            (qtype::DICTIONARY, qattribute::NONE)
+        };
+        self.0.qtype = qtype;
+        self.0.attribute = attribute;
+        // Keys are not modified and the index of keys is kept.
+        let keys = match &mut self.0.value {
+            k0_inner::list(list) => &mut list.as_mut_vec::<K>().unwrap()[0],
             _ => unreachable!(),
+        };
+        let keys = match keys.0.qtype {
+            qtype::TABLE => &mut keys
+                .get_mut_dictionary()
+                .unwrap()
+                .as_mut_vec::<K>()
+                .unwrap()[1]
+                .as_mut_vec::<K>()
+                .unwrap()[0],
+            _ => keys,
+        };
+        if sorted || keys.0.attribute == qattribute::SORTED {
+            keys.0.attribute = attribute;
         }
     }
 
-    /// Decrement `n` of `k0_list`.
-    fn decrement(&mut self) {
-        match &mut self.0.value {
-            k0_inner::list(list) => list.n -= 1,
-            _ => unreachable!(),
+    /// Keep or drop the attribute after an element was added at `index` in the same way as q does, i.e.,
+    ///  sorted and unique attributes are kept only if they are still satisfied, grouped attribute is always
+    ///  kept and parted attribute is dropped.
+    fn update_attribute_at(&mut self, index: usize) {
+        let attribute = self.0.attribute;
+        let keep = match attribute {
+            qattribute::SORTED | qattribute::UNIQUE => match self.0.qtype {
+                qtype::STRING => attribute::satisfies_at(
+                    self.as_string().unwrap().as_bytes(),
+                    attribute,
+                    index,
+                    u8::cmp,
+                ),
+                _ => with_simple_list!(
+                    self,
+                    as_vec,
+                    |list, compare| attribute::satisfies_at(list, attribute, index, compare),
+                    false
+                ),
+            },
+            qattribute::GROUPED => true,
+            _ => false,
+        };
+        if !keep {
+            self.0.attribute = qattribute::NONE;
+        }
+    }
+
+    // Push/Pop //-------------------------------/
+
+    /// Drop the attribute after an element was removed in the same way as q does, i.e., parted attribute
+    ///  is dropped while other attributes are still satisfied.
+    fn update_attribute_on_removal(&mut self) {
+        if self.0.attribute == qattribute::PARTED {
+            self.0.attribute = qattribute::NONE;
         }
     }
 
@@ -3080,10 +3247,9 @@ impl K {
     /// # Note
     /// For the range of valid `DateTime`, see [`chrono`](https://docs.rs/chrono/latest/chrono/struct.DateTime.html#method.timestamp_nanos).
     pub fn push(&mut self, element: &dyn Any) -> Result<()> {
-        match self.0.qtype {
+        let pushed = match self.0.qtype {
             qtype::BOOL_LIST => {
                 if let Some(boolean) = element.downcast_ref::<bool>() {
-                    self.increment();
                     Ok(self.as_mut_vec::<G>().unwrap().push(*boolean as u8))
                 } else {
                     Err(Error::insert_wrong_element(false, qtype::BOOL_LIST, "bool"))
@@ -3091,7 +3257,6 @@ impl K {
             }
             qtype::GUID_LIST => {
                 if let Some(guid) = element.downcast_ref::<U>() {
-                    self.increment();
                     Ok(self.as_mut_vec::<U>().unwrap().push(*guid))
                 } else {
                     Err(Error::insert_wrong_element(
@@ -3103,7 +3268,6 @@ impl K {
             }
             qtype::BYTE_LIST => {
                 if let Some(byte) = element.downcast_ref::<u8>() {
-                    self.increment();
                     Ok(self.as_mut_vec::<G>().unwrap().push(*byte))
                 } else {
                     Err(Error::insert_wrong_element(false, qtype::BYTE_LIST, "u8"))
@@ -3111,7 +3275,6 @@ impl K {
             }
             qtype::SHORT_LIST => {
                 if let Some(short) = element.downcast_ref::<i16>() {
-                    self.increment();
                     Ok(self.as_mut_vec::<H>().unwrap().push(*short))
                 } else {
                     Err(Error::insert_wrong_element(false, qtype::SHORT_LIST, "i16"))
@@ -3119,7 +3282,6 @@ impl K {
             }
             qtype::INT_LIST => {
                 if let Some(int) = element.downcast_ref::<i32>() {
-                    self.increment();
                     Ok(self.as_mut_vec::<I>().unwrap().push(*int))
                 } else {
                     Err(Error::insert_wrong_element(
@@ -3131,7 +3293,6 @@ impl K {
             }
             qtype::LONG_LIST => {
                 if let Some(long) = element.downcast_ref::<i64>() {
-                    self.increment();
                     Ok(self.as_mut_vec::<J>().unwrap().push(*long))
                 } else {
                     Err(Error::insert_wrong_element(false, qtype::LONG_LIST, "i64"))
@@ -3139,7 +3300,6 @@ impl K {
             }
             qtype::REAL_LIST => {
                 if let Some(real) = element.downcast_ref::<f32>() {
-                    self.increment();
                     Ok(self.as_mut_vec::<E>().unwrap().push(*real))
                 } else {
                     Err(Error::insert_wrong_element(false, qtype::FLOAT_LIST, "f32"))
@@ -3147,7 +3307,6 @@ impl K {
             }
             qtype::FLOAT_LIST => {
                 if let Some(float) = element.downcast_ref::<f64>() {
-                    self.increment();
                     Ok(self.as_mut_vec::<F>().unwrap().push(*float))
                 } else {
                     Err(Error::insert_wrong_element(false, qtype::FLOAT_LIST, "f64"))
@@ -3161,9 +3320,8 @@ impl K {
                 }
             }
             qtype::SYMBOL_LIST => {
-                if let Some(symbol) = element.downcast_ref::<String>() {
-                    self.increment();
-                    Ok(self.as_mut_vec::<S>().unwrap().push(symbol.clone()))
+                if let Some(symbol) = downcast_symbol(element) {
+                    Ok(self.as_mut_vec::<S>().unwrap().push(symbol))
                 } else {
                     Err(Error::insert_wrong_element(
                         false,
@@ -3174,7 +3332,6 @@ impl K {
             }
             qtype::TIMESTAMP_LIST => {
                 if let Some(timestamp) = element.downcast_ref::<DateTime<Utc>>() {
-                    self.increment();
                     Ok(self
                         .as_mut_vec::<J>()
                         .unwrap()
@@ -3189,7 +3346,6 @@ impl K {
             }
             qtype::MONTH_LIST => {
                 if let Some(month) = element.downcast_ref::<NaiveDate>() {
-                    self.increment();
                     Ok(self
                         .as_mut_vec::<I>()
                         .unwrap()
@@ -3204,7 +3360,6 @@ impl K {
             }
             qtype::DATE_LIST => {
                 if let Some(date) = element.downcast_ref::<NaiveDate>() {
-                    self.increment();
                     Ok(self.as_mut_vec::<I>().unwrap().push(date_to_q_date(*date)))
                 } else {
                     Err(Error::insert_wrong_element(
@@ -3216,7 +3371,6 @@ impl K {
             }
             qtype::DATETIME_LIST => {
                 if let Some(datetime) = element.downcast_ref::<DateTime<Utc>>() {
-                    self.increment();
                     Ok(self
                         .as_mut_vec::<F>()
                         .unwrap()
@@ -3231,7 +3385,6 @@ impl K {
             }
             qtype::TIMESPAN_LIST => {
                 if let Some(timespan) = element.downcast_ref::<Duration>() {
-                    self.increment();
                     Ok(self
                         .as_mut_vec::<J>()
                         .unwrap()
@@ -3246,7 +3399,6 @@ impl K {
             }
             qtype::MINUTE_LIST => {
                 if let Some(minute) = element.downcast_ref::<Duration>() {
-                    self.increment();
                     Ok(self
                         .as_mut_vec::<I>()
                         .unwrap()
@@ -3261,7 +3413,6 @@ impl K {
             }
             qtype::SECOND_LIST => {
                 if let Some(second) = element.downcast_ref::<Duration>() {
-                    self.increment();
                     Ok(self
                         .as_mut_vec::<I>()
                         .unwrap()
@@ -3276,7 +3427,6 @@ impl K {
             }
             qtype::TIME_LIST => {
                 if let Some(time) = element.downcast_ref::<Duration>() {
-                    self.increment();
                     Ok(self
                         .as_mut_vec::<I>()
                         .unwrap()
@@ -3291,7 +3441,6 @@ impl K {
             }
             qtype::COMPOUND_LIST => {
                 if let Some(k) = element.downcast_ref::<K>() {
-                    self.increment();
                     Ok(self.as_mut_vec::<K>().unwrap().push(k.clone()))
                 } else {
                     Err(Error::insert_wrong_element(
@@ -3302,7 +3451,11 @@ impl K {
                 }
             }
             _ => Err(Error::invalid_operation("push", self.0.qtype, None)),
+        };
+        if pushed.is_ok() {
+            self.update_attribute_at(self.len() - 1);
         }
+        pushed
     }
 
     /// Insert an element to the underlying q list at the specified location by an index.
@@ -3338,10 +3491,9 @@ impl K {
         if index > self.len() {
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let inserted = match self.0.qtype {
                 qtype::BOOL_LIST => {
                     if let Some(boolean) = element.downcast_ref::<bool>() {
-                        self.increment();
                         Ok(self
                             .as_mut_vec::<G>()
                             .unwrap()
@@ -3352,7 +3504,6 @@ impl K {
                 }
                 qtype::GUID_LIST => {
                     if let Some(guid) = element.downcast_ref::<U>() {
-                        self.increment();
                         Ok(self.as_mut_vec::<U>().unwrap().insert(index, *guid))
                     } else {
                         Err(Error::insert_wrong_element(
@@ -3364,7 +3515,6 @@ impl K {
                 }
                 qtype::BYTE_LIST => {
                     if let Some(byte) = element.downcast_ref::<u8>() {
-                        self.increment();
                         Ok(self.as_mut_vec::<G>().unwrap().insert(index, *byte))
                     } else {
                         Err(Error::insert_wrong_element(true, qtype::BYTE_LIST, "u8"))
@@ -3372,7 +3522,6 @@ impl K {
                 }
                 qtype::SHORT_LIST => {
                     if let Some(short) = element.downcast_ref::<i16>() {
-                        self.increment();
                         Ok(self.as_mut_vec::<H>().unwrap().insert(index, *short))
                     } else {
                         Err(Error::insert_wrong_element(false, qtype::SHORT_LIST, "i16"))
@@ -3380,7 +3529,6 @@ impl K {
                 }
                 qtype::INT_LIST => {
                     if let Some(int) = element.downcast_ref::<i32>() {
-                        self.increment();
                         Ok(self.as_mut_vec::<I>().unwrap().insert(index, *int))
                     } else {
                         Err(Error::insert_wrong_element(false, qtype::INT_LIST, "i32"))
@@ -3388,7 +3536,6 @@ impl K {
                 }
                 qtype::LONG_LIST => {
                     if let Some(long) = element.downcast_ref::<i64>() {
-                        self.increment();
                         Ok(self.as_mut_vec::<J>().unwrap().insert(index, *long))
                     } else {
                         Err(Error::insert_wrong_element(false, qtype::LONG_LIST, "i64"))
@@ -3396,7 +3543,6 @@ impl K {
                 }
                 qtype::REAL_LIST => {
                     if let Some(real) = element.downcast_ref::<f32>() {
-                        self.increment();
                         Ok(self.as_mut_vec::<E>().unwrap().insert(index, *real))
                     } else {
                         Err(Error::insert_wrong_element(false, qtype::REAL_LIST, "f32"))
@@ -3404,7 +3550,6 @@ impl K {
                 }
                 qtype::FLOAT_LIST => {
                     if let Some(float) = element.downcast_ref::<f64>() {
-                        self.increment();
                         Ok(self.as_mut_vec::<F>().unwrap().insert(index, *float))
                     } else {
                         Err(Error::insert_wrong_element(false, qtype::FLOAT_LIST, "f64"))
@@ -3418,12 +3563,8 @@ impl K {
                     }
                 }
                 qtype::SYMBOL_LIST => {
-                    if let Some(symbol) = element.downcast_ref::<String>() {
-                        self.increment();
-                        Ok(self
-                            .as_mut_vec::<S>()
-                            .unwrap()
-                            .insert(index, symbol.clone()))
+                    if let Some(symbol) = downcast_symbol(element) {
+                        Ok(self.as_mut_vec::<S>().unwrap().insert(index, symbol))
                     } else {
                         Err(Error::insert_wrong_element(
                             false,
@@ -3434,7 +3575,6 @@ impl K {
                 }
                 qtype::TIMESTAMP_LIST => {
                     if let Some(timestamp) = element.downcast_ref::<DateTime<Utc>>() {
-                        self.increment();
                         Ok(self
                             .as_mut_vec::<J>()
                             .unwrap()
@@ -3449,7 +3589,6 @@ impl K {
                 }
                 qtype::MONTH_LIST => {
                     if let Some(month) = element.downcast_ref::<NaiveDate>() {
-                        self.increment();
                         Ok(self
                             .as_mut_vec::<I>()
                             .unwrap()
@@ -3464,7 +3603,6 @@ impl K {
                 }
                 qtype::DATE_LIST => {
                     if let Some(date) = element.downcast_ref::<NaiveDate>() {
-                        self.increment();
                         Ok(self
                             .as_mut_vec::<I>()
                             .unwrap()
@@ -3479,7 +3617,6 @@ impl K {
                 }
                 qtype::DATETIME_LIST => {
                     if let Some(datetime) = element.downcast_ref::<DateTime<Utc>>() {
-                        self.increment();
                         Ok(self
                             .as_mut_vec::<F>()
                             .unwrap()
@@ -3494,7 +3631,6 @@ impl K {
                 }
                 qtype::TIMESPAN_LIST => {
                     if let Some(timespan) = element.downcast_ref::<Duration>() {
-                        self.increment();
                         Ok(self.as_mut_vec::<J>().unwrap().insert(
                             index,
                             timespan.num_nanoseconds().expect("duration overflow"),
@@ -3509,7 +3645,6 @@ impl K {
                 }
                 qtype::MINUTE_LIST => {
                     if let Some(minute) = element.downcast_ref::<Duration>() {
-                        self.increment();
                         Ok(self
                             .as_mut_vec::<I>()
                             .unwrap()
@@ -3524,7 +3659,6 @@ impl K {
                 }
                 qtype::SECOND_LIST => {
                     if let Some(second) = element.downcast_ref::<Duration>() {
-                        self.increment();
                         Ok(self
                             .as_mut_vec::<I>()
                             .unwrap()
@@ -3539,7 +3673,6 @@ impl K {
                 }
                 qtype::TIME_LIST => {
                     if let Some(time) = element.downcast_ref::<Duration>() {
-                        self.increment();
                         Ok(self
                             .as_mut_vec::<I>()
                             .unwrap()
@@ -3554,7 +3687,6 @@ impl K {
                 }
                 qtype::COMPOUND_LIST => {
                     if let Some(k) = element.downcast_ref::<K>() {
-                        self.increment();
                         Ok(self.as_mut_vec::<K>().unwrap().insert(index, k.clone()))
                     } else {
                         Err(Error::insert_wrong_element(
@@ -3565,7 +3697,11 @@ impl K {
                     }
                 }
                 _ => Err(Error::invalid_operation("insert", self.0.qtype, None)),
+            };
+            if inserted.is_ok() {
+                self.update_attribute_at(index);
             }
+            inserted
         }
     }
 
@@ -3588,7 +3724,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::BOOL_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(self.as_mut_vec::<G>().unwrap().pop().unwrap() != 0)
                 }
                 _ => Err(Error::invalid_operation(
@@ -3625,7 +3761,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::GUID_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(self.as_mut_vec::<U>().unwrap().pop().unwrap())
                 }
                 _ => Err(Error::invalid_operation(
@@ -3656,7 +3792,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::BYTE_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(self.as_mut_vec::<G>().unwrap().pop().unwrap())
                 }
                 _ => Err(Error::invalid_operation(
@@ -3687,7 +3823,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::SHORT_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(self.as_mut_vec::<H>().unwrap().pop().unwrap())
                 }
                 _ => Err(Error::invalid_operation(
@@ -3718,7 +3854,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::INT_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(self.as_mut_vec::<I>().unwrap().pop().unwrap())
                 }
                 _ => Err(Error::invalid_operation(
@@ -3749,7 +3885,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::LONG_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(self.as_mut_vec::<J>().unwrap().pop().unwrap())
                 }
                 _ => Err(Error::invalid_operation(
@@ -3780,7 +3916,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::REAL_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(self.as_mut_vec::<E>().unwrap().pop().unwrap())
                 }
                 _ => Err(Error::invalid_operation(
@@ -3811,7 +3947,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::FLOAT_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(self.as_mut_vec::<F>().unwrap().pop().unwrap())
                 }
                 _ => Err(Error::invalid_operation(
@@ -3841,7 +3977,10 @@ impl K {
             Err(Error::pop_from_empty_list())
         } else {
             match self.0.qtype {
-                qtype::STRING => Ok(self.as_mut_string().unwrap().pop().unwrap()),
+                qtype::STRING => {
+                    self.update_attribute_on_removal();
+                    Ok(self.as_mut_string().unwrap().pop().unwrap())
+                }
                 _ => Err(Error::invalid_operation(
                     "pop_char",
                     self.0.qtype,
@@ -3870,8 +4009,8 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::SYMBOL_LIST => {
-                    self.decrement();
-                    Ok(self.as_mut_vec::<S>().unwrap().pop().unwrap())
+                    self.update_attribute_on_removal();
+                    Ok(String::from(self.as_mut_vec::<S>().unwrap().pop().unwrap()))
                 }
                 _ => Err(Error::invalid_operation(
                     "pop_symbol",
@@ -3905,7 +4044,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::TIMESTAMP_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(q_timestamp_to_datetime(
                         self.as_mut_vec::<J>().unwrap().pop().unwrap(),
                     ))
@@ -3945,7 +4084,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::MONTH_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(q_month_to_date(
                         self.as_mut_vec::<I>().unwrap().pop().unwrap(),
                     ))
@@ -3986,7 +4125,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::DATE_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(q_date_to_date(
                         self.as_mut_vec::<I>().unwrap().pop().unwrap(),
                     )?)
@@ -4026,7 +4165,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::DATETIME_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(q_datetime_to_datetime(
                         self.as_mut_vec::<F>().unwrap().pop().unwrap(),
                     ))
@@ -4066,7 +4205,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::TIMESPAN_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(Duration::nanoseconds(
                         self.as_mut_vec::<J>().unwrap().pop().unwrap(),
                     ))
@@ -4103,7 +4242,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::MINUTE_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(Duration::minutes(
                         self.as_mut_vec::<I>().unwrap().pop().unwrap() as i64,
                     ))
@@ -4140,7 +4279,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::SECOND_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(Duration::seconds(
                         self.as_mut_vec::<I>().unwrap().pop().unwrap() as i64,
                     ))
@@ -4180,7 +4319,7 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::TIME_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(Duration::milliseconds(
                         self.as_mut_vec::<I>().unwrap().pop().unwrap() as i64,
                     ))
@@ -4237,48 +4376,51 @@ impl K {
         } else {
             match self.0.qtype {
                 qtype::BOOL_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new_bool(
                         self.as_mut_vec::<G>().unwrap().pop().unwrap() != 0,
                     ))
                 }
                 qtype::GUID_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new_guid(self.as_mut_vec::<U>().unwrap().pop().unwrap()))
                 }
                 qtype::BYTE_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new_byte(self.as_mut_vec::<G>().unwrap().pop().unwrap()))
                 }
                 qtype::SHORT_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new_short(self.as_mut_vec::<H>().unwrap().pop().unwrap()))
                 }
                 qtype::INT_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new_int(self.as_mut_vec::<I>().unwrap().pop().unwrap()))
                 }
                 qtype::LONG_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new_long(self.as_mut_vec::<J>().unwrap().pop().unwrap()))
                 }
                 qtype::REAL_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new_real(self.as_mut_vec::<E>().unwrap().pop().unwrap()))
                 }
                 qtype::FLOAT_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new_float(self.as_mut_vec::<F>().unwrap().pop().unwrap()))
                 }
-                qtype::STRING => Ok(K::new_char(self.as_mut_string().unwrap().pop().unwrap())),
+                qtype::STRING => {
+                    self.update_attribute_on_removal();
+                    Ok(K::new_char(self.as_mut_string().unwrap().pop().unwrap()))
+                }
                 qtype::SYMBOL_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new_symbol(
                         self.as_mut_vec::<S>().unwrap().pop().unwrap(),
                     ))
                 }
                 qtype::TIMESTAMP_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new(
                         qtype::TIMESTAMP_ATOM,
                         qattribute::NONE,
@@ -4286,7 +4428,7 @@ impl K {
                     ))
                 }
                 qtype::MONTH_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new(
                         qtype::MONTH_ATOM,
                         qattribute::NONE,
@@ -4294,7 +4436,7 @@ impl K {
                     ))
                 }
                 qtype::DATE_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new(
                         qtype::DATE_ATOM,
                         qattribute::NONE,
@@ -4302,7 +4444,7 @@ impl K {
                     ))
                 }
                 qtype::DATETIME_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new(
                         qtype::DATETIME_ATOM,
                         qattribute::NONE,
@@ -4310,7 +4452,7 @@ impl K {
                     ))
                 }
                 qtype::TIMESPAN_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new(
                         qtype::TIMESPAN_ATOM,
                         qattribute::NONE,
@@ -4318,7 +4460,7 @@ impl K {
                     ))
                 }
                 qtype::MINUTE_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new(
                         qtype::MINUTE_ATOM,
                         qattribute::NONE,
@@ -4326,7 +4468,7 @@ impl K {
                     ))
                 }
                 qtype::SECOND_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new(
                         qtype::SECOND_ATOM,
                         qattribute::NONE,
@@ -4334,7 +4476,7 @@ impl K {
                     ))
                 }
                 qtype::TIME_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(K::new(
                         qtype::TIME_ATOM,
                         qattribute::NONE,
@@ -4342,7 +4484,7 @@ impl K {
                     ))
                 }
                 qtype::COMPOUND_LIST => {
-                    self.decrement();
+                    self.update_attribute_on_removal();
                     Ok(self.as_mut_vec::<K>().unwrap().pop().unwrap())
                 }
                 _ => Err(Error::invalid_operation("pop", self.0.qtype, None)),
@@ -4367,14 +4509,18 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::BOOL_LIST => Ok(self.as_mut_vec::<G>().unwrap().remove(index) != 0),
                 _ => Err(Error::invalid_operation(
                     "remove_bool",
                     self.0.qtype,
                     Some(qtype::BOOL_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4395,14 +4541,18 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::GUID_LIST => Ok(self.as_mut_vec::<U>().unwrap().remove(index)),
                 _ => Err(Error::invalid_operation(
                     "remove_guid",
                     self.0.qtype,
                     Some(qtype::GUID_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4423,14 +4573,18 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::BYTE_LIST => Ok(self.as_mut_vec::<G>().unwrap().remove(index)),
                 _ => Err(Error::invalid_operation(
                     "remove_byte",
                     self.0.qtype,
                     Some(qtype::BYTE_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4451,14 +4605,18 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::SHORT_LIST => Ok(self.as_mut_vec::<H>().unwrap().remove(index)),
                 _ => Err(Error::invalid_operation(
                     "remove_short",
                     self.0.qtype,
                     Some(qtype::SHORT_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4479,14 +4637,18 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::INT_LIST => Ok(self.as_mut_vec::<I>().unwrap().remove(index)),
                 _ => Err(Error::invalid_operation(
                     "remove_int",
                     self.0.qtype,
                     Some(qtype::INT_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4507,14 +4669,18 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::LONG_LIST => Ok(self.as_mut_vec::<J>().unwrap().remove(index)),
                 _ => Err(Error::invalid_operation(
                     "remove_long",
                     self.0.qtype,
                     Some(qtype::LONG_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4535,14 +4701,18 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::REAL_LIST => Ok(self.as_mut_vec::<E>().unwrap().remove(index)),
                 _ => Err(Error::invalid_operation(
                     "remove_real",
                     self.0.qtype,
                     Some(qtype::REAL_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4563,14 +4733,18 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::FLOAT_LIST => Ok(self.as_mut_vec::<F>().unwrap().remove(index)),
                 _ => Err(Error::invalid_operation(
                     "remove_float",
                     self.0.qtype,
                     Some(qtype::FLOAT_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4591,14 +4765,18 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::STRING => Ok(self.as_mut_string().unwrap().remove(index)),
                 _ => Err(Error::invalid_operation(
                     "remove_char",
                     self.0.qtype,
                     Some(qtype::STRING),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4626,14 +4804,20 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
-                qtype::SYMBOL_LIST => Ok(self.as_mut_vec::<S>().unwrap().remove(index)),
+            let removed = match self.0.qtype {
+                qtype::SYMBOL_LIST => {
+                    Ok(String::from(self.as_mut_vec::<S>().unwrap().remove(index)))
+                }
                 _ => Err(Error::invalid_operation(
                     "remove_symbol",
                     self.0.qtype,
                     Some(qtype::SYMBOL_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4661,7 +4845,7 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::TIMESTAMP_LIST => Ok(q_timestamp_to_datetime(
                     self.as_mut_vec::<J>().unwrap().remove(index),
                 )),
@@ -4670,7 +4854,11 @@ impl K {
                     self.0.qtype,
                     Some(qtype::TIMESTAMP_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4698,7 +4886,7 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::MONTH_LIST => Ok(q_month_to_date(
                     self.as_mut_vec::<I>().unwrap().remove(index),
                 )),
@@ -4707,7 +4895,11 @@ impl K {
                     self.0.qtype,
                     Some(qtype::MONTH_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4736,16 +4928,23 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
-                qtype::DATE_LIST => Ok(q_date_to_date(
-                    self.as_mut_vec::<I>().unwrap().remove(index),
-                )?),
+            let removed = match self.0.qtype {
+                qtype::DATE_LIST => {
+                    // Convert before removing so that a failure leaves the list intact.
+                    let date = q_date_to_date(self.as_vec::<I>()?[index])?;
+                    self.as_mut_vec::<I>()?.remove(index);
+                    Ok(date)
+                }
                 _ => Err(Error::invalid_operation(
                     "remove_date",
                     self.0.qtype,
                     Some(qtype::DATE_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4773,7 +4972,7 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::DATETIME_LIST => Ok(q_datetime_to_datetime(
                     self.as_mut_vec::<F>().unwrap().remove(index),
                 )),
@@ -4782,7 +4981,11 @@ impl K {
                     self.0.qtype,
                     Some(qtype::DATETIME_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4810,7 +5013,7 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::TIMESPAN_LIST => Ok(Duration::nanoseconds(
                     self.as_mut_vec::<J>().unwrap().remove(index),
                 )),
@@ -4819,7 +5022,11 @@ impl K {
                     self.0.qtype,
                     Some(qtype::TIMESPAN_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4844,7 +5051,7 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::MINUTE_LIST => Ok(Duration::minutes(
                     self.as_mut_vec::<I>().unwrap().remove(index) as i64,
                 )),
@@ -4853,7 +5060,11 @@ impl K {
                     self.0.qtype,
                     Some(qtype::MINUTE_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4878,7 +5089,7 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::SECOND_LIST => Ok(Duration::seconds(
                     self.as_mut_vec::<I>().unwrap().remove(index) as i64,
                 )),
@@ -4887,7 +5098,11 @@ impl K {
                     self.0.qtype,
                     Some(qtype::SECOND_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4916,7 +5131,7 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::TIME_LIST => Ok(Duration::milliseconds(
                     self.as_mut_vec::<I>().unwrap().remove(index) as i64,
                 )),
@@ -4925,7 +5140,11 @@ impl K {
                     self.0.qtype,
                     Some(qtype::TIME_LIST),
                 )),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -4968,7 +5187,7 @@ impl K {
             // 0 length
             Err(Error::index_out_of_bounds(self.len(), index))
         } else {
-            match self.0.qtype {
+            let removed = match self.0.qtype {
                 qtype::BOOL_LIST => Ok(K::new_bool(
                     self.as_mut_vec::<G>().unwrap().remove(index) != 0,
                 )),
@@ -5013,7 +5232,11 @@ impl K {
                 ))),
                 qtype::COMPOUND_LIST => Ok(self.as_mut_vec::<K>().unwrap().remove(index)),
                 _ => Err(Error::invalid_operation("remove", self.0.qtype, None)),
+            };
+            if removed.is_ok() {
+                self.update_attribute_on_removal();
             }
+            removed
         }
     }
 
@@ -5155,10 +5378,10 @@ impl K {
             _t @ qtype::COMPOUND_LIST..=qtype::TIME_LIST => {
                 // List
                 match &self.0.value {
-                    // string is stored as symbol (`String`).
-                    k0_inner::symbol(string) => string.len(),
+                    // string is stored as `String`.
+                    k0_inner::string(string) => string.len(),
                     // The other lists.
-                    k0_inner::list(list) => list.n as usize,
+                    k0_inner::list(list) => list.len(),
                     _ => unreachable!(),
                 }
             }
@@ -5175,9 +5398,9 @@ impl K {
                         {
                             k0_inner::list(column) => {
                                 // Return the number of rows
-                                column.n as usize
+                                column.len()
                             }
-                            k0_inner::symbol(column) => {
+                            k0_inner::string(column) => {
                                 // char column
                                 // Return the number of rows
                                 column.len()
@@ -5192,7 +5415,7 @@ impl K {
                 // Dictionary is a vector of [K (keys), K (values)]
                 // Get keys and return its length.
                 match &self.as_vec::<K>().unwrap()[0].0.value {
-                    k0_inner::list(list) => list.n as usize,
+                    k0_inner::list(list) => list.len(),
                     // Keyed table
                     // Get the number of rows by deligating it to table.len()
                     k0_inner::table(_) => self.as_vec::<K>().unwrap()[0].len(),
@@ -5254,7 +5477,7 @@ impl K {
                     Ok(K::new(
                         qtype::TABLE,
                         qattribute::NONE,
-                        k0_inner::table(self),
+                        k0_inner::table(Box::new(self)),
                     ))
                 } else {
                     Err(Error::object(self))
@@ -5310,9 +5533,12 @@ impl K {
                     // Maximum number of keys are #columns - 1
                     n = headers_columns[0].len() - 1;
                 }
-                let value_heders = K::new_symbol_list(
-                    headers_columns[0].as_mut_vec::<S>().unwrap().split_off(n),
+                let value_heders = K::new(
+                    qtype::SYMBOL_LIST,
                     qattribute::NONE,
+                    k0_inner::list(k0_list::new(
+                        headers_columns[0].as_mut_vec::<S>().unwrap().split_off(n),
+                    )),
                 );
                 let value_columns = K::new_compound_list(
                     headers_columns[1].as_mut_vec::<K>().unwrap().split_off(n),
@@ -5373,7 +5599,7 @@ impl K {
     /// ```
     pub fn unkey(mut self) -> Result<Self> {
         match self.0.qtype {
-            qtype::DICTIONARY => {
+            qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
                 // Key table and value table
                 let value_table = self.as_mut_vec::<K>().unwrap().pop().unwrap();
                 let key_table = self.as_mut_vec::<K>().unwrap().pop().unwrap();
@@ -5419,6 +5645,117 @@ impl K {
 // >> Private Functions
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
+//%% Storage //%%vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Get a symbol from an element passed to `push` or `insert` of a symbol list, which is either
+///  `String` or `Symbol`.
+fn downcast_symbol(element: &dyn Any) -> Option<S> {
+    match element.downcast_ref::<String>() {
+        Some(symbol) => Some(Symbol::from(symbol)),
+        None => element.downcast_ref::<S>().cloned(),
+    }
+}
+
+/// Get a mutable reference to a shared vector as `Vec<T>`, copying the vector if it is shared
+///  with another object. The type is checked before copying so that a wrong type does not
+///  cause a copy.
+fn make_mut_as<X, T>(vector: &mut Arc<Vec<X>>) -> Option<&mut Vec<T>>
+where
+    X: Clone + 'static,
+    T: 'static,
+{
+    if TypeId::of::<X>() == TypeId::of::<T>() {
+        (Arc::make_mut(vector) as &mut dyn Any).downcast_mut::<Vec<T>>()
+    } else {
+        None
+    }
+}
+
+//%% Attribute //%%vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Check if keys of a dictionary are sorted. As q does, the sorted attribute of a keyed table is held by
+///  the first key column.
+fn has_sorted_keys(keys: &K) -> bool {
+    match keys.0.qtype {
+        qtype::TABLE => key_columns(keys)
+            .first()
+            .is_some_and(|column| column.0.attribute == qattribute::SORTED),
+        _ => keys.0.attribute == qattribute::SORTED,
+    }
+}
+
+/// Get columns of a table.
+fn key_columns(table: &K) -> &Vec<K> {
+    table.get_dictionary().unwrap().as_vec::<K>().unwrap()[1]
+        .as_vec::<K>()
+        .unwrap()
+}
+
+/// Check if rows of a table from `start` are in ascending order comparing columns from the first one. A table
+///  with a column which is not a simple list is regarded as unsorted.
+fn rows_are_sorted(table: &K, start: usize) -> bool {
+    let columns = key_columns(table);
+    (start.max(1)..table.len()).all(|row| {
+        for column in columns {
+            let ordering = match column.0.qtype {
+                qtype::STRING => {
+                    let bytes = column.as_string().unwrap().as_bytes();
+                    Some(bytes[row - 1].cmp(&bytes[row]))
+                }
+                _ => with_simple_list!(
+                    column,
+                    as_vec,
+                    |list, compare| Some(compare(&list[row - 1], &list[row])),
+                    None
+                ),
+            };
+            match ordering {
+                Some(std::cmp::Ordering::Equal) => continue,
+                Some(ordering) => return ordering == std::cmp::Ordering::Less,
+                None => return false,
+            }
+        }
+        true
+    })
+}
+
+//%% Table //%%vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Get positions of elements of a simple list in ascending order keeping the order of equal elements. `None`
+///  for a list which is not a simple list.
+#[cfg(feature = "ipc-base")]
+fn ascending_rows(list: &K) -> Option<Vec<usize>> {
+    let mut rows = (0..list.len()).collect::<Vec<usize>>();
+    with_simple_list!(
+        list,
+        as_vec,
+        |elements, compare| {
+            rows.sort_by(|&left, &right| compare(&elements[left], &elements[right]));
+            Some(rows)
+        },
+        None
+    )
+}
+
+/// Select elements of a list at `rows`, dropping the attribute.
+#[cfg(feature = "ipc-base")]
+fn select_rows(list: &K, rows: &[usize]) -> K {
+    let value = match &list.0.value {
+        k0_inner::list(vector) => k0_inner::list(map_k0_list!(vector, |vector| rows
+            .iter()
+            .map(|&row| &vector[row])
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_list())),
+        k0_inner::string(string) => {
+            let chars = string.chars().collect::<Vec<char>>();
+            k0_inner::string(rows.iter().map(|&row| chars[row]).collect())
+        }
+        value => value.clone(),
+    };
+    K::new(list.0.qtype, qattribute::NONE, value)
+}
+
 //%% Constructors //%%vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
 
 /// Convert `DateTime<Utc>` into `i64`. The returned value is an elapsed time in nanoseconds since `2000.01.01D00:00:00`.
@@ -5559,9 +5896,9 @@ pub fn q_date_to_date(days: i32) -> Result<NaiveDate> {
         Ok(qinf::DATE)
     } else {
         Ok((NaiveDate::from_ymd_opt(2000, 1, 1)
-            .ok_or_else(|| Error::InvalidDateTime)?
+            .ok_or(Error::InvalidDateTime)?
             .and_hms_opt(0, 0, 0)
-            .ok_or_else(|| Error::InvalidDateTime)?
+            .ok_or(Error::InvalidDateTime)?
             .and_local_timezone(Utc)
             .unwrap()
             + Duration::days(days as i64))
@@ -5614,9 +5951,54 @@ pub fn q_time_to_duration(millis: i32) -> Duration {
 // >> Load Modules
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
+#[cfg(feature = "ipc-base")]
+mod auth;
+mod cast;
+mod codec;
+#[cfg(feature = "ipc-base")]
 mod connection;
 mod deserialize;
+mod dictionary;
 mod format;
+#[cfg(feature = "ipc-base")]
+mod handler;
+#[cfg(feature = "ipc-base")]
+mod listener;
+#[cfg(feature = "ipc-base")]
+mod pool;
+#[cfg(feature = "ipc-base")]
+mod publisher;
+#[cfg(feature = "ipc-base")]
+mod reconnect;
+mod schema;
 mod serialize;
+mod symbol;
+mod temporal;
+#[cfg(feature = "ipc-base")]
+mod tick;
+#[cfg(any(feature = "ipc-base", feature = "blocking-tls"))]
+mod tls;
 // Inject into `ipc` namespace.
+#[cfg(feature = "ipc-base")]
+pub use auth::*;
+pub use codec::*;
+#[cfg(feature = "ipc-base")]
 pub use connection::*;
+pub(crate) use dictionary::KeyIndex;
+#[cfg(feature = "ipc-base")]
+pub use handler::*;
+#[cfg(feature = "ipc-base")]
+pub use listener::*;
+#[cfg(feature = "ipc-base")]
+pub use pool::*;
+#[cfg(feature = "ipc-base")]
+pub use publisher::*;
+#[cfg(feature = "ipc-base")]
+pub use reconnect::*;
+pub use schema::*;
+pub use symbol::*;
+pub use temporal::*;
+#[cfg(feature = "ipc-base")]
+pub use tick::*;
+#[cfg(any(feature = "ipc-base", feature = "blocking-tls"))]
+pub use tls::*;
@@ -0,0 +1,1185 @@
+//! This module provides q-compatible arithmetic, aggregations and uniform functions over atoms and simple lists
+//!  of numeric and temporal types.
+//!
+//! As q does,
+//! - integral arithmetic propagates null and wraps on overflow (e.g. `0W + 1` is `0N`),
+//! - the result type follows q's type promotion (e.g. `1b + 1b` is `2i`, `2020.01.02 - 2020.01.01` is `1i`),
+//! - an atom operand is broadcast over a list operand and two list operands must have the same length,
+//! - aggregations ignore nulls, while `sum`-like functions count them as zero.
+//!
+//! | Category    | Functions                                                             |
+//! |-------------|-----------------------------------------------------------------------|
+//! | Arithmetic  | [`add`] (`+`), [`sub`] (`-`), [`mul`] (`*`), [`div`] (`%`)             |
+//! | Aggregation | [`sum`], [`avg`], [`min`], [`max`], [`med`], [`dev`], [`wavg`], [`wsum`], [`count`] |
+//! | Uniform     | [`sums`], [`deltas`], [`prev`], [`next`], [`mavg`], [`msum`], [`fills`] |
+//! | Bucketing   | [`xbar`]                                                              |
+//!
+//! # Example
+//! ```
+//! use kdbplus::qattribute;
+//! use kdbplus::ipc::*;
+//!
+//! fn main() {
+//!     let price = K::new_float_list(vec![101.5, 102.0, 103.5], qattribute::NONE);
+//!     let size = K::new_long_list(vec![100, qnull::LONG, 300], qattribute::NONE);
+//!
+//!     assert_eq!(format!("{}", ops::sum(&size).unwrap()), String::from("400"));
+//!     assert_eq!(format!("{}", ops::wavg(&size, &price).unwrap()), String::from("103"));
+//!     assert_eq!(
+//!         format!("{}", ops::mul(&price, &size).unwrap()),
+//!         String::from("10150 0n 31050")
+//!     );
+//! }
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::cast::into_atom;
+use super::*;
+use std::iter;
+use std::slice;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Evaluate an expression with `$T` bound to the Rust type of the underlying value of a list type returned
+///  by `value_type`.
+macro_rules! with_value_type {
+    ($value_type: expr, |$T: ident| $body: expr) => {
+        match $value_type {
+            qtype::BYTE_LIST => {
+                type $T = G;
+                $body
+            }
+            qtype::SHORT_LIST => {
+                type $T = H;
+                $body
+            }
+            qtype::INT_LIST => {
+                type $T = I;
+                $body
+            }
+            qtype::LONG_LIST => {
+                type $T = J;
+                $body
+            }
+            qtype::REAL_LIST => {
+                type $T = E;
+                $body
+            }
+            _ => {
+                type $T = F;
+                $body
+            }
+        }
+    };
+}
+
+/// Implement `Value` for a signed integral type.
+macro_rules! impl_integral_value {
+    ($name: ty, $base: ident) => {
+        impl Value for $name {
+            const NULL: $name = qnull_base::$base;
+            const INF: $name = qinf_base::$base;
+            const NINF: $name = qninf_base::$base;
+            const ZERO: $name = 0;
+
+            fn is_null(self) -> bool {
+                self == qnull_base::$base
+            }
+
+            fn add(self, other: $name) -> $name {
+                self.wrapping_add(other)
+            }
+
+            fn sub(self, other: $name) -> $name {
+                self.wrapping_sub(other)
+            }
+
+            fn mul(self, other: $name) -> $name {
+                self.wrapping_mul(other)
+            }
+
+            fn div(self, other: $name) -> $name {
+                if other == 0 {
+                    qnull_base::$base
+                } else {
+                    let quotient = self.wrapping_div(other);
+                    if self.wrapping_rem(other) != 0 && ((self < 0) != (other < 0)) {
+                        quotient - 1
+                    } else {
+                        quotient
+                    }
+                }
+            }
+
+            fn bar(self, bucket: $name) -> $name {
+                if bucket == 0 {
+                    qnull_base::$base
+                } else {
+                    self.div(bucket).wrapping_mul(bucket)
+                }
+            }
+
+            fn to_float(self) -> F {
+                if self.is_null() {
+                    qnull_base::F
+                } else {
+                    self as F
+                }
+            }
+        }
+    };
+}
+
+/// Implement `Value` for a floating point type.
+macro_rules! impl_float_value {
+    ($name: ty, $base: ident) => {
+        impl Value for $name {
+            const NULL: $name = qnull_base::$base;
+            const INF: $name = qinf_base::$base;
+            const NINF: $name = qninf_base::$base;
+            const ZERO: $name = 0.0;
+
+            fn is_null(self) -> bool {
+                self.is_nan()
+            }
+
+            fn add(self, other: $name) -> $name {
+                self + other
+            }
+
+            fn sub(self, other: $name) -> $name {
+                self - other
+            }
+
+            fn mul(self, other: $name) -> $name {
+                self * other
+            }
+
+            fn div(self, other: $name) -> $name {
+                self / other
+            }
+
+            fn bar(self, bucket: $name) -> $name {
+                (self / bucket).floor() * bucket
+            }
+
+            fn to_float(self) -> F {
+                F::from(self)
+            }
+        }
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Value %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Underlying value of a numeric or temporal type on which calculation is done.
+trait Value: Copy + PartialOrd + 'static {
+    /// Null.
+    const NULL: Self;
+    /// Positive infinity.
+    const INF: Self;
+    /// Negative infinity.
+    const NINF: Self;
+    /// Zero.
+    const ZERO: Self;
+    /// Check if the value is null.
+    fn is_null(self) -> bool;
+    /// Add wrapping on overflow.
+    fn add(self, other: Self) -> Self;
+    /// Subtract wrapping on overflow.
+    fn sub(self, other: Self) -> Self;
+    /// Multiply wrapping on overflow.
+    fn mul(self, other: Self) -> Self;
+    /// Divide. Integral division rounds down and returns null for zero divisor as q's `div` does.
+    fn div(self, other: Self) -> Self;
+    /// Round down to a multiple of a bucket.
+    fn bar(self, bucket: Self) -> Self;
+    /// Convert into float. Null becomes `0n`.
+    fn to_float(self) -> F;
+}
+
+//%% Operator %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Arithmetic operator.
+#[derive(Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+//%% Signature %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Types of an arithmetic operation.
+struct Signature {
+    /// Type of the result.
+    result: i8,
+    /// Type of the value on which calculation is done.
+    value: i8,
+    /// Type to which the left operand is cast.
+    left: i8,
+    /// Type to which the right operand is cast.
+    right: i8,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Value %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Value for G {
+    const NULL: G = 0;
+    const INF: G = G::MAX;
+    const NINF: G = 0;
+    const ZERO: G = 0;
+
+    fn is_null(self) -> bool {
+        false
+    }
+
+    fn add(self, other: G) -> G {
+        self.wrapping_add(other)
+    }
+
+    fn sub(self, other: G) -> G {
+        self.wrapping_sub(other)
+    }
+
+    fn mul(self, other: G) -> G {
+        self.wrapping_mul(other)
+    }
+
+    fn div(self, other: G) -> G {
+        self.checked_div(other).unwrap_or(0)
+    }
+
+    fn bar(self, bucket: G) -> G {
+        self.div(bucket).wrapping_mul(bucket)
+    }
+
+    fn to_float(self) -> F {
+        F::from(self)
+    }
+}
+
+impl_integral_value!(H, H);
+impl_integral_value!(I, I);
+impl_integral_value!(J, J);
+impl_float_value!(E, E);
+impl_float_value!(F, F);
+
+//%% Operator %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Operator {
+    /// q notation of the operator.
+    fn name(self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Div => "%",
+        }
+    }
+
+    /// Apply the operator propagating null.
+    fn apply<T>(self, left: T, right: T) -> T
+    where
+        T: Value,
+    {
+        if left.is_null() || right.is_null() {
+            return T::NULL;
+        }
+        match self {
+            Operator::Add => left.add(right),
+            Operator::Sub => left.sub(right),
+            Operator::Mul => left.mul(right),
+            Operator::Div => left.div(right),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Arithmetic %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Add two objects (`+` in q). A temporal value can be shifted by an integral value or a duration.
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_int_list = K::new_int_list(vec![1, qnull::INT, qinf::INT], qattribute::NONE);
+///     assert_eq!(
+///         format!("{}", ops::add(&q_int_list, &K::new_int(1)).unwrap()),
+///         String::from("2 0N 0Ni")
+///     );
+///
+///     let q_date = K::from("2020.02.28".parse::<QDate>().unwrap());
+///     assert_eq!(
+///         format!("{}", ops::add(&q_date, &K::new_long(2)).unwrap()),
+///         String::from("2020.03.01")
+///     );
+/// }
+/// ```
+pub fn add(left: &K, right: &K) -> Result<K> {
+    arithmetic(Operator::Add, left, right)
+}
+
+/// Subtract an object from another (`-` in q). The difference of two temporal values of the same type is a
+///  duration or a number of days or months.
+/// # Example
+/// ```
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let start = K::from("2020.01.01D09:30:00".parse::<QTimestamp>().unwrap());
+///     let end = K::from("2020.01.01D16:00:00".parse::<QTimestamp>().unwrap());
+///     assert_eq!(
+///         format!("{}", ops::sub(&end, &start).unwrap()),
+///         String::from("0D06:30:00.000000000")
+///     );
+/// }
+/// ```
+pub fn sub(left: &K, right: &K) -> Result<K> {
+    arithmetic(Operator::Sub, left, right)
+}
+
+/// Multiply two objects (`*` in q). A duration can be multiplied by an integral value.
+pub fn mul(left: &K, right: &K) -> Result<K> {
+    arithmetic(Operator::Mul, left, right)
+}
+
+/// Divide an object by another (`%` in q). The result is real if the wider operand is real, otherwise float.
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_long_list = K::new_long_list(vec![1, 0, qnull::LONG], qattribute::NONE);
+///     assert_eq!(
+///         format!("{}", ops::div(&q_long_list, &K::new_long(0)).unwrap()),
+///         String::from("0w 0n 0n")
+///     );
+/// }
+/// ```
+pub fn div(left: &K, right: &K) -> Result<K> {
+    arithmetic(Operator::Div, left, right)
+}
+
+//%% Aggregation %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Sum of a list counting null as zero. The sum of bool, byte, short and int is int.
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_bool_list = K::new_bool_list(vec![true, false, true], qattribute::NONE);
+///     assert_eq!(format!("{}", ops::sum(&q_bool_list).unwrap()), String::from("2i"));
+/// }
+/// ```
+pub fn sum(list: &K) -> Result<K> {
+    let (result, value) = types_of(list, "sum", sum_type)?;
+    let operand = prepare(list, result, value)?;
+    if operand.0.qtype < 0 {
+        return Ok(operand);
+    }
+    with_value_type!(value, |T| {
+        let total = as_slice::<T>(&operand)
+            .iter()
+            .filter(|element| !element.is_null())
+            .fold(T::ZERO, |total, element| total.add(*element));
+        Ok(build(result, k0_list::new(vec![total]), true))
+    })
+}
+
+/// Average of a list ignoring null. The result is float.
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_long_list = K::new_long_list(vec![1, qnull::LONG, 4], qattribute::NONE);
+///     assert_eq!(format!("{}", ops::avg(&q_long_list).unwrap()), String::from("2.5"));
+/// }
+/// ```
+pub fn avg(list: &K) -> Result<K> {
+    Ok(K::new_float(mean(&non_null_floats(list, "avg")?)))
+}
+
+/// Minimum of a list ignoring null. The minimum of an empty list is positive infinity and that of a list
+///  with only nulls is null.
+pub fn min(list: &K) -> Result<K> {
+    extremum(list, "min", false)
+}
+
+/// Maximum of a list ignoring null. The maximum of an empty list is negative infinity and that of a list
+///  with only nulls is null.
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_time_list = K::new_temporal_list(
+///         vec![QTime::from_raw(1000), QTime::NULL, QTime::from_raw(3000)],
+///         qattribute::NONE,
+///     );
+///     assert_eq!(format!("{}", ops::max(&q_time_list).unwrap()), String::from("00:00:03.000"));
+/// }
+/// ```
+pub fn max(list: &K) -> Result<K> {
+    extremum(list, "max", true)
+}
+
+/// Median of a list ignoring null. The result is float.
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_int_list = K::new_int_list(vec![4, 1, qnull::INT, 3, 2], qattribute::NONE);
+///     assert_eq!(format!("{}", ops::med(&q_int_list).unwrap()), String::from("2.5"));
+/// }
+/// ```
+pub fn med(list: &K) -> Result<K> {
+    let mut values = non_null_floats(list, "med")?;
+    values.sort_by(|left, right| left.partial_cmp(right).unwrap());
+    let middle = match values.len() {
+        0 => Vec::new(),
+        length => vec![values[(length - 1) / 2], values[length / 2]],
+    };
+    Ok(K::new_float(mean(&middle)))
+}
+
+/// Standard deviation of a list ignoring null. The result is float.
+pub fn dev(list: &K) -> Result<K> {
+    let values = non_null_floats(list, "dev")?;
+    let average = mean(&values);
+    let squares = values.iter().map(|value| value * value).collect::<Vec<F>>();
+    Ok(K::new_float((mean(&squares) - average * average).sqrt()))
+}
+
+/// Weighted average of values ignoring pairs containing null. The result is float.
+pub fn wavg(weights: &K, values: &K) -> Result<K> {
+    let pairs = broadcast(
+        (&floats(weights, "wavg")?, weights.0.qtype < 0),
+        (&floats(values, "wavg")?, values.0.qtype < 0),
+        |weight, value| (weight, value),
+    )?;
+    let (total, weight) = pairs
+        .into_iter()
+        .filter(|(weight, value)| !weight.is_nan() && !value.is_nan())
+        .fold((0.0, 0.0), |(total, sum), (weight, value)| {
+            (total + weight * value, sum + weight)
+        });
+    Ok(K::new_float(total / weight))
+}
+
+/// Weighted sum of values, i.e., the sum of products of weights and values.
+pub fn wsum(weights: &K, values: &K) -> Result<K> {
+    sum(&mul(weights, values)?)
+}
+
+/// Number of elements (rows for a table). An atom counts as 1.
+pub fn count(object: &K) -> K {
+    K::new_long(object.len() as J)
+}
+
+//%% Uniform %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Cumulative sums of a list counting null as zero. The type of the result is the same as [`sum`].
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_long_list = K::new_long_list(vec![1, qnull::LONG, 2, 3], qattribute::NONE);
+///     assert_eq!(format!("{}", ops::sums(&q_long_list).unwrap()), String::from("1 1 3 6"));
+/// }
+/// ```
+pub fn sums(list: &K) -> Result<K> {
+    let (result, value) = types_of(list, "sums", sum_type)?;
+    let operand = prepare(list, result, value)?;
+    with_value_type!(value, |T| {
+        let mut total = T::ZERO;
+        let sums = as_slice::<T>(&operand)
+            .iter()
+            .map(|element| {
+                if !element.is_null() {
+                    total = total.add(*element);
+                }
+                total
+            })
+            .collect::<Vec<T>>();
+        Ok(build(result, k0_list::new(sums), operand.0.qtype < 0))
+    })
+}
+
+/// Differences between adjacent elements of a list. The first element is kept.
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_long_list = K::new_long_list(vec![1, 4, 9, qnull::LONG, 25], qattribute::NONE);
+///     assert_eq!(
+///         format!("{}", ops::deltas(&q_long_list).unwrap()),
+///         String::from("1 3 5 0N 0N")
+///     );
+/// }
+/// ```
+pub fn deltas(list: &K) -> Result<K> {
+    let qtype = list.0.qtype;
+    if qtype < 0 || value_type(qtype).is_none() {
+        return Err(Error::invalid_operation("deltas", qtype, None));
+    }
+    let mut differences = sub(list, &prev(list)?)?;
+    if list.len() != 0 {
+        let value = value_type(differences.0.qtype).unwrap();
+        let first = prepare(list, qtype, value)?;
+        with_value_type!(value, |T| {
+            differences.as_mut_vec::<T>()?[0] = as_slice::<T>(&first)[0];
+        })
+    }
+    Ok(differences)
+}
+
+/// Shift elements of a list to the right filling the first element with null.
+pub fn prev(list: &K) -> Result<K> {
+    shift(list, "prev", true)
+}
+
+/// Shift elements of a list to the left filling the last element with null.
+pub fn next(list: &K) -> Result<K> {
+    shift(list, "next", false)
+}
+
+/// Moving averages of a list over `n` elements ignoring null. The result is float.
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_long_list = K::new_long_list(vec![1, 2, qnull::LONG, 4], qattribute::NONE);
+///     assert_eq!(
+///         format!("{}", ops::mavg(3, &q_long_list).unwrap()),
+///         String::from("1 1.5 1.5 3")
+///     );
+/// }
+/// ```
+pub fn mavg(n: usize, list: &K) -> Result<K> {
+    let qtype = list.0.qtype;
+    if qtype < 0 {
+        return Err(Error::invalid_operation("mavg", qtype, None));
+    }
+    let values = floats(list, "mavg")?;
+    let totals = moving(
+        n,
+        &values
+            .iter()
+            .map(|value| if value.is_nan() { 0.0 } else { *value })
+            .collect::<Vec<F>>(),
+    );
+    let counts = moving(
+        n,
+        &values
+            .iter()
+            .map(|value| !value.is_nan() as I)
+            .collect::<Vec<I>>(),
+    );
+    Ok(K::new_float_list(
+        totals
+            .into_iter()
+            .zip(counts)
+            .map(|(total, count)| total / count as F)
+            .collect(),
+        qattribute::NONE,
+    ))
+}
+
+/// Moving sums of a list over `n` elements counting null as zero. The type of the result is the same as
+///  [`sum`].
+pub fn msum(n: usize, list: &K) -> Result<K> {
+    let qtype = list.0.qtype;
+    if qtype < 0 {
+        return Err(Error::invalid_operation("msum", qtype, None));
+    }
+    let (result, value) = types_of(list, "msum", sum_type)?;
+    let operand = prepare(list, result, value)?;
+    with_value_type!(value, |T| {
+        let values = as_slice::<T>(&operand)
+            .iter()
+            .map(|element| if element.is_null() { T::ZERO } else { *element })
+            .collect::<Vec<T>>();
+        Ok(build(result, k0_list::new(moving(n, &values)), false))
+    })
+}
+
+/// Replace null elements of a list with the previous non-null element. Leading nulls are kept.
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_float_list = K::new_float_list(
+///         vec![qnull::FLOAT, 1.5, qnull::FLOAT, 3.0],
+///         qattribute::NONE,
+///     );
+///     assert_eq!(
+///         format!("{}", ops::fills(&q_float_list).unwrap()),
+///         String::from("0n 1.5 1.5 3")
+///     );
+/// }
+/// ```
+pub fn fills(list: &K) -> Result<K> {
+    let qtype = list.0.qtype;
+    let filled = match qtype {
+        qtype::GUID_LIST => k0_list::new(fill(list.as_vec::<U>()?, |guid| *guid == qnull_base::U)),
+        qtype::SYMBOL_LIST => k0_list::new(fill(list.as_vec::<S>()?, |symbol| symbol.is_empty())),
+        qtype::STRING => {
+            let characters = list.as_string()?.chars().collect::<Vec<char>>();
+            return Ok(K::new_string(
+                fill(&characters, |character| *character == ' ')
+                    .into_iter()
+                    .collect(),
+                qattribute::NONE,
+            ));
+        }
+        _ => match value_type(qtype) {
+            Some(value) if qtype > 0 => with_value_type!(value, |T| {
+                k0_list::new(fill(as_slice::<T>(list), |element| element.is_null()))
+            }),
+            _ => return Err(Error::invalid_operation("fills", qtype, None)),
+        },
+    };
+    Ok(build(qtype, filled, false))
+}
+
+//%% Bucketing %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Round elements down to a multiple of a bucket (`xbar` in q). The type of the result is the same as the
+///  list. For a timestamp, timespan, minute, second or time list, the bucket can be a duration.
+/// # Example
+/// ```
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let q_timestamp_list = K::new_temporal_list(
+///         vec![
+///             "2020.01.01D09:31:15".parse::<QTimestamp>().unwrap(),
+///             "2020.01.01D09:47:00".parse::<QTimestamp>().unwrap(),
+///         ],
+///         qattribute::NONE,
+///     );
+///     let q_bucket = K::from("00:15".parse::<QMinute>().unwrap());
+///     assert_eq!(
+///         format!("{}", ops::xbar(&q_bucket, &q_timestamp_list).unwrap()),
+///         String::from("2020.01.01D09:30:00.000000000 2020.01.01D09:45:00.000000000")
+///     );
+///
+///     let q_month = K::from("2020.05m".parse::<QMonth>().unwrap());
+///     assert_eq!(
+///         format!("{}", ops::xbar(&K::new_long(3), &q_month).unwrap()),
+///         String::from("2020.04m")
+///     );
+/// }
+/// ```
+pub fn xbar(bucket: &K, list: &K) -> Result<K> {
+    let qtype = list_type(list.0.qtype);
+    let bucket_type = list_type(bucket.0.qtype);
+    let value = value_type(qtype)
+        .filter(|_| !matches!(qtype, qtype::BOOL_LIST | qtype::BYTE_LIST))
+        .ok_or_else(|| Error::invalid_operation("xbar", list.0.qtype, None))?;
+    // The bucket is converted into the unit of the list.
+    let via = match (is_span_type(bucket_type), numeric_rank(bucket_type)) {
+        (true, _) if qtype == qtype::TIMESTAMP_LIST => qtype::TIMESPAN_LIST,
+        (true, _) if is_span_type(qtype) => qtype,
+        (false, Some(rank))
+            if rank <= 3 || matches!(value, qtype::REAL_LIST | qtype::FLOAT_LIST) =>
+        {
+            value
+        }
+        _ => {
+            return Err(Error::invalid_operation(
+                "xbar",
+                bucket.0.qtype,
+                Some(qtype),
+            ))
+        }
+    };
+    let bucket_operand = prepare(bucket, via, value)?;
+    with_value_type!(value, |T| {
+        let values = broadcast(
+            (as_slice::<T>(&bucket_operand), bucket.0.qtype < 0),
+            (as_slice::<T>(list), list.0.qtype < 0),
+            |bucket, element| {
+                if bucket.is_null() || element.is_null() {
+                    T::NULL
+                } else {
+                    element.bar(bucket)
+                }
+            },
+        )?;
+        Ok(build(
+            qtype,
+            k0_list::new(values),
+            bucket.0.qtype < 0 && list.0.qtype < 0,
+        ))
+    })
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Type %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Get a list type corresponding to an atom type. Other types are returned as they are.
+fn list_type(qtype: i8) -> i8 {
+    match qtype {
+        -19..=-1 => -qtype,
+        _ => qtype,
+    }
+}
+
+/// Get a list type of the underlying value of a numeric or temporal list type.
+fn value_type(qtype: i8) -> Option<i8> {
+    match qtype {
+        qtype::BOOL_LIST | qtype::BYTE_LIST => Some(qtype::BYTE_LIST),
+        qtype::SHORT_LIST => Some(qtype::SHORT_LIST),
+        qtype::INT_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => Some(qtype::INT_LIST),
+        qtype::LONG_LIST | qtype::TIMESTAMP_LIST | qtype::TIMESPAN_LIST => Some(qtype::LONG_LIST),
+        qtype::REAL_LIST => Some(qtype::REAL_LIST),
+        qtype::FLOAT_LIST | qtype::DATETIME_LIST => Some(qtype::FLOAT_LIST),
+        _ => None,
+    }
+}
+
+/// Get a rank of a numeric list type in type promotion.
+fn numeric_rank(qtype: i8) -> Option<u8> {
+    match qtype {
+        qtype::BOOL_LIST | qtype::BYTE_LIST => Some(0),
+        qtype::SHORT_LIST => Some(1),
+        qtype::INT_LIST => Some(2),
+        qtype::LONG_LIST => Some(3),
+        qtype::REAL_LIST => Some(4),
+        qtype::FLOAT_LIST => Some(5),
+        _ => None,
+    }
+}
+
+/// Check if a list type is a temporal type denoting a point in time.
+fn is_point_type(qtype: i8) -> bool {
+    matches!(
+        qtype,
+        qtype::TIMESTAMP_LIST | qtype::MONTH_LIST | qtype::DATE_LIST | qtype::DATETIME_LIST
+    )
+}
+
+/// Check if a list type is a temporal type denoting a duration.
+fn is_span_type(qtype: i8) -> bool {
+    matches!(
+        qtype,
+        qtype::TIMESPAN_LIST | qtype::MINUTE_LIST | qtype::SECOND_LIST | qtype::TIME_LIST
+    )
+}
+
+/// Get types of an arithmetic operation on list types. Returns `None` if the operation is not supported.
+fn signature(operator: Operator, left: i8, right: i8) -> Option<Signature> {
+    let same = |result: i8, value: i8| Signature {
+        result,
+        value,
+        left,
+        right,
+    };
+    match (numeric_rank(left), numeric_rank(right)) {
+        (Some(left_rank), Some(right_rank)) => {
+            let result = match (operator, left_rank.max(right_rank)) {
+                (Operator::Div, 4) => qtype::REAL_LIST,
+                (Operator::Div, _) => qtype::FLOAT_LIST,
+                (_, 1) => qtype::SHORT_LIST,
+                (_, 3) => qtype::LONG_LIST,
+                (_, 4) => qtype::REAL_LIST,
+                (_, 5) => qtype::FLOAT_LIST,
+                _ => qtype::INT_LIST,
+            };
+            Some(Signature {
+                result,
+                value: result,
+                left: result,
+                right: result,
+            })
+        }
+        (None, Some(rank)) => shift_signature(operator, left, rank).map(|signature| Signature {
+            right: signature.value,
+            ..signature
+        }),
+        (Some(rank), None) if operator != Operator::Sub => shift_signature(operator, right, rank)
+            .map(|signature| Signature {
+                left: signature.value,
+                ..signature
+            }),
+        (Some(_), None) => None,
+        (None, None) if is_span_type(left) && is_span_type(right) => match operator {
+            Operator::Add | Operator::Sub if left == right => {
+                Some(same(left, value_type(left).unwrap()))
+            }
+            Operator::Add | Operator::Sub => Some(Signature {
+                result: qtype::TIMESPAN_LIST,
+                value: qtype::LONG_LIST,
+                left: qtype::TIMESPAN_LIST,
+                right: qtype::TIMESPAN_LIST,
+            }),
+            _ => None,
+        },
+        (None, None) if operator == Operator::Sub && left == right && is_point_type(left) => {
+            match left {
+                qtype::TIMESTAMP_LIST => Some(same(qtype::TIMESPAN_LIST, qtype::LONG_LIST)),
+                qtype::DATETIME_LIST => Some(same(qtype::FLOAT_LIST, qtype::FLOAT_LIST)),
+                _ => Some(same(qtype::INT_LIST, qtype::INT_LIST)),
+            }
+        }
+        (None, None) => {
+            // Timestamp or date shifted by a duration is a timestamp.
+            let is_timestamp_like =
+                |qtype| matches!(qtype, qtype::TIMESTAMP_LIST | qtype::DATE_LIST);
+            let (point, span) = match operator {
+                Operator::Add if is_span_type(left) => (right, left),
+                Operator::Add | Operator::Sub => (left, right),
+                _ => return None,
+            };
+            (is_timestamp_like(point) && is_span_type(span)).then(|| {
+                let via = |qtype| match qtype == point {
+                    true => qtype::TIMESTAMP_LIST,
+                    false => qtype::TIMESPAN_LIST,
+                };
+                Signature {
+                    result: qtype::TIMESTAMP_LIST,
+                    value: qtype::LONG_LIST,
+                    left: via(left),
+                    right: via(right),
+                }
+            })
+        }
+    }
+}
+
+/// Get types of an arithmetic operation between a temporal type and a number. Both of `left` and `right`
+///  of the returned signature are the temporal type.
+fn shift_signature(operator: Operator, temporal: i8, rank: u8) -> Option<Signature> {
+    let value =
+        value_type(temporal).filter(|_| is_point_type(temporal) || is_span_type(temporal))?;
+    let is_supported = match operator {
+        Operator::Add | Operator::Sub => rank <= 3 || value == qtype::FLOAT_LIST,
+        Operator::Mul => rank <= 3 && is_span_type(temporal),
+        Operator::Div => false,
+    };
+    is_supported.then_some(Signature {
+        result: temporal,
+        value,
+        left: temporal,
+        right: temporal,
+    })
+}
+
+/// Get types of the result and the value of `sum`-like functions.
+fn sum_type(qtype: i8) -> Option<(i8, i8)> {
+    match qtype {
+        qtype::BOOL_LIST | qtype::BYTE_LIST | qtype::SHORT_LIST | qtype::INT_LIST => {
+            Some((qtype::INT_LIST, qtype::INT_LIST))
+        }
+        qtype::LONG_LIST | qtype::REAL_LIST | qtype::FLOAT_LIST => Some((qtype, qtype)),
+        _ if is_point_type(qtype) || is_span_type(qtype) => {
+            value_type(qtype).map(|value| (qtype, value))
+        }
+        _ => None,
+    }
+}
+
+/// Get types of an operand by a typing function or return an error of invalid operation.
+fn types_of(
+    object: &K,
+    operator: &'static str,
+    typing: fn(i8) -> Option<(i8, i8)>,
+) -> Result<(i8, i8)> {
+    typing(list_type(object.0.qtype))
+        .ok_or_else(|| Error::invalid_operation(operator, object.0.qtype, None))
+}
+
+//%% Access %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Cast an object to `via` type and then to the type of underlying value `value` if necessary.
+fn prepare(object: &K, via: i8, value: i8) -> Result<K> {
+    let object = match list_type(object.0.qtype) == via {
+        true => object.clone(),
+        false => object.cast(via)?,
+    };
+    match value_type(via) == Some(value) {
+        true => Ok(object),
+        false => object.cast(value),
+    }
+}
+
+/// Get underlying values of an atom or a list as a slice.
+fn as_slice<T>(object: &K) -> &[T]
+where
+    T: 'static,
+{
+    let atom: &dyn Any = match &object.0.value {
+        k0_inner::list(list) => return list.as_vec::<T>().unwrap(),
+        k0_inner::byte(value) => value,
+        k0_inner::short(value) => value,
+        k0_inner::int(value) => value,
+        k0_inner::long(value) => value,
+        k0_inner::real(value) => value,
+        k0_inner::float(value) => value,
+        _ => unreachable!(),
+    };
+    slice::from_ref(atom.downcast_ref::<T>().unwrap())
+}
+
+/// Convert elements of a numeric or temporal object into float. Null becomes `0n`.
+fn floats(object: &K, operator: &'static str) -> Result<Vec<F>> {
+    let value = value_type(list_type(object.0.qtype))
+        .ok_or_else(|| Error::invalid_operation(operator, object.0.qtype, None))?;
+    Ok(with_value_type!(value, |T| {
+        as_slice::<T>(object)
+            .iter()
+            .map(|element| element.to_float())
+            .collect()
+    }))
+}
+
+/// Convert non-null elements of a numeric or temporal object into float.
+fn non_null_floats(object: &K, operator: &'static str) -> Result<Vec<F>> {
+    let mut values = floats(object, operator)?;
+    values.retain(|value| !value.is_nan());
+    Ok(values)
+}
+
+/// Build an atom or a list from a list holder.
+fn build(qtype: i8, list: k0_list, atom: bool) -> K {
+    let list = K::new(qtype, qattribute::NONE, k0_inner::list(list));
+    match atom {
+        true => into_atom(list),
+        false => list,
+    }
+}
+
+//%% Calculation %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Apply an arithmetic operator to two objects.
+fn arithmetic(operator: Operator, left: &K, right: &K) -> Result<K> {
+    let left_type = list_type(left.0.qtype);
+    let right_type = list_type(right.0.qtype);
+    let signature = signature(operator, left_type, right_type).ok_or_else(|| {
+        let operand = match value_type(left_type) {
+            Some(_) => right,
+            None => left,
+        };
+        Error::invalid_operation(operator.name(), operand.0.qtype, None)
+    })?;
+    let left_operand = prepare(left, signature.left, signature.value)?;
+    let right_operand = prepare(right, signature.right, signature.value)?;
+    with_value_type!(signature.value, |T| {
+        let values = broadcast(
+            (as_slice::<T>(&left_operand), left.0.qtype < 0),
+            (as_slice::<T>(&right_operand), right.0.qtype < 0),
+            |left, right| operator.apply(left, right),
+        )?;
+        Ok(build(
+            signature.result,
+            k0_list::new(values),
+            left.0.qtype < 0 && right.0.qtype < 0,
+        ))
+    })
+}
+
+/// Apply a function to pairs of elements. An atom is paired with every element of the other operand.
+fn broadcast<T, R, Function>(
+    (left, is_left_atom): (&[T], bool),
+    (right, is_right_atom): (&[T], bool),
+    function: Function,
+) -> Result<Vec<R>>
+where
+    T: Copy,
+    Function: Fn(T, T) -> R,
+{
+    match (is_left_atom, is_right_atom) {
+        (true, _) => Ok(right
+            .iter()
+            .map(|element| function(left[0], *element))
+            .collect()),
+        (false, true) => Ok(left
+            .iter()
+            .map(|element| function(*element, right[0]))
+            .collect()),
+        (false, false) if left.len() == right.len() => Ok(left
+            .iter()
+            .zip(right.iter())
+            .map(|(left, right)| function(*left, *right))
+            .collect()),
+        _ => Err(Error::operand_length_mismatch(left.len(), right.len())),
+    }
+}
+
+/// Average of values. The average of an empty slice is `0n`.
+fn mean(values: &[F]) -> F {
+    values.iter().sum::<F>() / values.len() as F
+}
+
+/// Minimum or maximum of an object ignoring null.
+fn extremum(object: &K, operator: &'static str, is_max: bool) -> Result<K> {
+    let qtype = list_type(object.0.qtype);
+    let value = value_type(qtype)
+        .ok_or_else(|| Error::invalid_operation(operator, object.0.qtype, None))?;
+    if object.0.qtype < 0 {
+        Ok(object.clone())
+    } else if qtype == qtype::BOOL_LIST {
+        let bools = object.as_vec::<G>()?;
+        Ok(K::new_bool(match is_max {
+            true => bools.contains(&1),
+            false => !bools.contains(&0),
+        }))
+    } else {
+        with_value_type!(value, |T| {
+            let values = as_slice::<T>(object);
+            let found = values
+                .iter()
+                .copied()
+                .filter(|element| !element.is_null())
+                .reduce(
+                    |found, element| match (is_max, element > found, element < found) {
+                        (true, true, _) | (false, _, true) => element,
+                        _ => found,
+                    },
+                );
+            let extremum = match found {
+                Some(extremum) => extremum,
+                None if values.is_empty() && is_max => T::NINF,
+                None if values.is_empty() => T::INF,
+                None => T::NULL,
+            };
+            Ok(build(qtype, k0_list::new(vec![extremum]), true))
+        })
+    }
+}
+
+/// Moving sums over `n` elements computed as differences of cumulative sums as q does.
+fn moving<T>(n: usize, values: &[T]) -> Vec<T>
+where
+    T: Value,
+{
+    let mut total = T::ZERO;
+    let sums = values
+        .iter()
+        .map(|value| {
+            total = total.add(*value);
+            total
+        })
+        .collect::<Vec<T>>();
+    sums.iter()
+        .enumerate()
+        .map(|(i, sum)| match i.checked_sub(n) {
+            Some(j) => sum.sub(sums[j]),
+            None => *sum,
+        })
+        .collect()
+}
+
+/// Shift elements of a list by one.
+fn shift(list: &K, operator: &'static str, is_forward: bool) -> Result<K> {
+    let qtype = list.0.qtype;
+    let shifted = match qtype {
+        qtype::COMPOUND_LIST => {
+            k0_list::new(shift_vec(list.as_vec::<K>()?, K::new_null(), is_forward))
+        }
+        qtype::GUID_LIST => k0_list::new(shift_vec(list.as_vec::<U>()?, qnull_base::U, is_forward)),
+        qtype::SYMBOL_LIST => {
+            k0_list::new(shift_vec(list.as_vec::<S>()?, S::default(), is_forward))
+        }
+        qtype::STRING => {
+            let characters = list.as_string()?.chars().collect::<Vec<char>>();
+            return Ok(K::new_string(
+                shift_vec(&characters, ' ', is_forward)
+                    .into_iter()
+                    .collect(),
+                qattribute::NONE,
+            ));
+        }
+        _ => match value_type(qtype) {
+            Some(value) if qtype > 0 => with_value_type!(value, |T| {
+                k0_list::new(shift_vec(as_slice::<T>(list), T::NULL, is_forward))
+            }),
+            _ => return Err(Error::invalid_operation(operator, qtype, None)),
+        },
+    };
+    Ok(build(qtype, shifted, false))
+}
+
+/// Shift elements by one filling the vacant element with `null`.
+fn shift_vec<T>(vector: &[T], null: T, is_forward: bool) -> Vec<T>
+where
+    T: Clone,
+{
+    match (vector.len(), is_forward) {
+        (0, _) => Vec::new(),
+        (length, true) => iter::once(null)
+            .chain(vector[..length - 1].iter().cloned())
+            .collect(),
+        (_, false) => vector[1..]
+            .iter()
+            .cloned()
+            .chain(iter::once(null))
+            .collect(),
+    }
+}
+
+/// Replace null elements with the previous non-null element.
+fn fill<T, Null>(vector: &[T], is_null: Null) -> Vec<T>
+where
+    T: Clone,
+    Null: Fn(&T) -> bool,
+{
+    let mut last: Option<&T> = None;
+    vector
+        .iter()
+        .map(|element| match (is_null(element), last) {
+            (true, Some(previous)) => previous.clone(),
+            (true, None) => element.clone(),
+            (false, _) => {
+                last = Some(element);
+                element.clone()
+            }
+        })
+        .collect()
+}
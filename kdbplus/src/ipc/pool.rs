@@ -0,0 +1,395 @@
+//! This module provides a pool of authenticated connections to q/kdb+ processes.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::error::Error;
+use super::{qtype, ConnectionMethod, QStream, QStreamBuilder, Query, Result, K};
+use std::collections::VecDeque;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% QEndpoint %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Destination of a connection to q/kdb+ with connection options. It can be built from [`QStreamBuilder`]
+///  to customize options.
+#[derive(Clone, Debug)]
+pub struct QEndpoint {
+    /// Builder used for every connection.
+    builder: QStreamBuilder,
+}
+
+//%% QPoolOptions %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Options of [`QPool`].
+#[derive(Clone, Debug)]
+pub struct QPoolOptions {
+    /// Number of connections kept by the pool. Default: 4.
+    pub size: usize,
+    /// Maximum time to wait for a free connection in [`acquire`](QPool::acquire). Default: 30 seconds.
+    pub acquire_timeout: Duration,
+    /// Query sent to check if a connection is alive. Default: `"1b"`.
+    pub ping_query: String,
+    /// Whether to ping an idle connection before handing it out. Default: `true`.
+    pub validate_on_acquire: bool,
+}
+
+//%% QPoolMetrics %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Snapshot of statistics of [`QPool`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QPoolMetrics {
+    /// Maximum number of connections.
+    pub size: usize,
+    /// Number of connections waiting to be acquired.
+    pub idle: usize,
+    /// Number of connections leased out.
+    pub in_use: usize,
+    /// Total number of successful acquisitions.
+    pub acquired: u64,
+    /// Total number of acquisitions which timed out.
+    pub timeouts: u64,
+    /// Total number of connections discarded because they were broken.
+    pub discarded: u64,
+}
+
+//%% QPool %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Pool of connections to one or more q/kdb+ processes. Cloning the pool shares the same connections.
+/// # Example
+/// ```no_run
+/// use kdbplus::ipc::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let endpoints = vec![
+///         QEndpoint::new(ConnectionMethod::TCP, "hdb1", 5000, "ideal:person"),
+///         QEndpoint::new(ConnectionMethod::TCP, "hdb2", 5000, "ideal:person"),
+///     ];
+///     let pool = QPool::connect(endpoints, QPoolOptions::default()).await?;
+///
+///     // Lease a connection. It returns to the pool when dropped.
+///     let mut lease = pool.acquire().await?;
+///     let result = lease.send_sync_message(&"til 5").await?;
+///     println!("til 5: {}", result);
+///     drop(lease);
+///
+///     // Or acquire, query and release at once.
+///     let result = pool.query(&"count .Q.pt").await?;
+///     println!("count: {}", result);
+///
+///     println!("{:?}", pool.metrics());
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct QPool {
+    /// State shared with clones and leases.
+    inner: Arc<PoolInner>,
+}
+
+//%% PoolInner %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Shared state of `QPool`.
+struct PoolInner {
+    /// Endpoints to which connections are established in round robin.
+    endpoints: Vec<QEndpoint>,
+    /// Options given at construction.
+    options: QPoolOptions,
+    /// Connections waiting to be acquired.
+    idle: Mutex<VecDeque<QStream>>,
+    /// Permits limiting the number of leased connections to `options.size`.
+    permits: Arc<Semaphore>,
+    /// Index of the endpoint to which the next connection is established.
+    next_endpoint: AtomicUsize,
+    /// Counter of successful acquisitions.
+    acquired: AtomicU64,
+    /// Counter of acquisitions which timed out.
+    timeouts: AtomicU64,
+    /// Counter of discarded connections.
+    discarded: AtomicU64,
+}
+
+//%% QLease %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Connection leased from [`QPool`]. It dereferences to [`QStream`] and returns to the pool when dropped unless
+///  it is broken.
+pub struct QLease {
+    /// Leased connection. Always `Some` until dropped.
+    stream: Option<QStream>,
+    /// Whether the connection is unusable and must not return to the pool.
+    broken: bool,
+    /// Pool to which the connection returns.
+    pool: Arc<PoolInner>,
+    /// Permit released after the connection returns to the pool.
+    _permit: OwnedSemaphorePermit,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% QEndpoint %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QEndpoint {
+    /// Create an endpoint. Parameters are the same as [`QStream::connect`].
+    pub fn new(method: ConnectionMethod, host: &str, port: u16, credential: &str) -> Self {
+        QEndpoint {
+            builder: QStreamBuilder::new(method, host, port).credential(credential),
+        }
+    }
+
+    /// Connect to the endpoint.
+    pub async fn connect(&self) -> Result<QStream> {
+        self.builder.connect().await
+    }
+}
+
+impl From<QStreamBuilder> for QEndpoint {
+    fn from(builder: QStreamBuilder) -> Self {
+        QEndpoint { builder }
+    }
+}
+
+//%% QPoolOptions %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Default for QPoolOptions {
+    fn default() -> Self {
+        QPoolOptions {
+            size: 4,
+            acquire_timeout: Duration::from_secs(30),
+            ping_query: String::from("1b"),
+            validate_on_acquire: true,
+        }
+    }
+}
+
+//%% QPool %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QPool {
+    /// Open `options.size` connections distributed over `endpoints` in round robin.
+    /// # Parameters
+    /// - `endpoints`: Destinations of connections. Must not be empty.
+    /// - `options`: Pool options.
+    /// # Example
+    /// See the example of [`QPool`](struct.QPool.html).
+    pub async fn connect(endpoints: Vec<QEndpoint>, options: QPoolOptions) -> Result<Self> {
+        if endpoints.is_empty() {
+            return Err(Error::IO(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no endpoint for connection pool",
+            )));
+        }
+        let inner = PoolInner {
+            endpoints,
+            idle: Mutex::new(VecDeque::with_capacity(options.size)),
+            permits: Arc::new(Semaphore::new(options.size)),
+            options,
+            next_endpoint: AtomicUsize::new(0),
+            acquired: AtomicU64::new(0),
+            timeouts: AtomicU64::new(0),
+            discarded: AtomicU64::new(0),
+        };
+        for _ in 0..inner.options.size {
+            let stream = inner.open().await?;
+            inner.idle.lock().unwrap().push_back(stream);
+        }
+        Ok(QPool {
+            inner: Arc::new(inner),
+        })
+    }
+
+    /// Lease a connection waiting at most `acquire_timeout` for a free one. An idle connection which does not
+    ///  respond to the ping query is replaced with a new connection. The ping and the new connection are
+    ///  bounded by the same `acquire_timeout`.
+    /// # Errors
+    /// - `Error::Timeout("acquire")` if no connection becomes free or ready in time.
+    /// - Error of [`QStream::connect`] if a replacement connection cannot be established to any endpoint.
+    /// # Example
+    /// See the example of [`QPool`](struct.QPool.html).
+    pub async fn acquire(&self) -> Result<QLease> {
+        let inner = &self.inner;
+        let deadline = time::Instant::now() + inner.options.acquire_timeout;
+        let permit = match time::timeout_at(deadline, inner.permits.clone().acquire_owned()).await {
+            Ok(permit) => permit.expect("semaphore of pool is never closed"),
+            Err(_) => return Err(inner.acquire_timed_out()),
+        };
+        let idle = inner.idle.lock().unwrap().pop_front();
+        let stream = match idle {
+            Some(mut stream) => {
+                // A ping which does not finish in time leaves a half-read stream. Discard it as well.
+                let alive = !inner.options.validate_on_acquire
+                    || matches!(
+                        time::timeout_at(deadline, inner.ping(&mut stream)).await,
+                        Ok(true)
+                    );
+                match alive {
+                    true => stream,
+                    false => {
+                        inner.discarded.fetch_add(1, Ordering::Relaxed);
+                        inner.open_until(deadline).await?
+                    }
+                }
+            }
+            // A broken connection was discarded. Fill the vacancy.
+            None => inner.open_until(deadline).await?,
+        };
+        inner.acquired.fetch_add(1, Ordering::Relaxed);
+        Ok(QLease {
+            stream: Some(stream),
+            broken: false,
+            pool: Arc::clone(inner),
+            _permit: permit,
+        })
+    }
+
+    /// Send a message synchronously over a leased connection and release the connection.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    pub async fn query(&self, message: &dyn Query) -> Result<K> {
+        self.acquire().await?.send_sync_message(message).await
+    }
+
+    /// Get a snapshot of statistics of the pool.
+    pub fn metrics(&self) -> QPoolMetrics {
+        let inner = &self.inner;
+        QPoolMetrics {
+            size: inner.options.size,
+            idle: inner.idle.lock().unwrap().len(),
+            in_use: inner.options.size - inner.permits.available_permits(),
+            acquired: inner.acquired.load(Ordering::Relaxed),
+            timeouts: inner.timeouts.load(Ordering::Relaxed),
+            discarded: inner.discarded.load(Ordering::Relaxed),
+        }
+    }
+}
+
+//%% PoolInner %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl PoolInner {
+    /// Connect to the next endpoint. Following endpoints are tried in turn if the connection fails and the
+    ///  last error is returned if none of them succeeds.
+    async fn open(&self) -> Result<QStream> {
+        let start = self.next_endpoint.fetch_add(1, Ordering::Relaxed);
+        let mut error = None;
+        for i in 0..self.endpoints.len() {
+            match self.endpoints[(start + i) % self.endpoints.len()]
+                .connect()
+                .await
+            {
+                Ok(stream) => return Ok(stream),
+                Err(connection_error) => error = Some(connection_error),
+            }
+        }
+        Err(error.unwrap())
+    }
+
+    /// Connect to the next endpoint by `deadline`.
+    async fn open_until(&self, deadline: time::Instant) -> Result<QStream> {
+        match time::timeout_at(deadline, self.open()).await {
+            Ok(result) => result,
+            Err(_) => Err(self.acquire_timed_out()),
+        }
+    }
+
+    /// Count an acquisition which timed out and build the error to return.
+    fn acquire_timed_out(&self) -> Error {
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+        Error::timeout("acquire")
+    }
+
+    /// Check if a connection responds to the ping query without an error.
+    async fn ping(&self, stream: &mut QStream) -> bool {
+        matches!(
+            stream.send_sync_message(&self.options.ping_query.as_str()).await,
+            Ok(response) if response.get_type() != qtype::ERROR
+        )
+    }
+}
+
+//%% QLease %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QLease {
+    /// Send a message synchronously. The connection is marked broken on an IO error or a timeout, and also if
+    ///  the returned future is dropped before completion since the stream may be left in the middle of a message.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    pub async fn send_sync_message(&mut self, message: &dyn Query) -> Result<K> {
+        let was_broken = std::mem::replace(&mut self.broken, true);
+        let result = self.deref_mut().send_sync_message(message).await;
+        self.check(result, was_broken)
+    }
+
+    /// Send a message asynchronously. The connection is marked broken on an IO error or a timeout, and also if
+    ///  the returned future is dropped before completion since the stream may be left in the middle of a message.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    pub async fn send_async_message(&mut self, message: &dyn Query) -> Result<()> {
+        let was_broken = std::mem::replace(&mut self.broken, true);
+        let result = self.deref_mut().send_async_message(message).await;
+        self.check(result, was_broken)
+    }
+
+    /// Mark the connection broken so that it is discarded instead of returning to the pool. Use this after
+    ///  an error on the underlying [`QStream`] accessed directly.
+    pub fn mark_broken(&mut self) {
+        self.broken = true;
+    }
+
+    /// Check if the connection is marked broken.
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    /// Restore the mark set before sending a message to `was_broken` unless the result is an IO error or a
+    ///  timeout.
+    fn check<T>(&mut self, result: Result<T>, was_broken: bool) -> Result<T> {
+        if !matches!(result, Err(Error::IO(_) | Error::Timeout(_))) {
+            self.broken = was_broken;
+        }
+        result
+    }
+}
+
+impl Deref for QLease {
+    type Target = QStream;
+
+    fn deref(&self) -> &QStream {
+        self.stream.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for QLease {
+    fn deref_mut(&mut self) -> &mut QStream {
+        self.stream.as_mut().unwrap()
+    }
+}
+
+impl Drop for QLease {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            match self.broken {
+                true => {
+                    self.pool.discarded.fetch_add(1, Ordering::Relaxed);
+                }
+                false => self.pool.idle.lock().unwrap().push_back(stream),
+            }
+        }
+    }
+}
@@ -0,0 +1,568 @@
+//! This module provides a publisher which q processes subscribe to as they do to a kdb+ tickerplant.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::handler::is_disconnection;
+use super::tick::{SUBSCRIBE, SUBSCRIBE_AND_RECOVER};
+use super::{journal, qmsg_type, qnull, qtype, select_rows, QListener, QStream, Result, K, S};
+use chrono::NaiveDate;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Name of the subscription function of `tick.q`.
+const SUB: &str = ".u.sub";
+
+/// Suffix of the subscription query of `r.q` after `.u.sub[t;s]`, with whitespace removed.
+const RECOVERY_SUFFIX: &str = ";`.u`i`L)";
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Publisher %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Publisher serving q subscribers with the semantics of `.u.sub` and `.u.pub` of `tick.q`, so that a q
+///  process, e.g., an RDB started with `r.q`, can subscribe to a Rust process as it does to a tickerplant.
+///
+/// Each subscriber holds tables and symbols it subscribes to, and [`publish`](#method.publish) sends
+///  `` (`upd; `table; data) `` asynchronously to subscribers of the table with rows of the subscribed symbols.
+///  A subscriber is removed when its connection is closed. Updates to a slow subscriber are queued in memory
+///  as q does.
+///
+/// The following subscription queries are accepted in addition to the one of [`TickSubscriber`](struct.TickSubscriber.html):
+/// - `` (`.u.sub; `table; `symbols) `` or `".u.sub[`table;`symbols]"`. `` ` `` subscribes all tables or all symbols.
+/// - `` "(.u.sub[`;`];`.u `i`L)" `` sent by `r.q`, which also returns the number of messages in the journal and its path.
+///
+/// With a journal set by [`set_journal`](#method.set_journal), every update is logged before it is sent, so that
+///  a subscriber can replay the journal with `-11!` and receive later updates without loss or duplication.
+/// # Example
+/// ```no_run
+/// use kdbplus::qattribute;
+/// use kdbplus::ipc::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let trade = K::new_dictionary(
+///         K::new_symbol_list(vec![String::from("sym"), String::from("price")], qattribute::NONE),
+///         K::new_compound_list(vec![
+///             K::new_symbol_list(Vec::new(), qattribute::NONE),
+///             K::new_float_list(Vec::new(), qattribute::NONE),
+///         ]),
+///     )?
+///     .flip()?;
+///     let publisher = Publisher::new(vec![(String::from("trade"), trade)])?;
+///     publisher.set_journal("/data/tplog/sym2024.01.02").await?;
+///
+///     // q)h:hopen `::5010; h(".u.sub"; `trade; `AAPL)
+///     let builder = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 5010);
+///     let listener = QListener::bind(builder, 64).await?;
+///     let server = publisher.clone();
+///     tokio::spawn(async move { server.listen(listener).await });
+///
+///     let update = K::new_dictionary(
+///         K::new_symbol_list(vec![String::from("sym"), String::from("price")], qattribute::NONE),
+///         K::new_compound_list(vec![
+///             K::new_symbol_list(vec![String::from("AAPL"), String::from("MSFT")], qattribute::NONE),
+///             K::new_float_list(vec![185.5, 371.25], qattribute::NONE),
+///         ]),
+///     )?
+///     .flip()?;
+///     publisher.publish("trade", update).await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Publisher {
+    /// State shared with tasks serving subscribers.
+    shared: Arc<Shared>,
+}
+
+//%% Shared %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// State of a publisher shared with tasks serving subscribers.
+struct Shared {
+    /// Names and empty schemas of tables in the order of `.u.t`.
+    tables: Vec<(String, K)>,
+    /// Identifier of the next connection.
+    next_id: AtomicU64,
+    /// Subscribers and the journal. Both are updated under one lock so that a subscription sees every update
+    ///  either in the journal or in its queue.
+    state: Mutex<State>,
+}
+
+//%% State %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Mutable state of a publisher.
+#[derive(Default)]
+struct State {
+    /// Subscribers keyed by the identifier of their connections.
+    subscribers: HashMap<u64, Subscriber>,
+    /// Path and writer of the journal.
+    journal: Option<(String, journal::Writer)>,
+}
+
+//%% Subscriber %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Subscriptions of a connection and the queue of messages to it.
+struct Subscriber {
+    /// Subscribed symbols of each table. `None` for all symbols.
+    tables: HashMap<String, Option<HashSet<S>>>,
+    /// Queue of messages sent by the task serving the connection.
+    sender: mpsc::UnboundedSender<K>,
+}
+
+//%% Event %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Event on a connection of a subscriber.
+enum Event {
+    /// Message to send.
+    Outgoing(Option<K>),
+    /// First byte of a message from the subscriber.
+    Incoming(Result<u8>),
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Publisher %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Publisher {
+    /// Create a publisher of tables.
+    /// # Parameters
+    /// - `tables`: Names and empty tables of the schemas, which are returned to subscribers by `.u.sub`.
+    /// # Errors
+    /// IO error of `InvalidInput` if a schema is not a table.
+    /// # Example
+    /// See the example of [`Publisher`](struct.Publisher.html).
+    pub fn new(tables: Vec<(String, K)>) -> Result<Self> {
+        if let Some((table, _)) = tables
+            .iter()
+            .find(|(_, schema)| schema.get_type() != qtype::TABLE)
+        {
+            return Err(invalid_input(&format!(
+                "schema of {} is not a table",
+                table
+            )));
+        }
+        Ok(Publisher {
+            shared: Arc::new(Shared {
+                tables,
+                next_id: AtomicU64::new(0),
+                state: Mutex::new(State::default()),
+            }),
+        })
+    }
+
+    /// Log updates to a journal from now on. An existing journal is appended to and otherwise it is created.
+    ///  Call this again after [`end_of_day`](#method.end_of_day) to switch to the journal of the next day.
+    /// # Parameters
+    /// - `path`: Path of the journal, which subscribers open with `-11!`.
+    /// # Errors
+    /// IO error if the journal cannot be opened, e.g., if its last message is truncated. See [`journal::repair`](journal/fn.repair.html).
+    /// # Example
+    /// See the example of [`Publisher`](struct.Publisher.html).
+    pub async fn set_journal<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let writer = if path.exists() {
+            journal::Writer::open(path).await?
+        } else {
+            journal::Writer::create(path).await?
+        };
+        self.shared.state.lock().await.journal = Some((path.display().to_string(), writer));
+        Ok(())
+    }
+
+    /// Publish an update of a table as `.u.pub` does. The update is logged to the journal if any and sent to
+    ///  subscribers of the table. A subscriber of some symbols receives only the rows of those symbols in
+    ///  `sym` column and nothing if there is no such row.
+    /// # Parameters
+    /// - `table`: Name of the table.
+    /// - `data`: Rows to publish as a table with the same columns as the schema.
+    /// # Errors
+    /// - IO error of `InvalidInput` if `table` is unknown or `data` does not have the columns of the schema.
+    /// - IO error if the update cannot be logged. The update is not sent then.
+    /// # Example
+    /// See the example of [`Publisher`](struct.Publisher.html).
+    pub async fn publish(&self, table: &str, data: K) -> Result<()> {
+        let schema = self
+            .shared
+            .tables
+            .iter()
+            .find(|(name, _)| name == table)
+            .map(|(_, schema)| schema)
+            .ok_or_else(|| invalid_input(&format!("unknown table: {}", table)))?;
+        if data.get_type() != qtype::TABLE || data.cols()? != schema.cols()? {
+            return Err(invalid_input(&format!(
+                "update does not match the schema of {}",
+                table
+            )));
+        }
+
+        let mut state = self.shared.state.lock().await;
+        if let Some((_, writer)) = state.journal.as_mut() {
+            writer.append_update(table, data.clone()).await?;
+        }
+        let mut closed = Vec::new();
+        for (id, subscriber) in state.subscribers.iter() {
+            let rows = match subscriber.tables.get(table) {
+                Some(None) => data.clone(),
+                Some(Some(symbols)) => match select_symbols(&data, symbols)? {
+                    Some(rows) => rows,
+                    None => continue,
+                },
+                None => continue,
+            };
+            if subscriber.sender.send(update_message(table, rows)).is_err() {
+                closed.push(*id);
+            }
+        }
+        for id in closed {
+            state.subscribers.remove(&id);
+        }
+        Ok(())
+    }
+
+    /// Notify every subscriber of the end of day by `` (`.u.end; date) `` as `.u.end` does.
+    /// # Parameters
+    /// - `date`: Date which ended.
+    pub async fn end_of_day(&self, date: NaiveDate) -> Result<()> {
+        let message = K::new_compound_list(vec![
+            K::new_symbol(String::from(".u.end")),
+            K::new_date(date),
+        ]);
+        let mut state = self.shared.state.lock().await;
+        state
+            .subscribers
+            .retain(|_, subscriber| subscriber.sender.send(message.clone()).is_ok());
+        Ok(())
+    }
+
+    /// Get the number of connections subscribing to at least one table.
+    pub async fn subscribers(&self) -> usize {
+        self.shared.state.lock().await.subscribers.len()
+    }
+
+    /// Serve every client accepted by a listener in its own task until the listener is closed. A client which
+    ///  failed to connect, e.g., to authenticate, is skipped.
+    /// # Parameters
+    /// - `listener`: Listener accepting subscribers.
+    /// # Errors
+    /// IO error of `NotConnected` when the listener is closed.
+    /// # Example
+    /// See the example of [`Publisher`](struct.Publisher.html).
+    pub async fn listen(&self, mut listener: QListener) -> Result<()> {
+        while let Some(result) = listener.next_client().await {
+            if let Ok(socket) = result {
+                let publisher = self.clone();
+                tokio::spawn(async move {
+                    let _ = publisher.serve(socket).await;
+                });
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::NotConnected, "listener is closed").into())
+    }
+
+    /// Answer subscription queries of a client and send updates to it until it disconnects.
+    /// # Parameters
+    /// - `socket`: Accepted connection of a subscriber.
+    /// # Errors
+    /// IO error other than the disconnection of the client. The client is unsubscribed in any case.
+    pub async fn serve(&self, mut socket: QStream) -> Result<()> {
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let result = loop {
+            // Only the first byte of a request is awaited together with updates so that no byte is lost when
+            //  an update comes first.
+            let event = tokio::select! {
+                biased;
+                message = receiver.recv() => Event::Outgoing(message),
+                first = socket.receive_first_byte() => Event::Incoming(first),
+            };
+            let result = match event {
+                // `sender` is alive and so the queue is never closed.
+                Event::Outgoing(None) => Ok(()),
+                Event::Outgoing(Some(message)) => socket.send_async_message(&message).await,
+                Event::Incoming(first) => match first {
+                    Ok(first) => match socket.receive_message_after(first).await {
+                        Ok((qmsg_type::synchronous, query)) => {
+                            let response = self.subscribe(id, &sender, &query).await;
+                            socket.send_message(&response, qmsg_type::response).await
+                        }
+                        Ok((qmsg_type::asynchronous, query)) => {
+                            self.subscribe(id, &sender, &query).await;
+                            Ok(())
+                        }
+                        Ok(_) => Ok(()),
+                        Err(error) => Err(error),
+                    },
+                    Err(error) => Err(error),
+                },
+            };
+            match result {
+                Ok(()) => {}
+                Err(error) if is_disconnection(&error) => break Ok(()),
+                Err(error) => break Err(error),
+            }
+        };
+        self.shared.state.lock().await.subscribers.remove(&id);
+        result
+    }
+
+    /// Answer a query of a client, registering subscriptions if it is a subscription query.
+    async fn subscribe(&self, id: u64, sender: &mpsc::UnboundedSender<K>, query: &K) -> K {
+        let (function, tables, symbols) = match parse_query(query) {
+            Some(query) => query,
+            None => return K::new_error(String::from("nyi")),
+        };
+        let mut state = self.shared.state.lock().await;
+        let mut subscribed = Vec::new();
+        let mut response = Vec::new();
+        for table in tables.unwrap_or_else(|| {
+            self.shared
+                .tables
+                .iter()
+                .map(|(name, _)| S::from(name.as_str()))
+                .collect()
+        }) {
+            match self
+                .shared
+                .tables
+                .iter()
+                .find(|(name, _)| name.as_str() == table.as_str())
+            {
+                Some((name, schema)) => {
+                    subscribed.push(name.clone());
+                    response.push(K::new_compound_list(vec![
+                        K::new_symbol(name.clone()),
+                        schema.clone(),
+                    ]));
+                }
+                None => return K::new_error(table.to_string()),
+            }
+        }
+        let subscriber = state.subscribers.entry(id).or_insert_with(|| Subscriber {
+            tables: HashMap::new(),
+            sender: sender.clone(),
+        });
+        for table in subscribed {
+            subscriber.tables.insert(table, symbols.clone());
+        }
+
+        // `.u.sub` returns a pair of a table and its schema for a table and a list of them for all tables.
+        let response = match function {
+            Function::Sub { all_tables: false } | Function::Recovery { all_tables: false } => {
+                response.pop().unwrap()
+            }
+            _ => K::new_compound_list(response),
+        };
+        match function {
+            Function::Sub { .. } | Function::Subscribe => response,
+            Function::Recovery { .. } => {
+                let (chunks, path) = match state.journal.as_ref() {
+                    Some((path, writer)) => (writer.chunks() as i64, format!(":{}", path)),
+                    None => (qnull::LONG, String::new()),
+                };
+                K::new_compound_list(vec![
+                    response,
+                    K::new_compound_list(vec![K::new_long(chunks), K::new_symbol(path)]),
+                ])
+            }
+            Function::SubscribeAndRecover => {
+                // Updates after this point go to the queue of the subscriber. The logged ones are read after
+                //  releasing the lock so that publishing is not blocked while the journal is read.
+                let journal = state
+                    .journal
+                    .as_ref()
+                    .map(|(path, writer)| (path.clone(), writer.chunks()));
+                drop(state);
+                let log = match journal {
+                    Some((path, chunks)) => read_journal(&path, chunks).await,
+                    None => Ok(Vec::new()),
+                };
+                match log {
+                    Ok(log) => K::new_compound_list(vec![response, K::new_compound_list(log)]),
+                    Err(error) => K::new_error(error.to_string()),
+                }
+            }
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Form of a subscription query, which decides the form of the response.
+enum Function {
+    /// `.u.sub[t;s]`. A single pair of a table and its schema is returned unless all tables are subscribed.
+    Sub { all_tables: bool },
+    /// `.u.sub[t;s]` and `(.u.i; .u.L)` of `r.q`.
+    Recovery { all_tables: bool },
+    /// Subscription of `TickSubscriber`.
+    Subscribe,
+    /// Subscription of `TickSubscriber` with the messages in the journal.
+    SubscribeAndRecover,
+}
+
+/// Subscribed tables and symbols where `None` means all.
+type Subscription = (Function, Option<Vec<S>>, Option<HashSet<S>>);
+
+/// Parse a subscription query.
+fn parse_query(query: &K) -> Option<Subscription> {
+    match query.get_type() {
+        qtype::STRING => {
+            let query = query
+                .as_string()
+                .ok()?
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>();
+            let (function, sub) = match query
+                .strip_prefix('(')
+                .and_then(|query| query.strip_suffix(RECOVERY_SUFFIX))
+            {
+                Some(sub) => (Function::Recovery { all_tables: false }, sub),
+                None => (Function::Sub { all_tables: false }, query.as_str()),
+            };
+            let arguments = sub
+                .strip_prefix(SUB)?
+                .strip_prefix('[')?
+                .strip_suffix(']')?;
+            let (tables, symbols) = arguments.split_once(';')?;
+            let tables = parse_symbols(tables)?;
+            let symbols = parse_symbols(symbols)?;
+            finish(function, tables, symbols)
+        }
+        qtype::COMPOUND_LIST => match query.as_vec::<K>().ok()?.as_slice() {
+            [function, tables, symbols] => {
+                let function = match function.get_type() {
+                    qtype::SYMBOL_ATOM if function.get_symbol().ok()? == SUB => {
+                        Function::Sub { all_tables: false }
+                    }
+                    qtype::STRING => match function.as_string().ok()? {
+                        SUB => Function::Sub { all_tables: false },
+                        SUBSCRIBE => Function::Subscribe,
+                        SUBSCRIBE_AND_RECOVER => Function::SubscribeAndRecover,
+                        _ => return None,
+                    },
+                    _ => return None,
+                };
+                let tables = symbols_of(tables)?;
+                let symbols = symbols_of(symbols)?;
+                finish(function, tables, symbols)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Resolve null symbols into all tables or all symbols.
+fn finish(function: Function, tables: Vec<S>, symbols: Vec<S>) -> Option<Subscription> {
+    let all_symbols = symbols.len() == 1 && symbols[0].is_empty();
+    let symbols = (!all_symbols).then(|| symbols.into_iter().collect::<HashSet<S>>());
+    match function {
+        Function::Sub { .. } | Function::Recovery { .. } => match tables.as_slice() {
+            [table] if table.is_empty() => Some((
+                match function {
+                    Function::Sub { .. } => Function::Sub { all_tables: true },
+                    _ => Function::Recovery { all_tables: true },
+                },
+                None,
+                symbols,
+            )),
+            [_] => Some((function, Some(tables), symbols)),
+            _ => None,
+        },
+        // `{[t;s] .u.sub[;s] each $[count t;t;.u.t]}`
+        Function::Subscribe | Function::SubscribeAndRecover => {
+            let tables = (!tables.is_empty()).then_some(tables);
+            Some((function, tables, symbols))
+        }
+    }
+}
+
+/// Get symbols of a symbol atom or a symbol list.
+fn symbols_of(object: &K) -> Option<Vec<S>> {
+    match object.get_type() {
+        qtype::SYMBOL_ATOM => Some(vec![S::from(object.get_symbol().ok()?)]),
+        qtype::SYMBOL_LIST => Some(object.as_vec::<S>().ok()?.clone()),
+        _ => None,
+    }
+}
+
+/// Parse symbol literals such as `` ` ``, `` `a `` and `` `a`b ``.
+fn parse_symbols(literal: &str) -> Option<Vec<S>> {
+    let literal = literal.strip_prefix('`')?;
+    Some(literal.split('`').map(S::from).collect())
+}
+
+/// Read the first `chunks` messages of a journal.
+async fn read_journal(path: &str, chunks: usize) -> Result<Vec<K>> {
+    let mut reader = journal::Reader::open(path).await?;
+    let mut messages = Vec::with_capacity(chunks);
+    while messages.len() < chunks {
+        match reader.next_message().await? {
+            Some(message) => messages.push(message),
+            None => break,
+        }
+    }
+    Ok(messages)
+}
+
+/// Build `` (`upd; `table; data) ``.
+fn update_message(table: &str, data: K) -> K {
+    K::new_compound_list(vec![
+        K::new_symbol(String::from("upd")),
+        K::new_symbol(String::from(table)),
+        data,
+    ])
+}
+
+/// Select rows of a table whose `sym` is one of `symbols`. `None` if there is no such row. A table without
+///  `sym` column is returned as it is.
+fn select_symbols(table: &K, symbols: &HashSet<S>) -> Result<Option<K>> {
+    let rows = match table.get_column("sym") {
+        Ok(column) if column.get_type() == qtype::SYMBOL_LIST => column
+            .as_vec::<S>()?
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| symbols.contains(*symbol))
+            .map(|(row, _)| row)
+            .collect::<Vec<usize>>(),
+        _ => return Ok(Some(table.clone())),
+    };
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    if rows.len() == table.len() {
+        return Ok(Some(table.clone()));
+    }
+    let dictionary = table.get_dictionary()?.as_vec::<K>()?;
+    let columns = dictionary[1]
+        .as_vec::<K>()?
+        .iter()
+        .map(|column| select_rows(column, &rows))
+        .collect();
+    Ok(Some(
+        K::new_dictionary(dictionary[0].clone(), K::new_compound_list(columns))?.flip()?,
+    ))
+}
+
+/// Construct an IO error of `InvalidInput`.
+fn invalid_input(message: &str) -> super::error::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_string()).into()
+}
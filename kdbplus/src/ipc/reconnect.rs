@@ -0,0 +1,278 @@
+//! This module provides a stream which re-establishes a lost connection to q/kdb+ automatically.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::error::Error;
+use super::{qtype, QEndpoint, QStream, Query, Result, K};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::time;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% ReconnectOptions %%//vvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Options of [`QReconnectingStream`].
+#[derive(Clone, Debug)]
+pub struct ReconnectOptions {
+    /// Delay before the second connection attempt. Default: 100 milliseconds.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between attempts. Default: 30 seconds.
+    pub max_backoff: Duration,
+    /// Factor by which the delay grows after each failed attempt. Default: 2.0.
+    pub multiplier: f64,
+    /// Ratio by which the delay is randomly shortened or lengthened, between 0.0 and 1.0. Default: 0.2.
+    pub jitter: f64,
+    /// Maximum number of attempts for one (re)connection. `None` retries forever. Default: `None`.
+    pub max_attempts: Option<usize>,
+    /// Queries sent synchronously on every (re)connection, e.g. `".u.sub[`trade;`]"`. Default: empty.
+    pub init_script: Vec<String>,
+}
+
+//%% ConnectionEvent %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Event of a connection notified to subscribers of [`QReconnectingStream`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionEvent {
+    /// The connection was lost. Holds the message of the error.
+    Disconnected(String),
+    /// A connection attempt failed and the next attempt is made after `delay`.
+    RetryScheduled { attempt: usize, delay: Duration },
+    /// The connection was established after `attempts` attempts and the init script was replayed.
+    Connected { attempts: usize },
+}
+
+//%% QReconnectingStream %%//vvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Stream to communicate with q/kdb+ which reconnects with exponential backoff after the connection is lost.
+///
+/// When an operation fails with an IO error or a timeout, the error is returned to the caller and the stream is marked
+///  disconnected. The next operation re-establishes the connection, replays the init script and then proceeds.
+///  A failed query is not re-sent because it might not be idempotent.
+/// # Example
+/// ```no_run
+/// use kdbplus::ipc::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let endpoint = QEndpoint::new(ConnectionMethod::TCP, "localhost", 5000, "ideal:person");
+///     let options = ReconnectOptions {
+///         init_script: vec![String::from(".u.sub[`trade;`]")],
+///         ..ReconnectOptions::default()
+///     };
+///     let mut stream = QReconnectingStream::connect(endpoint, options).await?;
+///
+///     let mut events = stream.subscribe();
+///     tokio::spawn(async move {
+///         while let Ok(event) = events.recv().await {
+///             eprintln!("connection event: {:?}", event);
+///         }
+///     });
+///
+///     loop {
+///         match stream.receive_message().await {
+///             Ok((_, message)) => println!("update: {}", message),
+///             // Reconnected at the next `receive_message`.
+///             Err(error) => eprintln!("error: {}", error),
+///         }
+///     }
+/// }
+/// ```
+pub struct QReconnectingStream {
+    /// Destination of the connection.
+    endpoint: QEndpoint,
+    /// Options given at construction.
+    options: ReconnectOptions,
+    /// Current connection. `None` while disconnected.
+    stream: Option<QStream>,
+    /// Sender of connection events.
+    events: broadcast::Sender<ConnectionEvent>,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% ReconnectOptions %%//vvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Default for ReconnectOptions {
+    fn default() -> Self {
+        ReconnectOptions {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: None,
+            init_script: Vec::new(),
+        }
+    }
+}
+
+impl ReconnectOptions {
+    /// Delay after `attempt`-th failed attempt with jitter applied.
+    fn backoff(&self, attempt: usize) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as usize) as i32;
+        let delay = (self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0) * (2.0 * random() - 1.0);
+        // `Duration::from_secs_f64` panics on a negative, infinite or too large value. `max` also turns NaN,
+        //  e.g., of NaN jitter, into 0.
+        Duration::from_secs_f64((delay * (1.0 + jitter)).max(0.0).min(u32::MAX as f64))
+    }
+}
+
+//%% QReconnectingStream %%//vvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QReconnectingStream {
+    /// Connect to q/kdb+ retrying with backoff and run the init script.
+    /// # Parameters
+    /// - `endpoint`: Destination of the connection which is remembered for reconnection.
+    /// - `options`: Backoff and init script.
+    /// # Example
+    /// See the example of [`QReconnectingStream`](struct.QReconnectingStream.html).
+    pub async fn connect(endpoint: QEndpoint, options: ReconnectOptions) -> Result<Self> {
+        let (events, _) = broadcast::channel(64);
+        let mut stream = QReconnectingStream {
+            endpoint,
+            options,
+            stream: None,
+            events,
+        };
+        stream.reconnect().await?;
+        Ok(stream)
+    }
+
+    /// Subscribe to connection events. Events are sent only while a receiver is alive and a receiver lagging
+    ///  behind by more than 64 events loses the oldest ones.
+    pub fn subscribe(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events.subscribe()
+    }
+
+    /// Check if the stream currently holds a connection.
+    pub fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Close the current connection if any and connect again retrying with backoff. The init script is
+    ///  replayed on the new connection.
+    /// # Errors
+    /// - The error of the last attempt if `max_attempts` attempts fail.
+    /// - `Error::Object` holding the q error if a query of the init script fails. It is not retried.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        if let Some(stream) = self.stream.take() {
+            // The connection may be already broken.
+            let _ = stream.shutdown().await;
+        }
+        let mut attempt = 1;
+        loop {
+            match self.establish().await {
+                Ok(stream) => {
+                    self.stream = Some(stream);
+                    let _ = self
+                        .events
+                        .send(ConnectionEvent::Connected { attempts: attempt });
+                    return Ok(());
+                }
+                Err(error @ Error::Object(_)) => return Err(error),
+                Err(error) if self.options.max_attempts.is_some_and(|max| attempt >= max) => {
+                    return Err(error)
+                }
+                Err(_) => {
+                    let delay = self.options.backoff(attempt);
+                    let _ = self
+                        .events
+                        .send(ConnectionEvent::RetryScheduled { attempt, delay });
+                    time::sleep(delay).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Send a message asynchronously reconnecting beforehand if disconnected.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    pub async fn send_async_message(&mut self, message: &dyn Query) -> Result<()> {
+        let result = self.stream().await?.send_async_message(message).await;
+        self.check(result)
+    }
+
+    /// Send a message synchronously reconnecting beforehand if disconnected.
+    /// # Parameters
+    /// - `message`: q command to execute on the remote q process.
+    ///   - `&str`: q command in a string form.
+    ///   - `K`: Query in a functional form.
+    pub async fn send_sync_message(&mut self, message: &dyn Query) -> Result<K> {
+        let result = self.stream().await?.send_sync_message(message).await;
+        self.check(result)
+    }
+
+    /// Receive a message from a remote q process reconnecting beforehand if disconnected.
+    /// # Example
+    /// See the example of [`QReconnectingStream`](struct.QReconnectingStream.html).
+    pub async fn receive_message(&mut self) -> Result<(u8, K)> {
+        let result = self.stream().await?.receive_message().await;
+        self.check(result)
+    }
+
+    /// Shutdown the current connection if any.
+    pub async fn shutdown(mut self) -> Result<()> {
+        match self.stream.take() {
+            Some(stream) => stream.shutdown().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Connect to the endpoint and run the init script.
+    async fn establish(&self) -> Result<QStream> {
+        let mut stream = self.endpoint.connect().await?;
+        for query in self.options.init_script.iter() {
+            let response = stream.send_sync_message(&query.as_str()).await?;
+            if response.get_type() == qtype::ERROR {
+                return Err(Error::Object(response));
+            }
+        }
+        Ok(stream)
+    }
+
+    /// Get the current connection reconnecting if disconnected.
+    async fn stream(&mut self) -> Result<&mut QStream> {
+        if self.stream.is_none() {
+            self.reconnect().await?;
+        }
+        Ok(self.stream.as_mut().unwrap())
+    }
+
+    /// Drop the connection and notify subscribers if the result is an IO error or a timeout.
+    fn check<T>(&mut self, result: Result<T>) -> Result<T> {
+        if let Err(error @ (Error::IO(_) | Error::Timeout(_))) = &result {
+            self.stream = None;
+            let _ = self
+                .events
+                .send(ConnectionEvent::Disconnected(error.to_string()));
+        }
+        result
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Cheap pseudo random number in `[0, 1)` taken from the clock, which is enough to spread reconnections of
+///  many clients.
+fn random() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or(0);
+    // Scramble low bits which may be coarse on some platforms.
+    let mixed = nanos.wrapping_mul(2_654_435_761);
+    mixed as f64 / (u32::MAX as f64 + 1.0)
+}
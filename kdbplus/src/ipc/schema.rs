@@ -0,0 +1,437 @@
+//! This module provides table schema introspection equivalent to q's `meta`, `cols` and `keys`, and
+//!  validation of a table against an expected schema.
+//!
+//! # Example
+//! ```
+//! use kdbplus::qattribute;
+//! use kdbplus::ipc::*;
+//!
+//! fn main() {
+//!     let q_table = K::new_dictionary(
+//!         K::new_symbol_list(vec![String::from("sym"), String::from("price")], qattribute::NONE),
+//!         K::new_compound_list(vec![
+//!             K::new_symbol_list(vec![String::from("AAPL")], qattribute::GROUPED),
+//!             K::new_float_list(vec![101.5], qattribute::NONE),
+//!         ]),
+//!     )
+//!     .unwrap()
+//!     .flip()
+//!     .unwrap();
+//!
+//!     let expected = Schema::new(vec![
+//!         ColumnMeta::new("sym", 's').attribute(qattribute::GROUPED),
+//!         ColumnMeta::new("price", 'f'),
+//!     ]);
+//!     assert_eq!(q_table.meta().unwrap(), expected);
+//!     assert!(q_table.conforms_to(&expected).is_ok());
+//!
+//!     let wrong = Schema::new(vec![ColumnMeta::new("sym", 's'), ColumnMeta::new("price", 'j')]);
+//!     assert_eq!(
+//!         q_table.conforms_to(&wrong).unwrap_err().to_string(),
+//!         String::from("schema mismatch: column `price has type f but expected j")
+//!     );
+//! }
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::*;
+use std::fmt;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% ColumnMeta %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Metadata of a column, i.e., a row of q's `meta`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnMeta {
+    /// Column name (`c`).
+    pub name: S,
+    /// Type character (`t`). Lower case for a simple list, upper case for a compound list of simple lists
+    ///  and `' '` for other compound lists.
+    pub type_char: char,
+    /// Attribute of the column (`a`).
+    pub attribute: i8,
+}
+
+//%% Schema %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Schema of a table or a keyed table equivalent to q's `meta`. Key columns come first as q does.
+///
+/// Foreign keys (`f`) are not held since enumerations are sent over IPC as symbols.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schema {
+    /// Metadata of columns including key columns.
+    pub columns: Vec<ColumnMeta>,
+    /// The number of key columns. `0` for a table which is not keyed.
+    pub key_count: usize,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% ColumnMeta %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl ColumnMeta {
+    /// Build column metadata with no attribute.
+    pub fn new(name: &str, type_char: char) -> Self {
+        ColumnMeta {
+            name: Symbol::from(name),
+            type_char,
+            attribute: qattribute::NONE,
+        }
+    }
+
+    /// Set an attribute.
+    pub fn attribute(mut self, attribute: i8) -> Self {
+        self.attribute = attribute;
+        self
+    }
+}
+
+//%% Schema %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Schema {
+    /// Build a schema of a table which is not keyed.
+    pub fn new(columns: Vec<ColumnMeta>) -> Self {
+        Schema {
+            columns,
+            key_count: 0,
+        }
+    }
+
+    /// Build a schema of a keyed table whose first `key_count` columns are keys.
+    pub fn keyed(columns: Vec<ColumnMeta>, key_count: usize) -> Self {
+        Schema { columns, key_count }
+    }
+
+    /// Get names of all columns (q's `cols`).
+    pub fn cols(&self) -> Vec<S> {
+        self.columns
+            .iter()
+            .map(|column| column.name.clone())
+            .collect()
+    }
+
+    /// Get names of key columns (q's `keys`).
+    pub fn keys(&self) -> Vec<S> {
+        self.columns[..self.key_count]
+            .iter()
+            .map(|column| column.name.clone())
+            .collect()
+    }
+}
+
+impl fmt::Display for Schema {
+    /// Display in the same layout as q's `meta`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self
+            .columns
+            .iter()
+            .map(|column| column.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        writeln!(f, "{:width$}| t f a", "c", width = width)?;
+        writeln!(f, "{}| -----", "-".repeat(width))?;
+        for column in self.columns.iter() {
+            writeln!(
+                f,
+                "{:width$}| {}   {}",
+                column.name,
+                column.type_char,
+                attribute_char(column.attribute),
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+//%% K %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl K {
+    /// Get a schema of a table or a keyed table (q's `meta`).
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let q_keyed_table = K::new_dictionary(
+    ///         K::new_symbol_list(vec![String::from("id"), String::from("name")], qattribute::NONE),
+    ///         K::new_compound_list(vec![
+    ///             K::new_long_list(vec![1, 2], qattribute::UNIQUE),
+    ///             K::new_compound_list(vec![
+    ///                 K::new_string(String::from("Alice"), qattribute::NONE),
+    ///                 K::new_string(String::from("Bob"), qattribute::NONE),
+    ///             ]),
+    ///         ]),
+    ///     )
+    ///     .unwrap()
+    ///     .flip()
+    ///     .unwrap()
+    ///     .enkey(1)
+    ///     .unwrap();
+    ///
+    ///     let schema = q_keyed_table.meta().unwrap();
+    ///     assert_eq!(schema.keys(), vec!["id"]);
+    ///     assert_eq!(
+    ///         format!("{}", schema),
+    ///         String::from("c   | t f a\n----| -----\nid  | j   u\nname| C    \n")
+    ///     );
+    /// }
+    /// ```
+    pub fn meta(&self) -> Result<Schema> {
+        let (keys, values) = self.key_value_tables("meta")?;
+        let mut columns = Vec::new();
+        for table in keys.iter().chain(std::iter::once(&values)) {
+            for name in table.get_dictionary().unwrap().as_vec::<K>().unwrap()[0]
+                .as_vec::<S>()
+                .unwrap()
+            {
+                let column = self.get_column(name)?;
+                columns.push(ColumnMeta {
+                    name: name.clone(),
+                    type_char: column_type_char(column),
+                    attribute: column.get_attribute(),
+                });
+            }
+        }
+        let key_count = keys.map_or(0, |keys| {
+            keys.get_dictionary().unwrap().as_vec::<K>().unwrap()[0].len()
+        });
+        Ok(Schema { columns, key_count })
+    }
+
+    /// Get names of all columns of a table or a keyed table (q's `cols`).
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let q_table = K::new_dictionary(
+    ///         K::new_symbol_list(vec![String::from("a"), String::from("b")], qattribute::NONE),
+    ///         K::new_compound_list(vec![
+    ///             K::new_long_list(vec![1], qattribute::NONE),
+    ///             K::new_float_list(vec![1.5], qattribute::NONE),
+    ///         ]),
+    ///     )
+    ///     .unwrap()
+    ///     .flip()
+    ///     .unwrap();
+    ///     assert_eq!(q_table.cols().unwrap(), vec!["a", "b"]);
+    /// }
+    /// ```
+    pub fn cols(&self) -> Result<Vec<S>> {
+        let (keys, values) = self.key_value_tables("cols")?;
+        Ok(keys
+            .iter()
+            .chain(std::iter::once(&values))
+            .flat_map(|table| {
+                table.get_dictionary().unwrap().as_vec::<K>().unwrap()[0]
+                    .as_vec::<S>()
+                    .unwrap()
+                    .clone()
+            })
+            .collect())
+    }
+
+    /// Get names of key columns of a table or a keyed table (q's `keys`). Empty for a table which is not keyed.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let q_table = K::new_dictionary(
+    ///         K::new_symbol_list(vec![String::from("a"), String::from("b")], qattribute::NONE),
+    ///         K::new_compound_list(vec![
+    ///             K::new_long_list(vec![1], qattribute::NONE),
+    ///             K::new_float_list(vec![1.5], qattribute::NONE),
+    ///         ]),
+    ///     )
+    ///     .unwrap()
+    ///     .flip()
+    ///     .unwrap();
+    ///     assert!(q_table.keys().unwrap().is_empty());
+    ///     assert_eq!(q_table.enkey(1).unwrap().keys().unwrap(), vec!["a"]);
+    /// }
+    /// ```
+    pub fn keys(&self) -> Result<Vec<S>> {
+        let (keys, _) = self.key_value_tables("keys")?;
+        Ok(keys.map_or(Vec::new(), |keys| {
+            keys.get_dictionary().unwrap().as_vec::<K>().unwrap()[0]
+                .as_vec::<S>()
+                .unwrap()
+                .clone()
+        }))
+    }
+
+    /// Check if a table or a keyed table has an expected schema. Columns must appear in the same order with the
+    ///  same type characters. An attribute is checked only when it is specified in the expected schema. The
+    ///  returned error describes the first mismatch.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let q_table = K::new_dictionary(
+    ///         K::new_symbol_list(vec![String::from("time"), String::from("sym")], qattribute::NONE),
+    ///         K::new_compound_list(vec![
+    ///             K::new_temporal_list(vec![QTimestamp::from_raw(0)], qattribute::NONE),
+    ///             K::new_symbol_list(vec![String::from("AAPL")], qattribute::NONE),
+    ///         ]),
+    ///     )
+    ///     .unwrap()
+    ///     .flip()
+    ///     .unwrap();
+    ///
+    ///     let expected = Schema::new(vec![
+    ///         ColumnMeta::new("time", 'p').attribute(qattribute::SORTED),
+    ///         ColumnMeta::new("sym", 's'),
+    ///     ]);
+    ///     assert_eq!(
+    ///         q_table.conforms_to(&expected).unwrap_err().to_string(),
+    ///         String::from("schema mismatch: column `time has attribute none but expected `s#")
+    ///     );
+    /// }
+    /// ```
+    pub fn conforms_to(&self, schema: &Schema) -> Result<()> {
+        let actual = self.meta()?;
+        if actual.key_count != schema.key_count {
+            return Err(Error::schema_mismatch(format!(
+                "{} key columns but expected {}",
+                actual.key_count, schema.key_count
+            )));
+        }
+        for (i, expected) in schema.columns.iter().enumerate() {
+            let column = match actual.columns.get(i) {
+                Some(column) => column,
+                None => {
+                    return Err(Error::schema_mismatch(format!(
+                        "missing column `{}",
+                        expected.name
+                    )))
+                }
+            };
+            if column.name != expected.name {
+                return Err(Error::schema_mismatch(format!(
+                    "column {} is `{} but expected `{}",
+                    i, column.name, expected.name
+                )));
+            }
+            if column.type_char != expected.type_char {
+                return Err(Error::schema_mismatch(format!(
+                    "column `{} has type {} but expected {}",
+                    column.name, column.type_char, expected.type_char
+                )));
+            }
+            if expected.attribute != qattribute::NONE && column.attribute != expected.attribute {
+                return Err(Error::schema_mismatch(format!(
+                    "column `{} has attribute {} but expected {}",
+                    column.name,
+                    attribute_name(column.attribute),
+                    attribute_name(expected.attribute)
+                )));
+            }
+        }
+        match actual.columns.get(schema.columns.len()) {
+            Some(extra) => Err(Error::schema_mismatch(format!(
+                "unexpected column `{}",
+                extra.name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Split a table into an optional key table and a value table. A keyed table may have sorted keys.
+    fn key_value_tables(&self, operator: &'static str) -> Result<(Option<&K>, &K)> {
+        match self.0.qtype {
+            qtype::TABLE => Ok((None, self)),
+            qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+                let key_value = self.as_vec::<K>().unwrap();
+                if key_value[0].0.qtype == qtype::TABLE && key_value[1].0.qtype == qtype::TABLE {
+                    Ok((Some(&key_value[0]), &key_value[1]))
+                } else {
+                    Err(Error::invalid_operation(
+                        operator,
+                        self.0.qtype,
+                        Some(qtype::TABLE),
+                    ))
+                }
+            }
+            _ => Err(Error::invalid_operation(
+                operator,
+                self.0.qtype,
+                Some(qtype::TABLE),
+            )),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Get a type character of a list type.
+fn list_type_char(qtype: i8) -> char {
+    match qtype {
+        qtype::BOOL_LIST => 'b',
+        qtype::GUID_LIST => 'g',
+        qtype::BYTE_LIST => 'x',
+        qtype::SHORT_LIST => 'h',
+        qtype::INT_LIST => 'i',
+        qtype::LONG_LIST => 'j',
+        qtype::REAL_LIST => 'e',
+        qtype::FLOAT_LIST => 'f',
+        qtype::STRING => 'c',
+        qtype::SYMBOL_LIST => 's',
+        qtype::TIMESTAMP_LIST => 'p',
+        qtype::MONTH_LIST => 'm',
+        qtype::DATE_LIST => 'd',
+        qtype::DATETIME_LIST => 'z',
+        qtype::TIMESPAN_LIST => 'n',
+        qtype::MINUTE_LIST => 'u',
+        qtype::SECOND_LIST => 'v',
+        qtype::TIME_LIST => 't',
+        _ => ' ',
+    }
+}
+
+/// Get a type character of a column. A compound column is typed by its first element as q does.
+fn column_type_char(column: &K) -> char {
+    match column.get_type() {
+        qtype::COMPOUND_LIST => match column.as_vec::<K>().unwrap().first() {
+            Some(first) => list_type_char(first.get_type()).to_ascii_uppercase(),
+            None => ' ',
+        },
+        qtype => list_type_char(qtype),
+    }
+}
+
+/// Get a character of an attribute displayed by q's `meta`.
+fn attribute_char(attribute: i8) -> char {
+    match attribute {
+        qattribute::SORTED => 's',
+        qattribute::UNIQUE => 'u',
+        qattribute::PARTED => 'p',
+        qattribute::GROUPED => 'g',
+        _ => ' ',
+    }
+}
+
+/// Get a name of an attribute used in an error message.
+fn attribute_name(attribute: i8) -> String {
+    match attribute {
+        qattribute::NONE => String::from("none"),
+        _ => format!("`{}#", attribute_char(attribute)),
+    }
+}
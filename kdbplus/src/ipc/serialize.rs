@@ -79,6 +79,7 @@ fn serialize_q(obj: &K, stream: &mut Vec<u8>) {
         qtype::TABLE => serialize_table(obj, stream),
         qtype::DICTIONARY | qtype::SORTED_DICTIONARY => serialize_dictionary(obj, stream),
         qtype::NULL => serialize_null(stream),
+        qtype::ERROR => serialize_error(obj, stream),
         _ => unimplemented!(),
     };
 }
@@ -408,3 +409,12 @@ fn serialize_null(stream: &mut Vec<u8>) {
     // Data
     stream.push(0x00);
 }
+
+fn serialize_error(error: &K, stream: &mut Vec<u8>) {
+    // Type
+    stream.push(0x80);
+    // Message
+    stream.extend_from_slice(error.get_error_string().unwrap().as_bytes());
+    // Null byte
+    stream.push(0x00);
+}
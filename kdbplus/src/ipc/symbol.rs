@@ -0,0 +1,366 @@
+//! This module provides an interned q symbol type `Symbol` and a pool to intern symbols.
+//!
+//! A symbol is a reference-counted immutable string. Cloning a symbol does not allocate and two symbols
+//!  interned by the same pool share the same buffer, so that comparing them is a pointer comparison.
+//!  Symbol lists deserialized from a q process and built by [`K::new_symbol_list`](struct.K.html#method.new_symbol_list)
+//!  intern their elements within the list, i.e., a symbol column of a table with millions of rows allocates
+//!  a string only once for each distinct symbol.
+//!
+//! Long-lived processes such as a subscriber of a tickerplant can opt in to a global pool with
+//!  [`SymbolPool::enable_global`](struct.SymbolPool.html#method.enable_global) so that symbols are shared
+//!  across messages as well.
+//!
+//! # Example
+//! ```
+//! use kdbplus::qattribute;
+//! use kdbplus::ipc::*;
+//!
+//! fn main() {
+//!     let q_symbol_list = K::new_symbol_list(
+//!         vec![String::from("Apple"), String::from("Banana"), String::from("Apple")],
+//!         qattribute::NONE,
+//!     );
+//!     let symbols = q_symbol_list.as_vec::<S>().unwrap();
+//!     assert_eq!(symbols[0], "Apple");
+//!     assert!(Symbol::ptr_eq(&symbols[0], &symbols[2]));
+//! }
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use once_cell::sync::Lazy;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Flag of whether the global pool is used.
+static GLOBAL_POOL_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Pool shared by the entire process.
+static GLOBAL_POOL: Lazy<Mutex<SymbolPool>> = Lazy::new(|| Mutex::new(SymbolPool::new()));
+
+/// Null symbol shared by all null symbols.
+static NULL_SYMBOL: Lazy<Symbol> = Lazy::new(|| Symbol(Arc::from("")));
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Symbol %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// q symbol. It dereferences to `str` and compares with `str` and `String`.
+/// # Example
+/// ```
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let symbol = Symbol::from("Pluto");
+///     assert_eq!(symbol, "Pluto");
+///     assert_eq!(symbol.len(), 5);
+///     assert_eq!(String::from(symbol), String::from("Pluto"));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+//%% SymbolPool %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Pool to intern symbols. Symbols interned by the same pool share a buffer.
+/// # Example
+/// ```
+/// use kdbplus::ipc::*;
+///
+/// fn main() {
+///     let mut pool = SymbolPool::new();
+///     let first = pool.intern("AAPL");
+///     let second = pool.intern("AAPL");
+///     assert!(Symbol::ptr_eq(&first, &second));
+///     assert_eq!(pool.len(), 1);
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SymbolPool {
+    symbols: HashSet<Symbol>,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Symbol %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Symbol {
+    /// Build a symbol. If the global pool is enabled, the symbol is interned by the pool.
+    pub fn new(symbol: &str) -> Self {
+        if symbol.is_empty() {
+            NULL_SYMBOL.clone()
+        } else if SymbolPool::is_global_enabled() {
+            GLOBAL_POOL.lock().unwrap().intern(symbol)
+        } else {
+            Symbol(Arc::from(symbol))
+        }
+    }
+
+    /// Get the underlying string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Check if two symbols share the same buffer.
+    pub fn ptr_eq(this: &Symbol, other: &Symbol) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl Default for Symbol {
+    /// Null symbol.
+    fn default() -> Self {
+        NULL_SYMBOL.clone()
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        // Interned symbols are compared by pointer.
+        Symbol::ptr_eq(self, other) || self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        &*self.0 == other.as_str()
+    }
+}
+
+impl PartialEq<Symbol> for String {
+    fn eq(&self, other: &Symbol) -> bool {
+        self.as_str() == &*other.0
+    }
+}
+
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool {
+        *self == &*other.0
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Symbol) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Symbol) -> Ordering {
+        if Symbol::ptr_eq(self, other) {
+            Ordering::Equal
+        } else {
+            self.0.cmp(&other.0)
+        }
+    }
+}
+
+impl Hash for Symbol {
+    // Hash the string to be consistent with `Borrow<str>`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.0)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(symbol: &str) -> Self {
+        Symbol::new(symbol)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(symbol: String) -> Self {
+        if symbol.is_empty() || SymbolPool::is_global_enabled() {
+            Symbol::new(&symbol)
+        } else {
+            Symbol(Arc::from(symbol))
+        }
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(symbol: &String) -> Self {
+        Symbol::new(symbol)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        String::from(&*symbol.0)
+    }
+}
+
+//%% SymbolPool %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl SymbolPool {
+    /// Build an empty pool.
+    pub fn new() -> Self {
+        SymbolPool {
+            symbols: HashSet::new(),
+        }
+    }
+
+    /// Get a symbol from the pool. If the pool does not have the symbol yet, it is added to the pool.
+    pub fn intern(&mut self, symbol: &str) -> Symbol {
+        if symbol.is_empty() {
+            return NULL_SYMBOL.clone();
+        }
+        match self.symbols.get(symbol) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned = Symbol(Arc::from(symbol));
+                self.symbols.insert(interned.clone());
+                interned
+            }
+        }
+    }
+
+    /// Get the number of distinct symbols in the pool.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Check if the pool has no symbol.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Remove symbols which are not used outside of the pool and return the number of removed symbols.
+    pub fn purge(&mut self) -> usize {
+        let size = self.symbols.len();
+        self.symbols
+            .retain(|symbol| Arc::strong_count(&symbol.0) > 1);
+        size - self.symbols.len()
+    }
+
+    /// Use the global pool for all symbols built afterwards, i.e., symbols deserialized from q and
+    ///  built by `K::new_symbol`, `K::new_symbol_list` or `Symbol::from`. This is useful for a long-lived
+    ///  process receiving the same symbols repeatedly, e.g., a subscriber of a tickerplant.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     SymbolPool::enable_global();
+    ///     let q_symbol = K::new_symbol(String::from("MSFT"));
+    ///     let q_symbol_list = K::new_symbol_list(vec![String::from("MSFT")], qattribute::NONE);
+    ///     assert_eq!(SymbolPool::global_len(), 1);
+    ///     SymbolPool::disable_global();
+    /// }
+    /// ```
+    pub fn enable_global() {
+        GLOBAL_POOL_ENABLED.store(true, AtomicOrdering::Release);
+    }
+
+    /// Stop using the global pool and release symbols held by the pool.
+    pub fn disable_global() {
+        GLOBAL_POOL_ENABLED.store(false, AtomicOrdering::Release);
+        *GLOBAL_POOL.lock().unwrap() = SymbolPool::new();
+    }
+
+    /// Check if the global pool is enabled.
+    pub fn is_global_enabled() -> bool {
+        GLOBAL_POOL_ENABLED.load(AtomicOrdering::Acquire)
+    }
+
+    /// Get the number of distinct symbols in the global pool.
+    pub fn global_len() -> usize {
+        GLOBAL_POOL.lock().unwrap().len()
+    }
+
+    /// Remove symbols which are not used outside of the global pool and return the number of removed symbols.
+    pub fn purge_global() -> usize {
+        GLOBAL_POOL.lock().unwrap().purge()
+    }
+
+    /// Build a symbol list interning symbols with a new pool. If the global pool is enabled, the symbols are
+    ///  then replaced with those of the global pool, which is locked once for the distinct symbols instead of
+    ///  during the whole build.
+    pub(crate) fn intern_list<F>(build: F) -> Vec<Symbol>
+    where
+        F: FnOnce(&mut SymbolPool) -> Vec<Symbol>,
+    {
+        let mut pool = SymbolPool::new();
+        let mut list = build(&mut pool);
+        if SymbolPool::is_global_enabled() {
+            let mut global = GLOBAL_POOL.lock().unwrap();
+            let interned = pool
+                .symbols
+                .into_iter()
+                .map(|symbol| {
+                    let interned = global.intern(&symbol);
+                    (symbol, interned)
+                })
+                .collect::<HashMap<Symbol, Symbol>>();
+            drop(global);
+            for symbol in list.iter_mut() {
+                if let Some(interned) = interned.get(symbol.as_str()) {
+                    *symbol = interned.clone();
+                }
+            }
+        }
+        list
+    }
+}
@@ -0,0 +1,1020 @@
+//! This module provides typed q temporal values which keep q-native representation (elapsed time since kdb+ epoch
+//!  `2000.01.01`) together with null and infinity states. Arithmetic follows q semantics: null propagates and
+//!  overflow wraps like q integers (e.g. `0Wd + 1` is `0Nd`).
+//!
+//! | q           | Rust          | Raw value                                          |
+//! |-------------|---------------|----------------------------------------------------|
+//! | `timestamp` | `QTimestamp`  | `i64` nanoseconds since `2000.01.01D00:00:00`      |
+//! | `month`     | `QMonth`      | `i32` months since `2000.01m`                      |
+//! | `date`      | `QDate`       | `i32` days since `2000.01.01`                      |
+//! | `datetime`  | `QDateTime`   | `f64` days since `2000.01.01T00:00:00.000`         |
+//! | `timespan`  | `QTimespan`   | `i64` nanoseconds                                  |
+//! | `minute`    | `QMinute`     | `i32` minutes                                      |
+//! | `second`    | `QSecond`     | `i32` seconds                                      |
+//! | `time`      | `QTime`       | `i32` milliseconds                                 |
+//!
+//! # Example
+//! ```
+//! use kdbplus::ipc::*;
+//!
+//! fn main() {
+//!     let date: QDate = "2020.02.28".parse().unwrap();
+//!     assert_eq!(format!("{}", date + 2), String::from("2020.03.01"));
+//!
+//!     let start: QTimestamp = "2020.02.28D09:30:00.000000000".parse().unwrap();
+//!     let end = date + "0D16:00:00.000000000".parse::<QTimespan>().unwrap();
+//!     assert_eq!(format!("{}", end - start), String::from("0D06:30:00.000000000"));
+//!
+//!     assert!((QDate::NULL + 1).is_null());
+//! }
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::*;
+use std::cmp::Ordering;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% QTemporal %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+mod private {
+    use super::K;
+
+    /// Conversion between a temporal value and `K` object. Not exposed to prevent implementation outside of this crate.
+    pub trait Sealed: Sized {
+        fn into_atom(self) -> K;
+        fn from_atom(object: &K) -> Option<Self>;
+        fn into_list(list: Vec<Self>, attribute: i8) -> K;
+        fn from_list(object: &K) -> Option<Vec<Self>>;
+    }
+}
+
+/// Common feature of q temporal types which can be stored in `K` object.
+pub trait QTemporal: Copy + private::Sealed {
+    /// Underlying q value.
+    type Raw: Copy;
+    /// q type indicator of the atom.
+    const QTYPE: i8;
+    /// Build from an underlying q value.
+    fn from_raw(raw: Self::Raw) -> Self;
+    /// Get an underlying q value.
+    fn raw(self) -> Self::Raw;
+    /// Check if the value is null.
+    fn is_null(self) -> bool;
+    /// Check if the value is positive or negative infinity.
+    fn is_infinite(self) -> bool;
+}
+
+//%% Temporal Types %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// q timestamp. Elapsed time in nanoseconds since `2000.01.01D00:00:00`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QTimestamp(J);
+
+/// q month. Elapsed time in months since `2000.01m`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QMonth(I);
+
+/// q date. Elapsed time in days since `2000.01.01`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QDate(I);
+
+/// q datetime. Elapsed time in days with granularity of milliseconds since `2000.01.01T00:00:00.000`.
+/// # Note
+/// As q does, null datetimes are equal to each other and less than any other datetime.
+#[derive(Clone, Copy, Debug)]
+pub struct QDateTime(F);
+
+/// q timespan. Duration in nanoseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QTimespan(J);
+
+/// q minute. Duration in minutes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QMinute(I);
+
+/// q second. Duration in seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QSecond(I);
+
+/// q time. Duration in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QTime(I);
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Implement null/infinity constants, `QTemporal` and `K` conversion for a temporal type.
+macro_rules! impl_temporal {
+    ($name: ident, $raw: ty, $base: ident, $atom: expr, $list: expr, $holder: ident) => {
+        impl $name {
+            /// Null value.
+            pub const NULL: $name = $name(qnull_base::$base);
+            /// Positive infinity.
+            pub const INF: $name = $name(qinf_base::$base);
+            /// Negative infinity.
+            pub const NINF: $name = $name(qninf_base::$base);
+
+            /// Build from an underlying q value.
+            pub const fn from_raw(raw: $raw) -> Self {
+                $name(raw)
+            }
+
+            /// Get an underlying q value.
+            pub const fn raw(self) -> $raw {
+                self.0
+            }
+        }
+
+        impl QTemporal for $name {
+            type Raw = $raw;
+            const QTYPE: i8 = $atom;
+
+            fn from_raw(raw: $raw) -> Self {
+                $name(raw)
+            }
+
+            fn raw(self) -> $raw {
+                self.0
+            }
+
+            fn is_null(self) -> bool {
+                is_null_raw!($base, self.0)
+            }
+
+            fn is_infinite(self) -> bool {
+                is_infinite_raw!($base, self.0)
+            }
+        }
+
+        impl private::Sealed for $name {
+            fn into_atom(self) -> K {
+                K::new($atom, qattribute::NONE, k0_inner::$holder(self.0))
+            }
+
+            fn from_atom(object: &K) -> Option<Self> {
+                match &object.0.value {
+                    k0_inner::$holder(raw) if object.0.qtype == $atom => Some($name(*raw)),
+                    _ => None,
+                }
+            }
+
+            fn into_list(list: Vec<Self>, attribute: i8) -> K {
+                let array = list
+                    .into_iter()
+                    .map(|element| element.0)
+                    .collect::<Vec<$raw>>();
+                K::new($list, attribute, k0_inner::list(k0_list::new(array)))
+            }
+
+            fn from_list(object: &K) -> Option<Vec<Self>> {
+                if object.0.qtype == $list {
+                    object
+                        .as_vec::<$raw>()
+                        .ok()
+                        .map(|vector| vector.iter().map(|raw| $name(*raw)).collect())
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl From<$name> for K {
+            fn from(value: $name) -> K {
+                private::Sealed::into_atom(value)
+            }
+        }
+    };
+}
+
+/// Check if a raw value is null.
+macro_rules! is_null_raw {
+    (F, $value: expr) => {
+        $value.is_nan()
+    };
+    ($base: ident, $value: expr) => {
+        $value == qnull_base::$base
+    };
+}
+
+/// Check if a raw value is positive or negative infinity.
+macro_rules! is_infinite_raw {
+    (F, $value: expr) => {
+        $value.is_infinite()
+    };
+    ($base: ident, $value: expr) => {
+        $value == qinf_base::$base || $value == qninf_base::$base
+    };
+}
+
+/// Implement `+ offset` and `- offset` where offset is an integer of the same width as the underlying value.
+macro_rules! impl_offset {
+    ($name: ident, $raw: ty, $add: ident, $sub: ident) => {
+        impl Add<$raw> for $name {
+            type Output = $name;
+            fn add(self, rhs: $raw) -> $name {
+                $name($add(self.0, rhs))
+            }
+        }
+
+        impl Sub<$raw> for $name {
+            type Output = $name;
+            fn sub(self, rhs: $raw) -> $name {
+                $name($sub(self.0, rhs))
+            }
+        }
+
+        impl AddAssign<$raw> for $name {
+            fn add_assign(&mut self, rhs: $raw) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign<$raw> for $name {
+            fn sub_assign(&mut self, rhs: $raw) {
+                *self = *self - rhs;
+            }
+        }
+    };
+}
+
+/// Implement `+`, `-` and unary `-` between durations of the same type.
+macro_rules! impl_duration_arithmetic {
+    ($name: ident, $add: ident, $sub: ident) => {
+        impl Add for $name {
+            type Output = $name;
+            fn add(self, rhs: $name) -> $name {
+                $name($add(self.0, rhs.0))
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+            fn sub(self, rhs: $name) -> $name {
+                $name($sub(self.0, rhs.0))
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+            fn neg(self) -> $name {
+                // Wrapping negation keeps null (minimum value) as null and swaps infinities.
+                $name(self.0.wrapping_neg())
+            }
+        }
+    };
+}
+
+/// Implement conversion between a duration type and `chrono::Duration`.
+macro_rules! impl_duration_conversion {
+    ($name: ident, $raw: ty, $base: ident, $to_duration: ident, $num_units: expr) => {
+        impl $name {
+            /// Convert into `Duration`. Returns `None` for null and infinity.
+            pub fn to_duration(self) -> Option<Duration> {
+                if QTemporal::is_null(self) || QTemporal::is_infinite(self) {
+                    None
+                } else {
+                    Some($to_duration(self.0))
+                }
+            }
+        }
+
+        impl From<Duration> for $name {
+            /// Truncate `Duration` to the granularity of the type. Returns null if the value overflows.
+            fn from(duration: Duration) -> $name {
+                let num_units: fn(Duration) -> Option<i64> = $num_units;
+                $name(
+                    num_units(duration)
+                        .and_then(|units| <$raw>::try_from(units).ok())
+                        .unwrap_or(qnull_base::$base),
+                )
+            }
+        }
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Common %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl_temporal!(
+    QTimestamp,
+    J,
+    J,
+    qtype::TIMESTAMP_ATOM,
+    qtype::TIMESTAMP_LIST,
+    long
+);
+impl_temporal!(QMonth, I, I, qtype::MONTH_ATOM, qtype::MONTH_LIST, int);
+impl_temporal!(QDate, I, I, qtype::DATE_ATOM, qtype::DATE_LIST, int);
+impl_temporal!(
+    QDateTime,
+    F,
+    F,
+    qtype::DATETIME_ATOM,
+    qtype::DATETIME_LIST,
+    float
+);
+impl_temporal!(
+    QTimespan,
+    J,
+    J,
+    qtype::TIMESPAN_ATOM,
+    qtype::TIMESPAN_LIST,
+    long
+);
+impl_temporal!(QMinute, I, I, qtype::MINUTE_ATOM, qtype::MINUTE_LIST, int);
+impl_temporal!(QSecond, I, I, qtype::SECOND_ATOM, qtype::SECOND_LIST, int);
+impl_temporal!(QTime, I, I, qtype::TIME_ATOM, qtype::TIME_LIST, int);
+
+impl_offset!(QTimestamp, J, add_long, sub_long);
+impl_offset!(QMonth, I, add_int, sub_int);
+impl_offset!(QDate, I, add_int, sub_int);
+impl_offset!(QTimespan, J, add_long, sub_long);
+impl_offset!(QMinute, I, add_int, sub_int);
+impl_offset!(QSecond, I, add_int, sub_int);
+impl_offset!(QTime, I, add_int, sub_int);
+
+impl_duration_arithmetic!(QTimespan, add_long, sub_long);
+impl_duration_arithmetic!(QMinute, add_int, sub_int);
+impl_duration_arithmetic!(QSecond, add_int, sub_int);
+impl_duration_arithmetic!(QTime, add_int, sub_int);
+
+impl_duration_conversion!(QTimespan, J, J, q_timespan_to_duration, |duration| {
+    duration.num_nanoseconds()
+});
+impl_duration_conversion!(QMinute, I, I, q_minute_to_duration, |duration| {
+    Some(duration.num_minutes())
+});
+impl_duration_conversion!(QSecond, I, I, q_second_to_duration, |duration| {
+    Some(duration.num_seconds())
+});
+impl_duration_conversion!(QTime, I, I, q_time_to_duration, |duration| {
+    Some(duration.num_milliseconds())
+});
+
+//%% QTimestamp %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QTimestamp {
+    /// Convert into `DateTime<Utc>`. Returns `None` for null and infinity.
+    /// # Example
+    /// ```
+    /// use kdbplus::ipc::*;
+    /// use chrono::prelude::*;
+    ///
+    /// fn main() {
+    ///     let timestamp: QTimestamp = "2001.02.03D04:05:06.007008009".parse().unwrap();
+    ///     assert_eq!(
+    ///         timestamp.to_datetime(),
+    ///         NaiveDate::from_ymd_opt(2001, 2, 3)
+    ///             .unwrap()
+    ///             .and_hms_nano_opt(4, 5, 6, 7008009)
+    ///             .map(|datetime| datetime.and_utc())
+    ///     );
+    ///     assert_eq!(QTimestamp::NULL.to_datetime(), None);
+    /// }
+    /// ```
+    pub fn to_datetime(self) -> Option<DateTime<Utc>> {
+        if self.is_null() || self.is_infinite() {
+            None
+        } else {
+            Some(q_timestamp_to_datetime(self.0))
+        }
+    }
+
+    /// Date part of the timestamp (`` `date$ `` in q).
+    pub fn date(self) -> QDate {
+        if self.is_null() {
+            QDate::NULL
+        } else if self.0 == qinf_base::J {
+            QDate::INF
+        } else if self.0 == qninf_base::J {
+            QDate::NINF
+        } else {
+            QDate(self.0.div_euclid(ONE_DAY_NANOS) as I)
+        }
+    }
+
+    /// Time of the day as timespan (`` `timespan$ `` in q).
+    pub fn timespan(self) -> QTimespan {
+        if self.is_null() || self.is_infinite() {
+            QTimespan(self.0)
+        } else {
+            QTimespan(self.0.rem_euclid(ONE_DAY_NANOS))
+        }
+    }
+}
+
+impl From<DateTime<Utc>> for QTimestamp {
+    fn from(datetime: DateTime<Utc>) -> Self {
+        QTimestamp(datetime_to_q_timestamp(datetime))
+    }
+}
+
+impl From<QDate> for QTimestamp {
+    /// Midnight of the date. Infinity is kept and a date out of the range of timestamp becomes infinity.
+    fn from(date: QDate) -> Self {
+        if date.is_null() {
+            QTimestamp::NULL
+        } else if date.0 == qinf_base::I {
+            QTimestamp::INF
+        } else if date.0 == qninf_base::I {
+            QTimestamp::NINF
+        } else {
+            match (date.0 as J).checked_mul(ONE_DAY_NANOS) {
+                Some(nanos) if nanos > qninf_base::J && nanos < qinf_base::J => QTimestamp(nanos),
+                _ if date.0 < 0 => QTimestamp::NINF,
+                _ => QTimestamp::INF,
+            }
+        }
+    }
+}
+
+impl Add<QTimespan> for QTimestamp {
+    type Output = QTimestamp;
+    fn add(self, rhs: QTimespan) -> QTimestamp {
+        QTimestamp(add_long(self.0, rhs.0))
+    }
+}
+
+impl Sub<QTimespan> for QTimestamp {
+    type Output = QTimestamp;
+    fn sub(self, rhs: QTimespan) -> QTimestamp {
+        QTimestamp(sub_long(self.0, rhs.0))
+    }
+}
+
+impl Sub for QTimestamp {
+    type Output = QTimespan;
+    fn sub(self, rhs: QTimestamp) -> QTimespan {
+        QTimespan(sub_long(self.0, rhs.0))
+    }
+}
+
+impl FromStr for QTimestamp {
+    type Err = Error;
+
+    /// Parse q timestamp literal such as `2020.01.02D03:04:05.123456789`, `2020.01.02D03:04`, `2020.01.02` or `0Np`.
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.strip_suffix('p').unwrap_or(text);
+        if let Some(special) = parse_special(text) {
+            return Ok(QTimestamp(special.long()));
+        }
+        let (date, time) = match text.split_once('D') {
+            Some((date, time)) => (date, time),
+            None => (text, ""),
+        };
+        let days = parse_date(date)? as J;
+        let nanos = match time {
+            "" => 0,
+            _ => parse_time_of_day(time)?,
+        };
+        days.checked_mul(ONE_DAY_NANOS)
+            .and_then(|base| base.checked_add(nanos))
+            .filter(|nanos| *nanos > qninf_base::J && *nanos < qinf_base::J)
+            .map(QTimestamp)
+            .ok_or(Error::InvalidDateTime)
+    }
+}
+
+//%% QMonth %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QMonth {
+    /// Convert into the first day of the month. Returns `None` for null and infinity.
+    /// # Example
+    /// ```
+    /// use kdbplus::ipc::*;
+    /// use chrono::NaiveDate;
+    ///
+    /// fn main() {
+    ///     let month: QMonth = "1999.12m".parse().unwrap();
+    ///     assert_eq!(month.to_naive_date(), NaiveDate::from_ymd_opt(1999, 12, 1));
+    ///     assert_eq!(format!("{}", month + 1), String::from("2000.01m"));
+    /// }
+    /// ```
+    pub fn to_naive_date(self) -> Option<NaiveDate> {
+        if self.is_null() || self.is_infinite() {
+            None
+        } else {
+            NaiveDate::from_ymd_opt(
+                2000 + self.0.div_euclid(12),
+                1 + self.0.rem_euclid(12) as u32,
+                1,
+            )
+        }
+    }
+}
+
+impl From<NaiveDate> for QMonth {
+    fn from(date: NaiveDate) -> Self {
+        QMonth(date_to_q_month(date))
+    }
+}
+
+impl Sub for QMonth {
+    type Output = I;
+    /// Number of months between two months.
+    fn sub(self, rhs: QMonth) -> I {
+        sub_int(self.0, rhs.0)
+    }
+}
+
+impl FromStr for QMonth {
+    type Err = Error;
+
+    /// Parse q month literal such as `2020.01m`, `2020.01` or `0Nm`.
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.strip_suffix('m').unwrap_or(text);
+        if let Some(special) = parse_special(text) {
+            return Ok(QMonth(special.int()));
+        }
+        let (year, month) = text.split_once('.').ok_or(Error::InvalidDateTime)?;
+        let year = parse_digits(year, 4)? as I;
+        let month = parse_digits(month, 2)? as I;
+        if (1..=12).contains(&month) {
+            Ok(QMonth((year - 2000) * 12 + month - 1))
+        } else {
+            Err(Error::InvalidDateTime)
+        }
+    }
+}
+
+//%% QDate %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QDate {
+    /// Convert into `NaiveDate`. Returns `None` for null and infinity.
+    /// # Example
+    /// ```
+    /// use kdbplus::ipc::*;
+    /// use chrono::NaiveDate;
+    ///
+    /// fn main() {
+    ///     let date = QDate::from(NaiveDate::from_ymd_opt(2020, 2, 28).unwrap());
+    ///     assert_eq!((date + 1).to_naive_date(), NaiveDate::from_ymd_opt(2020, 2, 29));
+    ///     assert_eq!(QDate::INF.to_naive_date(), None);
+    /// }
+    /// ```
+    pub fn to_naive_date(self) -> Option<NaiveDate> {
+        if self.is_null() || self.is_infinite() {
+            None
+        } else {
+            NaiveDate::from_ymd_opt(2000, 1, 1)?.checked_add_signed(Duration::days(self.0 as i64))
+        }
+    }
+
+    /// Month of the date (`` `month$ `` in q).
+    pub fn month(self) -> QMonth {
+        if self.is_null() || self.is_infinite() {
+            QMonth(self.0)
+        } else {
+            self.to_naive_date()
+                .map(QMonth::from)
+                .unwrap_or(QMonth::NULL)
+        }
+    }
+}
+
+impl From<NaiveDate> for QDate {
+    fn from(date: NaiveDate) -> Self {
+        QDate(date_to_q_date(date))
+    }
+}
+
+impl Sub for QDate {
+    type Output = I;
+    /// Number of days between two dates.
+    fn sub(self, rhs: QDate) -> I {
+        sub_int(self.0, rhs.0)
+    }
+}
+
+impl Add<QTimespan> for QDate {
+    type Output = QTimestamp;
+    /// Timestamp of the date plus timespan as q does for `date + timespan`.
+    fn add(self, rhs: QTimespan) -> QTimestamp {
+        QTimestamp::from(self) + rhs
+    }
+}
+
+impl FromStr for QDate {
+    type Err = Error;
+
+    /// Parse q date literal such as `2020.01.02` or `0Nd`.
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.strip_suffix('d').unwrap_or(text);
+        if let Some(special) = parse_special(text) {
+            return Ok(QDate(special.int()));
+        }
+        parse_date(text).map(QDate)
+    }
+}
+
+//%% QDateTime %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl QDateTime {
+    /// Convert into `DateTime<Utc>` rounding to milliseconds. Returns `None` for null and infinity.
+    /// # Example
+    /// ```
+    /// use kdbplus::ipc::*;
+    /// use chrono::prelude::*;
+    ///
+    /// fn main() {
+    ///     let datetime: QDateTime = "2000.01.01T12:00:00.000".parse().unwrap();
+    ///     assert_eq!(datetime.raw(), 0.5);
+    ///     assert_eq!(
+    ///         datetime.to_datetime(),
+    ///         NaiveDate::from_ymd_opt(2000, 1, 1)
+    ///             .unwrap()
+    ///             .and_hms_opt(12, 0, 0)
+    ///             .map(|datetime| datetime.and_utc())
+    ///     );
+    /// }
+    /// ```
+    pub fn to_datetime(self) -> Option<DateTime<Utc>> {
+        if self.is_null() || self.is_infinite() {
+            None
+        } else {
+            let millis = (self.0 * ONE_DAY_MILLIS as f64).round() as i64;
+            Utc.timestamp_millis_opt(millis.checked_add(KDB_DAY_OFFSET as i64 * ONE_DAY_MILLIS)?)
+                .single()
+        }
+    }
+}
+
+impl From<DateTime<Utc>> for QDateTime {
+    fn from(datetime: DateTime<Utc>) -> Self {
+        QDateTime(datetime_to_q_datetime(datetime))
+    }
+}
+
+impl PartialEq for QDateTime {
+    fn eq(&self, other: &Self) -> bool {
+        (self.is_null() && other.is_null()) || self.0 == other.0
+    }
+}
+
+impl PartialOrd for QDateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.is_null(), other.is_null()) {
+            (true, true) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (false, false) => self.0.partial_cmp(&other.0),
+        }
+    }
+}
+
+impl Add<F> for QDateTime {
+    type Output = QDateTime;
+    fn add(self, rhs: F) -> QDateTime {
+        QDateTime(self.0 + rhs)
+    }
+}
+
+impl Sub<F> for QDateTime {
+    type Output = QDateTime;
+    fn sub(self, rhs: F) -> QDateTime {
+        QDateTime(self.0 - rhs)
+    }
+}
+
+impl Sub for QDateTime {
+    type Output = F;
+    /// Number of days between two datetimes.
+    fn sub(self, rhs: QDateTime) -> F {
+        self.0 - rhs.0
+    }
+}
+
+impl FromStr for QDateTime {
+    type Err = Error;
+
+    /// Parse q datetime literal such as `2020.01.02T03:04:05.006`, `2020.01.02` or `0Nz`.
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.strip_suffix('z').unwrap_or(text);
+        if let Some(special) = parse_special(text) {
+            return Ok(QDateTime(special.float()));
+        }
+        let (date, time) = match text.split_once('T') {
+            Some((date, time)) => (date, time),
+            None => (text, ""),
+        };
+        let days = parse_date(date)? as F;
+        let millis = match time {
+            "" => 0,
+            _ => parse_time_of_day(time)? / 1_000_000,
+        };
+        Ok(QDateTime(days + millis as F / ONE_DAY_MILLIS as F))
+    }
+}
+
+//%% QTimespan %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl Mul<J> for QTimespan {
+    type Output = QTimespan;
+    fn mul(self, rhs: J) -> QTimespan {
+        if self.is_null() || rhs == qnull_base::J {
+            QTimespan::NULL
+        } else {
+            QTimespan(self.0.wrapping_mul(rhs))
+        }
+    }
+}
+
+impl FromStr for QTimespan {
+    type Err = Error;
+
+    /// Parse q timespan literal such as `1D02:03:04.000000005`, `-0D00:01`, `12:00:00` or `0Nn`.
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.strip_suffix('n').unwrap_or(text);
+        if let Some(special) = parse_special(text) {
+            return Ok(QTimespan(special.long()));
+        }
+        let (negative, text) = split_sign(text);
+        let (days, time) = match text.split_once('D') {
+            Some((days, time)) => (parse_digits(days, 0)?, time),
+            None => (0, text),
+        };
+        let nanos = match time {
+            "" => 0,
+            _ => parse_time_of_day(time)?,
+        };
+        days.checked_mul(ONE_DAY_NANOS)
+            .and_then(|base| base.checked_add(nanos))
+            .filter(|nanos| *nanos < qinf_base::J)
+            .map(|nanos| QTimespan(if negative { -nanos } else { nanos }))
+            .ok_or(Error::InvalidDateTime)
+    }
+}
+
+//%% QMinute / QSecond / QTime %%//vvvvvvvvvvvvvvvvvv/
+
+/// Implement `FromStr` for time-of-day like types by truncating parsed nanoseconds.
+macro_rules! impl_from_str_time {
+    ($name: ident, $suffix: expr, $nanos_per_unit: expr, $example: expr) => {
+        impl FromStr for $name {
+            type Err = Error;
+
+            #[doc = concat!("Parse q literal such as `", $example, "`, `-", $example, "` or `0N", $suffix, "`.")]
+            fn from_str(text: &str) -> Result<Self> {
+                let text = text.strip_suffix($suffix).unwrap_or(text);
+                if let Some(special) = parse_special(text) {
+                    return Ok($name(special.int()));
+                }
+                let (negative, text) = split_sign(text);
+                let units = I::try_from(parse_time_of_day(text)? / $nanos_per_unit)
+                    .ok()
+                    .filter(|units| *units < qinf_base::I)
+                    .ok_or(Error::InvalidDateTime)?;
+                Ok($name(if negative { -units } else { units }))
+            }
+        }
+    };
+}
+
+impl_from_str_time!(QMinute, 'u', 60_000_000_000, "12:34");
+impl_from_str_time!(QSecond, 'v', 1_000_000_000, "12:34:56");
+impl_from_str_time!(QTime, 't', 1_000_000, "12:34:56.789");
+
+//%% K %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl K {
+    /// Construct q temporal list from a vector of temporal values.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let start: QDate = "2020.01.31".parse().unwrap();
+    ///     let q_date_list = K::new_temporal_list(vec![start, start + 1, QDate::INF], qattribute::SORTED);
+    ///     assert_eq!(
+    ///         format!("{}", q_date_list),
+    ///         String::from("`s#2020.01.31 2020.02.01 0Wd")
+    ///     );
+    /// }
+    /// ```
+    pub fn new_temporal_list<T>(list: Vec<T>, attribute: i8) -> Self
+    where
+        T: QTemporal,
+    {
+        private::Sealed::into_list(list, attribute)
+    }
+
+    /// Get underlying temporal value. The type parameter must match the type of the atom.
+    /// # Example
+    /// ```
+    /// use kdbplus::ipc::*;
+    /// use chrono::Duration;
+    ///
+    /// fn main() {
+    ///     let q_minute = K::new_minute(Duration::minutes(90));
+    ///     assert_eq!(q_minute.get_temporal::<QMinute>().unwrap().raw(), 90);
+    ///     assert!(q_minute.get_temporal::<QSecond>().is_err());
+    ///
+    ///     let q_date = K::from(QDate::NULL);
+    ///     assert!(q_date.get_temporal::<QDate>().unwrap().is_null());
+    /// }
+    /// ```
+    pub fn get_temporal<T>(&self) -> Result<T>
+    where
+        T: QTemporal,
+    {
+        T::from_atom(self).ok_or_else(|| Error::invalid_cast(self.0.qtype, T::QTYPE))
+    }
+
+    /// Get underlying temporal values of a list. The type parameter must match the type of the list.
+    /// # Example
+    /// ```
+    /// use kdbplus::qattribute;
+    /// use kdbplus::ipc::*;
+    ///
+    /// fn main() {
+    ///     let q_time_list = K::new_temporal_list(
+    ///         vec![QTime::from_raw(1000), QTime::NULL],
+    ///         qattribute::NONE,
+    ///     );
+    ///     assert_eq!(
+    ///         q_time_list.get_temporal_list::<QTime>(),
+    ///         Ok(vec![QTime::from_raw(1000), QTime::NULL])
+    ///     );
+    /// }
+    /// ```
+    pub fn get_temporal_list<T>(&self) -> Result<Vec<T>>
+    where
+        T: QTemporal,
+    {
+        T::from_list(self).ok_or_else(|| Error::invalid_cast_list(self.0.qtype))
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Arithmetic %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Add two int values propagating null and wrapping on overflow as q does.
+fn add_int(lhs: I, rhs: I) -> I {
+    if lhs == qnull_base::I || rhs == qnull_base::I {
+        qnull_base::I
+    } else {
+        lhs.wrapping_add(rhs)
+    }
+}
+
+/// Subtract two int values propagating null and wrapping on overflow as q does.
+fn sub_int(lhs: I, rhs: I) -> I {
+    if lhs == qnull_base::I || rhs == qnull_base::I {
+        qnull_base::I
+    } else {
+        lhs.wrapping_sub(rhs)
+    }
+}
+
+/// Add two long values propagating null and wrapping on overflow as q does.
+fn add_long(lhs: J, rhs: J) -> J {
+    if lhs == qnull_base::J || rhs == qnull_base::J {
+        qnull_base::J
+    } else {
+        lhs.wrapping_add(rhs)
+    }
+}
+
+/// Subtract two long values propagating null and wrapping on overflow as q does.
+fn sub_long(lhs: J, rhs: J) -> J {
+    if lhs == qnull_base::J || rhs == qnull_base::J {
+        qnull_base::J
+    } else {
+        lhs.wrapping_sub(rhs)
+    }
+}
+
+//%% Parser %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Null or infinity literal.
+enum Special {
+    Null,
+    Inf,
+    NInf,
+}
+
+impl Special {
+    fn int(self) -> I {
+        match self {
+            Special::Null => qnull_base::I,
+            Special::Inf => qinf_base::I,
+            Special::NInf => qninf_base::I,
+        }
+    }
+
+    fn long(self) -> J {
+        match self {
+            Special::Null => qnull_base::J,
+            Special::Inf => qinf_base::J,
+            Special::NInf => qninf_base::J,
+        }
+    }
+
+    fn float(self) -> F {
+        match self {
+            Special::Null => qnull_base::F,
+            Special::Inf => qinf_base::F,
+            Special::NInf => qninf_base::F,
+        }
+    }
+}
+
+/// Parse `0N`, `0W` and `-0W` (type suffix must be removed beforehand).
+fn parse_special(text: &str) -> Option<Special> {
+    match text {
+        "0N" => Some(Special::Null),
+        "0W" => Some(Special::Inf),
+        "-0W" => Some(Special::NInf),
+        _ => None,
+    }
+}
+
+/// Split a leading `-` from a text.
+fn split_sign(text: &str) -> (bool, &str) {
+    match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    }
+}
+
+/// Parse non-empty ASCII digits. If `width` is not 0, the number of digits must be `width`.
+fn parse_digits(text: &str, width: usize) -> Result<J> {
+    if text.is_empty()
+        || (width != 0 && text.len() != width)
+        || !text.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        Err(Error::InvalidDateTime)
+    } else {
+        text.parse::<J>().map_err(|_| Error::InvalidDateTime)
+    }
+}
+
+/// Parse `YYYY.MM.DD` into elapsed days since `2000.01.01`.
+fn parse_date(text: &str) -> Result<I> {
+    let mut parts = text.split('.');
+    let (year, month, day) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(year), Some(month), Some(day), None) => (
+            parse_digits(year, 4)?,
+            parse_digits(month, 2)?,
+            parse_digits(day, 2)?,
+        ),
+        _ => return Err(Error::InvalidDateTime),
+    };
+    let date = NaiveDate::from_ymd_opt(year as i32, month as u32, day as u32)
+        .ok_or(Error::InvalidDateTime)?;
+    Ok(date_to_q_date(date))
+}
+
+/// Parse `hh[:mm[:ss[.fffffffff]]]` into nanoseconds. Hours can exceed 24 as q allows.
+fn parse_time_of_day(text: &str) -> Result<J> {
+    let (clock, fraction) = match text.split_once('.') {
+        Some((clock, fraction)) => (clock, Some(fraction)),
+        None => (text, None),
+    };
+    let mut parts = clock.split(':');
+    let hours = parse_digits(parts.next().unwrap_or(""), 0)?;
+    let minutes = parts
+        .next()
+        .map(|minutes| parse_digits(minutes, 2))
+        .transpose()?;
+    let seconds = parts
+        .next()
+        .map(|seconds| parse_digits(seconds, 2))
+        .transpose()?;
+    if parts.next().is_some()
+        || minutes.is_some_and(|minutes| minutes >= 60)
+        || seconds.is_some_and(|seconds| seconds >= 60)
+        || (fraction.is_some() && seconds.is_none())
+    {
+        return Err(Error::InvalidDateTime);
+    }
+    let nanos = match fraction {
+        Some(fraction) if fraction.len() <= 9 => {
+            parse_digits(fraction, 0)? * 10_i64.pow(9 - fraction.len() as u32)
+        }
+        Some(_) => return Err(Error::InvalidDateTime),
+        None => 0,
+    };
+    hours
+        .checked_mul(3_600_000_000_000)
+        .and_then(|total| total.checked_add(minutes.unwrap_or(0) * 60_000_000_000))
+        .and_then(|total| total.checked_add(seconds.unwrap_or(0) * 1_000_000_000))
+        .and_then(|total| total.checked_add(nanos))
+        .ok_or(Error::InvalidDateTime)
+}
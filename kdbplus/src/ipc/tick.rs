@@ -0,0 +1,281 @@
+//! This module provides a client subscribing to a kdb+ tickerplant.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::error::Error;
+use super::{qattribute, qmsg_type, qtype, QStream, Result, K};
+use futures_core::Stream;
+use std::collections::VecDeque;
+use std::future::{self, Future};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Subscription query taking tables and symbols. All tables in `.u.t` are subscribed if no table is given.
+pub(crate) const SUBSCRIBE: &str = "{[t;s] .u.sub[;s] each $[count t;t;.u.t]}";
+
+/// Subscription query which also returns the messages logged so far, atomically with the subscription.
+pub(crate) const SUBSCRIBE_AND_RECOVER: &str =
+    "{[t;s] (.u.sub[;s] each $[count t;t;.u.t]; $[`L in key `.u; .u.i#get .u.L; ()])}";
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% TickOptions %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Options of [`TickSubscriber`].
+#[derive(Clone, Debug, Default)]
+pub struct TickOptions {
+    /// Tables to subscribe. Empty subscribes all tables of the tickerplant. Default: empty.
+    pub tables: Vec<String>,
+    /// Symbols to receive. Empty receives all symbols. Default: empty.
+    pub symbols: Vec<String>,
+    /// Replay the first `.u.i` messages of the tickerplant log `.u.L` before live updates. Default: `false`.
+    pub recover: bool,
+}
+
+//%% Update %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Update of a table published by a tickerplant as `` (`upd; `table; data) ``.
+#[derive(Clone, Debug)]
+pub struct Update {
+    /// Name of the updated table.
+    pub table: String,
+    /// Published rows. Usually a table for live updates and a list of columns for replayed updates.
+    pub data: K,
+}
+
+//%% TickSubscriber %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Pending read of the next message, which owns the stream while it is in progress.
+type PendingMessage = Pin<Box<dyn Future<Output = (QStream, Result<(u8, K)>)> + Send>>;
+
+/// Client subscribing to a tickerplant which follows `tick.q`, i.e., defines `.u.sub`, `.u.t`, `.u.i` and `.u.L`.
+///
+/// Updates are yielded by [`next_update`](#method.next_update) or as a [`Stream`](https://docs.rs/futures-core/latest/futures_core/stream/trait.Stream.html)
+///  of `Result<Update>`, which ends when the tickerplant closes the connection or after an IO error of the
+///  connection is yielded. Messages other than `` (`upd; `table; data) ``, e.g., `.u.end` at the end of day,
+///  are skipped.
+///
+/// With `recover` option, the log file of the tickerplant is read by the tickerplant itself with `get` and
+///  sent back in the response of the subscription, so that no update is lost or duplicated between the
+///  replay and live updates, as `r.q` does with `-11!`. Replayed updates are filtered by table but not by symbol.
+/// # Example
+/// ```no_run
+/// use kdbplus::ipc::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let stream = QStream::connect(ConnectionMethod::TCP, "localhost", 5010_u16, "ideal:person").await?;
+///     let options = TickOptions {
+///         tables: vec![String::from("trade")],
+///         symbols: vec![String::from("AAPL"), String::from("MSFT")],
+///         recover: true,
+///     };
+///     let mut subscriber = TickSubscriber::subscribe(stream, options).await?;
+///     for (table, schema) in subscriber.schemas() {
+///         println!("{}: {}", table, schema);
+///     }
+///     loop {
+///         let update = subscriber.next_update().await?;
+///         println!("{}: {}", update.table, update.data);
+///     }
+/// }
+/// ```
+pub struct TickSubscriber {
+    /// Connection to the tickerplant. `None` while a read is in progress.
+    stream: Option<QStream>,
+    /// Read in progress.
+    pending: Option<PendingMessage>,
+    /// Names and empty schemas of subscribed tables.
+    schemas: Vec<(String, K)>,
+    /// Replayed updates not yielded yet.
+    replay: VecDeque<Update>,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% TickSubscriber %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl TickSubscriber {
+    /// Subscribe to a tickerplant.
+    /// # Parameters
+    /// - `stream`: Connection to the tickerplant.
+    /// - `options`: Tables, symbols and recovery.
+    /// # Errors
+    /// - `Error::Object` holding the q error if the tickerplant fails the subscription, e.g., for an unknown table.
+    /// - IO error of `InvalidData` if the response is not of the form of `.u.sub`.
+    /// # Example
+    /// See the example of [`TickSubscriber`](struct.TickSubscriber.html).
+    pub async fn subscribe(mut stream: QStream, options: TickOptions) -> Result<Self> {
+        let symbols = if options.symbols.is_empty() {
+            K::new_symbol(String::new())
+        } else {
+            K::new_symbol_list(options.symbols, qattribute::NONE)
+        };
+        let query = if options.recover {
+            SUBSCRIBE_AND_RECOVER
+        } else {
+            SUBSCRIBE
+        };
+        let query = K::new_compound_list(vec![
+            K::new_string(String::from(query), qattribute::NONE),
+            K::new_symbol_list(options.tables, qattribute::NONE),
+            symbols,
+        ]);
+        let response = stream.send_sync_message(&query).await?;
+        if response.get_type() == qtype::ERROR {
+            return Err(Error::Object(response));
+        }
+        let (subscriptions, log) = if options.recover {
+            match response.as_vec::<K>()?.as_slice() {
+                [subscriptions, log] => (subscriptions.clone(), Some(log.clone())),
+                _ => return Err(invalid_data("unexpected response of subscription")),
+            }
+        } else {
+            (response, None)
+        };
+
+        let mut schemas = Vec::new();
+        for subscription in subscriptions.as_vec::<K>()? {
+            match subscription.as_vec::<K>()?.as_slice() {
+                [table, schema] => schemas.push((table.get_symbol()?.to_string(), schema.clone())),
+                _ => return Err(invalid_data("unexpected response of subscription")),
+            }
+        }
+        let mut replay = VecDeque::new();
+        if let Some(log) = log {
+            for message in log.as_vec::<K>()? {
+                if let Some(update) = parse_update(message) {
+                    if schemas.iter().any(|(table, _)| *table == update.table) {
+                        replay.push_back(update);
+                    }
+                }
+            }
+        }
+
+        Ok(TickSubscriber {
+            stream: Some(stream),
+            pending: None,
+            schemas,
+            replay,
+        })
+    }
+
+    /// Get the names and empty schemas of subscribed tables returned by `.u.sub`.
+    pub fn schemas(&self) -> &[(String, K)] {
+        &self.schemas
+    }
+
+    /// Get the number of replayed updates not yielded yet.
+    pub fn pending_replay(&self) -> usize {
+        self.replay.len()
+    }
+
+    /// Wait for the next update. Replayed updates come first.
+    /// # Errors
+    /// - IO error of `UnexpectedEof` if the tickerplant closed the connection, including every call after that.
+    /// - Other IO error of the connection, e.g., a message cut off in the middle, after which the connection
+    ///   is closed.
+    /// # Example
+    /// See the example of [`TickSubscriber`](struct.TickSubscriber.html).
+    pub async fn next_update(&mut self) -> Result<Update> {
+        match future::poll_fn(|cx| self.poll_update(cx)).await {
+            Some(result) => result,
+            None => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "tickerplant closed").into()),
+        }
+    }
+
+    /// Unsubscribe by closing the connection. A read in progress is abandoned.
+    pub async fn shutdown(self) -> Result<()> {
+        match self.stream {
+            Some(stream) => stream.shutdown().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Poll the next update. `None` if the connection is closed.
+    fn poll_update(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Update>>> {
+        if let Some(update) = self.replay.pop_front() {
+            return Poll::Ready(Some(Ok(update)));
+        }
+        loop {
+            let mut pending = match self.pending.take() {
+                Some(pending) => pending,
+                None => match self.stream.take() {
+                    Some(mut stream) => Box::pin(async move {
+                        let result = stream.receive_message().await;
+                        (stream, result)
+                    }),
+                    // Closed already.
+                    None => return Poll::Ready(None),
+                },
+            };
+            match pending.as_mut().poll(cx) {
+                Poll::Pending => {
+                    self.pending = Some(pending);
+                    return Poll::Pending;
+                }
+                // `receive_message` reports the connection closed between messages as aborted.
+                Poll::Ready((_, Err(Error::IO(error))))
+                    if error.kind() == io::ErrorKind::ConnectionAborted =>
+                {
+                    return Poll::Ready(None)
+                }
+                // The connection is unusable after an IO error, e.g., in the middle of a message.
+                Poll::Ready((_, Err(error @ Error::IO(_)))) => {
+                    return Poll::Ready(Some(Err(error)))
+                }
+                Poll::Ready((stream, result)) => {
+                    self.stream = Some(stream);
+                    match result {
+                        Ok((qmsg_type::response, _)) => {}
+                        Ok((_, message)) => {
+                            if let Some(update) = parse_update(&message) {
+                                return Poll::Ready(Some(Ok(update)));
+                            }
+                        }
+                        Err(error) => return Poll::Ready(Some(Err(error))),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Stream for TickSubscriber {
+    type Item = Result<Update>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_update(cx)
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Extract an update from a message of the form of `` (`upd; `table; data) ``.
+fn parse_update(message: &K) -> Option<Update> {
+    match message.as_vec::<K>().ok()?.as_slice() {
+        [function, table, data] if function.get_symbol().ok()? == "upd" => Some(Update {
+            table: table.get_symbol().ok()?.to_string(),
+            data: data.clone(),
+        }),
+        _ => None,
+    }
+}
+
+/// Construct an IO error of `InvalidData`.
+fn invalid_data(message: &str) -> Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string()).into()
+}
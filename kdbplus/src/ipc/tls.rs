@@ -0,0 +1,534 @@
+//! This module provides TLS settings of a client connecting to q/kdb+ and a thin layer over the TLS backend.
+//!
+//! The backend is native-tls with `ipc` feature and rustls with `ipc-rustls` feature. rustls is preferred if both
+//!  features are enabled. The blocking client always uses native-tls with `blocking-tls` feature.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::Result;
+use std::io;
+#[cfg(feature = "ipc-base")]
+use std::{env, path::Path};
+#[cfg(feature = "ipc-base")]
+use tokio::net::TcpStream;
+
+#[cfg(any(feature = "blocking-tls", not(feature = "ipc-rustls")))]
+use native_tls::{self, Certificate, Identity, Protocol};
+
+#[cfg(feature = "ipc-rustls")]
+use std::sync::Arc;
+#[cfg(feature = "ipc-rustls")]
+use tokio::io::AsyncWriteExt;
+#[cfg(feature = "ipc-rustls")]
+use tokio_rustls::rustls::{
+    self,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::WebPkiSupportedAlgorithms,
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, RootCertStore, ServerConfig, SignatureScheme,
+};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >>  Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% TlsVersion %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Version of the TLS protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsVersion {
+    /// TLS 1.0. Deprecated. rustls backend treats it as TLS 1.2.
+    Tls10,
+    /// TLS 1.1. Deprecated. rustls backend treats it as TLS 1.2.
+    Tls11,
+    /// TLS 1.2.
+    Tls12,
+    /// TLS 1.3.
+    Tls13,
+}
+
+//%% TlsConfig %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// TLS settings of a client given to [`QStreamBuilder::tls`](struct.QStreamBuilder.html#method.tls).
+///
+/// By default, the server certificate is verified against the system trust store and the host name of the
+///  connection, and no client certificate is presented.
+/// # Insecure Mode
+/// [`danger_accept_invalid_certs`](#method.danger_accept_invalid_certs) disables verification of the server
+///  certificate and its host name. It is meant for local testing with a self-signed certificate only, because
+///  any server can impersonate the q process then.
+/// # Example
+/// ```no_run
+/// use kdbplus::ipc::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let config = TlsConfig::new()
+///         .add_root_certificate_pem(&std::fs::read("/etc/pki/internal-ca.pem")?)
+///         .identity_pem(
+///             &std::fs::read("/etc/pki/client.pem")?,
+///             &std::fs::read("/etc/pki/client.key")?,
+///         )
+///         .min_protocol_version(TlsVersion::Tls12)
+///         .hostname("kdb.internal");
+///     let mut socket = QStreamBuilder::new(ConnectionMethod::TLS, "10.0.0.8", 5000)
+///         .credential("ideal:person")
+///         .tls(config)
+///         .connect()
+///         .await?;
+///     println!("{}", socket.send_sync_message(&"1+1").await?);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TlsConfig {
+    /// Additional trusted root certificates.
+    root_certificates: Vec<CertificateData>,
+    /// Whether to trust only `root_certificates`.
+    disable_built_in_roots: bool,
+    /// Client certificate for mutual TLS.
+    identity: Option<IdentityData>,
+    /// Minimum protocol version.
+    min_protocol_version: Option<TlsVersion>,
+    /// Name used for SNI and verification instead of the host of the connection.
+    hostname: Option<String>,
+    /// Whether to skip verification of the server certificate and its host name.
+    accept_invalid_certs: bool,
+}
+
+//%% CertificateData %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Encoded certificate.
+#[derive(Clone, Debug)]
+enum CertificateData {
+    Pem(Vec<u8>),
+    Der(Vec<u8>),
+}
+
+//%% IdentityData %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Encoded client certificate and private key.
+#[derive(Clone)]
+enum IdentityData {
+    /// PKCS #12 archive and its password.
+    #[cfg_attr(feature = "ipc-rustls", allow(dead_code))]
+    Pkcs12 { der: Vec<u8>, password: String },
+    /// PEM encoded certificate chain and PKCS #8 private key.
+    Pem { certificate: Vec<u8>, key: Vec<u8> },
+}
+
+//%% TlsStream %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// TLS stream of the backend over TCP.
+#[cfg(all(feature = "ipc-base", not(feature = "ipc-rustls")))]
+pub(crate) type TlsStream = tokio_native_tls::TlsStream<TcpStream>;
+
+/// TLS stream of the backend over TCP.
+#[cfg(feature = "ipc-rustls")]
+pub(crate) type TlsStream = tokio_rustls::TlsStream<TcpStream>;
+
+//%% TlsConnector %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Client side of the TLS backend built from [`TlsConfig`].
+#[cfg(all(feature = "ipc-base", not(feature = "ipc-rustls")))]
+pub(crate) struct TlsConnector(tokio_native_tls::TlsConnector);
+
+/// Client side of the TLS backend built from [`TlsConfig`].
+#[cfg(feature = "ipc-rustls")]
+pub(crate) struct TlsConnector(tokio_rustls::TlsConnector);
+
+//%% TlsAcceptor %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Server side of the TLS backend.
+#[cfg(all(feature = "ipc-base", not(feature = "ipc-rustls")))]
+pub(crate) struct TlsAcceptor(tokio_native_tls::TlsAcceptor);
+
+/// Server side of the TLS backend.
+#[cfg(feature = "ipc-rustls")]
+pub(crate) struct TlsAcceptor(tokio_rustls::TlsAcceptor);
+
+//%% AcceptAnyCertificate %%//vvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Verifier of rustls backend for the insecure mode which accepts any server certificate but still checks
+///  handshake signatures.
+#[cfg(feature = "ipc-rustls")]
+#[derive(Debug)]
+struct AcceptAnyCertificate(WebPkiSupportedAlgorithms);
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% TlsVersion %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[cfg(any(feature = "blocking-tls", not(feature = "ipc-rustls")))]
+impl From<TlsVersion> for Protocol {
+    fn from(version: TlsVersion) -> Self {
+        match version {
+            TlsVersion::Tls10 => Protocol::Tlsv10,
+            TlsVersion::Tls11 => Protocol::Tlsv11,
+            TlsVersion::Tls12 => Protocol::Tlsv12,
+            TlsVersion::Tls13 => Protocol::Tlsv13,
+        }
+    }
+}
+
+//%% TlsConfig %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl TlsConfig {
+    /// Create default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust a PEM encoded root certificate in addition to the system trust store.
+    pub fn add_root_certificate_pem(mut self, pem: &[u8]) -> Self {
+        self.root_certificates
+            .push(CertificateData::Pem(pem.to_vec()));
+        self
+    }
+
+    /// Trust a DER encoded root certificate in addition to the system trust store.
+    pub fn add_root_certificate_der(mut self, der: &[u8]) -> Self {
+        self.root_certificates
+            .push(CertificateData::Der(der.to_vec()));
+        self
+    }
+
+    /// Trust only the root certificates added to this configuration. Default: `false`.
+    pub fn disable_built_in_roots(mut self, disable: bool) -> Self {
+        self.disable_built_in_roots = disable;
+        self
+    }
+
+    /// Present a client certificate in a PKCS #12 archive for mutual TLS. Not supported by rustls backend.
+    pub fn identity_pkcs12(mut self, der: &[u8], password: &str) -> Self {
+        self.identity = Some(IdentityData::Pkcs12 {
+            der: der.to_vec(),
+            password: password.to_string(),
+        });
+        self
+    }
+
+    /// Present a PEM encoded client certificate chain and PKCS #8 private key for mutual TLS.
+    pub fn identity_pem(mut self, certificate: &[u8], key: &[u8]) -> Self {
+        self.identity = Some(IdentityData::Pem {
+            certificate: certificate.to_vec(),
+            key: key.to_vec(),
+        });
+        self
+    }
+
+    /// Set the minimum protocol version. Default: the minimum of the TLS backend.
+    pub fn min_protocol_version(mut self, version: TlsVersion) -> Self {
+        self.min_protocol_version = Some(version);
+        self
+    }
+
+    /// Use `hostname` for SNI and certificate verification instead of the host of the connection, e.g., when
+    ///  connecting by IP address. Default: the host of the connection.
+    pub fn hostname(mut self, hostname: &str) -> Self {
+        self.hostname = Some(hostname.to_string());
+        self
+    }
+
+    /// Skip verification of the server certificate and its host name. See [Insecure Mode](#insecure-mode).
+    ///  Default: `false`.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Get the name used for SNI and verification when connecting to `host`.
+    pub(crate) fn domain<'a>(&'a self, host: &'a str) -> &'a str {
+        self.hostname.as_deref().unwrap_or(host)
+    }
+
+    /// Build a connector of the TLS backend. An invalid certificate or key results in an IO error of
+    ///  `InvalidInput`.
+    #[cfg(all(feature = "ipc-base", not(feature = "ipc-rustls")))]
+    pub(crate) fn build_connector(&self) -> Result<TlsConnector> {
+        Ok(TlsConnector(self.build_native_connector()?.into()))
+    }
+
+    /// Build a connector of native-tls, which is also the backend of the blocking client. An invalid
+    ///  certificate or key results in an IO error of `InvalidInput`.
+    #[cfg(any(feature = "blocking-tls", not(feature = "ipc-rustls")))]
+    pub(crate) fn build_native_connector(&self) -> Result<native_tls::TlsConnector> {
+        let mut builder = native_tls::TlsConnector::builder();
+        for certificate in self.root_certificates.iter() {
+            let certificate = match certificate {
+                CertificateData::Pem(pem) => Certificate::from_pem(pem),
+                CertificateData::Der(der) => Certificate::from_der(der),
+            }
+            .map_err(invalid_input)?;
+            builder.add_root_certificate(certificate);
+        }
+        if let Some(identity) = &self.identity {
+            let identity = match identity {
+                IdentityData::Pkcs12 { der, password } => Identity::from_pkcs12(der, password),
+                IdentityData::Pem { certificate, key } => Identity::from_pkcs8(certificate, key),
+            }
+            .map_err(invalid_input)?;
+            builder.identity(identity);
+        }
+        builder
+            .disable_built_in_roots(self.disable_built_in_roots)
+            .min_protocol_version(self.min_protocol_version.map(Protocol::from))
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .danger_accept_invalid_hostnames(self.accept_invalid_certs);
+        Ok(builder.build().map_err(invalid_input)?)
+    }
+
+    /// Build a connector of the TLS backend. An invalid certificate or key results in an IO error of
+    ///  `InvalidInput`.
+    #[cfg(feature = "ipc-rustls")]
+    pub(crate) fn build_connector(&self) -> Result<TlsConnector> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut roots = RootCertStore::empty();
+        if !self.disable_built_in_roots {
+            // Skip certificates of the system trust store which cannot be loaded.
+            roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+        }
+        for certificate in self.root_certificates.iter() {
+            let certificate = match certificate {
+                CertificateData::Pem(pem) => {
+                    CertificateDer::from_pem_slice(pem).map_err(invalid_input)?
+                }
+                CertificateData::Der(der) => CertificateDer::from(der.clone()),
+            };
+            roots.add(certificate).map_err(invalid_input)?;
+        }
+        // rustls supports only TLS 1.2 and TLS 1.3.
+        let versions: &[&rustls::SupportedProtocolVersion] = match self.min_protocol_version {
+            Some(TlsVersion::Tls13) => &[&rustls::version::TLS13],
+            _ => rustls::ALL_VERSIONS,
+        };
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(versions)
+            .map_err(invalid_input)?;
+        let builder = if self.accept_invalid_certs {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(
+                    provider.signature_verification_algorithms,
+                )))
+        } else {
+            builder.with_root_certificates(roots)
+        };
+        let config = match &self.identity {
+            None => builder.with_no_client_auth(),
+            Some(IdentityData::Pem { certificate, key }) => {
+                let (chain, key) = pem_identity(certificate, key)?;
+                builder
+                    .with_client_auth_cert(chain, key)
+                    .map_err(invalid_input)?
+            }
+            Some(IdentityData::Pkcs12 { .. }) => {
+                return Err(invalid_input("PKCS #12 is not supported by rustls backend").into())
+            }
+        };
+        Ok(TlsConnector(Arc::new(config).into()))
+    }
+}
+
+//%% IdentityData %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl std::fmt::Debug for IdentityData {
+    /// Hide the private key and the password.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Pkcs12 { .. } => write!(f, "Pkcs12"),
+            Self::Pem { .. } => write!(f, "Pem"),
+        }
+    }
+}
+
+//%% TlsConnector %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[cfg(feature = "ipc-base")]
+impl TlsConnector {
+    /// Run TLS handshake over a TCP connection verifying the server as `domain`.
+    #[cfg(not(feature = "ipc-rustls"))]
+    pub(crate) async fn connect(&self, domain: &str, socket: TcpStream) -> Result<TlsStream> {
+        self.0.connect(domain, socket).await.map_err(|error| {
+            io::Error::new(io::ErrorKind::ConnectionAborted, error.to_string()).into()
+        })
+    }
+
+    /// Run TLS handshake over a TCP connection verifying the server as `domain`.
+    #[cfg(feature = "ipc-rustls")]
+    pub(crate) async fn connect(&self, domain: &str, socket: TcpStream) -> Result<TlsStream> {
+        let domain = ServerName::try_from(domain.to_string()).map_err(invalid_input)?;
+        Ok(self.0.connect(domain, socket).await?.into())
+    }
+}
+
+//%% TlsAcceptor %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[cfg(feature = "ipc-base")]
+impl TlsAcceptor {
+    /// Build an acceptor from a server identity specified by environmental variables. PEM files in
+    ///  `KDBPLUS_TLS_CERT_PEM_FILE` and `KDBPLUS_TLS_KEY_PEM_FILE` are used if the former is set. Otherwise
+    ///  PKCS #12 file in `KDBPLUS_TLS_KEY_FILE` and its password in `KDBPLUS_TLS_KEY_FILE_SECRET` are used.
+    pub(crate) async fn from_env() -> Result<Self> {
+        if let Ok(certificate_path) = env::var("KDBPLUS_TLS_CERT_PEM_FILE") {
+            let key_path = env::var("KDBPLUS_TLS_KEY_PEM_FILE").map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "KDBPLUS_TLS_KEY_PEM_FILE is not set",
+                )
+            })?;
+            let certificate = tokio::fs::read(Path::new(&certificate_path)).await?;
+            let key = tokio::fs::read(Path::new(&key_path)).await?;
+            Self::from_pem(&certificate, &key)
+        } else if let Ok(path) = env::var("KDBPLUS_TLS_KEY_FILE") {
+            let password = env::var("KDBPLUS_TLS_KEY_FILE_SECRET").map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "KDBPLUS_TLS_KEY_FILE_SECRET is not set",
+                )
+            })?;
+            let der = tokio::fs::read(Path::new(&path)).await?;
+            Self::from_pkcs12(&der, &password)
+        } else {
+            Err(io::Error::new(io::ErrorKind::NotFound, "KDBPLUS_TLS_KEY_FILE is not set").into())
+        }
+    }
+
+    /// Build an acceptor from a PKCS #12 archive.
+    #[cfg(not(feature = "ipc-rustls"))]
+    fn from_pkcs12(der: &[u8], password: &str) -> Result<Self> {
+        let identity = Identity::from_pkcs12(der, password)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "authentication failed"))?;
+        let acceptor = native_tls::TlsAcceptor::new(identity).map_err(invalid_input)?;
+        Ok(TlsAcceptor(acceptor.into()))
+    }
+
+    /// Build an acceptor from a PEM encoded certificate chain and PKCS #8 private key.
+    #[cfg(not(feature = "ipc-rustls"))]
+    fn from_pem(certificate: &[u8], key: &[u8]) -> Result<Self> {
+        let identity = Identity::from_pkcs8(certificate, key).map_err(invalid_input)?;
+        let acceptor = native_tls::TlsAcceptor::new(identity).map_err(invalid_input)?;
+        Ok(TlsAcceptor(acceptor.into()))
+    }
+
+    /// PKCS #12 is not supported by rustls backend.
+    #[cfg(feature = "ipc-rustls")]
+    fn from_pkcs12(_der: &[u8], _password: &str) -> Result<Self> {
+        Err(invalid_input(
+            "PKCS #12 is not supported by rustls backend. Use KDBPLUS_TLS_CERT_PEM_FILE and KDBPLUS_TLS_KEY_PEM_FILE",
+        )
+        .into())
+    }
+
+    /// Build an acceptor from a PEM encoded certificate chain and private key.
+    #[cfg(feature = "ipc-rustls")]
+    fn from_pem(certificate: &[u8], key: &[u8]) -> Result<Self> {
+        let (chain, key) = pem_identity(certificate, key)?;
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(invalid_input)?
+                .with_no_client_auth()
+                .with_single_cert(chain, key)
+                .map_err(invalid_input)?;
+        Ok(TlsAcceptor(Arc::new(config).into()))
+    }
+
+    /// Run TLS handshake over an accepted TCP connection.
+    #[cfg(not(feature = "ipc-rustls"))]
+    pub(crate) async fn accept(&self, socket: TcpStream) -> Result<TlsStream> {
+        self.0.accept(socket).await.map_err(|error| {
+            io::Error::new(io::ErrorKind::ConnectionAborted, error.to_string()).into()
+        })
+    }
+
+    /// Run TLS handshake over an accepted TCP connection.
+    #[cfg(feature = "ipc-rustls")]
+    pub(crate) async fn accept(&self, socket: TcpStream) -> Result<TlsStream> {
+        Ok(self.0.accept(socket).await?.into())
+    }
+}
+
+//%% AcceptAnyCertificate %%//vvvvvvvvvvvvvvvvvvvvvvv/
+
+#[cfg(feature = "ipc-rustls")]
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, certificate, signature, &self.0)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, certificate, signature, &self.0)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_schemes()
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Close the TLS session of a client.
+#[cfg(all(feature = "ipc-base", not(feature = "ipc-rustls")))]
+pub(crate) async fn shutdown(socket: &mut TlsStream) -> Result<()> {
+    socket.get_mut().shutdown()?;
+    Ok(())
+}
+
+/// Close the TLS session of a client.
+#[cfg(feature = "ipc-rustls")]
+pub(crate) async fn shutdown(socket: &mut TlsStream) -> Result<()> {
+    socket.shutdown().await?;
+    Ok(())
+}
+
+/// Decode a PEM encoded certificate chain and private key for rustls backend.
+#[cfg(feature = "ipc-rustls")]
+fn pem_identity(
+    certificate: &[u8],
+    key: &[u8],
+) -> io::Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
+    let chain = CertificateDer::pem_slice_iter(certificate)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(invalid_input)?;
+    if chain.is_empty() {
+        return Err(invalid_input("no certificate found"));
+    }
+    let key = PrivateKeyDer::from_pem_slice(key).map_err(invalid_input)?;
+    Ok((chain, key))
+}
+
+/// Convert an error of the TLS backend into an IO error of `InvalidInput`.
+fn invalid_input<E>(error: E) -> io::Error
+where
+    E: std::fmt::Display,
+{
+    io::Error::new(io::ErrorKind::InvalidInput, error.to_string())
+}
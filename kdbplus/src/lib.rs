@@ -402,8 +402,11 @@ pub mod qninf_base {
 // >> Export Modules
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
+#[cfg(any(feature = "api", feature = "ipc-core"))]
+mod attribute;
+
 #[cfg(feature = "api")]
 pub mod api;
 
-#[cfg(feature = "ipc")]
+#[cfg(feature = "ipc-core")]
 pub mod ipc;
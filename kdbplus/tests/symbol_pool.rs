@@ -0,0 +1,43 @@
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                     Load Library                      //
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+// The global pool is shared by the whole process and so tested in its own binary apart from `test.rs` whose
+//  tests run in parallel.
+
+use kdbplus::ipc::*;
+use kdbplus::*;
+
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                     Test Functions                    //
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+#[test]
+fn global_symbol_pool_test() -> Result<()> {
+    SymbolPool::enable_global();
+    let first = K::new_symbol(String::from("NAB"));
+    let second = K::new_symbol_list(
+        vec![
+            String::from("NAB"),
+            String::from("ANZ"),
+            String::from("NAB"),
+        ],
+        qattribute::NONE,
+    );
+    let symbols = second.as_vec::<S>()?;
+    assert!(Symbol::ptr_eq(&Symbol::from("NAB"), &symbols[0]));
+    assert!(Symbol::ptr_eq(&symbols[0], &symbols[2]));
+    assert_eq!(first.get_symbol()?, symbols[0].as_str());
+    assert_eq!(SymbolPool::global_len(), 2);
+
+    // Symbols used outside of the pool are kept
+    drop(second);
+    assert_eq!(SymbolPool::purge_global(), 1);
+    assert_eq!(SymbolPool::global_len(), 1);
+
+    SymbolPool::disable_global();
+    assert_eq!(SymbolPool::global_len(), 0);
+    assert!(!Symbol::ptr_eq(&Symbol::from("NAB"), &Symbol::from("NAB")));
+
+    Ok(())
+}
@@ -1360,6 +1360,418 @@ fn cast_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn type_cast_test() -> Result<()> {
+    // Numeric
+    let q_int_list = K::new_int_list(
+        vec![1, qnull_base::I, qinf_base::I, qninf_base::I],
+        qattribute::NONE,
+    );
+    assert_eq!(
+        format!("{}", q_int_list.cast(qtype::LONG_LIST)?),
+        String::from("1 0N 0W -0W")
+    );
+    assert_eq!(
+        format!("{}", q_int_list.cast(qtype::FLOAT_LIST)?),
+        String::from("1 0n 0w -0w")
+    );
+    assert_eq!(
+        format!("{}", q_int_list.cast(qtype::SHORT_LIST)?),
+        String::from("1 0N 0W -0Wh")
+    );
+    assert_eq!(
+        format!("{}", q_int_list.cast(qtype::BOOL_LIST)?),
+        String::from("1111b")
+    );
+    assert_eq!(
+        format!("{}", K::new_long(100000).cast(qtype::SHORT_ATOM)?),
+        String::from("-31072h")
+    );
+    assert_eq!(K::new_float(2.5).cast(qtype::LONG_ATOM)?.get_long()?, 3);
+    assert_eq!(K::new_real(-2.5).cast(qtype::INT_ATOM)?.get_int()?, -3);
+    assert_eq!(K::new_char('a').cast(qtype::LONG_ATOM)?.get_long()?, 97);
+    assert_eq!(K::new_long(98).cast(qtype::CHAR)?.get_char()?, 'b');
+    assert_eq!(K::new_long(258).cast(qtype::BYTE_ATOM)?.get_byte()?, 2);
+    assert_eq!(K::new_byte(0).cast(qtype::BOOL_ATOM)?.get_bool()?, false);
+
+    // Temporal
+    let q_timestamp_list = K::new_temporal_list(
+        vec![
+            "2020.02.29D23:59:59.123456789".parse::<QTimestamp>()?,
+            "1999.12.31D01:02:03.000000000".parse::<QTimestamp>()?,
+            QTimestamp::NULL,
+            QTimestamp::INF,
+        ],
+        qattribute::NONE,
+    );
+    assert_eq!(
+        format!("{}", q_timestamp_list.cast(qtype::DATE_LIST)?),
+        String::from("2020.02.29 1999.12.31 0N 0Wd")
+    );
+    assert_eq!(
+        format!("{}", q_timestamp_list.cast(qtype::MONTH_LIST)?),
+        String::from("2020.02 1999.12 0N 0Wm")
+    );
+    assert_eq!(
+        format!("{}", q_timestamp_list.cast(qtype::MINUTE_LIST)?),
+        String::from("23:59 01:02 0N 0Wu")
+    );
+    assert_eq!(
+        format!("{}", q_timestamp_list.cast(qtype::SECOND_LIST)?),
+        String::from("23:59:59 01:02:03 0N 0Wv")
+    );
+    assert_eq!(
+        format!("{}", q_timestamp_list.cast(qtype::TIME_LIST)?),
+        String::from("23:59:59.123 01:02:03.000 0N 0Wt")
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            K::from("2000.01.02".parse::<QDate>()?).cast(qtype::TIMESTAMP_ATOM)?
+        ),
+        String::from("2000.01.02D00:00:00.000000000")
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            K::from("2000.01.02".parse::<QDate>()?).cast(qtype::DATETIME_ATOM)?
+        ),
+        String::from("2000.01.02T00:00:00.000")
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            K::from("2020.03m".parse::<QMonth>()?).cast(qtype::DATE_ATOM)?
+        ),
+        String::from("2020.03.01")
+    );
+    assert_eq!(
+        K::from("1D01:30:00".parse::<QTimespan>()?)
+            .cast(qtype::MINUTE_ATOM)?
+            .get_temporal::<QMinute>()?
+            .raw(),
+        25 * 60 + 30
+    );
+    assert_eq!(
+        format!("{}", K::from(QDate::from_raw(1)).cast(qtype::LONG_ATOM)?),
+        String::from("1")
+    );
+    assert_eq!(
+        format!("{}", K::new_int(31).cast(qtype::DATE_ATOM)?),
+        String::from("2000.02.01")
+    );
+
+    // Symbol and string
+    let q_symbol_list = K::new_symbol_list(
+        vec![String::from("a"), String::from("bc")],
+        qattribute::NONE,
+    );
+    let q_string_list = q_symbol_list.cast(qtype::STRING)?;
+    assert_eq!(
+        format!("{}", q_string_list),
+        String::from("(,\"a\";\"bc\")")
+    );
+    assert_eq!(
+        format!("{}", q_string_list.cast(qtype::SYMBOL_ATOM)?),
+        String::from("`a`bc")
+    );
+    let q_string = K::new_string(String::from("abc"), qattribute::NONE);
+    let q_symbol = q_string.cast(qtype::SYMBOL_LIST)?;
+    assert_eq!(q_symbol.get_type(), qtype::SYMBOL_ATOM);
+    assert_eq!(q_symbol.get_symbol()?, "abc");
+    assert_eq!(
+        format!("{}", q_string.cast(qtype::SYMBOL_ATOM)?),
+        String::from("`abc")
+    );
+    assert_eq!(
+        format!("{}", q_string.cast(qtype::LONG_LIST)?),
+        String::from("97 98 99")
+    );
+
+    // Parse
+    let parse = |text: &str, qtype: i8| {
+        K::new_string(String::from(text), qattribute::NONE)
+            .cast(qtype)
+            .map(|atom| format!("{}", atom))
+    };
+    assert_eq!(parse("42", qtype::LONG_ATOM)?, String::from("42"));
+    assert_eq!(parse(" -7i", qtype::INT_ATOM)?, String::from("-7i"));
+    assert_eq!(parse("0W", qtype::SHORT_ATOM)?, String::from("0Wh"));
+    assert_eq!(parse("abc", qtype::LONG_ATOM)?, String::from("0N"));
+    assert_eq!(parse("1.5", qtype::FLOAT_ATOM)?, String::from("1.5"));
+    assert_eq!(parse("1.5", qtype::REAL_ATOM)?, String::from("1.5e"));
+    assert_eq!(parse("1", qtype::BOOL_ATOM)?, String::from("1b"));
+    assert_eq!(parse("ff", qtype::BYTE_ATOM)?, String::from("0xff"));
+    assert_eq!(
+        parse("8c680a01-5a49-5aab-5a65-d4bfddb6a661", qtype::GUID_ATOM)?,
+        String::from("8c680a01-5a49-5aab-5a65-d4bfddb6a661")
+    );
+    assert_eq!(
+        parse("2020.01.02D03:04:05", qtype::TIMESTAMP_ATOM)?,
+        String::from("2020.01.02D03:04:05.000000000")
+    );
+    assert_eq!(parse("12:34", qtype::MINUTE_ATOM)?, String::from("12:34"));
+    assert_eq!(parse("2020.13.01", qtype::DATE_ATOM)?, String::from("0Nd"));
+
+    // Invalid cast
+    assert_eq!(
+        K::new_symbol("a").cast(qtype::LONG_ATOM).unwrap_err(),
+        Error::InvalidCast {
+            from: "symbol",
+            to: "long"
+        }
+    );
+    assert_eq!(
+        K::from(QDate::from_raw(0))
+            .cast(qtype::MINUTE_ATOM)
+            .unwrap_err(),
+        Error::InvalidCast {
+            from: "date",
+            to: "minute"
+        }
+    );
+    assert_eq!(
+        K::new_long_list(vec![1], qattribute::NONE)
+            .cast(qtype::GUID_LIST)
+            .unwrap_err(),
+        Error::InvalidCast {
+            from: "long list",
+            to: "guid list"
+        }
+    );
+    assert!(K::new_long(1).cast(qtype::TABLE).is_err());
+    assert!(K::new_error(String::from("type"))
+        .cast(qtype::LONG_ATOM)
+        .is_err());
+    assert!(K::new_long(1).cast(qtype::ERROR).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn ops_test() -> Result<()> {
+    // Arithmetic
+    let q_long_list = K::new_long_list(vec![1, 2, qnull_base::J, 4], qattribute::NONE);
+    assert_eq!(
+        format!("{}", ops::add(&q_long_list, &K::new_int(10))?),
+        String::from("11 12 0N 14")
+    );
+    assert_eq!(
+        format!("{}", ops::sub(&K::new_short(1), &q_long_list)?),
+        String::from("0 -1 0N -3")
+    );
+    assert_eq!(
+        format!("{}", ops::mul(&q_long_list, &q_long_list)?),
+        String::from("1 4 0N 16")
+    );
+    assert_eq!(
+        format!("{}", ops::div(&q_long_list, &K::new_long(2))?),
+        String::from("0.5 1 0n 2")
+    );
+    assert_eq!(
+        format!("{}", ops::add(&K::new_bool(true), &K::new_bool(true))?),
+        String::from("2i")
+    );
+    assert_eq!(
+        format!("{}", ops::div(&K::new_real(1.0), &K::new_short(4))?),
+        String::from("0.25e")
+    );
+    assert_eq!(
+        ops::add(&K::new_long(qinf_base::J), &K::new_long(1))?.get_long()?,
+        qnull_base::J
+    );
+    assert_eq!(
+        ops::add(
+            &q_long_list,
+            &K::new_long_list(vec![1, 2], qattribute::NONE)
+        )
+        .unwrap_err(),
+        Error::OperandLengthMismatch { left: 4, right: 2 }
+    );
+    assert_eq!(
+        ops::add(&K::new_symbol(String::from("a")), &K::new_long(1)).unwrap_err(),
+        Error::InvalidOperation {
+            operator: "+",
+            operand_type: "symbol",
+            expected: None
+        }
+    );
+
+    // Temporal arithmetic
+    let q_date = K::from("2020.02.28".parse::<QDate>()?);
+    assert_eq!(
+        format!("{}", ops::add(&q_date, &K::new_int(2))?),
+        String::from("2020.03.01")
+    );
+    assert_eq!(
+        ops::sub(&K::from("2020.03.01".parse::<QDate>()?), &q_date)?.get_int()?,
+        2
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            ops::add(&q_date, &K::from("12:30".parse::<QMinute>()?))?
+        ),
+        String::from("2020.02.28D12:30:00.000000000")
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            ops::mul(&K::from("00:00:30".parse::<QSecond>()?), &K::new_long(3))?
+        ),
+        String::from("00:01:30")
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            ops::add(
+                &K::from("00:01".parse::<QMinute>()?),
+                &K::from("00:00:30".parse::<QSecond>()?)
+            )?
+        ),
+        String::from("0D00:01:30.000000000")
+    );
+    assert!(ops::add(&q_date, &q_date).is_err());
+    assert!(ops::mul(&q_date, &K::new_long(2)).is_err());
+
+    // Aggregation
+    let q_float_list = K::new_float_list(vec![3.0, qnull_base::F, 1.0, 2.0, 6.0], qattribute::NONE);
+    assert_eq!(ops::sum(&q_long_list)?.get_long()?, 7);
+    assert_eq!(ops::sum(&q_float_list)?.get_float()?, 12.0);
+    assert_eq!(
+        format!(
+            "{}",
+            ops::sum(&K::new_short_list(vec![30000, 30000], qattribute::NONE))?
+        ),
+        String::from("60000i")
+    );
+    assert_eq!(ops::avg(&q_float_list)?.get_float()?, 3.0);
+    assert_eq!(ops::min(&q_float_list)?.get_float()?, 1.0);
+    assert_eq!(ops::max(&q_long_list)?.get_long()?, 4);
+    assert_eq!(ops::med(&q_float_list)?.get_float()?, 2.5);
+    assert_eq_float!(
+        ops::dev(&q_float_list)?.get_float()?,
+        1.8708286933869707,
+        1e-10
+    );
+    assert!(ops::avg(&K::new_long_list(vec![], qattribute::NONE))?
+        .get_float()?
+        .is_nan());
+    assert_eq!(
+        ops::min(&K::new_long_list(vec![], qattribute::NONE))?.get_long()?,
+        qinf_base::J
+    );
+    assert_eq!(
+        ops::max(&K::new_long_list(vec![qnull_base::J], qattribute::NONE))?.get_long()?,
+        qnull_base::J
+    );
+    let q_weight_list = K::new_int_list(vec![1, 2, 3, 4, 5], qattribute::NONE);
+    assert_eq!(
+        ops::wavg(&q_weight_list, &q_float_list)?.get_float()?,
+        44.0 / 13.0
+    );
+    assert_eq!(ops::wsum(&q_weight_list, &q_float_list)?.get_float()?, 44.0);
+    assert_eq!(ops::count(&q_float_list).get_long()?, 5);
+    let q_date_list = K::new_temporal_list(
+        vec![
+            "2020.01.03".parse::<QDate>()?,
+            QDate::NULL,
+            "2020.01.01".parse::<QDate>()?,
+        ],
+        qattribute::NONE,
+    );
+    assert_eq!(
+        format!("{}", ops::min(&q_date_list)?),
+        String::from("2020.01.01")
+    );
+
+    // Uniform
+    assert_eq!(
+        format!("{}", ops::sums(&q_float_list)?),
+        String::from("3 3 4 6 12")
+    );
+    assert_eq!(
+        format!("{}", ops::deltas(&q_date_list)?),
+        String::from("7307 0N 0Ni")
+    );
+    assert_eq!(
+        format!("{}", ops::prev(&q_long_list)?),
+        String::from("0N 1 2 0N")
+    );
+    assert_eq!(
+        format!("{}", ops::next(&q_long_list)?),
+        String::from("2 0N 4 0N")
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            ops::prev(&K::new_symbol_list(
+                vec![String::from("a"), String::from("b")],
+                qattribute::NONE
+            ))?
+        ),
+        String::from("``a")
+    );
+    assert_eq!(
+        format!("{}", ops::msum(2, &q_long_list)?),
+        String::from("1 3 2 4")
+    );
+    assert_eq!(
+        format!("{}", ops::mavg(2, &q_float_list)?),
+        String::from("3 3 1 1.5 4")
+    );
+    assert_eq!(
+        format!("{}", ops::fills(&q_date_list)?),
+        String::from("2020.01.03 2020.01.03 2020.01.01")
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            ops::fills(&K::new_symbol_list(
+                vec![String::from(""), String::from("a"), String::from("")],
+                qattribute::NONE
+            ))?
+        ),
+        String::from("``a`a")
+    );
+    assert!(ops::prev(&K::new_long(1)).is_err());
+
+    // Bucketing
+    assert_eq!(
+        format!(
+            "{}",
+            ops::xbar(
+                &K::new_long(5),
+                &K::new_long_list(vec![-1, 4, 5, 12, qnull_base::J], qattribute::NONE)
+            )?
+        ),
+        String::from("-5 0 5 10 0N")
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            ops::xbar(
+                &K::from("00:05".parse::<QMinute>()?),
+                &K::from("09:33:12.345".parse::<QTime>()?)
+            )?
+        ),
+        String::from("09:30:00.000")
+    );
+    assert_eq!(
+        format!(
+            "{}",
+            ops::xbar(
+                &K::new_float(0.5),
+                &K::new_float_list(vec![1.2, -0.2], qattribute::NONE)
+            )?
+        ),
+        String::from("1 -0.5")
+    );
+    assert!(ops::xbar(&K::new_float(0.5), &K::new_long(1)).is_err());
+
+    Ok(())
+}
+
 #[test]
 fn length_test() -> Result<()> {
     // atom
@@ -1436,6 +1848,139 @@ fn length_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn temporal_test() -> Result<()> {
+    // parse and format
+    let q_timestamp: QTimestamp = "2019.05.09D00:39:02.000194756".parse()?;
+    assert_eq!(
+        format!("{}", q_timestamp),
+        String::from("2019.05.09D00:39:02.000194756")
+    );
+    let q_month: QMonth = "1998.01m".parse()?;
+    assert_eq!(format!("{}", q_month), String::from("1998.01m"));
+    let q_date: QDate = "2012.03.12".parse()?;
+    assert_eq!(format!("{}", q_date), String::from("2012.03.12"));
+    let q_datetime: QDateTime = "2013.01.10T00:09:50.038".parse()?;
+    assert_eq!(
+        format!("{}", q_datetime),
+        String::from("2013.01.10T00:09:50.038")
+    );
+    let q_timespan: QTimespan = "-1D04:34:59.277539844".parse()?;
+    assert_eq!(
+        format!("{}", q_timespan),
+        String::from("-1D04:34:59.277539844")
+    );
+    let q_minute: QMinute = "01:39".parse()?;
+    assert_eq!(format!("{}", q_minute), String::from("01:39"));
+    let q_second: QSecond = "01:01:42v".parse()?;
+    assert_eq!(format!("{}", q_second), String::from("01:01:42"));
+    let q_time: QTime = "07:44:03.489".parse()?;
+    assert_eq!(format!("{}", q_time), String::from("07:44:03.489"));
+    assert_eq!(
+        format!("{}", "0Np".parse::<QTimestamp>()?),
+        String::from("0Np")
+    );
+    assert_eq!(
+        format!("{}", "-0Wd".parse::<QDate>()?),
+        String::from("-0Wd")
+    );
+    assert_eq!(format!("{}", "0Wt".parse::<QTime>()?), String::from("0Wt"));
+    assert_eq!(
+        format!("{}", "2300.01.01".parse::<QDate>()?),
+        String::from("2300.01.01")
+    );
+    assert_eq!(
+        format!("{}", "0001.01.01".parse::<QDate>()?),
+        String::from("0001.01.01")
+    );
+    assert_eq!(
+        format!("{}", "9999.12.31".parse::<QDate>()?),
+        String::from("9999.12.31")
+    );
+    assert_eq!(format!("{}", QDate::INF - 1), String::from("5881610.07.10"));
+    assert_eq!(
+        format!("{}", QDate::NINF + 1),
+        String::from("-5877611.06.24")
+    );
+    assert!("2012.13.01".parse::<QDate>().is_err());
+    assert!("12:60".parse::<QMinute>().is_err());
+
+    // null and infinity
+    assert!(QDate::NULL.is_null());
+    assert!(QMonth::INF.is_infinite());
+    assert!(QDateTime::NULL.is_null());
+    assert_eq!(QDateTime::NULL, QDateTime::NULL);
+    assert!(QDateTime::NULL < q_datetime);
+    assert!(QTimestamp::NULL < QTimestamp::NINF);
+    assert_eq!(QDate::NULL.to_naive_date(), None);
+    assert_eq!(QTimespan::INF.to_duration(), None);
+
+    // arithmetic
+    assert_eq!(q_date + 20, "2012.04.01".parse()?);
+    assert_eq!("2012.04.01".parse::<QDate>()? - q_date, 20);
+    assert_eq!(q_month - 1, "1997.12m".parse()?);
+    assert!((QDate::NULL + 1).is_null());
+    assert_eq!(q_date - QDate::NULL, qnull::INT);
+    assert!((QDate::INF + 1).is_null());
+    let q_open = q_date + "0D09:30".parse::<QTimespan>()?;
+    assert_eq!(
+        format!("{}", q_open),
+        String::from("2012.03.12D09:30:00.000000000")
+    );
+    let q_close: QTimestamp = "2012.03.12D16:00:00.000000000".parse()?;
+    assert_eq!(q_close - q_open, "0D06:30:00".parse()?);
+    assert_eq!(q_open + (q_close - q_open), q_close);
+    assert_eq!(q_close.date(), q_date);
+    assert_eq!(
+        q_time + "00:00:00.511".parse::<QTime>()?,
+        "07:44:04.000".parse()?
+    );
+    assert_eq!(-q_minute, "-01:39".parse::<QMinute>()?);
+    assert_eq!(
+        QTimespan::from_raw(1_000_000_000) * 3,
+        "0D00:00:03".parse()?
+    );
+
+    // chrono
+    assert_eq!(q_date.to_naive_date(), NaiveDate::from_ymd_opt(2012, 3, 12));
+    assert_eq!(
+        QTimestamp::from(q_timestamp.to_datetime().unwrap()),
+        q_timestamp
+    );
+    assert_eq!(q_second.to_duration(), Some(Duration::seconds(3702)));
+    assert_eq!(QTime::from(Duration::milliseconds(27843489)), q_time);
+
+    // K
+    let q_date_atom = K::from(q_date);
+    assert_eq!(
+        q_date_atom.get_date()?,
+        NaiveDate::from_ymd_opt(2012, 3, 12).unwrap()
+    );
+    assert_eq!(q_date_atom.get_temporal::<QDate>()?, q_date);
+    assert_eq!(
+        q_date_atom.get_temporal::<QMonth>(),
+        Err(Error::InvalidCast {
+            from: "date",
+            to: "month"
+        })
+    );
+    let q_minute_atom = K::new_minute(Duration::minutes(99));
+    assert_eq!(q_minute_atom.get_temporal::<QMinute>()?, q_minute);
+    let q_timestamp_list =
+        K::new_temporal_list(vec![q_open, QTimestamp::NULL, q_close], qattribute::NONE);
+    assert_eq!(
+        format!("{}", q_timestamp_list),
+        String::from("2012.03.12D09:30:00.000000000 0N 2012.03.12D16:00:00.000000000")
+    );
+    assert_eq!(
+        q_timestamp_list.get_temporal_list::<QTimestamp>()?,
+        vec![q_open, QTimestamp::NULL, q_close]
+    );
+    assert!(q_timestamp_list.get_temporal_list::<QTimespan>().is_err());
+
+    Ok(())
+}
+
 #[test]
 fn push_pop_test() -> Result<()> {
     // empty list
@@ -2046,36 +2591,604 @@ fn push_pop_test() -> Result<()> {
     Ok(())
 }
 
-#[async_std::test]
-async fn functional_message_test(socket: &mut Qsocket) -> Result<()> {
-    // Connect to q process
-    let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "kdbuser:pass")
-        .await
-        .expect("Failed to connect");
+#[test]
+fn clone_test() -> Result<()> {
+    // Clones share the underlying list until one of them is modified.
+    let q_float_list = K::new_float_list(vec![1.5, 2.5, 3.5], qattribute::NONE);
+    let mut cloned = q_float_list.clone();
+    cloned.as_mut_vec::<F>()?[0] = 0.5;
+    cloned.push(&4.5_f64)?;
+    assert_eq!(*q_float_list.as_vec::<F>()?, vec![1.5, 2.5, 3.5]);
+    assert_eq!(*cloned.as_vec::<F>()?, vec![0.5, 2.5, 3.5, 4.5]);
+    assert_eq!(q_float_list.len(), 3);
+    assert_eq!(cloned.len(), 4);
+
+    // Wrong type does not affect the shared list.
+    assert!(cloned.as_mut_vec::<J>().is_err());
+
+    // Nested object.
+    let q_table = K::new_dictionary(
+        K::new_symbol_list(vec![String::from("a")], qattribute::NONE),
+        K::new_compound_list(vec![K::new_long_list(vec![1, 2], qattribute::NONE)]),
+    )?
+    .flip()?;
+    let mut cloned = q_table.clone();
+    cloned.get_mut_column("a")?.as_mut_vec::<J>()?[1] = 3;
+    assert_eq!(*q_table.get_column("a")?.as_vec::<J>()?, vec![1, 2]);
+    assert_eq!(*cloned.get_column("a")?.as_vec::<J>()?, vec![1, 3]);
 
-    // bool
-    let mut res_bool = socket
-        .send_sync_message(&add_null!(K::new_bool(true)))
-        .await?;
-    assert_eq!(res_bool.get_bool()?, true);
+    Ok(())
+}
 
-    // GUID
-    let mut res_guid = socket
-        .send_sync_message(&add_null!(K::new_guid([
-            0x1e_u8, 0x11, 0x17, 0x0c, 0x42, 0x24, 0x25, 0x2c, 0x1c, 0x14, 0x1e, 0x22, 0x4d, 0x3d,
-            0x46, 0x24
-        ])))
-        .await?;
-    assert_eq!(
-        res_guid.get_guid()?,
-        [
-            0x1e_u8, 0x11, 0x17, 0x0c, 0x42, 0x24, 0x25, 0x2c, 0x1c, 0x14, 0x1e, 0x22, 0x4d, 0x3d,
-            0x46, 0x24
-        ]
+#[test]
+fn symbol_test() -> Result<()> {
+    // Symbols are interned within a list.
+    let mut q_symbol_list = K::new_symbol_list(
+        vec![
+            String::from("AAPL"),
+            String::from("MSFT"),
+            String::from("AAPL"),
+        ],
+        qattribute::NONE,
     );
+    {
+        let symbols = q_symbol_list.as_vec::<S>()?;
+        assert!(Symbol::ptr_eq(&symbols[0], &symbols[2]));
+        assert!(!Symbol::ptr_eq(&symbols[0], &symbols[1]));
+        assert_eq!(symbols[0], "AAPL");
+        assert_eq!(symbols[1], String::from("MSFT"));
+    }
 
-    // GUID null
-    res_guid = socket
+    // Both `String` and `Symbol` can be pushed.
+    q_symbol_list.push(&String::from("GOOG"))?;
+    q_symbol_list.push(&Symbol::from("IBM"))?;
+    q_symbol_list.insert(0, &Symbol::from("TSLA"))?;
+    assert_eq!(
+        format!("{}", q_symbol_list),
+        String::from("`TSLA`AAPL`MSFT`AAPL`GOOG`IBM")
+    );
+    assert_eq!(q_symbol_list.pop_symbol()?, String::from("IBM"));
+    assert_eq!(q_symbol_list.remove_symbol(0)?, String::from("TSLA"));
+
+    // Atom
+    let q_symbol = K::new_symbol(Symbol::from("kdb+"));
+    assert_eq!(q_symbol.get_symbol()?, "kdb+");
+    assert_eq!(K::new_symbol("").get_symbol()?, qnull::SYMBOL);
+
+    // Local pool
+    let mut pool = SymbolPool::new();
+    let first = pool.intern("BHP");
+    let second = pool.intern("BHP");
+    assert!(Symbol::ptr_eq(&first, &second));
+    pool.intern("RIO");
+    assert_eq!(pool.len(), 2);
+    // Only `RIO` is not used outside of the pool.
+    assert_eq!(pool.purge(), 1);
+    assert_eq!(pool.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn dictionary_test() -> Result<()> {
+    // Dictionary
+    let mut q_dictionary = K::new_dictionary(
+        K::new_symbol_list(
+            vec![String::from("a"), String::from("b"), String::from("a")],
+            qattribute::NONE,
+        ),
+        K::new_long_list(vec![1, 2, 3], qattribute::NONE),
+    )?;
+    // The first key is found as q does.
+    assert_eq!(q_dictionary.get(&K::new_symbol("a"))?.get_long()?, 1);
+    assert!(q_dictionary.contains_key(&K::new_symbol("b"))?);
+    assert!(!q_dictionary.contains_key(&K::new_symbol("c"))?);
+    assert_eq!(
+        q_dictionary.get(&K::new_symbol("c")).unwrap_err(),
+        Error::NoSuchKey(String::from("`c"))
+    );
+    assert_eq!(
+        q_dictionary.get_mut(&K::new_symbol("a")).unwrap_err(),
+        Error::InvalidOperation {
+            operator: "get_mut",
+            operand_type: "long list",
+            expected: Some("compound list")
+        }
+    );
+
+    // Upsert keeps the index.
+    q_dictionary.upsert(K::new_symbol("c"), K::new_long(4))?;
+    q_dictionary.upsert(K::new_symbol("b"), K::new_long(20))?;
+    assert_eq!(q_dictionary.get(&K::new_symbol("c"))?.get_long()?, 4);
+    assert_eq!(
+        format!("{}", q_dictionary),
+        String::from("`a`b`a`c!1 20 3 4")
+    );
+    assert_eq!(
+        q_dictionary.upsert(K::new_symbol("d"), K::new_int(5)),
+        Err(Error::InvalidCast {
+            from: "int",
+            to: "long"
+        })
+    );
+    assert_eq!(q_dictionary.len(), 4);
+
+    // Removal and direct modification rebuild the index.
+    assert_eq!(q_dictionary.remove_key(&K::new_symbol("a"))?.get_long()?, 1);
+    assert_eq!(q_dictionary.get(&K::new_symbol("a"))?.get_long()?, 3);
+    q_dictionary.as_mut_vec::<K>()?[0].as_mut_vec::<S>()?[0] = Symbol::from("z");
+    assert!(!q_dictionary.contains_key(&K::new_symbol("b"))?);
+    assert_eq!(q_dictionary.get(&K::new_symbol("z"))?.get_long()?, 20);
+
+    // Sorted dictionary
+    let mut q_sorted_dictionary = K::new_dictionary(
+        K::new_int_list(vec![1, 3], qattribute::SORTED),
+        K::new_compound_list(vec![K::new_long(10), K::new_symbol("x")]),
+    )?;
+    q_sorted_dictionary.upsert(K::new_int(5), K::new_float(0.5))?;
+    assert_eq!(q_sorted_dictionary.get_type(), qtype::SORTED_DICTIONARY);
+    q_sorted_dictionary.upsert(K::new_int(2), K::new_null())?;
+    assert_eq!(q_sorted_dictionary.get_type(), qtype::DICTIONARY);
+    q_sorted_dictionary
+        .get_mut(&K::new_int(3))?
+        .get_symbol()
+        .map(|symbol| assert_eq!(symbol, "x"))?;
+    *q_sorted_dictionary.get_mut(&K::new_int(3))? = K::new_symbol("y");
+    assert_eq!(
+        format!("{}", q_sorted_dictionary),
+        String::from("1 3 5 2i!(10;`y;0.5;::)")
+    );
+
+    // Keyed table
+    let mut q_keyed_table = K::new_dictionary(
+        K::new_symbol_list(
+            vec![
+                String::from("sym"),
+                String::from("venue"),
+                String::from("price"),
+                String::from("size"),
+            ],
+            qattribute::NONE,
+        ),
+        K::new_compound_list(vec![
+            K::new_symbol_list(
+                vec![String::from("AAPL"), String::from("AAPL")],
+                qattribute::NONE,
+            ),
+            K::new_string(String::from("LN"), qattribute::NONE),
+            K::new_float_list(vec![101.5, 102.25], qattribute::NONE),
+            K::new_long_list(vec![100, 200], qattribute::NONE),
+        ]),
+    )?
+    .flip()?
+    .enkey(2)?;
+    let key = K::new_compound_list(vec![K::new_symbol("AAPL"), K::new_char('N')]);
+    assert_eq!(
+        format!("{}", q_keyed_table.get(&key)?),
+        String::from("`price`size!(102.25;200)")
+    );
+    assert_eq!(
+        q_keyed_table.get(&K::new_symbol("AAPL")).unwrap_err(),
+        Error::LengthMismatch {
+            key_length: 2,
+            value_length: 1
+        }
+    );
+
+    // Update by list and insert by dictionary.
+    q_keyed_table.upsert(
+        key.clone(),
+        K::new_compound_list(vec![K::new_float(103.0), K::new_long(300)]),
+    )?;
+    let new_key = K::new_compound_list(vec![K::new_symbol("MSFT"), K::new_char('X')]);
+    q_keyed_table.upsert(
+        new_key.clone(),
+        K::new_dictionary(
+            K::new_symbol_list(vec![String::from("size")], qattribute::NONE),
+            K::new_long_list(vec![50], qattribute::NONE),
+        )?,
+    )?;
+    assert_eq!(
+        format!("{}", q_keyed_table.get(&new_key)?),
+        String::from("`price`size!(0n;50)")
+    );
+    assert_eq!(q_keyed_table.len(), 3);
+    assert_eq!(
+        q_keyed_table.upsert(
+            K::new_compound_list(vec![K::new_symbol("IBM"), K::new_char('Y')]),
+            K::new_dictionary(
+                K::new_symbol_list(vec![String::from("volume")], qattribute::NONE),
+                K::new_long_list(vec![50], qattribute::NONE),
+            )?,
+        ),
+        Err(Error::NoSuchColumn(String::from("volume")))
+    );
+    assert_eq!(q_keyed_table.len(), 3);
+
+    // Delete
+    let removed = q_keyed_table.remove_key(&key)?;
+    assert_eq!(
+        format!("{}", removed),
+        String::from("`price`size!(103;300)")
+    );
+    assert!(!q_keyed_table.contains_key(&key)?);
+    assert!(q_keyed_table.contains_key(&new_key)?);
+    assert_eq!(q_keyed_table.len(), 2);
+
+    // Not a dictionary
+    assert_eq!(
+        K::new_long(1).contains_key(&K::new_long(1)),
+        Err(Error::InvalidOperation {
+            operator: "contains_key",
+            operand_type: "long",
+            expected: Some("dictionary")
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn schema_test() -> Result<()> {
+    let q_table = K::new_dictionary(
+        K::new_symbol_list(
+            vec![
+                String::from("date"),
+                String::from("sym"),
+                String::from("price"),
+                String::from("comment"),
+                String::from("misc"),
+            ],
+            qattribute::NONE,
+        ),
+        K::new_compound_list(vec![
+            K::new_temporal_list(vec![QDate::from_raw(0)], qattribute::SORTED),
+            K::new_symbol_list(vec![String::from("AAPL")], qattribute::NONE),
+            K::new_float_list(vec![101.5], qattribute::NONE),
+            K::new_compound_list(vec![K::new_string(String::from("open"), qattribute::NONE)]),
+            K::new_compound_list(vec![K::new_long(1)]),
+        ]),
+    )?
+    .flip()?;
+
+    let schema = q_table.meta()?;
+    assert_eq!(
+        schema,
+        Schema::new(vec![
+            ColumnMeta::new("date", 'd').attribute(qattribute::SORTED),
+            ColumnMeta::new("sym", 's'),
+            ColumnMeta::new("price", 'f'),
+            ColumnMeta::new("comment", 'C'),
+            ColumnMeta::new("misc", ' '),
+        ])
+    );
+    assert_eq!(
+        format!("{}", schema),
+        String::from(
+            "c      | t f a\n-------| -----\ndate   | d   s\nsym    | s    \nprice  | f    \ncomment| C    \nmisc   |      \n"
+        )
+    );
+    assert_eq!(
+        q_table.cols()?,
+        vec!["date", "sym", "price", "comment", "misc"]
+    );
+    assert!(q_table.keys()?.is_empty());
+    q_table.conforms_to(&schema)?;
+
+    // Keyed table
+    let q_keyed_table = q_table.enkey(2)?;
+    // Sorted by the first key column
+    assert_eq!(q_keyed_table.get_type(), qtype::SORTED_DICTIONARY);
+    let keyed_schema = q_keyed_table.meta()?;
+    assert_eq!(keyed_schema.key_count, 2);
+    assert_eq!(keyed_schema.keys(), vec!["date", "sym"]);
+    assert_eq!(keyed_schema.cols(), q_keyed_table.cols()?);
+    assert_eq!(q_keyed_table.keys()?, vec!["date", "sym"]);
+    q_keyed_table.conforms_to(&Schema::keyed(schema.columns.clone(), 2))?;
+
+    // Mismatch
+    assert_eq!(
+        q_keyed_table.conforms_to(&schema),
+        Err(Error::SchemaMismatch(String::from(
+            "2 key columns but expected 0"
+        )))
+    );
+    let mut columns = schema.columns.clone();
+    columns.pop();
+    assert_eq!(
+        q_keyed_table
+            .conforms_to(&Schema::keyed(columns.clone(), 2))
+            .unwrap_err()
+            .to_string(),
+        String::from("schema mismatch: unexpected column `misc")
+    );
+    columns.push(ColumnMeta::new("other", ' '));
+    assert_eq!(
+        q_keyed_table
+            .conforms_to(&Schema::keyed(columns.clone(), 2))
+            .unwrap_err()
+            .to_string(),
+        String::from("schema mismatch: column 4 is `misc but expected `other")
+    );
+    columns[4] = ColumnMeta::new("misc", ' ');
+    columns.push(ColumnMeta::new("extra", 'j'));
+    assert_eq!(
+        q_keyed_table
+            .conforms_to(&Schema::keyed(columns.clone(), 2))
+            .unwrap_err()
+            .to_string(),
+        String::from("schema mismatch: missing column `extra")
+    );
+    columns.pop();
+    columns[1] = ColumnMeta::new("sym", 's').attribute(qattribute::GROUPED);
+    assert_eq!(
+        q_keyed_table
+            .conforms_to(&Schema::keyed(columns, 2))
+            .unwrap_err()
+            .to_string(),
+        String::from("schema mismatch: column `sym has attribute none but expected `g#")
+    );
+
+    // Not a table
+    assert_eq!(
+        K::new_long(1).meta(),
+        Err(Error::InvalidOperation {
+            operator: "meta",
+            operand_type: "long",
+            expected: Some("table")
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn attribute_test() -> Result<()> {
+    // validation
+    let mut q_long_list = K::new_long_list(vec![3, 1, 3, 2], qattribute::NONE);
+    assert_eq!(
+        q_long_list.set_attribute(qattribute::SORTED),
+        Err(Error::InvalidAttribute {
+            attribute: "`s#",
+            qtype: "long list"
+        })
+    );
+    assert!(q_long_list.set_attribute(qattribute::UNIQUE).is_err());
+    assert!(q_long_list.set_attribute(qattribute::PARTED).is_err());
+    assert_eq!(q_long_list.get_attribute(), qattribute::NONE);
+    q_long_list.set_attribute(qattribute::GROUPED)?;
+    assert_eq!(q_long_list.get_attribute(), qattribute::GROUPED);
+
+    let mut q_float_list = K::new_float_list(vec![qnull::FLOAT, -0.5, 1.25], qattribute::NONE);
+    q_float_list.set_attribute(qattribute::SORTED)?;
+    q_float_list.set_attribute(qattribute::UNIQUE)?;
+    let mut q_string = K::new_string(String::from("aabbc"), qattribute::NONE);
+    q_string.set_attribute(qattribute::PARTED)?;
+    assert!(q_string.set_attribute(qattribute::UNIQUE).is_err());
+    let mut q_symbol_list = K::new_symbol_list(
+        vec![String::from("a"), String::from("b"), String::from("a")],
+        qattribute::NONE,
+    );
+    assert!(q_symbol_list.set_attribute(qattribute::PARTED).is_err());
+    let mut q_int = K::new_int(3);
+    assert_eq!(
+        q_int.set_attribute(qattribute::SORTED),
+        Err(Error::InvalidAttribute {
+            attribute: "`s#",
+            qtype: "int"
+        })
+    );
+    q_int.set_attribute(qattribute::NONE)?;
+    assert!(q_float_list.set_attribute(5).is_err());
+    assert!(q_float_list.apply_attribute(5).is_err());
+    let mut q_dictionary = K::new_dictionary(
+        K::new_long_list(vec![1, 2], qattribute::NONE),
+        K::new_symbol_list(vec![String::from("b"), String::from("a")], qattribute::NONE),
+    )?;
+    q_dictionary.set_attribute(qattribute::SORTED)?;
+    assert_eq!(q_dictionary.get_attribute(), qattribute::SORTED);
+    assert_eq!(q_dictionary.get_type(), qtype::SORTED_DICTIONARY);
+    assert_eq!(format!("{}", q_dictionary), String::from("`s#1 2!`b`a"));
+    assert!(q_dictionary.set_attribute(qattribute::UNIQUE).is_err());
+    q_dictionary.set_attribute(qattribute::NONE)?;
+    assert_eq!(q_dictionary.get_type(), qtype::DICTIONARY);
+    assert_eq!(format!("{}", q_dictionary), String::from("1 2!`b`a"));
+    let mut q_dictionary = K::new_dictionary(
+        K::new_long_list(vec![2, 1], qattribute::NONE),
+        K::new_long_list(vec![1, 2], qattribute::NONE),
+    )?;
+    assert!(q_dictionary.set_attribute(qattribute::SORTED).is_err());
+    let mut q_keyed_table = K::new_dictionary(
+        K::new_dictionary(
+            K::new_symbol_list(vec![String::from("id")], qattribute::NONE),
+            K::new_compound_list(vec![K::new_long_list(vec![1, 2], qattribute::NONE)]),
+        )?
+        .flip()?,
+        K::new_dictionary(
+            K::new_symbol_list(vec![String::from("name")], qattribute::NONE),
+            K::new_compound_list(vec![K::new_symbol_list(
+                vec![String::from("a"), String::from("b")],
+                qattribute::NONE,
+            )]),
+        )?
+        .flip()?,
+    )?;
+    q_keyed_table.set_attribute(qattribute::SORTED)?;
+    assert_eq!(q_keyed_table.get_attribute(), qattribute::SORTED);
+    assert_eq!(q_keyed_table.get_type(), qtype::SORTED_DICTIONARY);
+    assert_eq!(
+        q_keyed_table.get_column("id")?.get_attribute(),
+        qattribute::SORTED
+    );
+    q_keyed_table.upsert(
+        K::new_long(0),
+        K::new_compound_list(vec![K::new_symbol(String::from("c"))]),
+    )?;
+    assert_eq!(q_keyed_table.get_type(), qtype::DICTIONARY);
+    assert_eq!(q_keyed_table.get_attribute(), qattribute::NONE);
+    assert_eq!(
+        q_keyed_table.get_column("id")?.get_attribute(),
+        qattribute::NONE
+    );
+    let mut q_keyed_table = K::new_dictionary(
+        K::new_dictionary(
+            K::new_symbol_list(
+                vec![String::from("id"), String::from("venue")],
+                qattribute::NONE,
+            ),
+            K::new_compound_list(vec![
+                K::new_long_list(vec![1, 1], qattribute::NONE),
+                K::new_symbol_list(vec![String::from("b"), String::from("a")], qattribute::NONE),
+            ]),
+        )?
+        .flip()?,
+        K::new_dictionary(
+            K::new_symbol_list(vec![String::from("size")], qattribute::NONE),
+            K::new_compound_list(vec![K::new_long_list(vec![10, 20], qattribute::NONE)]),
+        )?
+        .flip()?,
+    )?;
+    assert!(q_keyed_table.set_attribute(qattribute::SORTED).is_err());
+    assert_eq!(q_keyed_table.get_type(), qtype::DICTIONARY);
+
+    // enforcement
+    q_symbol_list.apply_attribute(qattribute::PARTED)?;
+    assert_eq!(format!("{}", q_symbol_list), String::from("`p#`a`a`b"));
+    q_symbol_list.apply_attribute(qattribute::UNIQUE)?;
+    assert_eq!(format!("{}", q_symbol_list), String::from("`u#`a`b"));
+    assert_eq!(q_symbol_list.len(), 2);
+    q_long_list.apply_attribute(qattribute::SORTED)?;
+    assert_eq!(format!("{}", q_long_list), String::from("`s#1 2 3 3"));
+    assert!(q_int.apply_attribute(qattribute::SORTED).is_err());
+
+    // push and insert
+    q_long_list.push(&4_i64)?;
+    assert_eq!(q_long_list.get_attribute(), qattribute::SORTED);
+    q_long_list.insert(0, &0_i64)?;
+    assert_eq!(q_long_list.get_attribute(), qattribute::SORTED);
+    q_long_list.insert(1, &5_i64)?;
+    assert_eq!(q_long_list.get_attribute(), qattribute::NONE);
+
+    q_symbol_list.push(&String::from("c"))?;
+    assert_eq!(q_symbol_list.get_attribute(), qattribute::UNIQUE);
+    q_symbol_list.push(&String::from("a"))?;
+    assert_eq!(q_symbol_list.get_attribute(), qattribute::NONE);
+
+    q_string.push(&'c')?;
+    assert_eq!(q_string.get_attribute(), qattribute::NONE);
+
+    let mut q_int_list = K::new_int_list(vec![2, 1], qattribute::GROUPED);
+    q_int_list.push(&1)?;
+    assert_eq!(q_int_list.get_attribute(), qattribute::GROUPED);
+
+    // pop and remove
+    let mut q_short_list = K::new_short_list(vec![1, 2, 3, 4], qattribute::SORTED);
+    assert_eq!(q_short_list.remove_short(1)?, 2);
+    assert_eq!(q_short_list.len(), 3);
+    assert_eq!(q_short_list.remove(0)?.get_short()?, 1);
+    assert_eq!(q_short_list.len(), 2);
+    assert_eq!(q_short_list.get_attribute(), qattribute::SORTED);
+    q_short_list.set_attribute(qattribute::PARTED)?;
+    q_short_list.pop_short()?;
+    assert_eq!(q_short_list.get_attribute(), qattribute::NONE);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn sorted_dictionary_test() -> Result<()> {
+    let path = std::env::temp_dir().join("kdbplus_sorted_dictionary_test");
+    let mut q_dictionary = K::new_dictionary(
+        K::new_long_list(vec![1, 2], qattribute::NONE),
+        K::new_symbol_list(vec![String::from("b"), String::from("a")], qattribute::NONE),
+    )?;
+    q_dictionary.set_attribute(qattribute::SORTED)?;
+    let mut q_keyed_table = K::new_dictionary(
+        K::new_dictionary(
+            K::new_symbol_list(vec![String::from("id")], qattribute::NONE),
+            K::new_compound_list(vec![K::new_long_list(vec![1, 2], qattribute::NONE)]),
+        )?
+        .flip()?,
+        K::new_dictionary(
+            K::new_symbol_list(vec![String::from("name")], qattribute::NONE),
+            K::new_compound_list(vec![K::new_symbol_list(
+                vec![String::from("a"), String::from("b")],
+                qattribute::NONE,
+            )]),
+        )?
+        .flip()?,
+    )?;
+    q_keyed_table.set_attribute(qattribute::SORTED)?;
+
+    // Serialize as q does, i.e., `-8!`s#1 2!`b`a` and `-8!`s#([id:1 2] name:`a`b)`
+    let mut writer = journal::Writer::create(&path).await?;
+    writer.append(&q_dictionary).await?;
+    writer.append(&q_keyed_table).await?;
+    drop(writer);
+    let bytes = std::fs::read(&path)?;
+    assert_eq!(bytes[8..11], [0x7f, 0x07, 0x01]);
+    let position = bytes[8..]
+        .windows(2)
+        .position(|pair| pair == [0x7f, 0x62])
+        .unwrap()
+        + 8;
+    // The key table has `` ,`id `` and a list holding `` `s#1 2 ``.
+    assert_eq!(
+        bytes[position..position + 21],
+        [
+            0x7f, 0x62, 0x00, 0x63, 0x0b, 0x00, 0x01, 0x00, 0x00, 0x00, b'i', b'd', 0x00, 0x00,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x01
+        ]
+    );
+
+    // Deserialize
+    let mut reader = journal::Reader::open(&path).await?;
+    let received = reader.next_message().await?.unwrap();
+    assert_eq!(received.get_type(), qtype::SORTED_DICTIONARY);
+    assert_eq!(received.get_attribute(), qattribute::SORTED);
+    assert_eq!(format!("{}", received), String::from("`s#1 2!`b`a"));
+    let received = reader.next_message().await?.unwrap();
+    assert_eq!(received.get_type(), qtype::SORTED_DICTIONARY);
+    assert_eq!(received.get_attribute(), qattribute::SORTED);
+    assert_eq!(
+        received.get_column("id")?.get_attribute(),
+        qattribute::SORTED
+    );
+    std::fs::remove_file(&path)?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn functional_message_test(socket: &mut Qsocket) -> Result<()> {
+    // Connect to q process
+    let mut socket = QStream::connect(ConnectionMethod::TCP, "localhost", 5000, "kdbuser:pass")
+        .await
+        .expect("Failed to connect");
+
+    // bool
+    let mut res_bool = socket
+        .send_sync_message(&add_null!(K::new_bool(true)))
+        .await?;
+    assert_eq!(res_bool.get_bool()?, true);
+
+    // GUID
+    let mut res_guid = socket
+        .send_sync_message(&add_null!(K::new_guid([
+            0x1e_u8, 0x11, 0x17, 0x0c, 0x42, 0x24, 0x25, 0x2c, 0x1c, 0x14, 0x1e, 0x22, 0x4d, 0x3d,
+            0x46, 0x24
+        ])))
+        .await?;
+    assert_eq!(
+        res_guid.get_guid()?,
+        [
+            0x1e_u8, 0x11, 0x17, 0x0c, 0x42, 0x24, 0x25, 0x2c, 0x1c, 0x14, 0x1e, 0x22, 0x4d, 0x3d,
+            0x46, 0x24
+        ]
+    );
+
+    // GUID null
+    res_guid = socket
         .send_sync_message(&add_null!(K::new_guid(qnull::GUID)))
         .await?;
     assert_eq!(res_guid.get_guid()?, qnull::GUID);
@@ -2918,3 +4031,1800 @@ async fn compression_test() -> Result<()> {
 
     Ok(())
 }
+
+/// Spawn a listener which only completes the handshake and closes connections afterwards if `close` is
+///  `true`. Returns the port.
+fn spawn_handshake_listener(close: bool) -> Result<u16> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    std::thread::spawn(move || {
+        let mut sockets = Vec::new();
+        for mut socket in listener.incoming().flatten() {
+            let mut byte = [1u8; 1];
+            while byte[0] != 0 {
+                std::io::Read::read_exact(&mut socket, &mut byte).unwrap();
+            }
+            std::io::Write::write_all(&mut socket, &[3]).unwrap();
+            if !close {
+                sockets.push(socket);
+            }
+        }
+    });
+    Ok(port)
+}
+
+#[async_std::test]
+async fn pool_test() -> Result<()> {
+    let port = spawn_handshake_listener(false)?;
+    let endpoint = QEndpoint::new(ConnectionMethod::TCP, "127.0.0.1", port, "kdbuser:pass");
+    let options = QPoolOptions {
+        size: 1,
+        acquire_timeout: std::time::Duration::from_millis(100),
+        validate_on_acquire: false,
+        ..QPoolOptions::default()
+    };
+    let pool = QPool::connect(vec![endpoint], options).await?;
+    assert_eq!(pool.metrics().idle, 1);
+
+    // Acquire times out while the only connection is leased
+    let mut lease = pool.acquire().await?;
+    assert_eq!(pool.metrics().in_use, 1);
+    assert_eq!(pool.acquire().await.err(), Some(Error::Timeout("acquire")));
+    drop(lease);
+    assert_eq!(
+        pool.metrics(),
+        QPoolMetrics {
+            size: 1,
+            idle: 1,
+            in_use: 0,
+            acquired: 1,
+            timeouts: 1,
+            discarded: 0
+        }
+    );
+
+    // Broken connection is discarded and replaced on the next acquisition
+    lease = pool.acquire().await?;
+    lease.mark_broken();
+    drop(lease);
+    assert_eq!(pool.metrics().idle, 0);
+    assert_eq!(pool.metrics().discarded, 1);
+    lease = pool.acquire().await?;
+    assert!(!lease.is_broken());
+    drop(lease);
+    assert_eq!(pool.metrics().idle, 1);
+
+    // Query cancelled before the response leaves the connection broken
+    lease = pool.acquire().await?;
+    assert!(async_std::future::timeout(
+        std::time::Duration::from_millis(50),
+        lease.send_sync_message(&"1b")
+    )
+    .await
+    .is_err());
+    assert!(lease.is_broken());
+    drop(lease);
+    assert_eq!(pool.metrics().idle, 0);
+    assert_eq!(pool.metrics().discarded, 2);
+
+    // Ping and replacement connection which are never answered are bounded by the acquire timeout
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    std::thread::spawn(move || {
+        let mut sockets = Vec::new();
+        for mut socket in listener.incoming().flatten() {
+            // Only the first connection completes the handshake.
+            if sockets.is_empty() {
+                let mut byte = [1u8; 1];
+                while byte[0] != 0 {
+                    std::io::Read::read_exact(&mut socket, &mut byte).unwrap();
+                }
+                std::io::Write::write_all(&mut socket, &[3]).unwrap();
+            }
+            sockets.push(socket);
+        }
+    });
+    let endpoint = QEndpoint::new(ConnectionMethod::TCP, "127.0.0.1", port, "kdbuser:pass");
+    let options = QPoolOptions {
+        size: 1,
+        acquire_timeout: std::time::Duration::from_millis(100),
+        ..QPoolOptions::default()
+    };
+    let validated = QPool::connect(vec![endpoint], options).await?;
+    assert_eq!(
+        validated.acquire().await.err(),
+        Some(Error::Timeout("acquire"))
+    );
+    assert_eq!(validated.metrics().discarded, 1);
+    assert_eq!(validated.metrics().timeouts, 1);
+
+    assert!(QPool::connect(vec![], QPoolOptions::default())
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reconnect_test() -> Result<()> {
+    // Every connection is closed by the peer right after the handshake
+    let port = spawn_handshake_listener(true)?;
+    let endpoint = QEndpoint::new(ConnectionMethod::TCP, "127.0.0.1", port, "kdbuser:pass");
+    let mut stream = QReconnectingStream::connect(endpoint, ReconnectOptions::default()).await?;
+    let mut events = stream.subscribe();
+    assert!(stream.is_connected());
+
+    // Disconnection is detected and notified
+    assert!(matches!(stream.receive_message().await, Err(Error::IO(_))));
+    assert!(!stream.is_connected());
+    assert!(matches!(
+        events.recv().await,
+        Ok(ConnectionEvent::Disconnected(_))
+    ));
+
+    // Connection is re-established
+    stream.reconnect().await?;
+    assert!(stream.is_connected());
+    assert_eq!(
+        events.recv().await,
+        Ok(ConnectionEvent::Connected { attempts: 1 })
+    );
+
+    // Give up after `max_attempts`
+    let closed_port = std::net::TcpListener::bind("127.0.0.1:0")?
+        .local_addr()?
+        .port();
+    let options = ReconnectOptions {
+        initial_backoff: std::time::Duration::from_millis(1),
+        max_attempts: Some(3),
+        ..ReconnectOptions::default()
+    };
+    let endpoint = QEndpoint::new(
+        ConnectionMethod::TCP,
+        "127.0.0.1",
+        closed_port,
+        "kdbuser:pass",
+    );
+    assert!(QReconnectingStream::connect(endpoint, options)
+        .await
+        .is_err());
+
+    // q error of the init script is returned without retrying
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    std::thread::spawn(move || {
+        for mut socket in listener.incoming().flatten() {
+            let mut byte = [1u8; 1];
+            while byte[0] != 0 {
+                std::io::Read::read_exact(&mut socket, &mut byte).unwrap();
+            }
+            std::io::Write::write_all(&mut socket, &[3]).unwrap();
+            let mut header = [0u8; 8];
+            std::io::Read::read_exact(&mut socket, &mut header).unwrap();
+            let length = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+            let mut body = vec![0u8; length - 8];
+            std::io::Read::read_exact(&mut socket, &mut body).unwrap();
+            std::io::Write::write_all(
+                &mut socket,
+                &[1, 2, 0, 0, 14, 0, 0, 0, 0x80, b't', b'y', b'p', b'e', 0],
+            )
+            .unwrap();
+        }
+    });
+    let options = ReconnectOptions {
+        init_script: vec![String::from("1+`a")],
+        ..ReconnectOptions::default()
+    };
+    let endpoint = QEndpoint::new(ConnectionMethod::TCP, "127.0.0.1", port, "kdbuser:pass");
+    let error = async_std::future::timeout(
+        std::time::Duration::from_secs(5),
+        QReconnectingStream::connect(endpoint, options),
+    )
+    .await
+    .expect("init script error is retried")
+    .err()
+    .unwrap();
+    assert_eq!(
+        error
+            .into_inner()
+            .map(|error| error.get_error_string().map(String::from)),
+        Some(Ok(String::from("type")))
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn timeout_test() -> Result<()> {
+    // Listener which accepts TCP connections but never replies to the handshake
+    let silent_listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let silent_port = silent_listener.local_addr()?.port();
+    let timeouts = QTimeouts {
+        handshake: Some(std::time::Duration::from_millis(50)),
+        ..QTimeouts::default()
+    };
+    assert_eq!(
+        QStream::connect_with_timeouts(
+            ConnectionMethod::TCP,
+            "127.0.0.1",
+            silent_port,
+            "kdbuser:pass",
+            timeouts
+        )
+        .await
+        .err(),
+        Some(Error::Timeout("handshake"))
+    );
+
+    // Listener which completes the handshake but never responds to queries
+    let port = spawn_handshake_listener(false)?;
+    let timeouts = QTimeouts {
+        read: Some(std::time::Duration::from_millis(50)),
+        ..QTimeouts::default()
+    };
+    let mut socket = QStream::connect_with_timeouts(
+        ConnectionMethod::TCP,
+        "127.0.0.1",
+        port,
+        "kdbuser:pass",
+        timeouts,
+    )
+    .await?;
+    assert_eq!(socket.get_timeouts(), timeouts);
+    assert_eq!(
+        socket.receive_message().await.err(),
+        Some(Error::Timeout("read"))
+    );
+    assert!(socket.is_poisoned());
+    // Poisoned stream refuses any message exchange
+    assert!(matches!(
+        socket.send_async_message(&"1b").await,
+        Err(Error::IO(_))
+    ));
+
+    let mut socket =
+        QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "kdbuser:pass").await?;
+    assert_eq!(
+        socket
+            .send_sync_message_timeout(&"1b", std::time::Duration::from_millis(50))
+            .await
+            .err(),
+        Some(Error::Timeout("query"))
+    );
+    assert!(socket.is_poisoned());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn builder_test() -> Result<()> {
+    // Account file of the acceptor
+    let account_file = std::env::temp_dir().join("kdbplus_builder_test_accounts");
+    std::fs::write(
+        &account_file,
+        "kdbuser:9d4e1e23bd5b727046a9e3b4b7db57bd8d6ee684\n",
+    )?;
+    std::env::set_var("KDBPLUS_ACCOUNT_FILE", &account_file);
+    let port = std::net::TcpListener::bind("127.0.0.1:0")?
+        .local_addr()?
+        .port();
+
+    let acceptor = async_std::task::spawn(async move {
+        let mut socket = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", port)
+            .nodelay(true)
+            .accept()
+            .await?;
+        let (message_type, message) = socket.receive_message().await?;
+        assert_eq!(message_type, qmsg_type::asynchronous);
+        assert_eq!(message.as_string()?, "hello");
+        Ok::<_, Error>(())
+    });
+
+    // Wait for the acceptor to listen
+    let mut attempt = 0;
+    let mut socket = loop {
+        let result = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", port)
+            .credential("kdbuser:pass")
+            .nodelay(true)
+            .keepalive(true)
+            .send_buffer_size(1 << 16)
+            .recv_buffer_size(1 << 16)
+            .capability(3)
+            .compression(Compression::Never)
+            .connect()
+            .await;
+        match result {
+            Ok(socket) => break socket,
+            Err(_) if attempt < 100 => {
+                attempt += 1;
+                async_std::task::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            Err(error) => return Err(error),
+        }
+    };
+    assert_eq!(socket.get_connection_type(), "TCP");
+    socket.send_async_message(&"hello").await?;
+    acceptor.await?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn tls_config_test() -> Result<()> {
+    // Invalid certificate is rejected before connecting
+    let config = TlsConfig::new().add_root_certificate_pem(b"not a certificate");
+    match QStreamBuilder::new(ConnectionMethod::TLS, "127.0.0.1", 1)
+        .tls(config)
+        .connect()
+        .await
+    {
+        Err(Error::IO(error)) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput),
+        _ => panic!("invalid certificate was accepted"),
+    }
+
+    // Insecure mode with an explicit host name reaches the TLS handshake
+    let silent_listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let silent_port = silent_listener.local_addr()?.port();
+    let config = TlsConfig::new()
+        .min_protocol_version(TlsVersion::Tls12)
+        .hostname("kdb.internal")
+        .danger_accept_invalid_certs(true);
+    let result = QStreamBuilder::new(ConnectionMethod::TLS, "127.0.0.1", silent_port)
+        .credential("kdbuser:pass")
+        .timeouts(QTimeouts {
+            handshake: Some(std::time::Duration::from_millis(50)),
+            ..QTimeouts::default()
+        })
+        .tls(config)
+        .connect()
+        .await;
+    assert_eq!(result.err(), Some(Error::Timeout("handshake")));
+    drop(silent_listener);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn tls_accept_test() -> Result<()> {
+    // Account file and PEM identity of the acceptor
+    let account_file = std::env::temp_dir().join("kdbplus_tls_accept_test_accounts");
+    std::fs::write(
+        &account_file,
+        "kdbuser:9d4e1e23bd5b727046a9e3b4b7db57bd8d6ee684\n",
+    )?;
+    std::env::set_var("KDBPLUS_ACCOUNT_FILE", &account_file);
+    let tls_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/tls");
+    std::env::set_var("KDBPLUS_TLS_CERT_PEM_FILE", tls_dir.join("server.pem"));
+    std::env::set_var("KDBPLUS_TLS_KEY_PEM_FILE", tls_dir.join("server.key"));
+    let port = std::net::TcpListener::bind("127.0.0.1:0")?
+        .local_addr()?
+        .port();
+
+    let acceptor = async_std::task::spawn(async move {
+        let mut socket = QStreamBuilder::new(ConnectionMethod::TLS, "127.0.0.1", port)
+            .accept()
+            .await?;
+        let (message_type, message) = socket.receive_message().await?;
+        assert_eq!(message_type, qmsg_type::asynchronous);
+        assert_eq!(message.as_string()?, "hello");
+        Ok::<_, Error>(())
+    });
+
+    // Server certificate is verified against the test CA
+    let config = TlsConfig::new()
+        .add_root_certificate_pem(&std::fs::read(tls_dir.join("ca.pem"))?)
+        .disable_built_in_roots(true)
+        .hostname("localhost");
+    // Wait for the acceptor to listen
+    let mut attempt = 0;
+    let mut socket = loop {
+        let result = QStreamBuilder::new(ConnectionMethod::TLS, "127.0.0.1", port)
+            .credential("kdbuser:pass")
+            .tls(config.clone())
+            .connect()
+            .await;
+        match result {
+            Ok(socket) => break socket,
+            Err(_) if attempt < 100 => {
+                attempt += 1;
+                async_std::task::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            Err(error) => return Err(error),
+        }
+    };
+    assert_eq!(socket.get_connection_type(), "TLS");
+    socket.send_async_message(&"hello").await?;
+    acceptor.await?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn listener_test() -> Result<()> {
+    // Account file of the listener
+    let account_file = std::env::temp_dir().join("kdbplus_listener_test_accounts");
+    std::fs::write(
+        &account_file,
+        "kdbuser:9d4e1e23bd5b727046a9e3b4b7db57bd8d6ee684\n",
+    )?;
+    std::env::set_var("KDBPLUS_ACCOUNT_FILE", &account_file);
+
+    // Serve at most two TCP clients at once
+    let builder = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 0);
+    let mut listener = QListener::bind(builder, 2).await?;
+    let port = listener.local_addr().unwrap().port();
+    let client =
+        QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", port).credential("kdbuser:pass");
+    let mut client1 = client.connect().await?;
+    let mut client2 = client.connect().await?;
+    let mut server1 = listener.accept().await?;
+    let mut server2 = listener.accept().await?;
+    assert_eq!(listener.active_connections(), 2);
+    client1.send_async_message(&"hello").await?;
+    client2.send_async_message(&"world").await?;
+    assert_eq!(server1.receive_message().await?.1.as_string()?, "hello");
+    assert_eq!(server2.receive_message().await?.1.as_string()?, "world");
+
+    // Third client waits for a slot
+    let client3 = async_std::task::spawn(async move {
+        let mut client3 = client.connect().await?;
+        client3.send_async_message(&"again").await?;
+        Ok::<_, Error>(client3)
+    });
+    assert!(
+        async_std::future::timeout(std::time::Duration::from_millis(100), listener.accept())
+            .await
+            .is_err()
+    );
+    drop(server1);
+    let mut server3 = listener.accept().await?;
+    assert_eq!(server3.receive_message().await?.1.as_string()?, "again");
+    client3.await?;
+
+    // Unix domain socket
+    let builder =
+        QStreamBuilder::new(ConnectionMethod::UDS, "", 0).uds_path("kdbplus_listener_test");
+    let mut listener = QListener::bind(builder, 1).await?;
+    assert_eq!(listener.local_addr(), None);
+    let mut client = QStreamBuilder::new(ConnectionMethod::UDS, "", 0)
+        .uds_path("kdbplus_listener_test")
+        .credential("kdbuser:pass")
+        .connect()
+        .await?;
+    let mut server = listener.accept().await?;
+    assert_eq!(server.get_connection_type(), "UDS");
+    client.send_async_message(&"local").await?;
+    assert_eq!(server.receive_message().await?.1.as_string()?, "local");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn tls_listener_handshake_timeout_test() -> Result<()> {
+    // PEM identity of the listener
+    let tls_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/tls");
+    std::env::set_var("KDBPLUS_TLS_CERT_PEM_FILE", tls_dir.join("server.pem"));
+    std::env::set_var("KDBPLUS_TLS_KEY_PEM_FILE", tls_dir.join("server.key"));
+    let builder = QStreamBuilder::new(ConnectionMethod::TLS, "127.0.0.1", 0)
+        .authenticator(AllowAll)
+        .timeouts(QTimeouts {
+            handshake: Some(std::time::Duration::from_millis(100)),
+            ..QTimeouts::default()
+        });
+    let mut listener = QListener::bind(builder, 1).await?;
+    let port = listener.local_addr().unwrap().port();
+
+    // Client which never starts the TLS handshake holds the only slot until the time limit
+    let _stalled = std::net::TcpStream::connect(("127.0.0.1", port))?;
+    async_std::task::sleep(std::time::Duration::from_millis(20)).await;
+    let config = TlsConfig::new()
+        .add_root_certificate_pem(&std::fs::read(tls_dir.join("ca.pem"))?)
+        .disable_built_in_roots(true);
+    let client = async_std::task::spawn(async move {
+        QStreamBuilder::new(ConnectionMethod::TLS, "127.0.0.1", port)
+            .credential("kdbuser:pass")
+            .tls(config)
+            .connect()
+            .await
+    });
+    let server = async_std::future::timeout(std::time::Duration::from_secs(5), listener.accept())
+        .await
+        .expect("stalled TLS handshake must release the slot")?;
+    assert_eq!(server.get_connection_type(), "TLS");
+    client.await?;
+
+    Ok(())
+}
+
+/// Handler recording calls for `handler_test`.
+#[derive(Default)]
+struct RecordingHandler {
+    opened: std::sync::atomic::AtomicUsize,
+    closed: std::sync::atomic::AtomicUsize,
+    notes: std::sync::Mutex<Vec<String>>,
+}
+
+#[async_trait::async_trait]
+impl QHandler for RecordingHandler {
+    async fn on_sync(&self, message: &K) -> Result<K> {
+        let list = message.as_vec::<K>()?;
+        match list[0].get_symbol()? {
+            "add" => Ok(K::new_long(list[1].get_long()? + list[2].get_long()?)),
+            "notes" => Ok(K::new_long(self.notes.lock().unwrap().len() as i64)),
+            "explicit" => Ok(K::new_error(String::from("nyi"))),
+            // Cast error is sent back as q error
+            _ => Ok(K::new_long(list[1].get_long()?)),
+        }
+    }
+
+    async fn on_async(&self, message: &K) {
+        self.notes
+            .lock()
+            .unwrap()
+            .push(message.as_string().unwrap().to_string());
+    }
+
+    async fn on_open(&self) {
+        self.opened
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    async fn on_close(&self) {
+        self.closed
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[async_std::test]
+async fn handler_test() -> Result<()> {
+    // Account file of the listener
+    let account_file = std::env::temp_dir().join("kdbplus_handler_test_accounts");
+    std::fs::write(
+        &account_file,
+        "kdbuser:9d4e1e23bd5b727046a9e3b4b7db57bd8d6ee684\n",
+    )?;
+    std::env::set_var("KDBPLUS_ACCOUNT_FILE", &account_file);
+
+    let builder = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 0);
+    let listener = QListener::bind(builder, 4).await?;
+    let port = listener.local_addr().unwrap().port();
+    let handler = std::sync::Arc::new(RecordingHandler::default());
+    async_std::task::spawn(listener.serve(handler.clone()));
+
+    let mut client = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", port)
+        .credential("kdbuser:pass")
+        .connect()
+        .await?;
+    // .z.pg
+    let query = K::new_compound_list(vec![
+        K::new_symbol(String::from("add")),
+        K::new_long(1),
+        K::new_long(2),
+    ]);
+    assert_eq!(client.send_sync_message(&query).await?.get_long()?, 3);
+    // Explicit q error and error of the handler
+    let query = K::new_compound_list(vec![K::new_symbol(String::from("explicit"))]);
+    assert_eq!(
+        client.send_sync_message(&query).await?.get_error_string()?,
+        "nyi"
+    );
+    let query = K::new_compound_list(vec![
+        K::new_symbol(String::from("cast")),
+        K::new_symbol(String::from("a")),
+    ]);
+    assert_eq!(
+        client.send_sync_message(&query).await?.get_error_string()?,
+        "invalid cast from symbol to long"
+    );
+    // .z.ps
+    client.send_async_message(&"hello").await?;
+    let query = K::new_compound_list(vec![K::new_symbol(String::from("notes"))]);
+    assert_eq!(client.send_sync_message(&query).await?.get_long()?, 1);
+    assert_eq!(handler.notes.lock().unwrap()[0], "hello");
+    // .z.po and .z.pc
+    assert_eq!(handler.opened.load(std::sync::atomic::Ordering::SeqCst), 1);
+    client.shutdown().await?;
+    let mut attempt = 0;
+    while handler.closed.load(std::sync::atomic::Ordering::SeqCst) == 0 && attempt < 100 {
+        attempt += 1;
+        async_std::task::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(handler.closed.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn auth_test() -> Result<()> {
+    // In-memory accounts
+    let builder = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 0)
+        .authenticator(AccountMap::new().insert("kdbuser", "secret"));
+    let mut listener = QListener::bind(builder, 2).await?;
+    let port = listener.local_addr().unwrap().port();
+    let mut client = QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", port)
+        .credential("kdbuser:secret")
+        .connect()
+        .await?;
+    let mut server = listener.accept().await?;
+    client.send_async_message(&"hello").await?;
+    assert_eq!(server.receive_message().await?.1.as_string()?, "hello");
+    assert!(
+        QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", port)
+            .credential("kdbuser:pass")
+            .connect()
+            .await
+            .is_err()
+    );
+
+    // Client outside of allowed networks
+    let authenticator = IpAllowlist::new(AllowAll).allow("10.0.0.0/8")?;
+    assert!(authenticator.authenticate("kdbuser", "", Some("10.1.2.3:5000".parse().unwrap())));
+    assert!(!authenticator.authenticate("kdbuser", "", None));
+    assert!(IpAllowlist::new(AllowAll).allow("10.0.0.0/33").is_err());
+    let builder =
+        QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 0).authenticator(authenticator);
+    let listener = QListener::bind(builder, 1).await?;
+    let port = listener.local_addr().unwrap().port();
+    assert!(
+        QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", port)
+            .credential("kdbuser:pass")
+            .connect()
+            .await
+            .is_err()
+    );
+
+    // Account file is reloaded when modified
+    let account_file = std::env::temp_dir().join("kdbplus_auth_test_accounts");
+    std::fs::write(
+        &account_file,
+        "# comment\nkdbuser:9D4E1E23BD5B727046A9E3B4B7DB57BD8D6EE684\n",
+    )?;
+    let accounts = AccountFile::new(&account_file)?;
+    assert!(accounts.authenticate("kdbuser", "pass", None));
+    assert!(!accounts.authenticate("newuser", "pass", None));
+    async_std::task::sleep(std::time::Duration::from_millis(20)).await;
+    std::fs::write(
+        &account_file,
+        "newuser:9d4e1e23bd5b727046a9e3b4b7db57bd8d6ee684\n",
+    )?;
+    assert!(accounts.authenticate("newuser", "pass", None));
+    assert!(!accounts.authenticate("kdbuser", "pass", None));
+
+    // Password hashes other than SHA-1
+    #[cfg(feature = "auth-bcrypt")]
+    {
+        let hash = bcrypt::hash("pass", 4).unwrap();
+        std::fs::write(&account_file, format!("bcryptuser:{}\n", hash))?;
+        let accounts = AccountFile::new(&account_file)?;
+        assert!(accounts.authenticate("bcryptuser", "pass", None));
+        assert!(!accounts.authenticate("bcryptuser", "wrong", None));
+    }
+    #[cfg(feature = "auth-argon2")]
+    {
+        use argon2::password_hash::{PasswordHasher, SaltString};
+        let salt = SaltString::encode_b64(b"kdbplus_salt").unwrap();
+        let hash = argon2::Argon2::default()
+            .hash_password(b"pass", &salt)
+            .unwrap()
+            .to_string();
+        std::fs::write(&account_file, format!("argonuser:{}\n", hash))?;
+        let accounts = AccountFile::new(&account_file)?;
+        assert!(accounts.authenticate("argonuser", "pass", None));
+        assert!(!accounts.authenticate("argonuser", "wrong", None));
+    }
+
+    // Hash whose feature is not enabled is not loaded
+    #[cfg(not(feature = "auth-bcrypt"))]
+    {
+        std::fs::write(
+            &account_file,
+            "kdbuser:9d4e1e23bd5b727046a9e3b4b7db57bd8d6ee684\n",
+        )?;
+        let accounts = AccountFile::new(&account_file)?;
+        async_std::task::sleep(std::time::Duration::from_millis(20)).await;
+        let bcrypt_line =
+            "bcryptuser:$2b$04$abcdefghijklmnopqrstuuKmD7y0Z8c1s5v9oX3dWc4Q2jH6fL1mG\n";
+        std::fs::write(&account_file, bcrypt_line)?;
+        assert!(AccountFile::new(&account_file).is_err());
+        // The accounts loaded last are kept.
+        assert!(accounts.authenticate("kdbuser", "pass", None));
+    }
+
+    Ok(())
+}
+
+/// Table `trade` with columns `sym` and `size` for `tick_test`.
+fn trade_table(symbols: Vec<&str>, sizes: Vec<i64>) -> Result<K> {
+    K::new_dictionary(
+        K::new_symbol_list(
+            vec![String::from("sym"), String::from("size")],
+            qattribute::NONE,
+        ),
+        K::new_compound_list(vec![
+            K::new_symbol_list(
+                symbols.into_iter().map(String::from).collect(),
+                qattribute::NONE,
+            ),
+            K::new_long_list(sizes, qattribute::NONE),
+        ]),
+    )?
+    .flip()
+}
+
+/// Message of a tickerplant.
+fn tick_message(function: &str, table: &str, data: K) -> K {
+    K::new_compound_list(vec![
+        K::new_symbol(String::from(function)),
+        K::new_symbol(String::from(table)),
+        data,
+    ])
+}
+
+#[async_std::test]
+async fn tick_test() -> Result<()> {
+    use async_std::stream::StreamExt;
+
+    // Tickerplant emulated by a listener
+    let builder =
+        QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 0).authenticator(AllowAll);
+    let mut listener = QListener::bind(builder, 1).await?;
+    let port = listener.local_addr().unwrap().port();
+    let tickerplant = async_std::task::spawn(async move {
+        let mut socket = listener.accept().await?;
+        let (message_type, request) = socket.receive_message().await?;
+        assert_eq!(message_type, qmsg_type::synchronous);
+        let request = request.as_vec::<K>()?;
+        assert!(request[0].as_string()?.contains(".u.i#get .u.L"));
+        assert_eq!(*request[1].as_vec::<S>()?, vec![String::from("trade")]);
+        assert_eq!(*request[2].as_vec::<S>()?, vec![String::from("AAPL")]);
+        // Subscription and the log
+        let subscriptions = K::new_compound_list(vec![K::new_compound_list(vec![
+            K::new_symbol(String::from("trade")),
+            trade_table(vec![], vec![])?,
+        ])]);
+        let log = K::new_compound_list(vec![
+            tick_message("upd", "quote", K::new_long(0)),
+            tick_message("upd", "trade", trade_table(vec!["AAPL"], vec![100])?),
+        ]);
+        socket
+            .send_message(
+                &K::new_compound_list(vec![subscriptions, log]),
+                qmsg_type::response,
+            )
+            .await?;
+        // Live updates
+        socket
+            .send_async_message(&tick_message(
+                "upd",
+                "trade",
+                trade_table(vec!["AAPL"], vec![200])?,
+            ))
+            .await?;
+        socket
+            .send_async_message(&K::new_compound_list(vec![
+                K::new_symbol(String::from(".u.end")),
+                K::new_long(0),
+            ]))
+            .await?;
+        socket
+            .send_async_message(&tick_message(
+                "upd",
+                "trade",
+                trade_table(vec!["AAPL"], vec![300])?,
+            ))
+            .await?;
+        socket.shutdown().await
+    });
+
+    let stream = QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "").await?;
+    let options = TickOptions {
+        tables: vec![String::from("trade")],
+        symbols: vec![String::from("AAPL")],
+        recover: true,
+    };
+    let mut subscriber = TickSubscriber::subscribe(stream, options).await?;
+    assert_eq!(subscriber.schemas().len(), 1);
+    assert_eq!(subscriber.schemas()[0].0, "trade");
+    assert_eq!(subscriber.pending_replay(), 1);
+    // Replayed update, then live updates skipping `.u.end`
+    for size in [100, 200, 300] {
+        let update = subscriber.next_update().await?;
+        assert_eq!(update.table, "trade");
+        assert_eq!(update.data.get_column("size")?.as_vec::<J>()?[0], size);
+    }
+    // The tickerplant closed the connection.
+    assert!(subscriber.next().await.is_none());
+    match subscriber.next_update().await {
+        Err(Error::IO(error)) => assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof),
+        _ => panic!("expected the end of updates"),
+    }
+    tickerplant.await?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn split_test() -> Result<()> {
+    // Server echoing asynchronous messages and answering synchronous messages with themselves
+    let builder =
+        QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 0).authenticator(AllowAll);
+    let mut listener = QListener::bind(builder, 2).await?;
+    let port = listener.local_addr().unwrap().port();
+    async_std::task::spawn(async move {
+        loop {
+            let mut socket = listener.accept().await?;
+            async_std::task::spawn(async move {
+                while let Ok((message_type, message)) = socket.receive_message().await {
+                    let message_type = match message_type {
+                        qmsg_type::synchronous => qmsg_type::response,
+                        _ => qmsg_type::asynchronous,
+                    };
+                    socket.send_message(&message, message_type).await?;
+                }
+                Ok::<(), Error>(())
+            });
+        }
+        #[allow(unreachable_code)]
+        Ok::<(), Error>(())
+    });
+
+    let socket = QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "").await?;
+    let (mut reader, mut writer) = socket.into_split();
+    let sender = async_std::task::spawn(async move {
+        for i in 0..3 {
+            writer.send_async_message(&K::new_long(i)).await?;
+        }
+        writer
+            .send_message(&K::new_long(3), qmsg_type::synchronous)
+            .await?;
+        Ok::<QWriter, Error>(writer)
+    });
+    let mut received = Vec::new();
+    for _ in 0..4 {
+        let (message_type, message) = reader.receive_message().await?;
+        received.push((message_type, message.get_long()?));
+    }
+    assert_eq!(
+        received,
+        vec![
+            (qmsg_type::asynchronous, 0),
+            (qmsg_type::asynchronous, 1),
+            (qmsg_type::asynchronous, 2),
+            (qmsg_type::response, 3)
+        ]
+    );
+    let writer = sender.await?;
+
+    // Halves of different streams are not reunited.
+    let other = QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "").await?;
+    let (other_reader, other_writer) = other.into_split();
+    let (reader, other_writer) = match reader.reunite(other_writer) {
+        Err(ReuniteError(reader, writer)) => (reader, writer),
+        Ok(_) => panic!("halves of different streams are reunited"),
+    };
+    assert!(other_reader.reunite(other_writer).is_ok());
+
+    let mut socket = reader.reunite(writer)?;
+    assert_eq!(
+        socket
+            .send_sync_message(&K::new_long(4))
+            .await?
+            .get_long()?,
+        4
+    );
+    socket.shutdown().await?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn publisher_test() -> Result<()> {
+    let journal = std::env::temp_dir().join("kdbplus_publisher_test");
+    let _ = std::fs::remove_file(&journal);
+    let publisher = Publisher::new(vec![
+        (String::from("trade"), trade_table(vec![], vec![])?),
+        (String::from("quote"), trade_table(vec![], vec![])?),
+    ])?;
+    assert!(Publisher::new(vec![(String::from("trade"), K::new_long(0))]).is_err());
+    publisher.set_journal(&journal).await?;
+    publisher
+        .publish("trade", trade_table(vec!["AAPL"], vec![100])?)
+        .await?;
+    assert!(publisher
+        .publish("bid", trade_table(vec![], vec![])?)
+        .await
+        .is_err());
+    assert!(publisher.publish("trade", K::new_long(0)).await.is_err());
+
+    let builder =
+        QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 0).authenticator(AllowAll);
+    let listener = QListener::bind(builder, 8).await?;
+    let port = listener.local_addr().unwrap().port();
+    let server = publisher.clone();
+    async_std::task::spawn(async move { server.listen(listener).await });
+
+    // Functional subscription to a symbol
+    let mut aapl = QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "").await?;
+    let response = aapl
+        .send_sync_message(&K::new_compound_list(vec![
+            K::new_symbol(String::from(".u.sub")),
+            K::new_symbol(String::from("trade")),
+            K::new_symbol(String::from("AAPL")),
+        ]))
+        .await?;
+    assert_eq!(response.as_vec::<K>()?[0].get_symbol()?, "trade");
+    let response = aapl.send_sync_message(&".u.sub[`bid;`]").await?;
+    assert_eq!(response.get_error_string()?, "bid");
+
+    // Subscription of `r.q`
+    let mut rdb = QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "").await?;
+    let response = rdb.send_sync_message(&"(.u.sub[`;`];`.u `i`L)").await?;
+    let response = response.as_vec::<K>()?;
+    assert_eq!(response[0].len(), 2);
+    let log = response[1].as_vec::<K>()?;
+    assert_eq!(log[0].get_long()?, 1);
+    assert_eq!(
+        log[1].get_symbol()?,
+        format!(":{}", journal.display()).as_str()
+    );
+
+    // Subscription of `TickSubscriber` replaying the journal
+    let stream = QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "").await?;
+    let options = TickOptions {
+        tables: vec![String::from("trade")],
+        symbols: Vec::new(),
+        recover: true,
+    };
+    let mut subscriber = TickSubscriber::subscribe(stream, options).await?;
+    assert_eq!(subscriber.pending_replay(), 1);
+    assert_eq!(publisher.subscribers().await, 3);
+
+    publisher
+        .publish("trade", trade_table(vec!["MSFT", "AAPL"], vec![200, 300])?)
+        .await?;
+    publisher
+        .publish("quote", trade_table(vec!["IBM"], vec![400])?)
+        .await?;
+    let (_, update) = aapl.receive_message().await?;
+    let update = update.as_vec::<K>()?;
+    assert_eq!(update[0].get_symbol()?, "upd");
+    assert_eq!(update[1].get_symbol()?, "trade");
+    assert_eq!(
+        update[2].get_column("sym")?.as_vec::<S>()?,
+        &vec![String::from("AAPL")]
+    );
+    assert_eq!(update[2].get_column("size")?.as_vec::<J>()?, &vec![300_i64]);
+    let (_, update) = rdb.receive_message().await?;
+    assert_eq!(update.as_vec::<K>()?[2].len(), 2);
+    let (_, update) = rdb.receive_message().await?;
+    assert_eq!(update.as_vec::<K>()?[1].get_symbol()?, "quote");
+    let mut sizes = Vec::new();
+    for _ in 0..2 {
+        let update = subscriber.next_update().await?;
+        assert_eq!(update.table, "trade");
+        sizes.extend_from_slice(update.data.get_column("size")?.as_vec::<J>()?);
+    }
+    assert_eq!(sizes, vec![100, 200, 300]);
+    assert_eq!(
+        journal::count_chunks(&journal).await?,
+        journal::ChunkCount::Complete(3)
+    );
+
+    // Disconnected subscribers are removed
+    aapl.shutdown().await?;
+    subscriber.shutdown().await?;
+    for _ in 0..100 {
+        if publisher.subscribers().await == 1 {
+            break;
+        }
+        async_std::task::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(publisher.subscribers().await, 1);
+
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+    publisher.end_of_day(date).await?;
+    let (_, end) = rdb.receive_message().await?;
+    let end = end.as_vec::<K>()?;
+    assert_eq!(end[0].get_symbol()?, ".u.end");
+    assert_eq!(end[1].get_date()?, date);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn journal_test() -> Result<()> {
+    let path = std::env::temp_dir().join("kdbplus_journal_test");
+
+    // Write a journal as a tickerplant does
+    let mut writer = journal::Writer::create(&path).await?;
+    writer
+        .append_update("trade", trade_table(vec!["AAPL"], vec![100])?)
+        .await?;
+    writer
+        .append(&K::new_compound_list(vec![
+            K::new_symbol(String::from("upd")),
+            K::new_symbol(String::from("quote")),
+            K::new_compound_list(vec![
+                K::new_symbol_list(vec![String::from("MSFT")], qattribute::NONE),
+                K::new_float_list(vec![310.5], qattribute::NONE),
+            ]),
+        ]))
+        .await?;
+    assert_eq!(writer.chunks(), 2);
+    drop(writer);
+    let bytes = std::fs::read(&path)?;
+    assert_eq!(bytes[0..4], [0xff, 0x01, 0x00, 0x00]);
+    assert_eq!(u32::from_ne_bytes(bytes[4..8].try_into().unwrap()), 2);
+
+    // Read it back
+    let mut reader = journal::Reader::open(&path).await?;
+    let message = reader.next_message().await?.unwrap();
+    let message = message.as_vec::<K>()?;
+    assert_eq!(message[1].get_symbol()?, "trade");
+    assert_eq!(
+        message[2].get_column("size")?.as_vec::<J>()?,
+        &vec![100_i64]
+    );
+    let position = reader.position();
+    let message = reader.next_message().await?.unwrap();
+    assert_eq!(message.as_vec::<K>()?[1].get_symbol()?, "quote");
+    assert!(reader.next_message().await?.is_none());
+    assert_eq!(reader.chunks(), 2);
+    let mut reader = journal::Reader::open(&path).await?;
+    assert_eq!(reader.skip(3).await?, 2);
+
+    // Partially written chunk
+    std::fs::write(&path, &bytes[..bytes.len() - 3])?;
+    assert_eq!(
+        journal::count_chunks(&path).await?,
+        journal::ChunkCount::Truncated {
+            chunks: 1,
+            valid_length: position
+        }
+    );
+    let mut reader = journal::Reader::open(&path).await?;
+    assert!(reader.next_message().await?.is_some());
+    assert!(reader.next_message().await.is_err());
+    assert!(journal::Writer::open(&path).await.is_err());
+    journal::repair(&path).await?;
+    assert_eq!(
+        journal::count_chunks(&path).await?,
+        journal::ChunkCount::Complete(1)
+    );
+
+    // Append to the repaired journal
+    let mut writer = journal::Writer::open(&path).await?;
+    writer
+        .append_update("trade", trade_table(vec!["IBM"], vec![50])?)
+        .await?;
+    assert_eq!(writer.chunks(), 2);
+    drop(writer);
+    let mut reader = journal::Reader::open(&path).await?;
+    reader.skip(1).await?;
+    let message = reader.next_message().await?.unwrap();
+    assert_eq!(
+        message.as_vec::<K>()?[2].get_column("sym")?.as_vec::<S>()?,
+        &vec![String::from("IBM")]
+    );
+
+    // Chunk of a lambda, i.e., (`upd; {x}), is not cut off as a corrupt tail
+    let mut bytes = std::fs::read(&path)?;
+    bytes.extend_from_slice(&[
+        0, 0, 2, 0, 0, 0, 245, b'u', b'p', b'd', 0, 100, 0, 10, 0, 3, 0, 0, 0,
+    ]);
+    bytes.extend_from_slice(b"{x}");
+    std::fs::write(&path, &bytes)?;
+    assert!(journal::count_chunks(&path).await.is_err());
+    assert!(journal::repair(&path).await.is_err());
+    assert_eq!(std::fs::read(&path)?, bytes);
+    let mut reader = journal::Reader::open(&path).await?;
+    assert_eq!(reader.skip(2).await?, 2);
+    assert!(reader.next_message().await.is_err());
+
+    std::fs::write(&path, b"not a journal")?;
+    assert!(journal::Reader::open(&path).await.is_err());
+
+    Ok(())
+}
+
+/// Bytes of a file of a non-mappable symbol list.
+fn symbol_list_file(symbols: &[&str]) -> Vec<u8> {
+    let mut bytes = vec![0xff, 0x01, qtype::SYMBOL_LIST as u8, 0];
+    bytes.extend_from_slice(&(symbols.len() as u32).to_ne_bytes());
+    for symbol in symbols {
+        bytes.extend_from_slice(symbol.as_bytes());
+        bytes.push(0);
+    }
+    bytes
+}
+
+/// Bytes of a file of a mappable list. `domain` is written for an enumerated list.
+fn list_file(
+    qtype: i8,
+    attribute: i8,
+    domain: Option<&str>,
+    length: usize,
+    data: &[u8],
+) -> Vec<u8> {
+    let mut bytes = vec![0xfe, 0x20, qtype as u8, attribute as u8, 0, 0, 0, 0];
+    if let Some(domain) = domain {
+        let mut name = domain.as_bytes().to_vec();
+        name.resize(8, 0);
+        bytes.extend_from_slice(&name);
+    }
+    bytes.extend_from_slice(&(length as i64).to_ne_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+#[async_std::test]
+async fn disk_test() -> Result<()> {
+    let database = std::env::temp_dir().join("kdbplus_disk_test");
+    let _ = std::fs::remove_dir_all(&database);
+    let partition = database.join("2024.01.02").join("trade");
+    let splayed = database.join("quote");
+    std::fs::create_dir_all(&partition)?;
+    std::fs::create_dir_all(&splayed)?;
+    std::fs::write(
+        database.join("sym"),
+        symbol_list_file(&["AAPL", "MSFT", "IBM"]),
+    )?;
+
+    // Partitioned table with an enumerated column, a long column and a string column
+    std::fs::write(
+        partition.join(".d"),
+        symbol_list_file(&["sym", "size", "note"]),
+    )?;
+    let indices = [0_i32, 0, 2]
+        .iter()
+        .flat_map(|index| index.to_ne_bytes())
+        .collect::<Vec<u8>>();
+    std::fs::write(
+        partition.join("sym"),
+        list_file(
+            qtype::ENUM_LIST,
+            qattribute::PARTED,
+            Some("sym"),
+            3,
+            &indices,
+        ),
+    )?;
+    let sizes = [100_i64, 200, 300]
+        .iter()
+        .flat_map(|size| size.to_ne_bytes())
+        .collect::<Vec<u8>>();
+    std::fs::write(
+        partition.join("size"),
+        list_file(qtype::LONG_LIST, qattribute::NONE, None, 3, &sizes),
+    )?;
+    let ends = [2_i64, 2, 5]
+        .iter()
+        .flat_map(|end| end.to_ne_bytes())
+        .collect::<Vec<u8>>();
+    std::fs::write(
+        partition.join("note"),
+        list_file(77 + qtype::STRING, qattribute::NONE, None, 3, &ends),
+    )?;
+    std::fs::write(
+        partition.join("note#"),
+        list_file(qtype::STRING, qattribute::NONE, None, 5, b"hiabc"),
+    )?;
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+    let trade = disk::read_partition(&database, date, "trade").await?;
+    assert_eq!(trade.len(), 3);
+    assert_eq!(trade.get_column("date")?.as_vec::<I>()?, &vec![8767_i32; 3]);
+    assert_eq!(
+        trade.get_column("sym")?.as_vec::<S>()?,
+        &vec![
+            String::from("AAPL"),
+            String::from("AAPL"),
+            String::from("IBM")
+        ]
+    );
+    assert_eq!(trade.get_column("sym")?.get_attribute(), qattribute::PARTED);
+    assert_eq!(
+        trade.get_column("size")?.as_vec::<J>()?,
+        &vec![100_i64, 200, 300]
+    );
+    let notes = trade.get_column("note")?.as_vec::<K>()?;
+    assert_eq!(notes[0].as_string()?, "hi");
+    assert_eq!(notes[1].as_string()?, "");
+    assert_eq!(notes[2].as_string()?, "abc");
+
+    // Splayed table whose enumerated column does not record its domain
+    std::fs::write(splayed.join(".d"), symbol_list_file(&["sym", "price"]))?;
+    let indices = [1_i32, 2]
+        .iter()
+        .flat_map(|index| index.to_ne_bytes())
+        .collect::<Vec<u8>>();
+    std::fs::write(
+        splayed.join("sym"),
+        list_file(qtype::ENUM_LIST, qattribute::NONE, None, 2, &indices),
+    )?;
+    let prices = [310.5_f64, 140.25]
+        .iter()
+        .flat_map(|price| price.to_ne_bytes())
+        .collect::<Vec<u8>>();
+    std::fs::write(
+        splayed.join("price"),
+        list_file(qtype::FLOAT_LIST, qattribute::SORTED, None, 2, &prices),
+    )?;
+    let quote = disk::read_splayed(&splayed).await?;
+    assert_eq!(
+        quote.get_column("sym")?.as_vec::<S>()?,
+        &vec![String::from("MSFT"), String::from("IBM")]
+    );
+    assert_eq!(
+        quote.get_column("price")?.as_vec::<F>()?,
+        &vec![310.5_f64, 140.25]
+    );
+
+    // Single objects
+    let prices = disk::read_object(splayed.join("price")).await?;
+    assert_eq!(prices.get_attribute(), qattribute::SORTED);
+    assert_eq!(prices.len(), 2);
+    let symbols = disk::read_object(database.join("sym")).await?;
+    assert_eq!(symbols.as_vec::<S>()?.len(), 3);
+    assert!(disk::read_object(splayed.join("sym")).await.is_err());
+    std::fs::write(database.join("zipped"), b"kxzipped")?;
+    assert!(disk::read_object(database.join("zipped")).await.is_err());
+    std::fs::write(database.join("text"), b"hello")?;
+    assert!(disk::read_object(database.join("text")).await.is_err());
+
+    // Corrupt headers
+    let is_invalid_data = |result: Result<K>| match result {
+        Err(Error::IO(error)) => error.kind() == std::io::ErrorKind::InvalidData,
+        _ => false,
+    };
+    std::fs::write(
+        database.join("overflow"),
+        list_file(
+            qtype::LONG_LIST,
+            qattribute::NONE,
+            None,
+            usize::MAX / 4,
+            &sizes,
+        ),
+    )?;
+    assert!(is_invalid_data(
+        disk::read_object(database.join("overflow")).await
+    ));
+    std::fs::write(
+        partition.join("note"),
+        list_file(
+            77 + qtype::STRING,
+            qattribute::NONE,
+            None,
+            usize::MAX / 4,
+            &ends,
+        ),
+    )?;
+    assert!(is_invalid_data(
+        disk::read_partition(&database, date, "trade").await
+    ));
+    let ends = [2_i64, i64::MAX, 5]
+        .iter()
+        .flat_map(|end| end.to_ne_bytes())
+        .collect::<Vec<u8>>();
+    std::fs::write(
+        partition.join("note"),
+        list_file(77 + qtype::STRING, qattribute::NONE, None, 3, &ends),
+    )?;
+    assert!(is_invalid_data(
+        disk::read_partition(&database, date, "trade").await
+    ));
+    let ends = [4_i64, 2, 5]
+        .iter()
+        .flat_map(|end| end.to_ne_bytes())
+        .collect::<Vec<u8>>();
+    std::fs::write(
+        partition.join("note"),
+        list_file(77 + qtype::STRING, qattribute::NONE, None, 3, &ends),
+    )?;
+    assert!(is_invalid_data(
+        disk::read_partition(&database, date, "trade").await
+    ));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn disk_write_test() -> Result<()> {
+    let database = std::env::temp_dir().join("kdbplus_disk_write_test");
+    let _ = std::fs::remove_dir_all(&database);
+    let table = K::new_dictionary(
+        K::new_symbol_list(
+            vec![
+                String::from("size"),
+                String::from("sym"),
+                String::from("note"),
+            ],
+            qattribute::NONE,
+        ),
+        K::new_compound_list(vec![
+            K::new_long_list(vec![100, 200, 300], qattribute::SORTED),
+            K::new_symbol_list(
+                vec![
+                    String::from("MSFT"),
+                    String::from("AAPL"),
+                    String::from("MSFT"),
+                ],
+                qattribute::NONE,
+            ),
+            K::new_compound_list(vec![
+                K::new_string(String::from("a"), qattribute::NONE),
+                K::new_string(String::from("bc"), qattribute::NONE),
+                K::new_string(String::from(""), qattribute::NONE),
+            ]),
+        ]),
+    )?
+    .flip()?;
+
+    // Splayed table
+    let splayed = database.join("trade");
+    disk::write_splayed(&splayed, &table, database.join("sym")).await?;
+    let bytes = std::fs::read(splayed.join("size"))?;
+    assert_eq!(
+        bytes[0..8],
+        [
+            0xfe,
+            0x20,
+            qtype::LONG_LIST as u8,
+            qattribute::SORTED as u8,
+            0,
+            0,
+            0,
+            0
+        ]
+    );
+    assert_eq!(i64::from_ne_bytes(bytes[8..16].try_into().unwrap()), 3);
+    assert_eq!(bytes.len(), 16 + 3 * 8);
+    let bytes = std::fs::read(splayed.join("sym"))?;
+    assert_eq!(bytes[2], qtype::ENUM_LIST as u8);
+    assert_eq!(&bytes[8..16], b"sym\0\0\0\0\0");
+    assert!(splayed.join("note#").exists());
+    let trade = disk::read_splayed(&splayed).await?;
+    assert_eq!(format!("{}", trade), format!("{}", table));
+    assert_eq!(
+        disk::read_object(database.join("sym"))
+            .await?
+            .as_vec::<S>()?,
+        &vec![String::from("MSFT"), String::from("AAPL")]
+    );
+
+    // Partition sharing the sym file which has bytes after its last symbol
+    let mut sym_file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(database.join("sym"))?;
+    std::io::Write::write_all(&mut sym_file, b"JUNK\0")?;
+    drop(sym_file);
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+    let table = K::new_dictionary(
+        K::new_symbol_list(
+            vec![String::from("size"), String::from("sym")],
+            qattribute::NONE,
+        ),
+        K::new_compound_list(vec![
+            K::new_long_list(vec![1, 2, 3, 4], qattribute::NONE),
+            K::new_symbol_list(
+                vec![
+                    String::from("IBM"),
+                    String::from("MSFT"),
+                    String::from("AAPL"),
+                    String::from("IBM"),
+                ],
+                qattribute::NONE,
+            ),
+        ]),
+    )?
+    .flip()?;
+    disk::write_partition(&database, date, "quote", &table, None).await?;
+    let quote = disk::read_partition(&database, date, "quote").await?;
+    assert_eq!(
+        quote.get_column("sym")?.as_vec::<S>()?,
+        &vec![
+            String::from("IBM"),
+            String::from("MSFT"),
+            String::from("AAPL"),
+            String::from("IBM")
+        ]
+    );
+    assert_eq!(
+        quote.get_column("size")?.as_vec::<J>()?,
+        &vec![1_i64, 2, 3, 4]
+    );
+    assert_eq!(
+        disk::read_object(database.join("sym"))
+            .await?
+            .as_vec::<S>()?,
+        &vec![
+            String::from("MSFT"),
+            String::from("AAPL"),
+            String::from("IBM")
+        ]
+    );
+    assert_eq!(
+        std::fs::read(database.join("sym"))?.len(),
+        8 + "MSFT\0AAPL\0IBM\0".len()
+    );
+
+    // Parted by sym as `.Q.dpft[database; 2024.01.03; `sym; `quote]` does
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+    disk::write_partition(&database, date, "quote", &table, Some("sym")).await?;
+    let quote = disk::read_partition(&database, date, "quote").await?;
+    assert_eq!(quote.cols()?, vec!["date", "sym", "size"]);
+    assert_eq!(
+        quote.get_column("sym")?.as_vec::<S>()?,
+        &vec![
+            String::from("AAPL"),
+            String::from("IBM"),
+            String::from("IBM"),
+            String::from("MSFT")
+        ]
+    );
+    assert_eq!(quote.get_column("sym")?.get_attribute(), qattribute::PARTED);
+    assert_eq!(
+        quote.get_column("size")?.as_vec::<J>()?,
+        &vec![3_i64, 1, 4, 2]
+    );
+    let bytes = std::fs::read(database.join("2024.01.03/quote/sym"))?;
+    assert_eq!(
+        bytes[..16],
+        [0xfe, 0x20, 0x14, 0x03, 0, 0, 0, 0, b's', b'y', b'm', 0, 0, 0, 0, 0]
+    );
+    assert!(
+        disk::write_partition(&database, date, "quote", &table, Some("price"))
+            .await
+            .is_err()
+    );
+    assert!(
+        disk::write_splayed(&splayed, &K::new_long(1), database.join("sym"))
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn disk_fixture_test() -> Result<()> {
+    // Splayed table written by `tests/disk/make.q`
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/disk/db");
+    let trade = disk::read_splayed(fixture.join("trade")).await?;
+    assert_eq!(
+        format!("{}", trade),
+        String::from("+`sym`price`note!(`IBM`MSFT`IBM;101.5 202.25 99;(\"buy\";\"\";\"sell\"))")
+    );
+    assert_eq!(
+        disk::read_object(fixture.join("sym"))
+            .await?
+            .as_vec::<S>()?,
+        &vec![String::from("IBM"), String::from("MSFT")]
+    );
+
+    // Written files are the same as those of q
+    let database = std::env::temp_dir().join("kdbplus_disk_fixture_test");
+    let _ = std::fs::remove_dir_all(&database);
+    disk::write_splayed(database.join("trade"), &trade, database.join("sym")).await?;
+    for file in [
+        "sym",
+        "trade/.d",
+        "trade/sym",
+        "trade/price",
+        "trade/note",
+        "trade/note#",
+    ] {
+        assert_eq!(
+            std::fs::read(database.join(file))?,
+            std::fs::read(fixture.join(file))?,
+            "{}",
+            file
+        );
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn disk_compressed_fixture_test() -> Result<()> {
+    // `til 3000` compressed by `tests/disk/compressed/make.q`
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/disk/compressed");
+    let files = [
+        "q",
+        #[cfg(feature = "disk-gzip")]
+        "gzip",
+        #[cfg(feature = "disk-snappy")]
+        "snappy",
+        #[cfg(feature = "disk-lz4")]
+        "lz4hc",
+        #[cfg(feature = "disk-zstd")]
+        "zstd",
+    ];
+    for file in files {
+        assert_eq!(
+            disk::read_object(fixture.join(file)).await?.as_vec::<J>()?,
+            &(0..3000).collect::<Vec<J>>(),
+            "{}",
+            file
+        );
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn disk_compression_test() -> Result<()> {
+    let database = std::env::temp_dir().join("kdbplus_disk_compression_test");
+    let _ = std::fs::remove_dir_all(&database);
+    let table = K::new_dictionary(
+        K::new_symbol_list(
+            vec![String::from("sym"), String::from("size")],
+            qattribute::NONE,
+        ),
+        K::new_compound_list(vec![
+            K::new_symbol_list(
+                (0..3000)
+                    .map(|i| String::from(["AAPL", "MSFT", "IBM"][i % 3]))
+                    .collect(),
+                qattribute::NONE,
+            ),
+            K::new_long_list((0..3000).map(|i| i / 7).collect(), qattribute::NONE),
+        ]),
+    )?
+    .flip()?;
+    let splayed = database.join("trade");
+    disk::write_splayed(&splayed, &table, database.join("sym")).await?;
+    let size = splayed.join("size");
+    let original = std::fs::read(&size)?;
+    // Enumerated column stored in one block
+    let parameters = disk::CompressionParameters {
+        logical_block_size: 20,
+        algorithm: disk::CompressionAlgorithm::Q,
+        level: 0,
+    };
+    disk::compress_file(splayed.join("sym"), splayed.join("sym"), parameters).await?;
+
+    let algorithms = [
+        (disk::CompressionAlgorithm::Q, 0),
+        #[cfg(feature = "disk-gzip")]
+        (disk::CompressionAlgorithm::Gzip, 6),
+        #[cfg(feature = "disk-snappy")]
+        (disk::CompressionAlgorithm::Snappy, 0),
+        #[cfg(feature = "disk-lz4")]
+        (disk::CompressionAlgorithm::Lz4hc, 9),
+        #[cfg(feature = "disk-zstd")]
+        (disk::CompressionAlgorithm::Zstd, 3),
+    ];
+    for (algorithm, level) in algorithms {
+        let parameters = disk::CompressionParameters {
+            logical_block_size: 12,
+            algorithm,
+            level,
+        };
+        std::fs::write(&size, &original)?;
+        disk::compress_file(&size, &size, parameters).await?;
+        let compressed = std::fs::read(&size)?;
+        assert!(compressed.starts_with(b"kxzipped"));
+        assert!(compressed.len() < original.len());
+        // Trailer
+        let trailer = &compressed[compressed.len() - 16..];
+        assert_eq!(
+            u64::from_ne_bytes(trailer[0..8].try_into().unwrap()),
+            original.len() as u64
+        );
+        assert_eq!(u32::from_ne_bytes(trailer[8..12].try_into().unwrap()), 6);
+        assert_eq!(trailer[12..16], [12, algorithm as u8, level, 0]);
+        disk::compress_file(&size, &size, parameters)
+            .await
+            .unwrap_err();
+        let trade = disk::read_splayed(&splayed).await?;
+        assert_eq!(format!("{}", trade), format!("{}", table));
+    }
+
+    // Invalid parameters
+    let parameters = disk::CompressionParameters {
+        logical_block_size: 11,
+        algorithm: disk::CompressionAlgorithm::Q,
+        level: 0,
+    };
+    assert!(
+        disk::compress_file(&size, database.join("size"), parameters)
+            .await
+            .is_err()
+    );
+    let parameters = disk::CompressionParameters {
+        logical_block_size: 17,
+        algorithm: disk::CompressionAlgorithm::Q,
+        level: 1,
+    };
+    assert!(
+        disk::compress_file(&size, database.join("size"), parameters)
+            .await
+            .is_err()
+    );
+
+    // Corrupted file
+    let mut compressed = std::fs::read(&size)?;
+    compressed.truncate(compressed.len() - 20);
+    std::fs::write(&size, &compressed)?;
+    assert!(disk::read_object(&size).await.is_err());
+
+    // Block of q IPC compression cut off after 5 of 100 bytes while the sizes and the trailer are consistent
+    let mut compressed = b"kxzipped".to_vec();
+    compressed.extend_from_slice(&108_u32.to_ne_bytes());
+    compressed.extend_from_slice(&[0, 1, 2, 3, 4, 5]);
+    compressed.extend_from_slice(&10_u32.to_ne_bytes());
+    compressed.extend_from_slice(&100_i64.to_ne_bytes());
+    compressed.extend_from_slice(&1_u32.to_ne_bytes());
+    compressed.extend_from_slice(&[17, 1, 0, 0]);
+    std::fs::write(&size, &compressed)?;
+    match disk::read_object(&size).await {
+        Err(Error::IO(error)) => {
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+            assert!(error.to_string().ends_with("compressed file is corrupted"));
+        }
+        _ => panic!("expected a corrupted file"),
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "blocking")]
+#[async_std::test]
+async fn blocking_test() -> Result<()> {
+    // Server echoing asynchronous messages and answering synchronous messages with themselves
+    let builder =
+        QStreamBuilder::new(ConnectionMethod::TCP, "127.0.0.1", 0).authenticator(AllowAll);
+    let mut listener = QListener::bind(builder, 2).await?;
+    let port = listener.local_addr().unwrap().port();
+    async_std::task::spawn(async move {
+        loop {
+            let mut socket = listener.accept().await?;
+            async_std::task::spawn(async move {
+                while let Ok((message_type, message)) = socket.receive_message().await {
+                    let message_type = match message_type {
+                        qmsg_type::synchronous => qmsg_type::response,
+                        _ => qmsg_type::asynchronous,
+                    };
+                    socket.send_message(&message, message_type).await?;
+                }
+                Ok::<(), Error>(())
+            });
+        }
+        #[allow(unreachable_code)]
+        Ok::<(), Error>(())
+    });
+
+    async_std::task::spawn_blocking(move || {
+        let mut socket = blocking::QStream::connect(ConnectionMethod::TCP, "127.0.0.1", port, "")?;
+        assert_eq!(socket.get_connection_type(), "TCP");
+
+        // Text query
+        assert_eq!(socket.send_sync_message(&"1+1")?.as_string()?, "1+1");
+
+        // Functional query
+        let message = K::new_compound_list(vec![K::new_symbol(String::from("f")), K::new_long(7)]);
+        assert_eq!(
+            format!("{}", socket.send_sync_message(&message)?),
+            format!("{}", message)
+        );
+
+        // Compressed query
+        socket.enforce_compression();
+        let large = K::new_long_list(vec![1; 2000], qattribute::NONE);
+        assert_eq!(
+            socket.send_sync_message(&large)?.as_vec::<J>()?,
+            &vec![1; 2000]
+        );
+
+        // Asynchronous message comes back as an asynchronous message.
+        socket.send_async_message(&K::new_long(3))?;
+        let (message_type, message) = socket.receive_message()?;
+        assert_eq!(
+            (message_type, message.get_long()?),
+            (qmsg_type::asynchronous, 3)
+        );
+
+        // Nothing arrives and the stream is poisoned.
+        match socket.receive_message_timeout(std::time::Duration::from_millis(100)) {
+            Err(Error::Timeout("read")) => assert!(socket.is_poisoned()),
+            _ => panic!("expected a timeout"),
+        }
+        assert!(socket.send_async_message(&K::new_long(4)).is_err());
+        socket.shutdown()?;
+
+        // Nobody is listening.
+        let closed = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let closed_port = closed.local_addr()?.port();
+        drop(closed);
+        assert!(
+            blocking::QStream::connect(ConnectionMethod::TCP, "127.0.0.1", closed_port, "")
+                .is_err()
+        );
+
+        Ok::<(), Error>(())
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "blocking-tls")]
+#[async_std::test]
+async fn blocking_tls_test() -> Result<()> {
+    // PEM identity of the listener
+    let tls_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/tls");
+    std::env::set_var("KDBPLUS_TLS_CERT_PEM_FILE", tls_dir.join("server.pem"));
+    std::env::set_var("KDBPLUS_TLS_KEY_PEM_FILE", tls_dir.join("server.key"));
+    let builder =
+        QStreamBuilder::new(ConnectionMethod::TLS, "127.0.0.1", 0).authenticator(AllowAll);
+    let mut listener = QListener::bind(builder, 2).await?;
+    let port = listener.local_addr().unwrap().port();
+    async_std::task::spawn(async move {
+        loop {
+            let mut socket = listener.accept().await?;
+            async_std::task::spawn(async move {
+                while let Ok((_, message)) = socket.receive_message().await {
+                    socket.send_message(&message, qmsg_type::response).await?;
+                }
+                Ok::<(), Error>(())
+            });
+        }
+        #[allow(unreachable_code)]
+        Ok::<(), Error>(())
+    });
+
+    async_std::task::spawn_blocking(move || {
+        // Server certificate is verified against the test CA
+        let config = TlsConfig::new()
+            .add_root_certificate_pem(&std::fs::read(tls_dir.join("ca.pem"))?)
+            .disable_built_in_roots(true)
+            .hostname("localhost");
+        let mut socket = blocking::QStream::connect_with_options(
+            ConnectionMethod::TLS,
+            "127.0.0.1",
+            port,
+            "",
+            QTimeouts::default(),
+            config,
+        )?;
+        assert_eq!(socket.get_connection_type(), "TLS");
+        // TLS acceptor defines a function to close the connection first.
+        let (message_type, _) = socket.receive_message()?;
+        assert_eq!(message_type, qmsg_type::asynchronous);
+        assert_eq!(socket.send_sync_message(&K::new_long(5))?.get_long()?, 5);
+
+        // Invalid certificate is rejected before connecting
+        let config = TlsConfig::new().add_root_certificate_pem(b"not a certificate");
+        match blocking::QStream::connect_with_options(
+            ConnectionMethod::TLS,
+            "127.0.0.1",
+            port,
+            "",
+            QTimeouts::default(),
+            config,
+        ) {
+            Err(Error::IO(error)) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput),
+            _ => panic!("invalid certificate was accepted"),
+        }
+
+        // Server certificate does not match another host name
+        let config = TlsConfig::new()
+            .add_root_certificate_pem(&std::fs::read(tls_dir.join("ca.pem"))?)
+            .disable_built_in_roots(true)
+            .hostname("kdb.internal");
+        assert!(blocking::QStream::connect_with_options(
+            ConnectionMethod::TLS,
+            "127.0.0.1",
+            port,
+            "",
+            QTimeouts::default(),
+            config,
+        )
+        .is_err());
+
+        Ok::<(), Error>(())
+    })
+    .await?;
+
+    Ok(())
+}